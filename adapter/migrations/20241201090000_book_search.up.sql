@@ -0,0 +1,11 @@
+-- 蔵書検索用のトライグラムインデックス
+-- pg_trgm は文字単位で分割するため、日本語の部分一致検索にも利用できる
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS books_title_trgm_idx ON books USING GIN (title gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_author_trgm_idx ON books USING GIN (author gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_isbn_trgm_idx ON books USING GIN (isbn gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_description_trgm_idx ON books USING GIN (description gin_trgm_ops);
+
+-- 所有者での絞り込み用
+CREATE INDEX IF NOT EXISTS books_user_id_idx ON books (user_id);
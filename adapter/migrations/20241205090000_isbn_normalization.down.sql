@@ -0,0 +1 @@
+ALTER TABLE books DROP CONSTRAINT IF EXISTS books_isbn_format_check;
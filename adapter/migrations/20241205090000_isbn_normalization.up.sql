@@ -0,0 +1,20 @@
+-- ISBN を区切りのない ISBN-13 に正規化する
+UPDATE books SET isbn = upper(regexp_replace(isbn, '[-[:space:]]', '', 'g'));
+
+-- ISBN-10 は 978 を付けてチェックディジットを再計算する
+UPDATE books
+SET isbn = '978' || substr(isbn, 1, 9) || (
+    (10 - (
+        9 + 7 * 3 + 8
+        + substr(isbn, 1, 1)::int * 3 + substr(isbn, 2, 1)::int
+        + substr(isbn, 3, 1)::int * 3 + substr(isbn, 4, 1)::int
+        + substr(isbn, 5, 1)::int * 3 + substr(isbn, 6, 1)::int
+        + substr(isbn, 7, 1)::int * 3 + substr(isbn, 8, 1)::int
+        + substr(isbn, 9, 1)::int * 3
+    ) % 10) % 10
+)::text
+WHERE isbn ~ '^[0-9]{9}[0-9X]$';
+
+-- 既存データに不正な値が残っていても移行できるよう、検証は新しい行からに限る
+ALTER TABLE books
+    ADD CONSTRAINT books_isbn_format_check CHECK (isbn ~ '^97[89][0-9]{10}$') NOT VALID;
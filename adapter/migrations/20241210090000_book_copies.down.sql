@@ -0,0 +1,9 @@
+ALTER TABLE returned_checkouts DROP COLUMN IF EXISTS book_copy_id;
+
+-- 複数冊を貸し出している蔵書があると元の制約には戻せない
+ALTER TABLE checkouts DROP COLUMN IF EXISTS book_copy_id;
+DROP INDEX IF EXISTS checkouts_book_id_idx;
+ALTER TABLE checkouts ADD CONSTRAINT checkouts_book_id_key UNIQUE (book_id);
+
+DROP TABLE IF EXISTS book_copies;
+DROP SEQUENCE IF EXISTS book_copy_barcode_seq;
@@ -0,0 +1,54 @@
+-- 蔵書（書誌情報）とは別に、貸し出しの単位となる物理的な冊を管理する
+CREATE SEQUENCE IF NOT EXISTS book_copy_barcode_seq;
+
+CREATE TABLE IF NOT EXISTS book_copies (
+    book_copy_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    book_id UUID NOT NULL,
+    -- 指定がなければ連番から採番する
+    barcode VARCHAR(64) NOT NULL UNIQUE
+        DEFAULT 'C' || lpad(nextval('book_copy_barcode_seq')::text, 9, '0'),
+    condition VARCHAR(32) NOT NULL DEFAULT 'Good',
+    location VARCHAR(255) NOT NULL DEFAULT '',
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    updated_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    -- checkouts から (book_copy_id, book_id) の組で参照するため
+    UNIQUE (book_copy_id, book_id),
+
+    FOREIGN KEY (book_id) REFERENCES books(book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS book_copies_book_id_idx ON book_copies (book_id);
+
+CREATE TRIGGER book_copies_updated_at_trigger
+    BEFORE UPDATE ON book_copies FOR EACH ROW
+    EXECUTE PROCEDURE set_updated_at();
+
+-- 既存の蔵書はそれぞれ 1 冊ずつ所蔵しているものとする
+INSERT INTO book_copies (book_id, created_at)
+SELECT book_id, created_at FROM books ORDER BY created_at;
+
+-- 貸し出しは冊単位で行う
+ALTER TABLE checkouts ADD COLUMN book_copy_id UUID;
+UPDATE checkouts c SET book_copy_id = bc.book_copy_id
+FROM book_copies bc WHERE bc.book_id = c.book_id;
+ALTER TABLE checkouts
+    ALTER COLUMN book_copy_id SET NOT NULL,
+    DROP CONSTRAINT IF EXISTS checkouts_book_id_key,
+    ADD CONSTRAINT checkouts_book_copy_id_key UNIQUE (book_copy_id),
+    ADD FOREIGN KEY (book_copy_id, book_id) REFERENCES book_copies(book_copy_id, book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS checkouts_book_id_idx ON checkouts (book_id);
+
+ALTER TABLE returned_checkouts ADD COLUMN book_copy_id UUID;
+UPDATE returned_checkouts rc SET book_copy_id = bc.book_copy_id
+FROM book_copies bc WHERE bc.book_id = rc.book_id;
+ALTER TABLE returned_checkouts
+    ALTER COLUMN book_copy_id SET NOT NULL,
+    ADD FOREIGN KEY (book_copy_id, book_id) REFERENCES book_copies(book_copy_id, book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE;
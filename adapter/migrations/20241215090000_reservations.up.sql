@@ -0,0 +1,24 @@
+-- 貸出中の蔵書に対する予約（蔵書ごとに予約日時の順で並ぶ待ち行列）
+CREATE TABLE IF NOT EXISTS reservations (
+    reservation_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    book_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    reserved_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    -- 返却された冊の取り置きが始まった日時と、受け取り期限
+    ready_at TIMESTAMP(3) WITH TIME ZONE,
+    expires_at TIMESTAMP(3) WITH TIME ZONE,
+
+    -- 同じ蔵書を同じ利用者が重ねて予約することはできない
+    UNIQUE (book_id, user_id),
+    CHECK ((ready_at IS NULL) = (expires_at IS NULL)),
+
+    FOREIGN KEY (book_id) REFERENCES books(book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS reservations_book_id_reserved_at_idx
+    ON reservations (book_id, reserved_at, reservation_id);
@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS checkouts_due_at_idx;
+
+ALTER TABLE returned_checkouts
+    DROP COLUMN IF EXISTS renewal_count,
+    DROP COLUMN IF EXISTS due_at;
+
+ALTER TABLE checkouts
+    DROP COLUMN IF EXISTS renewal_count,
+    DROP COLUMN IF EXISTS due_at;
@@ -0,0 +1,16 @@
+-- 貸出の返却期限と延長回数
+-- 既存の貸出は、貸出日時から既定の貸出期間（14 日）を返却期限とする
+ALTER TABLE checkouts
+    ADD COLUMN due_at TIMESTAMP(3) WITH TIME ZONE,
+    ADD COLUMN renewal_count INTEGER NOT NULL DEFAULT 0 CHECK (renewal_count >= 0);
+UPDATE checkouts SET due_at = checked_out_at + interval '14 days';
+ALTER TABLE checkouts ALTER COLUMN due_at SET NOT NULL;
+
+ALTER TABLE returned_checkouts
+    ADD COLUMN due_at TIMESTAMP(3) WITH TIME ZONE,
+    ADD COLUMN renewal_count INTEGER NOT NULL DEFAULT 0 CHECK (renewal_count >= 0);
+UPDATE returned_checkouts SET due_at = checked_out_at + interval '14 days';
+ALTER TABLE returned_checkouts ALTER COLUMN due_at SET NOT NULL;
+
+-- 延滞中の貸出の絞り込みに使う
+CREATE INDEX IF NOT EXISTS checkouts_due_at_idx ON checkouts (due_at);
@@ -0,0 +1,12 @@
+-- ログインに失敗した記録（監査用）
+-- 存在しないメールアドレスでの失敗も記録するため、users とは関連付けない
+CREATE TABLE IF NOT EXISTS login_failures (
+    login_failure_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    email VARCHAR(255) NOT NULL,
+    ip_address VARCHAR(45),
+    user_agent VARCHAR(512),
+    attempted_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3)
+);
+
+CREATE INDEX IF NOT EXISTS login_failures_email_attempted_at_idx
+    ON login_failures (email, attempted_at);
@@ -0,0 +1,27 @@
+-- TOTP による二要素認証の設定
+-- enabled_at が NULL の間は登録中で、最初のコードを確認すると有効になる
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id UUID PRIMARY KEY,
+    secret VARCHAR(64) NOT NULL,
+    enabled_at TIMESTAMP(3) WITH TIME ZONE,
+    -- 同じコードを再利用されないよう、最後に受け付けたステップを記録する
+    last_used_step BIGINT,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+-- 認証アプリを使えないときのための一度だけ使えるリカバリーコード（SHA-256 で保存する）
+CREATE TABLE IF NOT EXISTS user_recovery_codes (
+    recovery_code_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMP(3) WITH TIME ZONE,
+
+    UNIQUE (user_id, code_hash),
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
@@ -0,0 +1,17 @@
+-- シングルサインオンだけで利用するユーザーはパスワードを持たない
+ALTER TABLE users ALTER COLUMN password_hash DROP NOT NULL;
+
+-- IdP の利用者（issuer と sub の組）とユーザーの紐づけ
+CREATE TABLE IF NOT EXISTS user_oidc_identities (
+    issuer VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    user_id UUID NOT NULL,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    PRIMARY KEY (issuer, subject),
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS user_oidc_identities_user_id_idx ON user_oidc_identities (user_id);
@@ -0,0 +1,19 @@
+-- 管理者からの招待。受け入れるとユーザーを作成して削除する
+-- トークンそのものは保存せず、SHA-256 のハッシュで照合する
+CREATE TABLE IF NOT EXISTS user_invitations (
+    invitation_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    email VARCHAR(255) NOT NULL UNIQUE,
+    role_id UUID NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    invited_by UUID,
+    expires_at TIMESTAMP(3) WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (role_id) REFERENCES roles(role_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE,
+    -- 招待した管理者が削除されても招待は残す
+    FOREIGN KEY (invited_by) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE SET NULL
+);
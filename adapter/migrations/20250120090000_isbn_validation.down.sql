@@ -0,0 +1,8 @@
+ALTER TABLE books DROP CONSTRAINT books_isbn_format_check;
+UPDATE books SET isbn = legacy_isbn WHERE isbn IS NULL;
+ALTER TABLE books ALTER COLUMN isbn SET NOT NULL;
+ALTER TABLE books DROP COLUMN legacy_isbn;
+ALTER TABLE books
+    ADD CONSTRAINT books_isbn_format_check CHECK (isbn ~ '^97[89][0-9]{10}$') NOT VALID;
+
+DROP FUNCTION IF EXISTS is_valid_isbn13(TEXT);
@@ -0,0 +1,21 @@
+-- kernel の Isbn と同じく、978/979 で始まる数字 13 桁でチェックディジットが正しいものを有効とする。
+-- STRICT なので NULL には NULL を返し、ISBN が空の蔵書は CHECK 制約を通る
+CREATE OR REPLACE FUNCTION is_valid_isbn13(isbn TEXT) RETURNS BOOLEAN
+LANGUAGE sql IMMUTABLE STRICT AS $$
+    SELECT CASE
+        WHEN isbn ~ '^97[89][0-9]{10}$' THEN (
+            SELECT sum(substr(isbn, i, 1)::int * CASE WHEN i % 2 = 1 THEN 1 ELSE 3 END) % 10 = 0
+            FROM generate_series(1, 13) AS i
+        )
+        ELSE FALSE
+    END
+$$;
+
+-- 正規化しても有効にならなかった ISBN は legacy_isbn に退避し、isbn を空にする。
+-- 退避した値は書誌を更新して正しい ISBN を設定したときに消す
+ALTER TABLE books ADD COLUMN legacy_isbn VARCHAR(255);
+ALTER TABLE books ALTER COLUMN isbn DROP NOT NULL;
+UPDATE books SET legacy_isbn = isbn, isbn = NULL WHERE NOT is_valid_isbn13(isbn);
+
+ALTER TABLE books DROP CONSTRAINT books_isbn_format_check;
+ALTER TABLE books ADD CONSTRAINT books_isbn_format_check CHECK (is_valid_isbn13(isbn));
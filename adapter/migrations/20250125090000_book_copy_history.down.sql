@@ -0,0 +1,6 @@
+ALTER TABLE returned_checkouts
+    DROP CONSTRAINT IF EXISTS returned_checkouts_book_copy_id_book_id_fkey,
+    ADD CONSTRAINT returned_checkouts_book_copy_id_book_id_fkey
+        FOREIGN KEY (book_copy_id, book_id) REFERENCES book_copies(book_copy_id, book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE;
@@ -0,0 +1,8 @@
+-- 返却済みの貸出履歴がある冊は削除できないようにする。
+-- 蔵書ごと削除した場合は returned_checkouts の book_id からたどって履歴も削除される
+ALTER TABLE returned_checkouts
+    DROP CONSTRAINT IF EXISTS returned_checkouts_book_copy_id_book_id_fkey,
+    ADD CONSTRAINT returned_checkouts_book_copy_id_book_id_fkey
+        FOREIGN KEY (book_copy_id, book_id) REFERENCES book_copies(book_copy_id, book_id)
+        ON UPDATE CASCADE
+        ON DELETE RESTRICT;
@@ -1,4 +1,5 @@
 pub mod model;
+pub(crate) mod pagination;
 
 use derive_new::new;
 use shared::config::DatabaseConfig;
@@ -0,0 +1,55 @@
+use kernel::model::api_key::{ApiKey, ApiKeyIdError, ApiKeyNameError, ApiKeyScopeError};
+use sqlx::types::chrono::{DateTime, Utc};
+use thiserror::Error;
+use uuid::Uuid;
+
+pub struct ApiKeyRow {
+    pub api_key_id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<ApiKeyRow> for ApiKey {
+    type Error = ApiKeyRowError;
+
+    fn try_from(value: ApiKeyRow) -> Result<Self, Self::Error> {
+        let ApiKeyRow {
+            api_key_id,
+            name,
+            key_prefix,
+            scopes,
+            expires_at,
+            last_used_at,
+            created_at,
+        } = value;
+
+        Ok(ApiKey::new(
+            api_key_id.try_into()?,
+            name.try_into()?,
+            key_prefix,
+            scopes
+                .iter()
+                .map(|scope| scope.parse())
+                .collect::<Result<_, _>>()?,
+            expires_at,
+            last_used_at,
+            created_at,
+        ))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ApiKeyRowError {
+    #[error("saved api key id is invalid: {0}")]
+    InvalidApiKeyId(#[from] ApiKeyIdError),
+
+    #[error("saved api key name is invalid: {0}")]
+    InvalidApiKeyName(#[from] ApiKeyNameError),
+
+    #[error("saved api key scope is invalid: {0}")]
+    InvalidApiKeyScope(#[from] ApiKeyScopeError),
+}
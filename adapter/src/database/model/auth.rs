@@ -1,26 +1,66 @@
+use chrono::{DateTime, Utc};
 use kernel::model::{
-    auth::{event::CreateToken, AccessToken},
+    auth::{
+        session::{SessionClient, SessionId},
+        AccessToken, RefreshToken,
+    },
     user::UserId,
     value_object::ValueObject,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::redis::model::{RedisKey, RedisValue, RedisValueError};
 
 pub struct UserRow {
     pub user_id: uuid::Uuid,
-    pub password_hash: String,
+    // シングルサインオンだけで利用するユーザーは None
+    pub password_hash: Option<String>,
 }
 
+// アクセストークン -> ユーザー ID（とトークンファミリー ID）
 pub struct AuthorizationKey(AccessToken);
-pub struct AuthorizedUserId(UserId);
+pub struct AuthorizedUserId {
+    user_id: UserId,
+    family_id: Option<TokenFamilyId>,
+}
 
-pub fn from(event: CreateToken) -> (AuthorizationKey, AuthorizedUserId) {
-    (
-        AuthorizationKey::from(event.access_token),
-        AuthorizedUserId::from(event.user_id),
-    )
+// 1 回のログインから発行されたアクセストークン・リフレッシュトークンの系列を表す ID。
+// API ではセッション ID として公開する
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenFamilyId(uuid::Uuid);
+
+// リフレッシュトークン -> トークンファミリー ID
+pub struct RefreshTokenKey(RefreshToken);
+// 使用済みのリフレッシュトークン -> トークンファミリー ID
+pub struct UsedRefreshTokenKey(RefreshToken);
+
+// トークンファミリー ID -> 現在有効なトークンの組とセッションの情報
+pub struct TokenFamilyKey(TokenFamilyId);
+#[derive(Debug, PartialEq, Eq)]
+pub struct TokenFamily {
+    pub user_id: UserId,
+    pub access_token: AccessToken,
+    pub refresh_token: RefreshToken,
+    pub client: SessionClient,
+    pub created_at: DateTime<Utc>,
 }
 
+// トークンファミリー ID -> アクセストークンが最後に使われた日時
+pub struct LastUsedAtKey(TokenFamilyId);
+pub struct LastUsedAt(pub DateTime<Utc>);
+
+// ユーザー ID -> ログイン中のトークンファミリー ID の集合
+pub struct UserSessionsKey(UserId);
+
+// 署名付きアクセストークンの拒否リスト。有効期限までの間だけ残す
+pub enum RevokedTokenKey {
+    // リフレッシュで置き換えたアクセストークン（jti）
+    Token(uuid::Uuid),
+    // ログアウトや強制ログアウトで無効にしたセッション
+    Family(TokenFamilyId),
+}
+pub struct Revoked;
+
 impl From<AuthorizationKey> for AccessToken {
     fn from(authorization_key: AuthorizationKey) -> Self {
         authorization_key.0
@@ -39,12 +79,6 @@ impl From<&AccessToken> for AuthorizationKey {
     }
 }
 
-impl From<UserId> for AuthorizedUserId {
-    fn from(user_id: UserId) -> Self {
-        Self(user_id)
-    }
-}
-
 impl RedisKey for AuthorizationKey {
     type Value = AuthorizedUserId;
 
@@ -53,28 +87,304 @@ impl RedisKey for AuthorizationKey {
     }
 }
 
+impl AuthorizedUserId {
+    pub fn new(user_id: UserId, family_id: TokenFamilyId) -> Self {
+        Self {
+            user_id,
+            family_id: Some(family_id),
+        }
+    }
+
+    pub fn family_id(&self) -> Option<TokenFamilyId> {
+        self.family_id
+    }
+
+    pub fn into_inner(self) -> UserId {
+        self.user_id
+    }
+}
+
+// リフレッシュトークン導入前に発行されたトークンはユーザー ID のみを保持している
 impl TryFrom<String> for AuthorizedUserId {
     type Error = RedisValueError;
 
     fn try_from(s: String) -> Result<Self, Self::Error> {
-        Ok(Self(
-            UserId::try_from(
-                s.parse::<uuid::Uuid>()
-                    .map_err(|e| RedisValueError::ParsingError(Box::new(e)))?,
-            )
-            .map_err(|e| RedisValueError::ParsingError(Box::new(e)))?,
-        ))
+        let (user_id, family_id) = match s.split_once(':') {
+            Some((user_id, family_id)) => (user_id, Some(family_id.parse()?)),
+            None => (s.as_str(), None),
+        };
+        Ok(Self {
+            user_id: parse_user_id(user_id)?,
+            family_id,
+        })
     }
 }
 
 impl RedisValue for AuthorizedUserId {
     fn inner(&self) -> String {
-        self.0.inner_ref().to_string()
+        match &self.family_id {
+            Some(family_id) => format!("{}:{}", self.user_id.inner_ref(), family_id.0),
+            None => self.user_id.inner_ref().to_string(),
+        }
     }
 }
 
-impl AuthorizedUserId {
-    pub fn into_inner(self) -> UserId {
-        self.0
+impl TokenFamilyId {
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4())
+    }
+}
+
+impl From<uuid::Uuid> for TokenFamilyId {
+    fn from(id: uuid::Uuid) -> Self {
+        Self(id)
+    }
+}
+
+impl From<TokenFamilyId> for uuid::Uuid {
+    fn from(family_id: TokenFamilyId) -> Self {
+        family_id.0
+    }
+}
+
+impl From<TokenFamilyId> for SessionId {
+    fn from(family_id: TokenFamilyId) -> Self {
+        SessionId::new(family_id.0)
+    }
+}
+
+impl From<&SessionId> for TokenFamilyId {
+    fn from(session_id: &SessionId) -> Self {
+        Self(*session_id.inner_ref())
+    }
+}
+
+impl std::str::FromStr for TokenFamilyId {
+    type Err = RedisValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<uuid::Uuid>()
+            .map(Self)
+            .map_err(|e| RedisValueError::ParsingError(Box::new(e)))
+    }
+}
+
+impl TryFrom<String> for TokenFamilyId {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl RedisValue for TokenFamilyId {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl From<&RefreshToken> for RefreshTokenKey {
+    fn from(refresh_token: &RefreshToken) -> Self {
+        Self(refresh_token.clone())
+    }
+}
+
+impl RedisKey for RefreshTokenKey {
+    type Value = TokenFamilyId;
+
+    fn inner(&self) -> String {
+        format!("refresh_token:{}", self.0.inner_ref())
+    }
+}
+
+impl From<&RefreshToken> for UsedRefreshTokenKey {
+    fn from(refresh_token: &RefreshToken) -> Self {
+        Self(refresh_token.clone())
+    }
+}
+
+impl RedisKey for UsedRefreshTokenKey {
+    type Value = TokenFamilyId;
+
+    fn inner(&self) -> String {
+        format!("used_refresh_token:{}", self.0.inner_ref())
+    }
+}
+
+impl From<TokenFamilyId> for TokenFamilyKey {
+    fn from(family_id: TokenFamilyId) -> Self {
+        Self(family_id)
+    }
+}
+
+impl RedisKey for TokenFamilyKey {
+    type Value = TokenFamily;
+
+    fn inner(&self) -> String {
+        format!("token_family:{}", self.0 .0)
+    }
+}
+
+// User-Agent には ':' などが含まれるため JSON で保存する
+#[derive(Serialize, Deserialize)]
+struct TokenFamilyRecord {
+    user_id: uuid::Uuid,
+    access_token: String,
+    refresh_token: String,
+    user_agent: Option<String>,
+    ip_address: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<String> for TokenFamily {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let record: TokenFamilyRecord =
+            serde_json::from_str(&s).map_err(|e| RedisValueError::ParsingError(Box::new(e)))?;
+        Ok(Self {
+            user_id: UserId::new(record.user_id),
+            access_token: AccessToken::new(record.access_token),
+            refresh_token: RefreshToken::new(record.refresh_token),
+            client: SessionClient {
+                user_agent: record.user_agent,
+                ip_address: record.ip_address,
+            },
+            created_at: record.created_at,
+        })
+    }
+}
+
+impl RedisValue for TokenFamily {
+    fn inner(&self) -> String {
+        let record = TokenFamilyRecord {
+            user_id: *self.user_id.inner_ref(),
+            access_token: self.access_token.inner_ref().clone(),
+            refresh_token: self.refresh_token.inner_ref().clone(),
+            user_agent: self.client.user_agent.clone(),
+            ip_address: self.client.ip_address.clone(),
+            created_at: self.created_at,
+        };
+        // 文字列と日時のみからなるため失敗しない
+        serde_json::to_string(&record).expect("token family must be serializable")
+    }
+}
+
+impl From<TokenFamilyId> for LastUsedAtKey {
+    fn from(family_id: TokenFamilyId) -> Self {
+        Self(family_id)
+    }
+}
+
+impl RedisKey for LastUsedAtKey {
+    type Value = LastUsedAt;
+
+    fn inner(&self) -> String {
+        format!("last_used_at:{}", self.0 .0)
+    }
+}
+
+impl TryFrom<String> for LastUsedAt {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        DateTime::parse_from_rfc3339(&s)
+            .map(|x| Self(x.with_timezone(&Utc)))
+            .map_err(|e| RedisValueError::ParsingError(Box::new(e)))
+    }
+}
+
+impl RedisValue for LastUsedAt {
+    fn inner(&self) -> String {
+        self.0.to_rfc3339()
+    }
+}
+
+impl From<&UserId> for UserSessionsKey {
+    fn from(user_id: &UserId) -> Self {
+        Self(user_id.clone())
+    }
+}
+
+impl RedisKey for UserSessionsKey {
+    type Value = TokenFamilyId;
+
+    fn inner(&self) -> String {
+        format!("user_sessions:{}", self.0.inner_ref())
+    }
+}
+
+impl RedisKey for RevokedTokenKey {
+    type Value = Revoked;
+
+    fn inner(&self) -> String {
+        match self {
+            Self::Token(jti) => format!("revoked_token:{jti}"),
+            Self::Family(family_id) => format!("revoked_family:{}", family_id.0),
+        }
+    }
+}
+
+impl TryFrom<String> for Revoked {
+    type Error = RedisValueError;
+
+    fn try_from(_: String) -> Result<Self, Self::Error> {
+        Ok(Self)
+    }
+}
+
+impl RedisValue for Revoked {
+    fn inner(&self) -> String {
+        "1".into()
+    }
+}
+
+fn parse_user_id(s: &str) -> Result<UserId, RedisValueError> {
+    UserId::try_from(
+        s.parse::<uuid::Uuid>()
+            .map_err(|e| RedisValueError::ParsingError(Box::new(e)))?,
+    )
+    .map_err(|e| RedisValueError::ParsingError(Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authorized_user_id_round_trips_with_family() {
+        let user_id = UserId::new(uuid::Uuid::new_v4());
+        let family_id = TokenFamilyId::generate();
+        let value = AuthorizedUserId::new(user_id.clone(), family_id);
+
+        let parsed = AuthorizedUserId::try_from(value.inner()).unwrap();
+        assert_eq!(parsed.family_id(), Some(family_id));
+        assert_eq!(parsed.into_inner(), user_id);
+    }
+
+    #[test]
+    fn authorized_user_id_accepts_legacy_value() {
+        let user_id = UserId::new(uuid::Uuid::new_v4());
+
+        let parsed = AuthorizedUserId::try_from(user_id.inner_ref().to_string()).unwrap();
+        assert_eq!(parsed.family_id(), None);
+        assert_eq!(parsed.into_inner(), user_id);
+    }
+
+    #[test]
+    fn token_family_round_trips() {
+        let family = TokenFamily {
+            user_id: UserId::new(uuid::Uuid::new_v4()),
+            access_token: AccessToken::new("a".repeat(32)),
+            refresh_token: RefreshToken::new("b".repeat(32)),
+            client: SessionClient {
+                user_agent: Some("Mozilla/5.0 (X11; Linux x86_64)".into()),
+                ip_address: Some("::1".into()),
+            },
+            created_at: Utc::now(),
+        };
+
+        assert_eq!(TokenFamily::try_from(family.inner()).unwrap(), family);
+        assert!(TokenFamily::try_from("broken".to_string()).is_err());
     }
 }
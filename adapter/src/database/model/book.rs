@@ -1,5 +1,8 @@
 use kernel::model::{
-    book::{AuthorError, Book, BookIdError, Checkout, DescriptionError, IsbnError, TitleError},
+    book::{
+        AuthorError, Book, BookIdError, Checkout, DescriptionError, Isbn, IsbnError, TitleError,
+    },
+    book_copy::{BookCopyCount, BookCopyIdError},
     checkout::CheckoutIdError,
     user::{BookOwner, CheckoutUser, UserIdError, UserNameError},
 };
@@ -11,7 +14,7 @@ pub struct BookRow {
     pub book_id: Uuid,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Option<String>,
     pub description: String,
     pub owner_id: Uuid,
     pub owner_name: String,
@@ -42,7 +45,11 @@ pub enum BookRowError {
 }
 
 impl BookRow {
-    pub fn try_into_book(self, checkout: Option<Checkout>) -> Result<Book, BookRowError> {
+    pub fn try_into_book(
+        self,
+        copy_count: BookCopyCount,
+        checkouts: Vec<Checkout>,
+    ) -> Result<Book, BookRowError> {
         let BookRow {
             book_id,
             title,
@@ -62,25 +69,41 @@ impl BookRow {
             book_id.try_into()?,
             title.try_into()?,
             author.try_into()?,
-            isbn.try_into()?,
+            isbn.map(Isbn::try_from).transpose()?,
             description.try_into()?,
             book_owner,
-            checkout,
+            copy_count,
+            checkouts,
         ))
     }
 }
 
+#[derive(sqlx::FromRow)]
 pub struct PagenatedBookRow {
     pub total: i64,
     pub book_id: Uuid,
 }
 
+#[derive(sqlx::FromRow)]
+pub struct BookCursorRow {
+    pub book_id: Uuid,
+    pub sort_key: Option<String>,
+}
+
+pub struct BookCopyCountRow {
+    pub book_id: Uuid,
+    pub total: i64,
+    pub available: i64,
+}
+
 pub struct BookCheckoutRow {
     pub checkout_id: Uuid,
     pub book_id: Uuid,
+    pub book_copy_id: Uuid,
     pub user_id: Uuid,
     pub user_name: String,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
 }
 
 impl TryFrom<BookCheckoutRow> for Checkout {
@@ -90,18 +113,22 @@ impl TryFrom<BookCheckoutRow> for Checkout {
         BookCheckoutRow {
             checkout_id,
             book_id: _,
+            book_copy_id,
             user_id,
             user_name,
             checked_out_at,
+            due_at,
         }: BookCheckoutRow,
     ) -> Result<Self, Self::Error> {
         Ok(Checkout {
             checkout_id: checkout_id.try_into()?,
+            book_copy_id: book_copy_id.try_into()?,
             checked_out_by: CheckoutUser {
                 user_id: user_id.try_into()?,
                 user_name: user_name.try_into()?,
             },
             checked_out_at,
+            due_at,
         })
     }
 }
@@ -111,6 +138,9 @@ pub enum BookCheckoutRowError {
     #[error("saved book checkout id is invalid: {0}")]
     InvalidBookCheckoutId(#[from] CheckoutIdError),
 
+    #[error("saved book checkout copy id is invalid: {0}")]
+    InvalidBookCheckoutCopyId(#[from] BookCopyIdError),
+
     #[error("saved book checkout user id is invalid: {0}")]
     InvalidBookCheckoutUserId(#[from] UserIdError),
 
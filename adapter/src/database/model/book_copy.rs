@@ -0,0 +1,141 @@
+use kernel::model::{
+    book::{BookIdError, Checkout},
+    book_copy::{
+        BarcodeError, BookCopy, BookCopyId, BookCopyIdError, CopyCondition, CopyLocationError,
+    },
+    checkout::CheckoutIdError,
+    user::{CheckoutUser, UserIdError, UserNameError},
+};
+use sqlx::types::chrono::{DateTime, Utc};
+use strum::{Display, EnumString};
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Debug, EnumString, Display)]
+pub enum CopyConditionName {
+    New,
+    Good,
+    Worn,
+    Damaged,
+}
+
+impl From<CopyConditionName> for CopyCondition {
+    fn from(value: CopyConditionName) -> Self {
+        match value {
+            CopyConditionName::New => CopyCondition::New,
+            CopyConditionName::Good => CopyCondition::Good,
+            CopyConditionName::Worn => CopyCondition::Worn,
+            CopyConditionName::Damaged => CopyCondition::Damaged,
+        }
+    }
+}
+
+impl From<CopyCondition> for CopyConditionName {
+    fn from(value: CopyCondition) -> Self {
+        match value {
+            CopyCondition::New => CopyConditionName::New,
+            CopyCondition::Good => CopyConditionName::Good,
+            CopyCondition::Worn => CopyConditionName::Worn,
+            CopyCondition::Damaged => CopyConditionName::Damaged,
+        }
+    }
+}
+
+// 貸出中の冊は checkout_ で始まる列と checked_out_at, due_at が Some になる
+pub struct BookCopyRow {
+    pub book_copy_id: Uuid,
+    pub book_id: Uuid,
+    pub barcode: String,
+    pub condition: String,
+    pub location: String,
+    pub checkout_id: Option<Uuid>,
+    pub checkout_user_id: Option<Uuid>,
+    pub checkout_user_name: Option<String>,
+    pub checked_out_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<BookCopyRow> for BookCopy {
+    type Error = BookCopyRowError;
+
+    fn try_from(value: BookCopyRow) -> Result<Self, Self::Error> {
+        let BookCopyRow {
+            book_copy_id,
+            book_id,
+            barcode,
+            condition,
+            location,
+            checkout_id,
+            checkout_user_id,
+            checkout_user_name,
+            checked_out_at,
+            due_at,
+        } = value;
+
+        let book_copy_id: BookCopyId = book_copy_id.try_into()?;
+        let checkout = match (
+            checkout_id,
+            checkout_user_id,
+            checkout_user_name,
+            checked_out_at,
+            due_at,
+        ) {
+            (
+                Some(checkout_id),
+                Some(user_id),
+                Some(user_name),
+                Some(checked_out_at),
+                Some(due_at),
+            ) => Some(Checkout {
+                checkout_id: checkout_id.try_into()?,
+                book_copy_id: book_copy_id.clone(),
+                checked_out_by: CheckoutUser {
+                    user_id: user_id.try_into()?,
+                    user_name: user_name.try_into()?,
+                },
+                checked_out_at,
+                due_at,
+            }),
+            _ => None,
+        };
+
+        Ok(BookCopy::new(
+            book_copy_id,
+            book_id.try_into()?,
+            barcode.try_into()?,
+            condition
+                .parse::<CopyConditionName>()
+                .map_err(|_| BookCopyRowError::InvalidCondition(condition))?
+                .into(),
+            location.try_into()?,
+            checkout,
+        ))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum BookCopyRowError {
+    #[error("saved book copy id is invalid: {0}")]
+    InvalidBookCopyId(#[from] BookCopyIdError),
+
+    #[error("saved book id is invalid: {0}")]
+    InvalidBookId(#[from] BookIdError),
+
+    #[error("saved barcode is invalid: {0}")]
+    InvalidBarcode(#[from] BarcodeError),
+
+    #[error("saved condition is invalid: {0}")]
+    InvalidCondition(String),
+
+    #[error("saved location is invalid: {0}")]
+    InvalidLocation(#[from] CopyLocationError),
+
+    #[error("saved checkout id is invalid: {0}")]
+    InvalidCheckoutId(#[from] CheckoutIdError),
+
+    #[error("saved checkout user id is invalid: {0}")]
+    InvalidCheckoutUserId(#[from] UserIdError),
+
+    #[error("saved checkout user name is invalid: {0}")]
+    InvalidCheckoutUserName(#[from] UserNameError),
+}
@@ -1,12 +1,16 @@
 use kernel::model::{
-    book::{AuthorError, BookIdError, IsbnError, TitleError},
-    checkout::{Checkout, CheckoutBook, CheckoutIdError},
+    book::{AuthorError, BookIdError, Isbn, IsbnError, TitleError},
+    book_copy::{BarcodeError, BookCopyIdError},
+    checkout::{policy::BorrowerStanding, Checkout, CheckoutBook, CheckoutIdError},
     user::UserIdError,
 };
+
 use sqlx::types::chrono::{DateTime, Utc};
 use thiserror::Error;
 use uuid::Uuid;
 
+use super::user::UserRoleName;
+
 // 貸出状態を確認するための型
 // 蔵書が存在する場合はこの型にはまるレコードが存在する
 // 貸出中の場合は checkout_id と user_id がSomeになる
@@ -17,14 +21,61 @@ pub(crate) struct CheckoutStateRow {
     pub user_id: Option<Uuid>,
 }
 
+// 貸出規則の判断に使う、借りようとしている利用者の状況
+pub(crate) struct BorrowerStandingRow {
+    pub role_name: String,
+    pub active_loans: i64,
+    pub overdue_loans: i64,
+    pub owns_book: bool,
+}
+
+impl TryFrom<BorrowerStandingRow> for BorrowerStanding {
+    type Error = strum::ParseError;
+
+    fn try_from(value: BorrowerStandingRow) -> Result<Self, Self::Error> {
+        let BorrowerStandingRow {
+            role_name,
+            active_loans,
+            overdue_loans,
+            owns_book,
+        } = value;
+
+        Ok(BorrowerStanding {
+            role: role_name.parse::<UserRoleName>()?.into(),
+            active_loans,
+            overdue_loans,
+            owns_book,
+        })
+    }
+}
+
+// 延長の可否を判断するための貸出の状態
+pub(crate) struct RenewalStateRow {
+    pub user_id: Uuid,
+    pub role_name: String,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
+}
+
+// 冊の貸出状態を確認するための型
+// 貸出中の場合は checkout_id が Some になる
+pub(crate) struct BookCopyStateRow {
+    pub book_copy_id: Uuid,
+    pub checkout_id: Option<Uuid>,
+}
+
 pub(crate) struct CheckoutRow {
     pub checkout_id: Uuid,
     pub user_id: Uuid,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
     pub book_id: Uuid,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Option<String>,
+    pub book_copy_id: Uuid,
+    pub barcode: String,
 }
 
 impl TryFrom<CheckoutRow> for Checkout {
@@ -35,22 +86,30 @@ impl TryFrom<CheckoutRow> for Checkout {
             checkout_id,
             user_id,
             checked_out_at,
+            due_at,
+            renewal_count,
             book_id,
             title,
             author,
             isbn,
+            book_copy_id,
+            barcode,
         } = value;
 
         Ok(Checkout::new(
             checkout_id.try_into()?,
             user_id.try_into()?,
             checked_out_at,
+            due_at,
+            renewal_count,
             None,
             CheckoutBook::new(
                 book_id.try_into()?,
                 title.try_into()?,
                 author.try_into()?,
-                isbn.try_into()?,
+                isbn.map(Isbn::try_from).transpose()?,
+                book_copy_id.try_into()?,
+                barcode.try_into()?,
             ),
         ))
     }
@@ -75,17 +134,27 @@ pub enum CheckoutRowError {
 
     #[error("saved isbn is invalid: {0}")]
     InvalidIsbn(#[from] IsbnError),
+
+    #[error("saved book copy id is invalid: {0}")]
+    InvalidBookCopyId(#[from] BookCopyIdError),
+
+    #[error("saved barcode is invalid: {0}")]
+    InvalidBarcode(#[from] BarcodeError),
 }
 
 pub(crate) struct ReturnedCheckoutRow {
     pub checkout_id: Uuid,
     pub user_id: Uuid,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
     pub returned_at: DateTime<Utc>,
     pub book_id: Uuid,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Option<String>,
+    pub book_copy_id: Uuid,
+    pub barcode: String,
 }
 
 impl TryFrom<ReturnedCheckoutRow> for Checkout {
@@ -96,23 +165,31 @@ impl TryFrom<ReturnedCheckoutRow> for Checkout {
             checkout_id,
             user_id,
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at,
             book_id,
             title,
             author,
             isbn,
+            book_copy_id,
+            barcode,
         } = value;
 
         Ok(Checkout::new(
             checkout_id.try_into()?,
             user_id.try_into()?,
             checked_out_at,
+            due_at,
+            renewal_count,
             Some(returned_at),
             CheckoutBook::new(
                 book_id.try_into()?,
                 title.try_into()?,
                 author.try_into()?,
-                isbn.try_into()?,
+                isbn.map(Isbn::try_from).transpose()?,
+                book_copy_id.try_into()?,
+                barcode.try_into()?,
             ),
         ))
     }
@@ -137,4 +214,10 @@ pub enum ReturnedCheckoutRowError {
 
     #[error("saved isbn is invalid: {0}")]
     InvalidIsbn(#[from] IsbnError),
+
+    #[error("saved book copy id is invalid: {0}")]
+    InvalidBookCopyId(#[from] BookCopyIdError),
+
+    #[error("saved barcode is invalid: {0}")]
+    InvalidBarcode(#[from] BarcodeError),
 }
@@ -0,0 +1,86 @@
+use kernel::model::{
+    user::{email_change::EmailChangeToken, UserEmail, UserId},
+    value_object::ValueObject,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::redis::model::{RedisKey, RedisValue, RedisValueError};
+
+// 確認用のトークン -> 変更を申し込んだユーザーと新しいメールアドレス
+pub struct EmailChangeKey(EmailChangeToken);
+
+#[derive(Serialize, Deserialize)]
+pub struct PendingEmailChange {
+    pub user_id: uuid::Uuid,
+    pub new_email: String,
+}
+
+// ユーザー ID -> 最後に発行した確認用のトークン。
+// 新しく申し込んだときに、以前のトークンを無効にするために使う
+pub struct UserEmailChangeKey(UserId);
+pub struct IssuedEmailChangeToken(pub EmailChangeToken);
+
+impl From<&EmailChangeToken> for EmailChangeKey {
+    fn from(token: &EmailChangeToken) -> Self {
+        Self(token.clone())
+    }
+}
+
+impl RedisKey for EmailChangeKey {
+    type Value = PendingEmailChange;
+
+    fn inner(&self) -> String {
+        format!("email_change:{}", self.0.inner_ref())
+    }
+}
+
+impl PendingEmailChange {
+    pub fn new(user_id: &UserId, new_email: &UserEmail) -> Self {
+        Self {
+            user_id: *user_id.inner_ref(),
+            new_email: new_email.to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for PendingEmailChange {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        serde_json::from_str(&s).map_err(|e| RedisValueError::ParsingError(Box::new(e)))
+    }
+}
+
+impl RedisValue for PendingEmailChange {
+    fn inner(&self) -> String {
+        serde_json::to_string(self).expect("pending email change must be serializable")
+    }
+}
+
+impl From<&UserId> for UserEmailChangeKey {
+    fn from(user_id: &UserId) -> Self {
+        Self(user_id.clone())
+    }
+}
+
+impl RedisKey for UserEmailChangeKey {
+    type Value = IssuedEmailChangeToken;
+
+    fn inner(&self) -> String {
+        format!("user_email_change:{}", self.0.inner_ref())
+    }
+}
+
+impl TryFrom<String> for IssuedEmailChangeToken {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(Self(EmailChangeToken::new(s)))
+    }
+}
+
+impl RedisValue for IssuedEmailChangeToken {
+    fn inner(&self) -> String {
+        self.0.inner_ref().to_string()
+    }
+}
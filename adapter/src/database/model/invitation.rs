@@ -0,0 +1,54 @@
+use kernel::model::{
+    invitation::{Invitation, InvitationIdError},
+    user::{UserEmail, UserEmailError, UserId},
+};
+use sqlx::types::chrono::{DateTime, Utc};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::user::UserRoleName;
+
+pub struct InvitationRow {
+    pub invitation_id: Uuid,
+    pub email: String,
+    pub role_name: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<InvitationRow> for Invitation {
+    type Error = InvitationRowError;
+
+    fn try_from(value: InvitationRow) -> Result<Self, Self::Error> {
+        let InvitationRow {
+            invitation_id,
+            email,
+            role_name,
+            invited_by,
+            expires_at,
+            created_at,
+        } = value;
+
+        Ok(Invitation::new(
+            invitation_id.try_into()?,
+            email.parse::<UserEmail>()?,
+            role_name.parse::<UserRoleName>()?.into(),
+            invited_by.map(UserId::new),
+            expires_at,
+            created_at,
+        ))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum InvitationRowError {
+    #[error("saved invitation id is invalid: {0}")]
+    InvalidInvitationId(#[from] InvitationIdError),
+
+    #[error("saved invitation email is invalid: {0}")]
+    InvalidEmail(#[from] UserEmailError),
+
+    #[error("saved invitation role is invalid: {0}")]
+    InvalidRole(#[from] strum::ParseError),
+}
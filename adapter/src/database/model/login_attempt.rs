@@ -0,0 +1,78 @@
+use kernel::model::user::UserEmail;
+
+use crate::redis::model::{RedisKey, RedisValue, RedisValueError};
+
+// ログインの失敗を数える単位
+#[derive(Clone)]
+pub enum LoginSubject {
+    Email(String),
+    IpAddress(String),
+}
+
+// 連続で失敗した回数
+pub struct LoginFailureCountKey(LoginSubject);
+// ロック中であることを表すキー。有効期間がロックの残り時間になる
+pub struct LoginLockoutKey(LoginSubject);
+pub struct LoginFailureCount(pub u64);
+
+impl LoginSubject {
+    // 大文字・小文字の違いでロックを回避されないよう小文字にそろえる
+    pub fn email(email: &UserEmail) -> Self {
+        Self::Email(email.to_string().to_lowercase())
+    }
+
+    pub fn ip_address(ip_address: &str) -> Self {
+        Self::IpAddress(ip_address.to_string())
+    }
+
+    fn key(&self) -> String {
+        match self {
+            Self::Email(email) => format!("email:{email}"),
+            Self::IpAddress(ip_address) => format!("ip:{ip_address}"),
+        }
+    }
+}
+
+impl From<LoginSubject> for LoginFailureCountKey {
+    fn from(subject: LoginSubject) -> Self {
+        Self(subject)
+    }
+}
+
+impl RedisKey for LoginFailureCountKey {
+    type Value = LoginFailureCount;
+
+    fn inner(&self) -> String {
+        format!("login_failures:{}", self.0.key())
+    }
+}
+
+impl From<LoginSubject> for LoginLockoutKey {
+    fn from(subject: LoginSubject) -> Self {
+        Self(subject)
+    }
+}
+
+impl RedisKey for LoginLockoutKey {
+    type Value = LoginFailureCount;
+
+    fn inner(&self) -> String {
+        format!("login_lockout:{}", self.0.key())
+    }
+}
+
+impl TryFrom<String> for LoginFailureCount {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse::<u64>()
+            .map(Self)
+            .map_err(|e| RedisValueError::ParsingError(Box::new(e)))
+    }
+}
+
+impl RedisValue for LoginFailureCount {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
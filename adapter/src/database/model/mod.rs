@@ -1,4 +1,13 @@
+pub mod api_key;
 pub mod auth;
 pub mod book;
+pub mod book_copy;
 pub mod checkout;
+pub mod email_change;
+pub mod invitation;
+pub mod login_attempt;
+pub mod oidc;
+pub mod password_reset;
+pub mod reservation;
+pub mod totp;
 pub mod user;
@@ -0,0 +1,43 @@
+use kernel::model::{auth::oidc::OidcState, value_object::ValueObject};
+use serde::{Deserialize, Serialize};
+
+use crate::redis::model::{RedisKey, RedisValue, RedisValueError};
+
+// IdP へのリダイレクトから戻るのを待っているログイン
+pub struct OidcLoginKey(OidcState);
+
+#[derive(Serialize, Deserialize)]
+pub struct OidcLoginState {
+    // ID トークンに含まれる nonce と照合する
+    pub nonce: String,
+    // 認可コードの交換時に送る PKCE の検証値
+    pub code_verifier: String,
+}
+
+impl From<&OidcState> for OidcLoginKey {
+    fn from(state: &OidcState) -> Self {
+        Self(state.clone())
+    }
+}
+
+impl RedisKey for OidcLoginKey {
+    type Value = OidcLoginState;
+
+    fn inner(&self) -> String {
+        format!("oidc_login:{}", self.0.inner_ref())
+    }
+}
+
+impl TryFrom<String> for OidcLoginState {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        serde_json::from_str(&s).map_err(|e| RedisValueError::ParsingError(Box::new(e)))
+    }
+}
+
+impl RedisValue for OidcLoginState {
+    fn inner(&self) -> String {
+        serde_json::to_string(self).expect("oidc login state must be serializable")
+    }
+}
@@ -0,0 +1,73 @@
+use kernel::model::{
+    auth::password_reset::PasswordResetToken, user::UserId, value_object::ValueObject,
+};
+
+use crate::redis::model::{RedisKey, RedisValue, RedisValueError};
+
+// 再設定用のトークン -> ユーザー ID
+pub struct PasswordResetKey(PasswordResetToken);
+pub struct PasswordResetUserId(pub UserId);
+
+// ユーザー ID -> 最後に発行した再設定用のトークン。
+// 新しく発行したときに、以前のトークンを無効にするために使う
+pub struct UserPasswordResetKey(UserId);
+pub struct IssuedPasswordResetToken(pub PasswordResetToken);
+
+impl From<&PasswordResetToken> for PasswordResetKey {
+    fn from(token: &PasswordResetToken) -> Self {
+        Self(token.clone())
+    }
+}
+
+impl RedisKey for PasswordResetKey {
+    type Value = PasswordResetUserId;
+
+    fn inner(&self) -> String {
+        format!("password_reset:{}", self.0.inner_ref())
+    }
+}
+
+impl TryFrom<String> for PasswordResetUserId {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let user_id = s
+            .parse::<uuid::Uuid>()
+            .map_err(|e| RedisValueError::ParsingError(Box::new(e)))?;
+        Ok(Self(UserId::new(user_id)))
+    }
+}
+
+impl RedisValue for PasswordResetUserId {
+    fn inner(&self) -> String {
+        self.0.inner_ref().to_string()
+    }
+}
+
+impl From<&UserId> for UserPasswordResetKey {
+    fn from(user_id: &UserId) -> Self {
+        Self(user_id.clone())
+    }
+}
+
+impl RedisKey for UserPasswordResetKey {
+    type Value = IssuedPasswordResetToken;
+
+    fn inner(&self) -> String {
+        format!("user_password_reset:{}", self.0.inner_ref())
+    }
+}
+
+impl TryFrom<String> for IssuedPasswordResetToken {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(Self(PasswordResetToken::new(s)))
+    }
+}
+
+impl RedisValue for IssuedPasswordResetToken {
+    fn inner(&self) -> String {
+        self.0.inner_ref().to_string()
+    }
+}
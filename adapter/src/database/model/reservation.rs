@@ -0,0 +1,85 @@
+use kernel::model::{
+    book::BookIdError,
+    reservation::{Reservation, ReservationIdError, ReservationStatus},
+    user::UserIdError,
+};
+use sqlx::types::chrono::{DateTime, Utc};
+use thiserror::Error;
+use uuid::Uuid;
+
+pub struct ReservationRow {
+    pub reservation_id: Uuid,
+    pub book_id: Uuid,
+    pub user_id: Uuid,
+    pub reserved_at: DateTime<Utc>,
+    pub position: i64,
+    pub ready_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ReservationRow> for Reservation {
+    type Error = ReservationRowError;
+
+    fn try_from(value: ReservationRow) -> Result<Self, Self::Error> {
+        let ReservationRow {
+            reservation_id,
+            book_id,
+            user_id,
+            reserved_at,
+            position,
+            ready_at,
+            expires_at,
+        } = value;
+
+        let status = match (ready_at, expires_at) {
+            (Some(ready_at), Some(expires_at)) => ReservationStatus::ReadyForPickup {
+                ready_at,
+                expires_at,
+            },
+            _ => ReservationStatus::Waiting,
+        };
+
+        Ok(Reservation::new(
+            reservation_id.try_into()?,
+            book_id.try_into()?,
+            user_id.try_into()?,
+            reserved_at,
+            position,
+            status,
+        ))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ReservationRowError {
+    #[error("saved reservation id is invalid: {0}")]
+    InvalidReservationId(#[from] ReservationIdError),
+
+    #[error("saved book id is invalid: {0}")]
+    InvalidBookId(#[from] BookIdError),
+
+    #[error("saved user id is invalid: {0}")]
+    InvalidUserId(#[from] UserIdError),
+}
+
+// 蔵書の貸出可能数と予約の待ち行列の状態
+pub(crate) struct HoldQueueStateRow {
+    // 貸出中でない冊の数
+    pub available: i64,
+    // 予約の件数
+    pub queued: i64,
+    // 利用者自身が予約している場合、その前に並んでいる予約の件数
+    pub ahead: i64,
+    pub reserved: bool,
+}
+
+impl HoldQueueStateRow {
+    // 取り置かれている冊は、待ち行列の先頭から順に割り当てる
+    pub fn allows_checkout(&self) -> bool {
+        if self.reserved {
+            self.ahead < self.available
+        } else {
+            self.queued < self.available
+        }
+    }
+}
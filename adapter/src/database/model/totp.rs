@@ -0,0 +1,99 @@
+use std::str::FromStr;
+
+use kernel::model::{
+    auth::totp::{LoginChallenge, LoginChallengeToken},
+    user::{UserEmail, UserId},
+    value_object::ValueObject,
+};
+use serde::{Deserialize, Serialize};
+
+use super::login_attempt::LoginFailureCount;
+use crate::redis::model::{RedisKey, RedisValue, RedisValueError};
+
+// 二要素目の入力を待っているログイン
+pub struct LoginChallengeKey(LoginChallengeToken);
+pub struct StoredLoginChallenge(pub LoginChallenge);
+// 二要素目を間違えた回数
+pub struct LoginChallengeFailureKey(LoginChallengeToken);
+
+impl From<&LoginChallengeToken> for LoginChallengeKey {
+    fn from(challenge_token: &LoginChallengeToken) -> Self {
+        Self(challenge_token.clone())
+    }
+}
+
+impl RedisKey for LoginChallengeKey {
+    type Value = StoredLoginChallenge;
+
+    fn inner(&self) -> String {
+        format!("login_challenge:{}", self.0.inner_ref())
+    }
+}
+
+impl From<&LoginChallengeToken> for LoginChallengeFailureKey {
+    fn from(challenge_token: &LoginChallengeToken) -> Self {
+        Self(challenge_token.clone())
+    }
+}
+
+impl RedisKey for LoginChallengeFailureKey {
+    type Value = LoginFailureCount;
+
+    fn inner(&self) -> String {
+        format!("login_challenge_failures:{}", self.0.inner_ref())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct LoginChallengeRecord {
+    user_id: uuid::Uuid,
+    email: String,
+    setup_required: bool,
+}
+
+impl TryFrom<String> for StoredLoginChallenge {
+    type Error = RedisValueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let record: LoginChallengeRecord =
+            serde_json::from_str(&s).map_err(|e| RedisValueError::ParsingError(Box::new(e)))?;
+        Ok(Self(LoginChallenge {
+            user_id: UserId::new(record.user_id),
+            email: UserEmail::from_str(&record.email)
+                .map_err(|e| RedisValueError::ParsingError(Box::new(e)))?,
+            setup_required: record.setup_required,
+        }))
+    }
+}
+
+impl RedisValue for StoredLoginChallenge {
+    fn inner(&self) -> String {
+        let record = LoginChallengeRecord {
+            user_id: *self.0.user_id.inner_ref(),
+            email: self.0.email.to_string(),
+            setup_required: self.0.setup_required,
+        };
+        serde_json::to_string(&record).expect("login challenge must be serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_challenge_round_trips() {
+        let challenge = LoginChallenge {
+            user_id: UserId::new(uuid::Uuid::new_v4()),
+            email: UserEmail::from_str("alice@example.com").unwrap(),
+            setup_required: true,
+        };
+
+        let StoredLoginChallenge(parsed) =
+            StoredLoginChallenge::try_from(StoredLoginChallenge(challenge.clone()).inner())
+                .unwrap();
+        assert_eq!(parsed.user_id, challenge.user_id);
+        assert_eq!(parsed.email, challenge.email);
+        assert!(parsed.setup_required);
+    }
+}
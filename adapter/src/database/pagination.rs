@@ -0,0 +1,13 @@
+use kernel::model::list::Cursor;
+
+// キーセット方式のページングでは、続きの有無を判定するため limit + 1 件を取得する。
+// 余分に取得した 1 件を取り除き、続きがある場合は最後の要素を指すカーソルを返す。
+pub(crate) fn take_page<R>(
+    rows: &mut Vec<R>,
+    limit: i64,
+    cursor_of: impl Fn(&R) -> Cursor,
+) -> Option<Cursor> {
+    let has_next = rows.len() as i64 > limit;
+    rows.truncate(limit.max(0) as usize);
+    rows.last().filter(|_| has_next).map(cursor_of)
+}
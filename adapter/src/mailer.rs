@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use kernel::{
+    model::mail::Mail,
+    repository::mailer::{Mailer, MailerError, MailerResult},
+};
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use shared::config::{MailConfig, MailTransportConfig};
+use thiserror::Error;
+
+// 設定に応じて SMTP・ファイル・ログのいずれかにメールを送る Mailer
+pub struct MailerImpl {
+    from: Mailbox,
+    transport: Transport,
+}
+
+enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    File(AsyncFileTransport<Tokio1Executor>),
+    Console,
+}
+
+#[derive(Debug, Error)]
+pub enum MailerConfigError {
+    #[error("invalid MAIL_FROM: {0}")]
+    InvalidFrom(#[from] lettre::address::AddressError),
+
+    #[error("invalid SMTP_URL: {0}")]
+    InvalidSmtpUrl(#[from] lettre::transport::smtp::Error),
+}
+
+impl MailerImpl {
+    pub fn new(config: &MailConfig) -> Result<Self, MailerConfigError> {
+        let transport = match &config.transport {
+            MailTransportConfig::Smtp { url } => {
+                Transport::Smtp(AsyncSmtpTransport::<Tokio1Executor>::from_url(url)?.build())
+            }
+            MailTransportConfig::File { dir } => Transport::File(AsyncFileTransport::new(dir)),
+            MailTransportConfig::Console => Transport::Console,
+        };
+        Ok(Self {
+            from: config.from.parse()?,
+            transport,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for MailerImpl {
+    async fn send(&self, mail: Mail) -> MailerResult<()> {
+        let to = mail
+            .to
+            .to_string()
+            .parse::<Mailbox>()
+            .map_err(|e| MailerError::InvalidMail(Box::new(e)))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(mail.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body)
+            .map_err(|e| MailerError::InvalidMail(Box::new(e)))?;
+
+        match &self.transport {
+            Transport::Smtp(transport) => {
+                transport
+                    .send(message)
+                    .await
+                    .map_err(|e| MailerError::Transport(Box::new(e)))?;
+            }
+            Transport::File(transport) => {
+                transport
+                    .send(message)
+                    .await
+                    .map_err(|e| MailerError::Transport(Box::new(e)))?;
+            }
+            Transport::Console => {
+                tracing::info!(
+                    message = %String::from_utf8_lossy(&message.formatted()),
+                    "mail is written to the log instead of being sent",
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_transport_writes_eml() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("mailer-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir)?;
+        let mailer = MailerImpl::new(&MailConfig {
+            from: "Library <no-reply@example.com>".into(),
+            link_base_url: "http://localhost:8080".into(),
+            transport: MailTransportConfig::File {
+                dir: dir.to_string_lossy().into_owned(),
+            },
+        })?;
+
+        mailer
+            .send(Mail {
+                to: "alice@example.com".parse()?,
+                subject: "Hello".into(),
+                body: "See you at the library.".into(),
+            })
+            .await?;
+
+        let files = std::fs::read_dir(&dir)?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(files.len(), 1);
+        let eml = std::fs::read_to_string(files[0].path())?;
+        assert!(eml.contains("To: alice@example.com"));
+        assert!(eml.contains("Subject: Hello"));
+        assert!(eml.contains("See you at the library."));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,605 @@
+// OpenID Connect の認可コードフロー（PKCE）で IdP とやり取りする。
+// ディスカバリーの結果と JWKS はメモリにキャッシュする
+use std::{
+    str::FromStr,
+    sync::{Arc, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
+
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::{
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use kernel::model::user::{UserEmail, UserRole};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use shared::config::OidcConfig;
+
+const DISCOVERY_TTL: Duration = Duration::from_secs(3600);
+const JWKS_TTL: Duration = Duration::from_secs(3600);
+// 未知の kid の ID トークンを受け取っても、JWKS はこの間隔より短く取り直さない
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+// IdP との時計のずれとして許容する秒数
+const CLOCK_SKEW_SECONDS: u64 = 60;
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+const STATE_CHARS: usize = 32;
+// RFC 7636 では 43 文字以上 128 文字以下
+const CODE_VERIFIER_CHARS: usize = 64;
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+struct Cached<T> {
+    value: Arc<T>,
+    fetched_at: Instant,
+}
+
+// ID トークンから取り出した利用者の情報
+#[derive(Debug, Clone)]
+pub struct OidcIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub email: UserEmail,
+    pub email_verified: bool,
+    pub name: Option<String>,
+    // ロールを決めるクレームを設定していない場合は None
+    pub role: Option<UserRole>,
+}
+
+pub struct OidcClient {
+    config: OidcConfig,
+    http: reqwest::Client,
+    discovery: RwLock<Option<Cached<ProviderMetadata>>>,
+    jwks: RwLock<Option<Cached<JwkSet>>>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig) -> OidcResult<Self> {
+        let http = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?;
+        Ok(Self {
+            config,
+            http,
+            discovery: RwLock::new(None),
+            jwks: RwLock::new(None),
+        })
+    }
+
+    // 利用者を送る IdP の認可エンドポイントの URL を組み立てる
+    pub async fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> OidcResult<String> {
+        let metadata = self.metadata().await?;
+        let code_challenge = code_challenge(code_verifier);
+        let query = [
+            ("response_type", "code"),
+            ("client_id", self.config.client_id.as_str()),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+            ("scope", self.config.scopes.as_str()),
+            ("state", state),
+            ("nonce", nonce),
+            ("code_challenge", code_challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{name}={}", urlencoding::encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+        let separator = if metadata.authorization_endpoint.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        Ok(format!(
+            "{}{separator}{query}",
+            metadata.authorization_endpoint
+        ))
+    }
+
+    // 認可コードを ID トークンと交換する
+    pub async fn exchange_code(&self, code: &str, code_verifier: &str) -> OidcResult<String> {
+        let metadata = self.metadata().await?;
+        let mut request = self.http.post(&metadata.token_endpoint).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+            ("code_verifier", code_verifier),
+            ("client_id", self.config.client_id.as_str()),
+        ]);
+        if let Some(client_secret) = &self.config.client_secret {
+            request = request.basic_auth(&self.config.client_id, Some(client_secret));
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(OidcError::Provider(format!(
+                "token endpoint responded with {status}: {body}"
+            )));
+        }
+
+        Ok(response.json::<TokenResponse>().await?.id_token)
+    }
+
+    // ID トークンの署名・issuer・audience・有効期限・nonce を確認し、利用者の情報を取り出す
+    pub async fn validate_id_token(&self, id_token: &str, nonce: &str) -> OidcResult<OidcIdentity> {
+        let metadata = self.metadata().await?;
+
+        let header = jsonwebtoken::decode_header(id_token).map_err(invalid_id_token)?;
+        // 共通鍵の署名は IdP 以外でも作れてしまうため受け付けない
+        if !is_asymmetric(header.alg) {
+            return Err(OidcError::InvalidIdToken(format!(
+                "unsupported signing algorithm: {:?}",
+                header.alg
+            )));
+        }
+        let jwk = self.find_jwk(&metadata, header.kid.as_deref()).await?;
+        let key = DecodingKey::from_jwk(&jwk).map_err(invalid_id_token)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        validation.leeway = CLOCK_SKEW_SECONDS;
+        let claims = jsonwebtoken::decode::<Map<String, Value>>(id_token, &key, &validation)
+            .map_err(invalid_id_token)?
+            .claims;
+
+        // 別のログインで発行された ID トークンを使い回されないよう、ログインの開始時の nonce と照合する
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            return Err(OidcError::InvalidIdToken("nonce does not match".into()));
+        }
+
+        self.identity(&metadata.issuer, &claims)
+    }
+
+    fn identity(&self, issuer: &str, claims: &Map<String, Value>) -> OidcResult<OidcIdentity> {
+        let claim = |name: &str| {
+            claims
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| OidcError::InvalidIdToken(format!("{name} claim is missing")))
+        };
+        let subject = claim("sub")?.to_string();
+        let email = UserEmail::from_str(claim("email")?).map_err(invalid_id_token)?;
+        // 真偽値の代わりに文字列を返す IdP もある
+        let email_verified = match claims.get("email_verified") {
+            Some(Value::Bool(verified)) => *verified,
+            Some(Value::String(verified)) => verified == "true",
+            _ => false,
+        };
+        let name = claims
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let role = self.config.role_claim.as_deref().map(|role_claim| {
+            let is_admin = claim_values(claims, role_claim)
+                .iter()
+                .any(|value| self.config.admin_role_values.contains(value));
+            if is_admin {
+                UserRole::Admin
+            } else {
+                UserRole::User
+            }
+        });
+
+        Ok(OidcIdentity {
+            issuer: issuer.to_string(),
+            subject,
+            email,
+            email_verified,
+            name,
+            role,
+        })
+    }
+
+    async fn metadata(&self) -> OidcResult<Arc<ProviderMetadata>> {
+        if let Some(metadata) = fresh(&self.discovery, DISCOVERY_TTL) {
+            return Ok(metadata);
+        }
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer_url.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        // 設定した issuer と異なる IdP の応答は信用しない
+        if metadata.issuer.trim_end_matches('/') != self.config.issuer_url.trim_end_matches('/') {
+            return Err(OidcError::Provider(format!(
+                "issuer mismatch: expected {}, got {}",
+                self.config.issuer_url, metadata.issuer
+            )));
+        }
+
+        Ok(store(&self.discovery, metadata))
+    }
+
+    async fn fetch_jwks(&self, metadata: &ProviderMetadata) -> OidcResult<Arc<JwkSet>> {
+        let jwks: JwkSet = self
+            .http
+            .get(&metadata.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(store(&self.jwks, jwks))
+    }
+
+    async fn find_jwk(&self, metadata: &ProviderMetadata, kid: Option<&str>) -> OidcResult<Jwk> {
+        let jwks = match fresh(&self.jwks, JWKS_TTL) {
+            Some(jwks) => jwks,
+            None => self.fetch_jwks(metadata).await?,
+        };
+        if let Some(jwk) = select_jwk(&jwks, kid) {
+            return Ok(jwk);
+        }
+
+        // IdP が鍵をローテーションした直後はキャッシュに新しい鍵がないため、取り直して探す
+        if fresh(&self.jwks, JWKS_MIN_REFRESH_INTERVAL).is_none() {
+            let jwks = self.fetch_jwks(metadata).await?;
+            if let Some(jwk) = select_jwk(&jwks, kid) {
+                return Ok(jwk);
+            }
+        }
+
+        Err(OidcError::InvalidIdToken(format!(
+            "no signing key found for kid {kid:?}"
+        )))
+    }
+}
+
+// ログインの開始時に発行する state と nonce
+pub fn generate_state() -> String {
+    random_chars(STATE_CHARS)
+}
+
+pub fn generate_code_verifier() -> String {
+    random_chars(CODE_VERIFIER_CHARS)
+}
+
+fn random_chars(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+// PKCE の S256 方式のチャレンジ
+fn code_challenge(code_verifier: &str) -> String {
+    BASE64URL_NOPAD.encode(&Sha256::digest(code_verifier.as_bytes()))
+}
+
+fn is_asymmetric(alg: Algorithm) -> bool {
+    !matches!(alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512)
+}
+
+// kid がない ID トークンは、JWKS に鍵が 1 つしかない場合のみその鍵で検証する
+fn select_jwk(jwks: &JwkSet, kid: Option<&str>) -> Option<Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid).cloned(),
+        None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+        None => None,
+    }
+}
+
+// ドット区切りのクレーム名をたどり、文字列または文字列の配列の値を返す
+fn claim_values(claims: &Map<String, Value>, path: &str) -> Vec<String> {
+    let mut segments = path.split('.');
+    let Some(mut value) = segments.next().and_then(|first| claims.get(first)) else {
+        return Vec::new();
+    };
+    for segment in segments {
+        match value.get(segment) {
+            Some(next) => value = next,
+            None => return Vec::new(),
+        }
+    }
+
+    match value {
+        Value::String(value) => vec![value.clone()],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn fresh<T>(cache: &RwLock<Option<Cached<T>>>, ttl: Duration) -> Option<Arc<T>> {
+    let cache = cache.read().unwrap_or_else(PoisonError::into_inner);
+    cache
+        .as_ref()
+        .filter(|cached| cached.fetched_at.elapsed() < ttl)
+        .map(|cached| cached.value.clone())
+}
+
+fn store<T>(cache: &RwLock<Option<Cached<T>>>, value: T) -> Arc<T> {
+    let value = Arc::new(value);
+    *cache.write().unwrap_or_else(PoisonError::into_inner) = Some(Cached {
+        value: value.clone(),
+        fetched_at: Instant::now(),
+    });
+    value
+}
+
+fn invalid_id_token(e: impl std::fmt::Display) -> OidcError {
+    OidcError::InvalidIdToken(e.to_string())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("failed to request the identity provider: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("unexpected response from the identity provider: {0}")]
+    Provider(String),
+
+    #[error("invalid id token: {0}")]
+    InvalidIdToken(String),
+}
+
+pub type OidcResult<T> = Result<T, OidcError>;
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use axum::{
+        extract::State,
+        http::StatusCode,
+        routing::{get, post},
+        Form, Json, Router,
+    };
+    use jsonwebtoken::{EncodingKey, Header};
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    const CLIENT_ID: &str = "book-manager";
+    const CODE: &str = "authorization-code";
+
+    // 認可エンドポイントに渡された値を覚えておき、トークンエンドポイントで ID トークンを返すテスト用の IdP
+    struct MockIdp {
+        issuer: String,
+        encoding_key: EncodingKey,
+        public_key: Vec<u8>,
+        // 認可リクエストの code_challenge と nonce
+        authorization: Mutex<Option<(String, String)>>,
+        claims: Value,
+    }
+
+    async fn start_idp(claims: Value) -> Arc<MockIdp> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let idp = Arc::new(MockIdp {
+            issuer: format!("http://{}", listener.local_addr().unwrap()),
+            encoding_key: EncodingKey::from_ed_der(pkcs8.as_ref()),
+            public_key: key_pair.public_key().as_ref().to_vec(),
+            authorization: Mutex::new(None),
+            claims,
+        });
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(idp.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        idp
+    }
+
+    async fn discovery(State(idp): State<Arc<MockIdp>>) -> Json<Value> {
+        Json(json!({
+            "issuer": idp.issuer,
+            "authorization_endpoint": format!("{}/authorize", idp.issuer),
+            "token_endpoint": format!("{}/token", idp.issuer),
+            "jwks_uri": format!("{}/jwks", idp.issuer),
+        }))
+    }
+
+    async fn jwks(State(idp): State<Arc<MockIdp>>) -> Json<Value> {
+        Json(json!({
+            "keys": [{
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "x": BASE64URL_NOPAD.encode(&idp.public_key),
+                "kid": "key-1",
+                "alg": "EdDSA",
+                "use": "sig",
+            }]
+        }))
+    }
+
+    async fn token(
+        State(idp): State<Arc<MockIdp>>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<Value>, StatusCode> {
+        let (challenge, nonce) = idp.authorization.lock().unwrap().clone().unwrap();
+        if form.get("code").map(String::as_str) != Some(CODE)
+            || form.get("code_verifier").map(|v| code_challenge(v)) != Some(challenge)
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut claims = json!({
+            "iss": idp.issuer,
+            "aud": CLIENT_ID,
+            "sub": "idp-user-1",
+            "iat": now,
+            "exp": now + 300,
+            "nonce": nonce,
+            "email": "dave@example.com",
+            "name": "Dave",
+        });
+        for (name, value) in idp.claims.as_object().unwrap() {
+            claims[name] = value.clone();
+        }
+
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("key-1".into());
+        let id_token = jsonwebtoken::encode(&header, &claims, &idp.encoding_key).unwrap();
+        Ok(Json(
+            json!({ "id_token": id_token, "token_type": "Bearer" }),
+        ))
+    }
+
+    fn client(idp: &MockIdp) -> OidcClient {
+        OidcClient::new(OidcConfig {
+            issuer_url: idp.issuer.clone(),
+            client_id: CLIENT_ID.into(),
+            client_secret: Some("secret".into()),
+            redirect_uri: "http://localhost:8080/auth/oidc/callback".into(),
+            scopes: "openid email profile".into(),
+            role_claim: Some("realm_access.roles".into()),
+            admin_role_values: vec!["library-admin".into()],
+        })
+        .unwrap()
+    }
+
+    // ブラウザが認可エンドポイントにアクセスしたときの代わりに、URL の値を IdP に渡す
+    fn authorize(idp: &MockIdp, authorization_url: &str) -> HashMap<String, String> {
+        let (_, query) = authorization_url.split_once('?').unwrap();
+        let params: HashMap<String, String> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    urlencoding::decode(value).unwrap().into_owned(),
+                )
+            })
+            .collect();
+        *idp.authorization.lock().unwrap() =
+            Some((params["code_challenge"].clone(), params["nonce"].clone()));
+        params
+    }
+
+    #[tokio::test]
+    async fn completes_authorization_code_flow_with_pkce() {
+        let idp = start_idp(json!({
+            "email_verified": "true",
+            "realm_access": { "roles": ["staff", "library-admin"] },
+        }))
+        .await;
+        let client = client(&idp);
+        let code_verifier = generate_code_verifier();
+
+        let url = client
+            .authorization_url("state-1", "nonce-1", &code_verifier)
+            .await
+            .unwrap();
+        assert!(url.starts_with(&format!("{}/authorize?", idp.issuer)));
+        let params = authorize(&idp, &url);
+        assert_eq!(params["state"], "state-1");
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["scope"], "openid email profile");
+
+        let id_token = client.exchange_code(CODE, &code_verifier).await.unwrap();
+        let identity = client
+            .validate_id_token(&id_token, "nonce-1")
+            .await
+            .unwrap();
+        assert_eq!(identity.issuer, idp.issuer);
+        assert_eq!(identity.subject, "idp-user-1");
+        assert_eq!(identity.email.to_string(), "dave@example.com");
+        assert!(identity.email_verified);
+        assert_eq!(identity.name.as_deref(), Some("Dave"));
+        assert_eq!(identity.role, Some(UserRole::Admin));
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_code_verifier() {
+        let idp = start_idp(json!({})).await;
+        let client = client(&idp);
+
+        let url = client
+            .authorization_url("state-1", "nonce-1", &generate_code_verifier())
+            .await
+            .unwrap();
+        authorize(&idp, &url);
+
+        let res = client.exchange_code(CODE, &generate_code_verifier()).await;
+        assert!(matches!(res, Err(OidcError::Provider(_))));
+    }
+
+    #[tokio::test]
+    async fn rejects_id_token_for_other_login_or_client() {
+        for (claims, nonce) in [
+            (json!({}), "other-nonce"),
+            (json!({ "aud": "other-client" }), "nonce-1"),
+            (json!({ "exp": 0 }), "nonce-1"),
+        ] {
+            let idp = start_idp(claims).await;
+            let client = client(&idp);
+            let code_verifier = generate_code_verifier();
+            let url = client
+                .authorization_url("state-1", "nonce-1", &code_verifier)
+                .await
+                .unwrap();
+            authorize(&idp, &url);
+
+            let id_token = client.exchange_code(CODE, &code_verifier).await.unwrap();
+            let res = client.validate_id_token(&id_token, nonce).await;
+            assert!(matches!(res, Err(OidcError::InvalidIdToken(_))));
+        }
+    }
+
+    #[test]
+    fn reads_string_and_nested_array_claims() {
+        let claims = json!({
+            "role": "admin",
+            "realm_access": { "roles": ["staff", "library-admin"] },
+        });
+        let claims = claims.as_object().unwrap();
+
+        assert_eq!(claim_values(claims, "role"), vec!["admin"]);
+        assert_eq!(
+            claim_values(claims, "realm_access.roles"),
+            vec!["staff", "library-admin"]
+        );
+        assert!(claim_values(claims, "realm_access.groups").is_empty());
+    }
+
+    #[test]
+    fn computes_rfc7636_code_challenge() {
+        // RFC 7636 Appendix B
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+}
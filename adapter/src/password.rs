@@ -0,0 +1,111 @@
+use std::sync::LazyLock;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, PasswordHash,
+};
+use thiserror::Error;
+
+// 存在しないメールアドレスでのログイン時に照合するハッシュ。
+// 実際のパスワードのハッシュと同じパラメータで生成する
+static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| {
+    hash(&uuid::Uuid::new_v4().to_string())
+        .expect("argon2 hashing with the default parameters must succeed")
+});
+
+#[derive(Debug, Error)]
+pub enum PasswordHashError {
+    #[error(transparent)]
+    Argon2(#[from] argon2::password_hash::Error),
+
+    #[error(transparent)]
+    Bcrypt(#[from] bcrypt::BcryptError),
+}
+
+// パスワードの照合結果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verification {
+    Invalid,
+    Valid,
+    // 一致したが、以前の方式やパラメータのハッシュなので作り直すべき
+    ValidNeedsRehash,
+}
+
+// Argon2id の既定のパラメータでハッシュ化する
+pub fn hash(password: &str) -> Result<String, PasswordHashError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+// Argon2 のハッシュに加え、以前使っていた bcrypt のハッシュも照合できる
+pub fn verify(password: &str, hash: &str) -> Result<Verification, PasswordHashError> {
+    if hash.starts_with("$2") {
+        return Ok(match bcrypt::verify(password, hash)? {
+            true => Verification::ValidNeedsRehash,
+            false => Verification::Invalid,
+        });
+    }
+
+    let parsed = PasswordHash::new(hash)?;
+    match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+        Ok(()) if is_current(&parsed) => Ok(Verification::Valid),
+        Ok(()) => Ok(Verification::ValidNeedsRehash),
+        Err(argon2::password_hash::Error::Password) => Ok(Verification::Invalid),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// 照合する相手がいない場合に、照合したときと同じだけ時間をかける
+pub fn verify_dummy(password: &str) {
+    let _ = verify(password, &DUMMY_PASSWORD_HASH);
+}
+
+fn is_current(hash: &PasswordHash) -> bool {
+    let current = Params::default();
+    hash.algorithm == Algorithm::Argon2id.ident()
+        && Params::try_from(hash).is_ok_and(|params| {
+            params.m_cost() == current.m_cost()
+                && params.t_cost() == current.t_cost()
+                && params.p_cost() == current.p_cost()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify() -> anyhow::Result<()> {
+        let hashed = hash("correct horse")?;
+        assert!(hashed.starts_with("$argon2id$"));
+        assert_eq!(verify("correct horse", &hashed)?, Verification::Valid);
+        assert_eq!(verify("wrong horse", &hashed)?, Verification::Invalid);
+        Ok(())
+    }
+
+    #[test]
+    fn test_outdated_hashes_need_rehash() -> anyhow::Result<()> {
+        let bcrypt = bcrypt::hash("correct horse", 4)?;
+        assert_eq!(
+            verify("correct horse", &bcrypt)?,
+            Verification::ValidNeedsRehash
+        );
+        assert_eq!(verify("wrong horse", &bcrypt)?, Verification::Invalid);
+
+        let weak = Argon2::new(
+            Algorithm::Argon2id,
+            argon2::Version::V0x13,
+            Params::new(1024, 1, 1, None).map_err(argon2::password_hash::Error::from)?,
+        )
+        .hash_password(b"correct horse", &SaltString::generate(&mut OsRng))?
+        .to_string();
+        assert_eq!(
+            verify("correct horse", &weak)?,
+            Verification::ValidNeedsRehash
+        );
+
+        Ok(())
+    }
+}
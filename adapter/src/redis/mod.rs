@@ -1,5 +1,5 @@
 use model::{RedisKey, RedisValue, RedisValueError};
-use redis::AsyncCommands;
+use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
 use shared::config::RedisConfig;
 
 pub mod model;
@@ -25,18 +25,93 @@ impl RedisClient {
         Ok(())
     }
 
+    // キーが存在しない場合のみ値を書き込み、書き込めたかどうかを返す
+    pub async fn set_nx_ex<T: RedisKey>(
+        &self,
+        key: &T,
+        value: &T::Value,
+        ttl: u64,
+    ) -> RedisClientResult<bool> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::EX(ttl));
+        let result: Option<String> = conn
+            .set_options(key.inner(), value.inner(), options)
+            .await?;
+        Ok(result.is_some())
+    }
+
     pub async fn get<T: RedisKey>(&self, key: &T) -> RedisClientResult<Option<T::Value>> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
         let value: Option<String> = conn.get(key.inner()).await?;
         Ok(value.map(T::Value::try_from).transpose()?)
     }
 
+    // 値を取得すると同時にキーを削除する。一度しか使えない値の取得に使う
+    pub async fn get_del<T: RedisKey>(&self, key: &T) -> RedisClientResult<Option<T::Value>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let value: Option<String> = conn.get_del(key.inner()).await?;
+        Ok(value.map(T::Value::try_from).transpose()?)
+    }
+
+    // いずれかのキーが存在するかどうかを 1 回の問い合わせで確かめる
+    pub async fn exists_any<T: RedisKey>(&self, keys: &[T]) -> RedisClientResult<bool> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let keys = keys.iter().map(RedisKey::inner).collect::<Vec<_>>();
+        let count: u64 = conn.exists(keys).await?;
+        Ok(count > 0)
+    }
+
     pub async fn delete<T: RedisKey>(&self, key: &T) -> RedisClientResult<()> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
         let _: () = conn.del(key.inner()).await?;
         Ok(())
     }
 
+    // 集合型のキーに値を追加する
+    pub async fn sadd<T: RedisKey>(&self, key: &T, member: &T::Value) -> RedisClientResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let _: () = conn.sadd(key.inner(), member.inner()).await?;
+        Ok(())
+    }
+
+    pub async fn srem<T: RedisKey>(&self, key: &T, member: &T::Value) -> RedisClientResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let _: () = conn.srem(key.inner(), member.inner()).await?;
+        Ok(())
+    }
+
+    pub async fn smembers<T: RedisKey>(&self, key: &T) -> RedisClientResult<Vec<T::Value>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let members: Vec<String> = conn.smembers(key.inner()).await?;
+        Ok(members
+            .into_iter()
+            .map(T::Value::try_from)
+            .collect::<Result<_, _>>()?)
+    }
+
+    // 値を 1 増やし、増やした後の値を返す。有効期限は増やすたびに ttl 秒に延長する
+    pub async fn incr<T: RedisKey>(&self, key: &T, ttl: u64) -> RedisClientResult<u64> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let value: u64 = conn.incr(key.inner(), 1).await?;
+        let _: () = conn.expire(key.inner(), ttl as i64).await?;
+        Ok(value)
+    }
+
+    // キーの残りの有効期間の秒数を返す。キーが存在しないか有効期限がない場合は None
+    pub async fn ttl<T: RedisKey>(&self, key: &T) -> RedisClientResult<Option<u64>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let ttl: i64 = conn.ttl(key.inner()).await?;
+        Ok(u64::try_from(ttl).ok())
+    }
+
+    pub async fn expire<T: RedisKey>(&self, key: &T, ttl: u64) -> RedisClientResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let _: () = conn.expire(key.inner(), ttl as i64).await?;
+        Ok(())
+    }
+
     // ヘルスチェック用の接続確認関数
     pub async fn try_connect(&self) -> RedisClientResult<()> {
         let _ = self.client.get_multiplexed_async_connection().await?;
@@ -0,0 +1,288 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        api_key::{
+            event::{CreateApiKey, DeleteApiKey},
+            ApiKey, ApiKeyGrant, ApiKeyId, ApiKeyScope, CreatedApiKey, API_KEY_PREFIX,
+        },
+        user::UserId,
+        value_object::ValueObject,
+    },
+    repository::api_key::{ApiKeyRepository, ApiKeyRepositoryError, ApiKeyRepositoryResult},
+};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+use crate::database::{model::api_key::ApiKeyRow, ConnectionPool};
+
+// API キーのうち、接頭辞を除いた乱数部分の文字数
+const API_KEY_RANDOM_CHARS: usize = 40;
+// 一覧に表示する、キーの先頭の文字数
+const API_KEY_DISPLAY_CHARS: usize = 12;
+
+#[derive(new)]
+pub struct ApiKeyRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl ApiKeyRepository for ApiKeyRepositoryImpl {
+    async fn create(&self, event: CreateApiKey) -> ApiKeyRepositoryResult<CreatedApiKey> {
+        let CreateApiKey {
+            user_id,
+            name,
+            scopes,
+            expires_at,
+        } = event;
+
+        let key = generate_key();
+        let scope_names = scopes
+            .iter()
+            .map(|scope| scope.as_str().to_string())
+            .collect::<Vec<_>>();
+
+        let row = sqlx::query_as!(
+            ApiKeyRow,
+            r#"
+                INSERT INTO api_keys (user_id, name, key_prefix, key_hash, scopes, expires_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING
+                    api_key_id,
+                    name,
+                    key_prefix,
+                    scopes AS "scopes: Vec<String>",
+                    expires_at,
+                    last_used_at,
+                    created_at
+            "#,
+            user_id.inner_ref(),
+            name.inner_ref(),
+            &key[..API_KEY_DISPLAY_CHARS],
+            hash_key(&key),
+            &scope_names,
+            expires_at,
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(|e| ApiKeyRepositoryError::Unexpected(Box::new(e)))?;
+
+        let api_key =
+            ApiKey::try_from(row).map_err(|e| ApiKeyRepositoryError::Unexpected(Box::new(e)))?;
+        Ok(CreatedApiKey { api_key, key })
+    }
+
+    async fn find_all(&self, user_id: &UserId) -> ApiKeyRepositoryResult<Vec<ApiKey>> {
+        sqlx::query_as!(
+            ApiKeyRow,
+            r#"
+                SELECT
+                    api_key_id,
+                    name,
+                    key_prefix,
+                    scopes AS "scopes: Vec<String>",
+                    expires_at,
+                    last_used_at,
+                    created_at
+                FROM api_keys
+                WHERE user_id = $1
+                ORDER BY created_at DESC, api_key_id DESC
+            "#,
+            user_id.inner_ref()
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(|e| ApiKeyRepositoryError::Unexpected(Box::new(e)))?
+        .into_iter()
+        .map(ApiKey::try_from)
+        .collect::<Result<_, _>>()
+        .map_err(|e| ApiKeyRepositoryError::Unexpected(Box::new(e)))
+    }
+
+    async fn delete(&self, event: DeleteApiKey) -> ApiKeyRepositoryResult<()> {
+        // 他のユーザーのキーは存在しないものとして扱う
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM api_keys
+                WHERE api_key_id = $1 AND user_id = $2
+            "#,
+            event.api_key_id.inner_ref(),
+            event.requested_by.inner_ref(),
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| ApiKeyRepositoryError::Unexpected(Box::new(e)))?;
+
+        if res.rows_affected() < 1 {
+            return Err(ApiKeyRepositoryError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_all(&self, user_id: &UserId) -> ApiKeyRepositoryResult<()> {
+        sqlx::query!(
+            r#"
+                DELETE FROM api_keys
+                WHERE user_id = $1
+            "#,
+            user_id.inner_ref(),
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| ApiKeyRepositoryError::Unexpected(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn fetch_grant(&self, key: &str) -> ApiKeyRepositoryResult<Option<ApiKeyGrant>> {
+        let row = sqlx::query!(
+            r#"
+                UPDATE api_keys
+                SET last_used_at = CURRENT_TIMESTAMP(3)
+                WHERE key_hash = $1 AND expires_at > CURRENT_TIMESTAMP(3)
+                RETURNING api_key_id, user_id, scopes AS "scopes: Vec<String>"
+            "#,
+            hash_key(key),
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(|e| ApiKeyRepositoryError::Unexpected(Box::new(e)))?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let scopes = row
+            .scopes
+            .iter()
+            .map(|scope| scope.parse::<ApiKeyScope>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| ApiKeyRepositoryError::Unexpected(Box::new(e)))?;
+
+        Ok(Some(ApiKeyGrant {
+            api_key_id: ApiKeyId::new(row.api_key_id),
+            user_id: UserId::new(row.user_id),
+            scopes,
+        }))
+    }
+}
+
+fn generate_key() -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(API_KEY_RANDOM_CHARS)
+        .map(char::from)
+        .collect();
+    format!("{API_KEY_PREFIX}{random}")
+}
+
+// キーは十分な長さの乱数なので、ソルトなしの SHA-256 で照合する
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use chrono::{Duration, Utc};
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_api_key_lifecycle(pool: sqlx::PgPool) -> Result<()> {
+        let repo = ApiKeyRepositoryImpl::new(ConnectionPool::new(pool));
+        let alice =
+            UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<uuid::Uuid>()?)?;
+        let bob = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02".parse::<uuid::Uuid>()?)?;
+
+        let created = repo
+            .create(CreateApiKey {
+                user_id: alice.clone(),
+                name: "inventory script".to_string().try_into()?,
+                scopes: vec![ApiKeyScope::BooksRead, ApiKeyScope::CheckoutsWrite],
+                expires_at: Utc::now() + Duration::days(30),
+            })
+            .await?;
+        assert!(created.key.starts_with(API_KEY_PREFIX));
+        assert!(created.key.starts_with(created.api_key.key_prefix()));
+
+        let grant = repo.fetch_grant(&created.key).await?.expect("grant");
+        assert_eq!(grant.user_id, alice);
+        assert_eq!(
+            grant.scopes,
+            vec![ApiKeyScope::BooksRead, ApiKeyScope::CheckoutsWrite]
+        );
+        assert!(repo.fetch_grant("rbm_unknown").await?.is_none());
+
+        let keys = repo.find_all(&alice).await?;
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].last_used_at().is_some());
+        assert!(repo.find_all(&bob).await?.is_empty());
+
+        // 他のユーザーのキーは削除できない
+        let res = repo
+            .delete(DeleteApiKey {
+                api_key_id: created.api_key.api_key_id().clone(),
+                requested_by: bob,
+            })
+            .await;
+        assert!(matches!(res, Err(ApiKeyRepositoryError::NotFound)));
+
+        repo.delete(DeleteApiKey {
+            api_key_id: created.api_key.api_key_id().clone(),
+            requested_by: alice,
+        })
+        .await?;
+        assert!(repo.fetch_grant(&created.key).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_expired_api_key_is_rejected(pool: sqlx::PgPool) -> Result<()> {
+        let repo = ApiKeyRepositoryImpl::new(ConnectionPool::new(pool));
+        let alice =
+            UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<uuid::Uuid>()?)?;
+
+        let created = repo
+            .create(CreateApiKey {
+                user_id: alice,
+                name: "expired".to_string().try_into()?,
+                scopes: vec![ApiKeyScope::BooksRead],
+                expires_at: Utc::now() - Duration::seconds(1),
+            })
+            .await?;
+        assert!(repo.fetch_grant(&created.key).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_delete_all_api_keys_of_user(pool: sqlx::PgPool) -> Result<()> {
+        let repo = ApiKeyRepositoryImpl::new(ConnectionPool::new(pool));
+        let alice =
+            UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<uuid::Uuid>()?)?;
+        let bob = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02".parse::<uuid::Uuid>()?)?;
+
+        let mut keys = Vec::new();
+        for (user_id, name) in [(&alice, "first"), (&alice, "second"), (&bob, "bob's")] {
+            let created = repo
+                .create(CreateApiKey {
+                    user_id: user_id.clone(),
+                    name: name.to_string().try_into()?,
+                    scopes: vec![ApiKeyScope::BooksRead],
+                    expires_at: Utc::now() + Duration::days(30),
+                })
+                .await?;
+            keys.push(created.key);
+        }
+
+        repo.delete_all(&alice).await?;
+        assert!(repo.fetch_grant(&keys[0]).await?.is_none());
+        assert!(repo.fetch_grant(&keys[1]).await?.is_none());
+        // 他のユーザーのキーは残る
+        assert!(repo.fetch_grant(&keys[2]).await?.is_some());
+
+        Ok(())
+    }
+}
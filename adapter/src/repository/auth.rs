@@ -1,10 +1,16 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::Utc;
 use derive_new::new;
 use kernel::{
     model::{
-        auth::{event::CreateToken, AccessToken},
+        auth::{
+            event::{CreateToken, DeleteSession, RotateToken},
+            jwk::PublicJwk,
+            session::Session,
+            AccessToken, AuthTokens, RefreshToken, TokenSubject,
+        },
         user::{Password, UserEmail, UserId},
         value_object::ValueObject,
     },
@@ -13,10 +19,15 @@ use kernel::{
 
 use crate::{
     database::{
-        model::auth::{AuthorizationKey, UserRow},
+        model::auth::{
+            AuthorizationKey, AuthorizedUserId, LastUsedAt, LastUsedAtKey, RefreshTokenKey,
+            TokenFamily, TokenFamilyId, TokenFamilyKey, UsedRefreshTokenKey, UserRow,
+            UserSessionsKey,
+        },
         ConnectionPool,
     },
-    redis::RedisClient,
+    password::{self, Verification},
+    redis::{RedisClient, RedisClientError},
 };
 
 #[derive(new)]
@@ -24,20 +35,33 @@ pub struct AuthRepositoryImpl {
     db: ConnectionPool,
     kvs: Arc<RedisClient>,
     ttl: u64,
+    refresh_ttl: u64,
+    sliding_expiration: bool,
 }
 
 #[async_trait]
 impl AuthRepository for AuthRepositoryImpl {
-    async fn fetch_user_id_from_token(
+    async fn fetch_token_subject(
         &self,
         access_token: &AccessToken,
-    ) -> AuthRepositoryResult<Option<UserId>> {
+    ) -> AuthRepositoryResult<Option<TokenSubject>> {
         let key: AuthorizationKey = access_token.into();
-        self.kvs
-            .get(&key)
-            .await
-            .map(|x| x.map(|y| y.into_inner()))
-            .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))
+        let Some(authorized_user_id) = self.kvs.get(&key).await.map_err(unexpected)? else {
+            return Ok(None);
+        };
+
+        if self.sliding_expiration {
+            self.kvs.expire(&key, self.ttl).await.map_err(unexpected)?;
+        }
+        if let Some(family_id) = authorized_user_id.family_id() {
+            self.families().touch(family_id).await?;
+        }
+
+        // ロールはトークンに含まれないため、呼び出し側でユーザーから取得する
+        Ok(Some(TokenSubject {
+            user_id: authorized_user_id.into_inner(),
+            role: None,
+        }))
     }
 
     async fn verify_user(
@@ -45,41 +69,454 @@ impl AuthRepository for AuthRepositoryImpl {
         email: &UserEmail,
         password: &Password,
     ) -> AuthRepositoryResult<UserId> {
-        let user_row = sqlx::query_as!(
-            UserRow,
-            r#"SELECT user_id, password_hash FROM users WHERE email = $1"#,
-            email.inner_ref().to_string()
-        )
-        .fetch_optional(self.db.inner_ref())
-        .await
-        .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))?
-        .ok_or(AuthRepositoryError::InvalidPassword)?;
+        verify_password(&self.db, email, password).await
+    }
 
-        let valid = bcrypt::verify(password.inner_ref(), &user_row.password_hash)
-            .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))?;
+    async fn create_token(&self, event: CreateToken) -> AuthRepositoryResult<AuthTokens> {
+        let CreateToken {
+            user_id,
+            access_token,
+            refresh_token,
+            client,
+        } = event;
+
+        let family = TokenFamily {
+            user_id,
+            access_token,
+            refresh_token,
+            client,
+            created_at: Utc::now(),
+        };
+        self.issue_tokens(TokenFamilyId::generate(), family).await
+    }
+
+    async fn rotate_token(&self, event: RotateToken) -> AuthRepositoryResult<AuthTokens> {
+        let RotateToken {
+            refresh_token,
+            new_access_token,
+            new_refresh_token,
+            client,
+        } = event;
+
+        let (family_id, current) = match self.families().refresh(&refresh_token).await? {
+            Refresh::Valid(family_id, family) => (family_id, family),
+            Refresh::Reused(family_id, family) => {
+                // 漏洩したトークンが使われた可能性があるため、ファミリーごと失効させる
+                self.revoke_family(family_id, &family.user_id).await?;
+                return Err(AuthRepositoryError::RefreshTokenReused);
+            }
+        };
+
+        self.kvs
+            .delete(&AuthorizationKey::from(&current.access_token))
+            .await
+            .map_err(unexpected)?;
+
+        let family = TokenFamily {
+            access_token: new_access_token,
+            refresh_token: new_refresh_token,
+            client,
+            ..current
+        };
+        self.issue_tokens(family_id, family).await
+    }
+
+    async fn delete_token(&self, access_token: &AccessToken) -> AuthRepositoryResult<()> {
+        let key: AuthorizationKey = access_token.into();
+        let authorized_user_id = self.kvs.get(&key).await.map_err(unexpected)?;
+        self.kvs.delete(&key).await.map_err(unexpected)?;
+
+        // 同じログインで発行したリフレッシュトークンも使えないようにする
+        if let Some(authorized_user_id) = authorized_user_id {
+            if let Some(family_id) = authorized_user_id.family_id() {
+                self.revoke_family(family_id, &authorized_user_id.into_inner())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn find_sessions(
+        &self,
+        user_id: &UserId,
+        current: &AccessToken,
+    ) -> AuthRepositoryResult<Vec<Session>> {
+        self.families().find_sessions(user_id, current).await
+    }
+
+    async fn delete_session(&self, event: DeleteSession) -> AuthRepositoryResult<()> {
+        let family_id = TokenFamilyId::from(&event.session_id);
+        let family = self.families().find(family_id).await?;
+
+        // 他のユーザーのセッションは存在しないものとして扱う
+        match family {
+            Some(family) if family.user_id == event.user_id => {
+                self.revoke_family(family_id, &event.user_id).await
+            }
+            _ => Err(AuthRepositoryError::SessionNotFound),
+        }
+    }
+
+    // リフレッシュトークン導入前に発行されたアクセストークンはセッションに属さないため、
+    // ここでは無効にできず TTL で失効するのを待つことになる
+    async fn delete_all_sessions(&self, user_id: &UserId) -> AuthRepositoryResult<()> {
+        for family_id in self.families().find_ids(user_id).await? {
+            self.revoke_family(family_id, user_id).await?;
+        }
+        self.families().clear(user_id).await
+    }
 
-        if !valid {
-            return Err(AuthRepositoryError::InvalidPassword);
+    async fn delete_other_sessions(
+        &self,
+        user_id: &UserId,
+        current: &AccessToken,
+    ) -> AuthRepositoryResult<()> {
+        for family_id in self.families().find_ids(user_id).await? {
+            let family = self.families().find(family_id).await?;
+            if family.is_some_and(|x| &x.access_token == current) {
+                continue;
+            }
+            self.revoke_family(family_id, user_id).await?;
         }
+        Ok(())
+    }
 
-        Ok(user_row.user_id.try_into()?)
+    fn public_keys(&self) -> Vec<PublicJwk> {
+        Vec::new()
     }
+}
 
-    async fn create_token(&self, event: CreateToken) -> AuthRepositoryResult<AccessToken> {
-        let (authorization_key, authorized_user_id) = crate::database::model::auth::from(event);
+impl AuthRepositoryImpl {
+    fn families(&self) -> TokenFamilies<'_> {
+        TokenFamilies {
+            kvs: &self.kvs,
+            refresh_ttl: self.refresh_ttl,
+        }
+    }
 
+    async fn issue_tokens(
+        &self,
+        family_id: TokenFamilyId,
+        family: TokenFamily,
+    ) -> AuthRepositoryResult<AuthTokens> {
         self.kvs
-            .set_ex(&authorization_key, &authorized_user_id, self.ttl)
+            .set_ex(
+                &AuthorizationKey::from(&family.access_token),
+                &AuthorizedUserId::new(family.user_id.clone(), family_id),
+                self.ttl,
+            )
+            .await
+            .map_err(unexpected)?;
+        self.families().save(family_id, &family).await?;
+
+        let TokenFamily {
+            user_id,
+            access_token,
+            refresh_token,
+            ..
+        } = family;
+        Ok(AuthTokens {
+            user_id,
+            access_token,
+            refresh_token,
+            expires_in: self.ttl,
+        })
+    }
+
+    // トークンファミリーに属するトークンとセッションの情報をすべて削除する
+    async fn revoke_family(
+        &self,
+        family_id: TokenFamilyId,
+        user_id: &UserId,
+    ) -> AuthRepositoryResult<()> {
+        if let Some(family) = self.families().remove(family_id, user_id).await? {
+            self.kvs
+                .delete(&AuthorizationKey::from(&family.access_token))
+                .await
+                .map_err(unexpected)?;
+        }
+        Ok(())
+    }
+}
+
+// パスワードを照合してユーザー ID を返す。アクセストークンの形式によらず共通。
+// 以前の方式で保存したハッシュは、照合できたときに現在の方式で保存し直す
+pub(crate) async fn verify_password(
+    db: &ConnectionPool,
+    email: &UserEmail,
+    password: &Password,
+) -> AuthRepositoryResult<UserId> {
+    let user_row = sqlx::query_as!(
+        UserRow,
+        r#"SELECT user_id, password_hash FROM users WHERE email = $1"#,
+        email.inner_ref().to_string()
+    )
+    .fetch_optional(db.inner_ref())
+    .await
+    .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))?;
+
+    // 存在しないメールアドレスやパスワードを持たないユーザーでも同じだけ時間をかけ、
+    // 応答時間からアカウントの有無を推測されないようにする
+    let Some((user_id, password_hash)) =
+        user_row.and_then(|row| row.password_hash.map(|hash| (row.user_id, hash)))
+    else {
+        password::verify_dummy(password.inner_ref());
+        return Err(AuthRepositoryError::InvalidPassword);
+    };
+
+    match password::verify(password.inner_ref(), &password_hash)
+        .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))?
+    {
+        Verification::Invalid => return Err(AuthRepositoryError::InvalidPassword),
+        Verification::Valid => {}
+        Verification::ValidNeedsRehash => {
+            let new_hash = password::hash(password.inner_ref())
+                .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))?;
+            // 同時にパスワードが変更されていた場合は上書きしない
+            sqlx::query!(
+                r#"
+                    UPDATE users SET password_hash = $1
+                    WHERE user_id = $2 AND password_hash = $3
+                "#,
+                new_hash,
+                user_id,
+                password_hash,
+            )
+            .execute(db.inner_ref())
             .await
             .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))?;
-        Ok(authorization_key.into())
+        }
     }
 
-    async fn delete_token(&self, access_token: &AccessToken) -> AuthRepositoryResult<()> {
-        let key: AuthorizationKey = access_token.into();
+    Ok(user_id.try_into()?)
+}
+
+// リフレッシュトークンの確認結果
+pub(crate) enum Refresh {
+    Valid(TokenFamilyId, TokenFamily),
+    // 使用済みのリフレッシュトークンが再び使われた
+    Reused(TokenFamilyId, TokenFamily),
+}
+
+// トークンファミリー（セッション）とリフレッシュトークンの保存。
+// アクセストークンの扱いだけが実装ごとに異なり、それ以外はこれを共通で使う
+pub(crate) struct TokenFamilies<'a> {
+    pub(crate) kvs: &'a RedisClient,
+    pub(crate) refresh_ttl: u64,
+}
+
+impl TokenFamilies<'_> {
+    pub(crate) async fn save(
+        &self,
+        family_id: TokenFamilyId,
+        family: &TokenFamily,
+    ) -> AuthRepositoryResult<()> {
+        self.kvs
+            .set_ex(
+                &RefreshTokenKey::from(&family.refresh_token),
+                &family_id,
+                self.refresh_ttl,
+            )
+            .await
+            .map_err(unexpected)?;
+        self.kvs
+            .set_ex(&TokenFamilyKey::from(family_id), family, self.refresh_ttl)
+            .await
+            .map_err(unexpected)?;
+        self.touch(family_id).await?;
+
+        // セッション一覧の集合はいずれかのセッションが有効な間は残しておく
+        let sessions_key = UserSessionsKey::from(&family.user_id);
+        self.kvs
+            .sadd(&sessions_key, &family_id)
+            .await
+            .map_err(unexpected)?;
+        self.kvs
+            .expire(&sessions_key, self.refresh_ttl)
+            .await
+            .map_err(unexpected)
+    }
+
+    pub(crate) async fn find(
+        &self,
+        family_id: TokenFamilyId,
+    ) -> AuthRepositoryResult<Option<TokenFamily>> {
+        self.kvs
+            .get(&TokenFamilyKey::from(family_id))
+            .await
+            .map_err(unexpected)
+    }
+
+    pub(crate) async fn find_ids(
+        &self,
+        user_id: &UserId,
+    ) -> AuthRepositoryResult<Vec<TokenFamilyId>> {
+        self.kvs
+            .smembers(&UserSessionsKey::from(user_id))
+            .await
+            .map_err(unexpected)
+    }
+
+    pub(crate) async fn refresh(
+        &self,
+        refresh_token: &RefreshToken,
+    ) -> AuthRepositoryResult<Refresh> {
+        let family_id = self
+            .kvs
+            .get(&RefreshTokenKey::from(refresh_token))
+            .await
+            .map_err(unexpected)?
+            .ok_or(AuthRepositoryError::InvalidRefreshToken)?;
+
+        // ログアウト・失効済み、または期限切れのトークンファミリー
+        let current = self
+            .find(family_id)
+            .await?
+            .ok_or(AuthRepositoryError::InvalidRefreshToken)?;
+
+        // 同じリフレッシュトークンでの同時リクエストも 1 つしか通さないよう、
+        // 使用済みの印を SET NX で付けてから判定する
+        let first_use = self
+            .kvs
+            .set_nx_ex(
+                &UsedRefreshTokenKey::from(refresh_token),
+                &family_id,
+                self.refresh_ttl,
+            )
+            .await
+            .map_err(unexpected)?;
+        if !first_use || &current.refresh_token != refresh_token {
+            return Ok(Refresh::Reused(family_id, current));
+        }
+        Ok(Refresh::Valid(family_id, current))
+    }
+
+    // トークンファミリーとセッションの情報を削除し、削除したファミリーを返す
+    pub(crate) async fn remove(
+        &self,
+        family_id: TokenFamilyId,
+        user_id: &UserId,
+    ) -> AuthRepositoryResult<Option<TokenFamily>> {
+        let family_key = TokenFamilyKey::from(family_id);
+        let family = self.kvs.get(&family_key).await.map_err(unexpected)?;
+        self.kvs.delete(&family_key).await.map_err(unexpected)?;
         self.kvs
-            .delete(&key)
+            .delete(&LastUsedAtKey::from(family_id))
             .await
-            .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))
+            .map_err(unexpected)?;
+        self.kvs
+            .srem(&UserSessionsKey::from(user_id), &family_id)
+            .await
+            .map_err(unexpected)?;
+        Ok(family)
+    }
+
+    pub(crate) async fn clear(&self, user_id: &UserId) -> AuthRepositoryResult<()> {
+        self.kvs
+            .delete(&UserSessionsKey::from(user_id))
+            .await
+            .map_err(unexpected)
+    }
+
+    pub(crate) async fn find_sessions(
+        &self,
+        user_id: &UserId,
+        current: &AccessToken,
+    ) -> AuthRepositoryResult<Vec<Session>> {
+        let sessions_key = UserSessionsKey::from(user_id);
+        let family_ids = self.find_ids(user_id).await?;
+
+        let mut sessions = Vec::with_capacity(family_ids.len());
+        for family_id in family_ids {
+            // 期限切れで消えたトークンファミリーは集合からも取り除く
+            let Some(family) = self.find(family_id).await? else {
+                self.kvs
+                    .srem(&sessions_key, &family_id)
+                    .await
+                    .map_err(unexpected)?;
+                continue;
+            };
+            let last_used_at = self
+                .kvs
+                .get(&LastUsedAtKey::from(family_id))
+                .await
+                .map_err(unexpected)?
+                .map(|x| x.0)
+                .unwrap_or(family.created_at);
+
+            sessions.push(Session {
+                session_id: family_id.into(),
+                current: &family.access_token == current,
+                client: family.client,
+                created_at: family.created_at,
+                last_used_at,
+            });
+        }
+        sessions.sort_by_key(|x| std::cmp::Reverse(x.last_used_at));
+
+        Ok(sessions)
+    }
+
+    pub(crate) async fn touch(&self, family_id: TokenFamilyId) -> AuthRepositoryResult<()> {
+        self.kvs
+            .set_ex(
+                &LastUsedAtKey::from(family_id),
+                &LastUsedAt(Utc::now()),
+                self.refresh_ttl,
+            )
+            .await
+            .map_err(unexpected)
+    }
+}
+
+pub(crate) fn unexpected(e: RedisClientError) -> AuthRepositoryError {
+    AuthRepositoryError::Unexpected(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    async fn password_hash_of(pool: &sqlx::PgPool, email: &str) -> Result<String> {
+        Ok(sqlx::query_scalar!(
+            r#"SELECT password_hash AS "password_hash!" FROM users WHERE email = $1"#,
+            email
+        )
+        .fetch_one(pool)
+        .await?)
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_verify_password_rehashes_bcrypt_hash(pool: sqlx::PgPool) -> Result<()> {
+        let db = ConnectionPool::new(pool.clone());
+        let email: UserEmail = "alice@example.com".parse()?;
+        sqlx::query!(
+            r#"UPDATE users SET password_hash = $1 WHERE email = $2"#,
+            bcrypt::hash("correct horse", 4)?,
+            email.to_string(),
+        )
+        .execute(&pool)
+        .await?;
+
+        let res = verify_password(&db, &email, &Password::new("wrong horse".into())).await;
+        assert!(matches!(res, Err(AuthRepositoryError::InvalidPassword)));
+        assert!(password_hash_of(&pool, "alice@example.com")
+            .await?
+            .starts_with("$2"));
+
+        // 照合できたときだけ Argon2id のハッシュに置き換わり、以降もそのパスワードでログインできる
+        let password = Password::new("correct horse".into());
+        let user_id = verify_password(&db, &email, &password).await?;
+        let rehashed = password_hash_of(&pool, "alice@example.com").await?;
+        assert!(rehashed.starts_with("$argon2id$"));
+        assert_eq!(verify_password(&db, &email, &password).await?, user_id);
+        assert_eq!(
+            password_hash_of(&pool, "alice@example.com").await?,
+            rehashed
+        );
+
+        Ok(())
     }
 }
@@ -3,8 +3,12 @@ use std::collections::HashMap;
 use async_trait::async_trait;
 use derive_new::new;
 use kernel::model::book::event::{DeleteBook, UpdateBook};
-use kernel::model::book::{BookIdError, BookListOptions, Checkout};
-use kernel::model::list::PaginatedList;
+use kernel::model::book::{
+    BookAvailability, BookIdError, BookListCursorOptions, BookListFilter, BookListOptions,
+    BookSort, BookSortKey, Checkout,
+};
+use kernel::model::book_copy::BookCopyCount;
+use kernel::model::list::{Cursor, CursorPage, PaginatedList, SortOrder};
 use kernel::model::user::UserId;
 use kernel::model::value_object::ValueObject;
 use kernel::repository::book::{BookRepositoryError, BookRepositoryResult};
@@ -12,11 +16,13 @@ use kernel::{
     model::book::{event::CreateBook, Book, BookId},
     repository::book::BookRepository,
 };
+use uuid::Uuid;
 
 use crate::database::model::book::{
-    BookCheckoutRow, BookCheckoutRowError, BookRow, BookRowError, PagenatedBookRow,
+    BookCheckoutRow, BookCheckoutRowError, BookCopyCountRow, BookCursorRow, BookRow, BookRowError,
+    PagenatedBookRow,
 };
-use crate::database::ConnectionPool;
+use crate::database::{pagination::take_page, ConnectionPool};
 
 #[derive(new)]
 pub struct BookRepositoryImpl {
@@ -26,10 +32,17 @@ pub struct BookRepositoryImpl {
 #[async_trait]
 impl BookRepository for BookRepositoryImpl {
     async fn create(&self, event: CreateBook, owner_id: UserId) -> BookRepositoryResult<()> {
-        sqlx::query!(
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
+        let book_id = sqlx::query_scalar!(
             r#"
             INSERT INTO books (title, author, isbn, description, user_id)
             VALUES ($1, $2, $3, $4, $5)
+            RETURNING book_id
             "#,
             event.title.inner_ref(),
             event.author.inner_ref(),
@@ -37,9 +50,25 @@ impl BookRepository for BookRepositoryImpl {
             event.description.inner_ref(),
             owner_id.inner_ref(),
         )
-        .execute(self.db.inner_ref())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
+        // 登録した時点で 1 冊所蔵しているものとする
+        sqlx::query!(
+            r#"
+            INSERT INTO book_copies (book_id) VALUES ($1)
+            "#,
+            book_id,
+        )
+        .execute(&mut *tx)
         .await
         .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
         Ok(())
     }
 
@@ -47,69 +76,38 @@ impl BookRepository for BookRepositoryImpl {
         &self,
         options: BookListOptions,
     ) -> BookRepositoryResult<PaginatedList<Book>> {
-        let BookListOptions { limit, offset } = options;
-
-        let rows = sqlx::query_as!(
-            PagenatedBookRow,
-            r#"
-                SELECT
-                    COUNT(*) OVER() AS "total!",
-                    book_id
-                FROM books
-                ORDER BY created_at DESC
-                LIMIT $1 OFFSET $2
-            "#,
+        let BookListOptions {
             limit,
             offset,
-        )
-        .fetch_all(self.db.inner_ref())
-        .await
-        .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
-
-        let total = rows.first().map(|r| r.total).unwrap_or_default(); // レコードが一つもないときは total は 0 にする
+            filter,
+            sort,
+        } = options;
 
-        let book_ids = rows.into_iter().map(|r| r.book_id).collect::<Vec<_>>();
-
-        let rows = sqlx::query_as!(
-            BookRow,
+        let mut query = sqlx::QueryBuilder::new(
             r#"
                 SELECT
-                    b.book_id,
-                    b.title,
-                    b.author,
-                    b.isbn,
-                    b.description,
-                    u.user_id AS owner_id,
-                    u.name AS owner_name
-                FROM books b
-                INNER JOIN users u USING(user_id)
-                WHERE b.book_id IN (SELECT * FROM UNNEST($1::uuid[]))
-                ORDER BY b.created_at DESC
+                    COUNT(*) OVER() AS total,
+                    b.book_id
             "#,
-            &book_ids,
-        )
-        .fetch_all(self.db.inner_ref())
-        .await
-        .map_err(|e| BookRepositoryError::Unexpected(e.into()))?;
+        );
+        push_book_list_source(&mut query, &filter, sort);
+        push_book_list_order(&mut query, &filter, sort);
+        query
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
 
-        let book_ids = rows
-            .iter()
-            .map(|book| {
-                book.book_id
-                    .try_into()
-                    .map_err(|e: BookIdError| BookRepositoryError::InvalidSavedEntity(e.into()))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        let mut checkouts = self.find_checkouts(&book_ids).await?;
+        let rows = query
+            .build_query_as::<PagenatedBookRow>()
+            .fetch_all(self.db.inner_ref())
+            .await
+            .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
 
-        let items = rows
-            .into_iter()
-            .map(|row| {
-                let checkout = checkouts.remove(&row.book_id.try_into()?);
-                row.try_into_book(checkout)
-            })
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e: BookRowError| BookRepositoryError::InvalidSavedEntity(e.into()))?;
+        let total = rows.first().map(|r| r.total).unwrap_or_default(); // レコードが一つもないときは total は 0 にする
+
+        let book_ids = rows.into_iter().map(|r| r.book_id).collect::<Vec<_>>();
+        let items = self.find_by_ids_in_order(&book_ids).await?;
 
         Ok(PaginatedList {
             total,
@@ -119,6 +117,49 @@ impl BookRepository for BookRepositoryImpl {
         })
     }
 
+    async fn find_all_by_cursor(
+        &self,
+        options: BookListCursorOptions,
+    ) -> BookRepositoryResult<CursorPage<Book>> {
+        let BookListCursorOptions {
+            limit,
+            after,
+            filter,
+            sort,
+        } = options;
+
+        let mut query = sqlx::QueryBuilder::new("SELECT b.book_id, (");
+        push_sort_expression(&mut query, &filter, sort);
+        query.push(")::text AS sort_key");
+        push_book_list_source(&mut query, &filter, sort);
+        if let Some(cursor) = &after {
+            push_cursor_condition(&mut query, &filter, sort, cursor);
+        }
+        push_book_list_order(&mut query, &filter, sort);
+        // 続きがあるかを判定するため、1 件多く取得する
+        query.push(" LIMIT ").push_bind(limit.saturating_add(1));
+
+        let mut rows = query
+            .build_query_as::<BookCursorRow>()
+            .fetch_all(self.db.inner_ref())
+            .await
+            .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
+        let next_cursor = take_page(&mut rows, limit, |row| Cursor {
+            sort_key: row.sort_key.clone(),
+            id: row.book_id,
+        });
+
+        let book_ids = rows.into_iter().map(|r| r.book_id).collect::<Vec<_>>();
+        let items = self.find_by_ids_in_order(&book_ids).await?;
+
+        Ok(CursorPage {
+            limit,
+            items,
+            next_cursor,
+        })
+    }
+
     async fn find_by_id(&self, book_id: &BookId) -> BookRepositoryResult<Option<Book>> {
         let row = sqlx::query_as!(
             BookRow,
@@ -147,12 +188,19 @@ impl BookRepository for BookRepositoryImpl {
                     .book_id
                     .try_into()
                     .map_err(|e: BookIdError| BookRepositoryError::InvalidSavedEntity(e.into()))?;
-                let checkout = self
-                    .find_checkouts(&[book_id.clone()])
+                let book_ids = std::slice::from_ref(&book_id);
+                let checkouts = self
+                    .find_checkouts(book_ids)
                     .await?
-                    .remove(&book_id);
+                    .remove(&book_id)
+                    .unwrap_or_default();
+                let copy_count = self
+                    .find_copy_counts(book_ids)
+                    .await?
+                    .remove(&book_id)
+                    .unwrap_or_default();
                 let book = r
-                    .try_into_book(checkout)
+                    .try_into_book(copy_count, checkouts)
                     .map_err(|e: BookRowError| BookRepositoryError::InvalidSavedEntity(e.into()))?;
                 Ok(Some(book))
             }
@@ -161,64 +209,149 @@ impl BookRepository for BookRepositoryImpl {
     }
 
     async fn update(&self, event: UpdateBook) -> BookRepositoryResult<()> {
-        let res = sqlx::query!(
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
+        self.check_book_owner(&mut tx, &event.book_id, &event.requested_by)
+            .await?;
+
+        sqlx::query!(
             r#"
                 UPDATE books
                 SET
                     title = $1,
                     author = $2,
                     isbn = $3,
+                    -- 正しい ISBN に直されたので、退避していた旧データは不要になる
+                    legacy_isbn = NULL,
                     description = $4
                 WHERE book_id = $5
-                AND user_id = $6
             "#,
             event.title.inner_ref(),
             event.author.inner_ref(),
             event.isbn.inner_ref(),
             event.description.inner_ref(),
             event.book_id.inner_ref(),
-            event.requested_by.inner_ref(),
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
         .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
 
-        if res.rows_affected() < 1 {
-            return Err(BookRepositoryError::NoResourceAffected(
-                "No books record has been updated.".to_string(),
-            ));
-        }
+        tx.commit()
+            .await
+            .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
 
         Ok(())
     }
 
     async fn delete(&self, event: DeleteBook) -> BookRepositoryResult<()> {
-        let res = sqlx::query!(
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
+        self.check_book_owner(&mut tx, &event.book_id, &event.requested_by)
+            .await?;
+
+        sqlx::query!(
             r#"
-                DELETE FROM books WHERE book_id = $1 AND user_id = $2
+                DELETE FROM books WHERE book_id = $1
             "#,
             event.book_id.inner_ref(),
-            event.requested_by.inner_ref(),
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
         .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
 
-        if res.rows_affected() < 1 {
-            return Err(BookRepositoryError::NoResourceAffected(
-                "No books record has been deleted.".to_string(),
-            ));
-        }
+        tx.commit()
+            .await
+            .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
 
         Ok(())
     }
 }
 
 impl BookRepositoryImpl {
+    // 蔵書の変更・削除は所有者のみが行える
+    async fn check_book_owner(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        book_id: &BookId,
+        user_id: &UserId,
+    ) -> BookRepositoryResult<()> {
+        let owner_id = sqlx::query_scalar!(
+            r#"
+                SELECT user_id FROM books WHERE book_id = $1 FOR UPDATE
+            "#,
+            book_id.inner_ref(),
+        )
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
+        match owner_id {
+            None => Err(BookRepositoryError::NotFound(book_id.clone())),
+            Some(owner_id) if &owner_id != user_id.inner_ref() => Err(
+                BookRepositoryError::NotBookOwner(book_id.clone(), user_id.clone()),
+            ),
+            Some(_) => Ok(()),
+        }
+    }
+
+    // 与えられた蔵書 ID の順序を保ったまま蔵書を取得する
+    async fn find_by_ids_in_order(&self, book_ids: &[Uuid]) -> BookRepositoryResult<Vec<Book>> {
+        let rows = sqlx::query_as!(
+            BookRow,
+            r#"
+                SELECT
+                    b.book_id,
+                    b.title,
+                    b.author,
+                    b.isbn,
+                    b.description,
+                    u.user_id AS owner_id,
+                    u.name AS owner_name
+                FROM UNNEST($1::uuid[]) WITH ORDINALITY AS t(book_id, ord)
+                INNER JOIN books b ON b.book_id = t.book_id
+                INNER JOIN users u ON u.user_id = b.user_id
+                ORDER BY t.ord
+            "#,
+            book_ids,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(|e| BookRepositoryError::Unexpected(e.into()))?;
+
+        let book_ids = rows
+            .iter()
+            .map(|book| {
+                book.book_id
+                    .try_into()
+                    .map_err(|e: BookIdError| BookRepositoryError::InvalidSavedEntity(e.into()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut checkouts = self.find_checkouts(&book_ids).await?;
+        let mut copy_counts = self.find_copy_counts(&book_ids).await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let book_id = row.book_id.try_into()?;
+                let copy_count = copy_counts.remove(&book_id).unwrap_or_default();
+                let checkouts = checkouts.remove(&book_id).unwrap_or_default();
+                row.try_into_book(copy_count, checkouts)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: BookRowError| BookRepositoryError::InvalidSavedEntity(e.into()))
+    }
+
     async fn find_checkouts(
         &self,
         book_ids: &[BookId],
-    ) -> BookRepositoryResult<HashMap<BookId, Checkout>> {
+    ) -> BookRepositoryResult<HashMap<BookId, Vec<Checkout>>> {
         let book_ids = book_ids.iter().map(|b| *b.inner_ref()).collect::<Vec<_>>();
 
         let res = sqlx::query_as!(
@@ -227,12 +360,15 @@ impl BookRepositoryImpl {
                 SELECT
                     checkout_id,
                     book_id,
+                    book_copy_id,
                     user_id,
                     u.name AS user_name,
-                    checked_out_at
+                    checked_out_at,
+                    due_at
                 FROM checkouts
                 INNER JOIN users u USING(user_id)
                 WHERE book_id IN (SELECT * FROM UNNEST($1::uuid[]))
+                ORDER BY checked_out_at ASC
             "#,
             &book_ids[..],
         )
@@ -240,21 +376,296 @@ impl BookRepositoryImpl {
         .await
         .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
 
-        let map =
-            res.into_iter()
-                .map(|r| {
-                    let book_id = r.book_id.try_into().map_err(|e: BookIdError| {
-                        BookRepositoryError::InvalidSavedEntity(e.into())
-                    })?;
-                    let checkout = r.try_into().map_err(|e: BookCheckoutRowError| {
-                        BookRepositoryError::InvalidSavedEntity(e.into())
-                    })?;
-                    Ok((book_id, checkout))
-                })
-                .collect::<Result<HashMap<_, _>, _>>()?;
+        let mut map: HashMap<BookId, Vec<Checkout>> = HashMap::new();
+        for r in res {
+            let book_id = r
+                .book_id
+                .try_into()
+                .map_err(|e: BookIdError| BookRepositoryError::InvalidSavedEntity(e.into()))?;
+            let checkout = r.try_into().map_err(|e: BookCheckoutRowError| {
+                BookRepositoryError::InvalidSavedEntity(e.into())
+            })?;
+            map.entry(book_id).or_default().push(checkout);
+        }
 
         Ok(map)
     }
+
+    // 蔵書ごとの所蔵数と貸出可能数を集計する
+    async fn find_copy_counts(
+        &self,
+        book_ids: &[BookId],
+    ) -> BookRepositoryResult<HashMap<BookId, BookCopyCount>> {
+        let book_ids = book_ids.iter().map(|b| *b.inner_ref()).collect::<Vec<_>>();
+
+        let res = sqlx::query_as!(
+            BookCopyCountRow,
+            r#"
+                SELECT
+                    bc.book_id,
+                    COUNT(*) AS "total!",
+                    COUNT(*) FILTER (WHERE c.checkout_id IS NULL) AS "available!"
+                FROM book_copies bc
+                LEFT OUTER JOIN checkouts c ON c.book_copy_id = bc.book_copy_id
+                WHERE bc.book_id IN (SELECT * FROM UNNEST($1::uuid[]))
+                GROUP BY bc.book_id
+            "#,
+            &book_ids[..],
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(|e| BookRepositoryError::Unexpected(Box::new(e)))?;
+
+        res.into_iter()
+            .map(|r| {
+                let book_id = r
+                    .book_id
+                    .try_into()
+                    .map_err(|e: BookIdError| BookRepositoryError::InvalidSavedEntity(e.into()))?;
+                Ok((
+                    book_id,
+                    BookCopyCount {
+                        total: r.total,
+                        available: r.available,
+                    },
+                ))
+            })
+            .collect()
+    }
+}
+
+// 絞り込み条件を WHERE 句に追加する
+fn push_book_list_filter(
+    query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    filter: &BookListFilter,
+) {
+    let BookListFilter {
+        keyword,
+        title,
+        author,
+        isbn,
+        description,
+        owner_id,
+        availability,
+        checked_out_by,
+    } = filter;
+
+    if let Some(keyword) = keyword {
+        let pattern = like_pattern(keyword);
+        query
+            .push(" AND (b.title ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR b.author ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR b.isbn ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR b.description ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+
+    for (column, value) in [
+        ("b.title", title),
+        ("b.author", author),
+        ("b.isbn", isbn),
+        ("b.description", description),
+    ] {
+        if let Some(value) = value {
+            query
+                .push(format!(" AND {column} ILIKE "))
+                .push_bind(like_pattern(value));
+        }
+    }
+
+    if let Some(owner_id) = owner_id {
+        query
+            .push(" AND b.user_id = ")
+            .push_bind(*owner_id.inner_ref());
+    }
+
+    // 貸出中でない冊が 1 冊でもあれば貸出可能とする
+    const AVAILABLE_COPY_EXISTS: &str = r#"
+        EXISTS (
+            SELECT 1 FROM book_copies bc
+            WHERE bc.book_id = b.book_id
+                AND NOT EXISTS (SELECT 1 FROM checkouts c WHERE c.book_copy_id = bc.book_copy_id)
+        )
+    "#;
+    match availability {
+        Some(BookAvailability::Available) => {
+            query.push(" AND ").push(AVAILABLE_COPY_EXISTS);
+        }
+        Some(BookAvailability::CheckedOut) => {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM checkouts c WHERE c.book_id = b.book_id)")
+                .push(" AND NOT ")
+                .push(AVAILABLE_COPY_EXISTS);
+        }
+        None => {}
+    }
+
+    if let Some(user_id) = checked_out_by {
+        query
+            .push(" AND EXISTS (SELECT 1 FROM checkouts c WHERE c.book_id = b.book_id AND c.user_id = ")
+            .push_bind(*user_id.inner_ref())
+            .push(")");
+    }
+}
+
+// FROM 句と WHERE 句を追加する
+fn push_book_list_source(
+    query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    filter: &BookListFilter,
+    sort: BookSort,
+) {
+    query.push(" FROM books b");
+    if matches!(
+        sort.key,
+        BookSortKey::LastCheckedOutAt | BookSortKey::CheckoutCount
+    ) {
+        // 貸出履歴による並び替えのときだけ、貸出中・返却済みの貸出を集計する
+        query.push(
+            r#"
+            LEFT JOIN LATERAL (
+                SELECT
+                    MAX(h.checked_out_at) AS last_checked_out_at,
+                    COUNT(*) AS checkout_count
+                FROM (
+                    SELECT checked_out_at FROM checkouts WHERE book_id = b.book_id
+                    UNION ALL
+                    SELECT checked_out_at FROM returned_checkouts WHERE book_id = b.book_id
+                ) h
+            ) s ON TRUE
+            "#,
+        );
+    }
+    query.push(" WHERE TRUE");
+    push_book_list_filter(query, filter);
+}
+
+// キーワードが無い場合の関連度順は作成日時順として扱う
+fn effective_sort_key(filter: &BookListFilter, sort: BookSort) -> BookSortKey {
+    match (sort.key, &filter.keyword) {
+        (BookSortKey::Relevance, None) => BookSortKey::CreatedAt,
+        (key, _) => key,
+    }
+}
+
+// 並び替えに使う値を求める式を追加する
+fn push_sort_expression(
+    query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    filter: &BookListFilter,
+    sort: BookSort,
+) {
+    match (effective_sort_key(filter, sort), &filter.keyword) {
+        (BookSortKey::Relevance, Some(keyword)) => {
+            query
+                .push("word_similarity(")
+                .push_bind(keyword.clone())
+                .push(", b.title) * 2 + word_similarity(")
+                .push_bind(keyword.clone())
+                .push(", b.author) + word_similarity(")
+                .push_bind(keyword.clone())
+                .push(", b.description)");
+        }
+        (BookSortKey::Title, _) => {
+            query.push("b.title");
+        }
+        (BookSortKey::Author, _) => {
+            query.push("b.author");
+        }
+        (BookSortKey::CreatedAt | BookSortKey::Relevance, _) => {
+            query.push("b.created_at");
+        }
+        (BookSortKey::UpdatedAt, _) => {
+            query.push("b.updated_at");
+        }
+        (BookSortKey::LastCheckedOutAt, _) => {
+            query.push("s.last_checked_out_at");
+        }
+        (BookSortKey::CheckoutCount, _) => {
+            query.push("s.checkout_count");
+        }
+    }
+}
+
+// カーソルに保存した並び替えキーの値を元の型に戻すための型名
+fn sort_key_type(filter: &BookListFilter, sort: BookSort) -> &'static str {
+    match effective_sort_key(filter, sort) {
+        BookSortKey::Title | BookSortKey::Author => "text",
+        BookSortKey::CreatedAt | BookSortKey::UpdatedAt | BookSortKey::LastCheckedOutAt => {
+            "timestamptz"
+        }
+        BookSortKey::CheckoutCount => "bigint",
+        BookSortKey::Relevance => "real",
+    }
+}
+
+fn sort_direction(sort: BookSort) -> &'static str {
+    match sort.order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    }
+}
+
+// 並び順を ORDER BY 句として追加する
+// ページングの結果が安定するよう、最後に book_id で順序を確定させる
+fn push_book_list_order(
+    query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    filter: &BookListFilter,
+    sort: BookSort,
+) {
+    let direction = sort_direction(sort);
+
+    query.push(" ORDER BY ");
+    push_sort_expression(query, filter, sort);
+    query.push(format!(" {direction} NULLS LAST, b.book_id {direction}"));
+}
+
+// カーソルが指す蔵書より後ろにある蔵書だけに絞り込む
+// ORDER BY と同じく NULL は昇順・降順どちらでも最後に並ぶものとして扱う
+fn push_cursor_condition(
+    query: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    filter: &BookListFilter,
+    sort: BookSort,
+    cursor: &Cursor,
+) {
+    let operator = match sort.order {
+        SortOrder::Asc => ">",
+        SortOrder::Desc => "<",
+    };
+
+    match &cursor.sort_key {
+        Some(sort_key) => {
+            query.push(" AND ((");
+            push_sort_expression(query, filter, sort);
+            query.push(") IS NULL OR ((");
+            push_sort_expression(query, filter, sort);
+            query
+                .push(format!("), b.book_id) {operator} (CAST("))
+                .push_bind(sort_key.clone())
+                .push(format!(" AS {}), ", sort_key_type(filter, sort)))
+                .push_bind(cursor.id)
+                .push("))");
+        }
+        None => {
+            query.push(" AND (");
+            push_sort_expression(query, filter, sort);
+            query
+                .push(format!(") IS NULL AND b.book_id {operator} "))
+                .push_bind(cursor.id);
+        }
+    }
+}
+
+// ILIKE 用の部分一致パターンを作る
+// 利用者の入力に含まれるワイルドカード文字はエスケープする
+fn like_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
 }
 
 #[cfg(test)]
@@ -263,7 +674,7 @@ mod tests {
     use kernel::{
         model::{
             book::{Author, Description, Isbn, Title},
-            user::{event::CreateUser, Password, UserEmail, UserName},
+            user::{event::CreateUser, password::PasswordPolicy, Password, UserEmail, UserName},
         },
         repository::user::UserRepository,
     };
@@ -275,14 +686,15 @@ mod tests {
 
     #[sqlx::test(fixtures("common"))]
     async fn test_register_book(pool: sqlx::PgPool) -> Result<()> {
-        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_repo =
+            UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), PasswordPolicy::default());
 
         let repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
 
         let user = CreateUser {
             name: UserName::try_from("test user".to_string())?,
             email: "test@example.com".parse::<UserEmail>()?,
-            password: Password::try_from("password".to_string())?,
+            password: Password::try_from("a-new-password".to_string())?,
         };
 
         let user = user_repo.create(user).await?;
@@ -290,7 +702,7 @@ mod tests {
         let book = CreateBook {
             title: Title::try_from("test title".to_string())?,
             author: Author::try_from("test author".to_string())?,
-            isbn: Isbn::try_from("test isbn".to_string())?,
+            isbn: Isbn::try_from("4-06-536957-6".to_string())?,
             description: Description::try_from("test description".to_string())?,
         };
 
@@ -299,6 +711,8 @@ mod tests {
         let options = BookListOptions {
             limit: 10,
             offset: 0,
+            filter: BookListFilter::default(),
+            sort: BookSort::default(),
         };
 
         let res = repo.find_all(options).await?;
@@ -318,16 +732,25 @@ mod tests {
             isbn,
             description,
             owner,
+            copy_count,
             ..
         } = res.ok_or(anyhow::anyhow!("book not found"))?;
 
         assert_eq!(book_id.inner_ref(), id.inner_ref());
         assert_eq!(title.inner_ref(), "test title");
         assert_eq!(author.inner_ref(), "test author");
-        assert_eq!(isbn.inner_ref(), "test isbn");
+        assert_eq!(isbn.map(Isbn::into_inner).as_deref(), Some("9784065369579"));
         assert_eq!(description.inner_ref(), "test description");
         assert_eq!(owner.user_id, *user.user_id());
         assert_eq!(owner.user_name, "test user".to_string().try_into()?);
+        // 登録時に 1 冊所蔵している
+        assert_eq!(
+            copy_count,
+            BookCopyCount {
+                total: 1,
+                available: 1
+            }
+        );
 
         Ok(())
     }
@@ -358,7 +781,7 @@ mod tests {
             book_id: book_id.clone(),
             title,
             author,
-            isbn,
+            isbn: isbn.ok_or(anyhow::anyhow!("isbn not found"))?,
             description: NEW_DESCRIPTION.to_string().try_into()?,
             requested_by: UserId::try_from(
                 "5b4c96ac-316a-4bee-8e69-cac5eb84ff4c".parse::<Uuid>()?,
@@ -377,4 +800,412 @@ mod tests {
 
         Ok(())
     }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_update_and_delete_book_by_others(pool: sqlx::PgPool) -> Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let book_id = BookId::try_from("9890736e-a4e4-461a-a77d-eac3517ef11b".parse::<Uuid>()?)?;
+        let book = repo
+            .find_by_id(&book_id)
+            .await?
+            .ok_or(anyhow::anyhow!("book not found"))?;
+        let update = |book_id: BookId, requested_by: UserId| -> Result<UpdateBook> {
+            Ok(UpdateBook {
+                book_id,
+                title: book.title.clone(),
+                author: book.author.clone(),
+                isbn: book.isbn.clone().ok_or(anyhow::anyhow!("isbn not found"))?,
+                description: book.description.clone(),
+                requested_by,
+            })
+        };
+        let other = UserId::new(Uuid::new_v4());
+        let missing = BookId::new(Uuid::new_v4());
+
+        // 所有者以外は変更も削除もできない
+        let res = repo.update(update(book_id.clone(), other.clone())?).await;
+        assert!(matches!(res, Err(BookRepositoryError::NotBookOwner(..))));
+        let res = repo
+            .delete(DeleteBook {
+                book_id: book_id.clone(),
+                requested_by: other.clone(),
+            })
+            .await;
+        assert!(matches!(res, Err(BookRepositoryError::NotBookOwner(..))));
+        assert!(repo.find_by_id(&book_id).await?.is_some());
+
+        // 存在しない蔵書は見つからないものとして扱う
+        let res = repo.update(update(missing.clone(), other.clone())?).await;
+        assert!(matches!(res, Err(BookRepositoryError::NotFound(id)) if id == missing));
+        let res = repo
+            .delete(DeleteBook {
+                book_id: missing.clone(),
+                requested_by: other,
+            })
+            .await;
+        assert!(matches!(res, Err(BookRepositoryError::NotFound(id)) if id == missing));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_delete_book_with_checkout_history(pool: sqlx::PgPool) -> Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let book_id = BookId::try_from("9890736e-a4e4-461a-a77d-eac3517ef11b".parse::<Uuid>()?)?;
+        let owner_id = UserId::try_from("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c".parse::<Uuid>()?)?;
+        sqlx::query!(
+            r#"
+                INSERT INTO returned_checkouts (
+                    checkout_id, book_id, book_copy_id, user_id, checked_out_at, due_at
+                )
+                VALUES (gen_random_uuid(), $1, $2, $3, now(), now())
+            "#,
+            book_id.inner_ref(),
+            "0a3b1f6e-6a43-4a2b-9d52-0f3a4c1e7b01".parse::<Uuid>()?,
+            owner_id.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+
+        // 冊だけを削除することはできないが、蔵書ごとであれば履歴と一緒に削除できる
+        repo.delete(DeleteBook {
+            book_id: book_id.clone(),
+            requested_by: owner_id,
+        })
+        .await?;
+        assert!(repo.find_by_id(&book_id).await?.is_none());
+        let history = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM returned_checkouts WHERE book_id = $1",
+            book_id.inner_ref(),
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(history, Some(0));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_book_with_legacy_isbn(pool: sqlx::PgPool) -> Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // マイグレーションで正しい ISBN に直せず、旧データを退避した蔵書
+        let book_id = BookId::try_from("9890736e-a4e4-461a-a77d-eac3517ef11b".parse::<Uuid>()?)?;
+        sqlx::query!(
+            "UPDATE books SET isbn = NULL, legacy_isbn = 'ISBN4-7980-6170' WHERE book_id = $1",
+            book_id.inner_ref()
+        )
+        .execute(&pool)
+        .await?;
+
+        // 一覧と詳細はエラーにならず、ISBN が空のまま返る
+        let list = repo
+            .find_all(BookListOptions {
+                limit: 10,
+                offset: 0,
+                filter: BookListFilter::default(),
+                sort: BookSort::new(BookSortKey::Title, None),
+            })
+            .await?;
+        assert!(list
+            .items
+            .iter()
+            .any(|book| book.book_id == book_id && book.isbn.is_none()));
+        let book = repo
+            .find_by_id(&book_id)
+            .await?
+            .ok_or(anyhow::anyhow!("book not found"))?;
+        assert!(book.isbn.is_none());
+
+        // 正しい ISBN で更新すると退避していた旧データは消える
+        repo.update(UpdateBook {
+            book_id: book_id.clone(),
+            title: book.title,
+            author: book.author,
+            isbn: Isbn::try_from("9784798061702".to_string())?,
+            description: book.description,
+            requested_by: UserId::try_from(
+                "5b4c96ac-316a-4bee-8e69-cac5eb84ff4c".parse::<Uuid>()?,
+            )?,
+        })
+        .await?;
+        let legacy_isbn = sqlx::query_scalar!(
+            "SELECT legacy_isbn FROM books WHERE book_id = $1",
+            book_id.inner_ref()
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert!(legacy_isbn.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_search_books(pool: sqlx::PgPool) -> Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let search = |filter: BookListFilter, key: BookSortKey| BookListOptions {
+            limit: 10,
+            offset: 0,
+            filter,
+            sort: BookSort::new(key, None),
+        };
+
+        // 日本語のキーワードによる部分一致検索
+        let res = repo
+            .find_all(search(
+                BookListFilter {
+                    keyword: Some("システム".to_string()),
+                    ..Default::default()
+                },
+                BookSortKey::Relevance,
+            ))
+            .await?;
+        assert_eq!(res.total, 1);
+        assert_eq!(
+            res.items[0].book_id.inner_ref(),
+            &"f397b83a-dd2a-4a01-9e77-db1eea7de5b6".parse::<Uuid>()?
+        );
+
+        // 著者名による絞り込み
+        let res = repo
+            .find_all(search(
+                BookListFilter {
+                    author: Some("豊田".to_string()),
+                    ..Default::default()
+                },
+                BookSortKey::default(),
+            ))
+            .await?;
+        assert_eq!(res.total, 1);
+
+        // ワイルドカード文字はそのまま検索される
+        let res = repo
+            .find_all(search(
+                BookListFilter {
+                    keyword: Some("%".to_string()),
+                    ..Default::default()
+                },
+                BookSortKey::default(),
+            ))
+            .await?;
+        assert_eq!(res.total, 0);
+
+        // 貸出状況と所有者による絞り込み
+        let owner_id = UserId::try_from("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c".parse::<Uuid>()?)?;
+        // 2 冊所蔵している蔵書は 1 冊貸し出しても貸出可能のまま
+        sqlx::query!(
+            r#"
+                INSERT INTO checkouts (book_id, book_copy_id, user_id, due_at)
+                VALUES ($1, $2, $5, now()), ($3, $4, $5, now())
+            "#,
+            "9890736e-a4e4-461a-a77d-eac3517ef11b".parse::<Uuid>()?,
+            "0a3b1f6e-6a43-4a2b-9d52-0f3a4c1e7b01".parse::<Uuid>()?,
+            "17afb850-c786-49c5-a303-a3a443a2212c".parse::<Uuid>()?,
+            "0a3b1f6e-6a43-4a2b-9d52-0f3a4c1e7b03".parse::<Uuid>()?,
+            owner_id.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+
+        let res = repo
+            .find_all(search(
+                BookListFilter {
+                    owner_id: Some(owner_id.clone()),
+                    availability: Some(BookAvailability::Available),
+                    ..Default::default()
+                },
+                BookSortKey::default(),
+            ))
+            .await?;
+        assert_eq!(res.total, 2);
+
+        let res = repo
+            .find_all(search(
+                BookListFilter {
+                    availability: Some(BookAvailability::CheckedOut),
+                    ..Default::default()
+                },
+                BookSortKey::default(),
+            ))
+            .await?;
+        assert_eq!(res.total, 1);
+        assert_eq!(
+            res.items[0].copy_count,
+            BookCopyCount {
+                total: 1,
+                available: 0
+            }
+        );
+
+        let res = repo
+            .find_all(search(
+                BookListFilter {
+                    checked_out_by: Some(owner_id),
+                    ..Default::default()
+                },
+                BookSortKey::Title,
+            ))
+            .await?;
+        assert_eq!(res.total, 2);
+        assert!(res.items.iter().all(|book| book.checkouts.len() == 1));
+        let multi_copy = res
+            .items
+            .iter()
+            .find(|book| book.copy_count.total == 2)
+            .ok_or(anyhow::anyhow!("book not found"))?;
+        assert_eq!(multi_copy.copy_count.available, 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_sort_books(pool: sqlx::PgPool) -> Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let user_id = "5b4c96ac-316a-4bee-8e69-cac5eb84ff4c".parse::<Uuid>()?;
+        let first = "9890736e-a4e4-461a-a77d-eac3517ef11b".parse::<Uuid>()?;
+        let second = "17afb850-c786-49c5-a303-a3a443a2212c".parse::<Uuid>()?;
+        let never = "f397b83a-dd2a-4a01-9e77-db1eea7de5b6".parse::<Uuid>()?;
+
+        // first は 2 回、second は 1 回貸し出されたことにする
+        sqlx::query!(
+            r#"
+                INSERT INTO returned_checkouts (
+                    checkout_id, book_id, book_copy_id, user_id, checked_out_at, due_at
+                )
+                VALUES
+                    (gen_random_uuid(), $1, $2, $5, now() - interval '3 days', now()),
+                    (gen_random_uuid(), $1, $2, $5, now() - interval '2 days', now()),
+                    (gen_random_uuid(), $3, $4, $5, now() - interval '1 days', now())
+            "#,
+            first,
+            "0a3b1f6e-6a43-4a2b-9d52-0f3a4c1e7b01".parse::<Uuid>()?,
+            second,
+            "0a3b1f6e-6a43-4a2b-9d52-0f3a4c1e7b03".parse::<Uuid>()?,
+            user_id,
+        )
+        .execute(&pool)
+        .await?;
+
+        let list = |key: BookSortKey, order: Option<SortOrder>| {
+            let repo = &repo;
+            async move {
+                let res = repo
+                    .find_all(BookListOptions {
+                        limit: 2,
+                        offset: 0,
+                        filter: BookListFilter::default(),
+                        sort: BookSort::new(key, order),
+                    })
+                    .await?;
+                anyhow::Ok(
+                    res.items
+                        .into_iter()
+                        .map(|b| *b.book_id.inner_ref())
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+
+        assert_eq!(
+            list(BookSortKey::CheckoutCount, None).await?,
+            vec![first, second]
+        );
+        // 貸出履歴のない蔵書は昇順・降順どちらでも最後になる
+        assert_eq!(
+            list(BookSortKey::LastCheckedOutAt, Some(SortOrder::Asc)).await?,
+            vec![first, second]
+        );
+        assert_eq!(
+            list(BookSortKey::LastCheckedOutAt, None).await?,
+            vec![second, first]
+        );
+        assert_eq!(list(BookSortKey::Title, None).await?[0], second);
+        assert_ne!(
+            list(BookSortKey::Title, Some(SortOrder::Desc)).await?[0],
+            second
+        );
+        assert!(!list(BookSortKey::CheckoutCount, None)
+            .await?
+            .contains(&never));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_find_books_by_cursor(pool: sqlx::PgPool) -> Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // 貸出履歴がある蔵書とない蔵書を混在させ、NULL を含む並び替えも検証する
+        sqlx::query!(
+            r#"
+                INSERT INTO returned_checkouts (
+                    checkout_id, book_id, book_copy_id, user_id, checked_out_at, due_at
+                )
+                VALUES (
+                    gen_random_uuid(),
+                    '9890736e-a4e4-461a-a77d-eac3517ef11b',
+                    '0a3b1f6e-6a43-4a2b-9d52-0f3a4c1e7b01',
+                    '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c',
+                    now(),
+                    now()
+                )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        for key in [
+            BookSortKey::Title,
+            BookSortKey::Author,
+            BookSortKey::CreatedAt,
+            BookSortKey::UpdatedAt,
+            BookSortKey::LastCheckedOutAt,
+            BookSortKey::CheckoutCount,
+        ] {
+            for order in [SortOrder::Asc, SortOrder::Desc] {
+                let sort = BookSort::new(key, Some(order));
+
+                let expected = repo
+                    .find_all(BookListOptions {
+                        limit: 10,
+                        offset: 0,
+                        filter: BookListFilter::default(),
+                        sort,
+                    })
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|b| b.book_id)
+                    .collect::<Vec<_>>();
+
+                // 1 件ずつカーソルをたどっても、同じ順序で全件を取得できる
+                let mut actual = vec![];
+                let mut after = None;
+                loop {
+                    let page = repo
+                        .find_all_by_cursor(BookListCursorOptions {
+                            limit: 1,
+                            after,
+                            filter: BookListFilter::default(),
+                            sort,
+                        })
+                        .await?;
+                    actual.extend(page.items.into_iter().map(|b| b.book_id));
+                    match page.next_cursor {
+                        Some(cursor) => after = Some(cursor),
+                        None => break,
+                    }
+                }
+
+                assert_eq!(actual, expected, "{key:?} {order:?}");
+                assert_eq!(actual.len(), 3);
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,392 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        book::BookId,
+        book_copy::{
+            event::{CreateBookCopy, DeleteBookCopy, UpdateBookCopy},
+            BookCopy,
+        },
+        user::UserId,
+        value_object::ValueObject,
+    },
+    repository::book_copy::{
+        BookCopyRepository, BookCopyRepositoryError, BookCopyRepositoryResult,
+    },
+};
+use uuid::Uuid;
+
+use crate::database::{
+    model::{
+        book_copy::{BookCopyRow, CopyConditionName},
+        checkout::BookCopyStateRow,
+    },
+    ConnectionPool,
+};
+
+#[derive(new)]
+pub struct BookCopyRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl BookCopyRepository for BookCopyRepositoryImpl {
+    async fn create(&self, event: CreateBookCopy) -> BookCopyRepositoryResult<()> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        self.check_book_owner(&mut tx, &event.book_id, &event.requested_by)
+            .await?;
+
+        let condition = CopyConditionName::from(event.condition).to_string();
+        let res = match &event.barcode {
+            Some(barcode) => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO book_copies (book_id, barcode, condition, location)
+                        VALUES ($1, $2, $3, $4)
+                    "#,
+                    event.book_id.inner_ref(),
+                    barcode.inner_ref(),
+                    condition,
+                    event.location.inner_ref(),
+                )
+                .execute(&mut *tx)
+                .await
+            }
+            // バーコードはテーブルの既定値で採番する
+            None => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO book_copies (book_id, condition, location)
+                        VALUES ($1, $2, $3)
+                    "#,
+                    event.book_id.inner_ref(),
+                    condition,
+                    event.location.inner_ref(),
+                )
+                .execute(&mut *tx)
+                .await
+            }
+        };
+
+        match res {
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                return Err(BookCopyRepositoryError::DuplicateBarcode(
+                    event.barcode.map(|b| b.into_inner()).unwrap_or_default(),
+                ))
+            }
+            Err(e) => return Err(BookCopyRepositoryError::Unexpected(e.into())),
+            Ok(_) => {}
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        Ok(())
+    }
+
+    async fn find_by_book_id(&self, book_id: &BookId) -> BookCopyRepositoryResult<Vec<BookCopy>> {
+        let book_exists = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (SELECT 1 FROM books WHERE book_id = $1) AS "exists!"
+            "#,
+            book_id.inner_ref(),
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        if !book_exists {
+            return Err(BookCopyRepositoryError::BookNotFound(book_id.clone()));
+        }
+
+        let rows = sqlx::query_as!(
+            BookCopyRow,
+            r#"
+                SELECT
+                    bc.book_copy_id,
+                    bc.book_id,
+                    bc.barcode,
+                    bc.condition,
+                    bc.location,
+                    c.checkout_id AS "checkout_id?: Uuid",
+                    c.user_id AS "checkout_user_id?: Uuid",
+                    u.name AS "checkout_user_name?",
+                    c.checked_out_at AS "checked_out_at?",
+                    c.due_at AS "due_at?"
+                FROM book_copies bc
+                LEFT OUTER JOIN checkouts c ON c.book_copy_id = bc.book_copy_id
+                LEFT OUTER JOIN users u ON u.user_id = c.user_id
+                WHERE bc.book_id = $1
+                ORDER BY bc.barcode
+            "#,
+            book_id.inner_ref(),
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        rows.into_iter()
+            .map(BookCopy::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| BookCopyRepositoryError::InvalidSavedEntity(e.into()))
+    }
+
+    async fn update(&self, event: UpdateBookCopy) -> BookCopyRepositoryResult<()> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        self.check_book_owner(&mut tx, &event.book_id, &event.requested_by)
+            .await?;
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE book_copies
+                SET condition = $1, location = $2
+                WHERE book_copy_id = $3 AND book_id = $4
+            "#,
+            CopyConditionName::from(event.condition).to_string(),
+            event.location.inner_ref(),
+            event.book_copy_id.inner_ref(),
+            event.book_id.inner_ref(),
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        if res.rows_affected() < 1 {
+            return Err(BookCopyRepositoryError::NotFound(event.book_copy_id));
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, event: DeleteBookCopy) -> BookCopyRepositoryResult<()> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        self.check_book_owner(&mut tx, &event.book_id, &event.requested_by)
+            .await?;
+
+        // 貸出中の冊は削除できない
+        let res = sqlx::query_as!(
+            BookCopyStateRow,
+            r#"
+                SELECT
+                    bc.book_copy_id,
+                    c.checkout_id AS "checkout_id?: Uuid"
+                FROM book_copies bc
+                LEFT OUTER JOIN checkouts c ON c.book_copy_id = bc.book_copy_id
+                WHERE bc.book_copy_id = $1 AND bc.book_id = $2
+                FOR UPDATE OF bc
+            "#,
+            event.book_copy_id.inner_ref(),
+            event.book_id.inner_ref(),
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        match res {
+            None => return Err(BookCopyRepositoryError::NotFound(event.book_copy_id)),
+            Some(BookCopyStateRow {
+                checkout_id: Some(_),
+                ..
+            }) => return Err(BookCopyRepositoryError::CheckedOut(event.book_copy_id)),
+            _ => {}
+        }
+
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM book_copies WHERE book_copy_id = $1
+            "#,
+            event.book_copy_id.inner_ref(),
+        )
+        .execute(&mut *tx)
+        .await;
+        match res {
+            Ok(_) => {}
+            // 返却済みの貸出履歴を消さないよう、履歴のある冊は削除できない
+            Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                return Err(BookCopyRepositoryError::HasCheckoutHistory(
+                    event.book_copy_id,
+                ))
+            }
+            Err(e) => return Err(BookCopyRepositoryError::Unexpected(e.into())),
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        Ok(())
+    }
+}
+
+impl BookCopyRepositoryImpl {
+    // 冊の追加・変更・削除は蔵書の所有者のみが行える
+    async fn check_book_owner(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        book_id: &BookId,
+        user_id: &UserId,
+    ) -> BookCopyRepositoryResult<()> {
+        let owner_id = sqlx::query_scalar!(
+            r#"
+                SELECT user_id FROM books WHERE book_id = $1 FOR SHARE
+            "#,
+            book_id.inner_ref(),
+        )
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| BookCopyRepositoryError::Unexpected(e.into()))?;
+
+        match owner_id {
+            None => Err(BookCopyRepositoryError::BookNotFound(book_id.clone())),
+            Some(owner_id) if &owner_id != user_id.inner_ref() => Err(
+                BookCopyRepositoryError::NotBookOwner(book_id.clone(), user_id.clone()),
+            ),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use kernel::model::book_copy::{Barcode, CopyCondition, CopyLocation};
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_manage_book_copies(pool: sqlx::PgPool) -> Result<()> {
+        let repo = BookCopyRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let book_id = BookId::try_from("9890736e-a4e4-461a-a77d-eac3517ef11b".parse::<Uuid>()?)?;
+        let owner_id = UserId::try_from("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c".parse::<Uuid>()?)?;
+
+        // バーコードを指定した冊と、採番させた冊を追加する
+        for barcode in [Some("TEST-0100"), None] {
+            repo.create(CreateBookCopy {
+                book_id: book_id.clone(),
+                barcode: barcode
+                    .map(|b| Barcode::try_from(b.to_string()))
+                    .transpose()?,
+                condition: CopyCondition::New,
+                location: CopyLocation::try_from("2F".to_string())?,
+                requested_by: owner_id.clone(),
+            })
+            .await?;
+        }
+
+        let copies = repo.find_by_book_id(&book_id).await?;
+        assert_eq!(copies.len(), 3);
+        assert!(copies.iter().all(BookCopy::is_available));
+
+        // 同じバーコードは登録できない
+        let res = repo
+            .create(CreateBookCopy {
+                book_id: book_id.clone(),
+                barcode: Some(Barcode::try_from("TEST-0100".to_string())?),
+                condition: CopyCondition::Good,
+                location: CopyLocation::try_from(String::new())?,
+                requested_by: owner_id.clone(),
+            })
+            .await;
+        assert!(matches!(
+            res,
+            Err(BookCopyRepositoryError::DuplicateBarcode(_))
+        ));
+
+        // 所有者以外は冊を削除できない
+        let book_copy_id = copies[0].book_copy_id().clone();
+        let res = repo
+            .delete(DeleteBookCopy {
+                book_copy_id: book_copy_id.clone(),
+                book_id: book_id.clone(),
+                requested_by: UserId::new(Uuid::new_v4()),
+            })
+            .await;
+        assert!(matches!(
+            res,
+            Err(BookCopyRepositoryError::NotBookOwner(..))
+        ));
+
+        // 貸出中の冊は削除できない
+        sqlx::query!(
+            "INSERT INTO checkouts (book_id, book_copy_id, user_id, due_at) VALUES ($1, $2, $3, now())",
+            book_id.inner_ref(),
+            book_copy_id.inner_ref(),
+            owner_id.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+        let res = repo
+            .delete(DeleteBookCopy {
+                book_copy_id: book_copy_id.clone(),
+                book_id: book_id.clone(),
+                requested_by: owner_id.clone(),
+            })
+            .await;
+        assert!(matches!(res, Err(BookCopyRepositoryError::CheckedOut(_))));
+
+        // 返却済みの貸出履歴がある冊も削除できない
+        let returned_copy_id = copies[2].book_copy_id().clone();
+        sqlx::query!(
+            r#"
+                INSERT INTO returned_checkouts
+                (checkout_id, book_id, book_copy_id, user_id, checked_out_at, due_at)
+                VALUES ($1, $2, $3, $4, now(), now())
+            "#,
+            Uuid::new_v4(),
+            book_id.inner_ref(),
+            returned_copy_id.inner_ref(),
+            owner_id.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+        let res = repo
+            .delete(DeleteBookCopy {
+                book_copy_id: returned_copy_id.clone(),
+                book_id: book_id.clone(),
+                requested_by: owner_id.clone(),
+            })
+            .await;
+        assert!(matches!(
+            res,
+            Err(BookCopyRepositoryError::HasCheckoutHistory(id)) if id == returned_copy_id
+        ));
+        let history = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM returned_checkouts WHERE book_copy_id = $1",
+            returned_copy_id.inner_ref(),
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(history, Some(1));
+
+        repo.delete(DeleteBookCopy {
+            book_copy_id: copies[1].book_copy_id().clone(),
+            book_id: book_id.clone(),
+            requested_by: owner_id,
+        })
+        .await?;
+        assert_eq!(repo.find_by_book_id(&book_id).await?.len(), 2);
+
+        Ok(())
+    }
+}
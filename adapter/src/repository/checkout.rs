@@ -4,24 +4,39 @@ use kernel::{
     model::{
         book::BookId,
         checkout::{
-            event::{CreateCheckout, UpdateReturned},
-            Checkout,
+            event::{CreateCheckout, RenewCheckout, UpdateReturned},
+            policy::{BorrowerStanding, CheckoutPolicy, CheckoutPolicyViolation},
+            Checkout, CheckoutListFilter, CheckoutListOptions, LoanTerms,
         },
+        list::{Cursor, CursorPage},
         user::UserId,
         value_object::ValueObject,
     },
     repository::checkout::{CheckoutRepository, CheckoutRepositoryError, CheckoutRepositoryResult},
 };
+use sqlx::types::chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use super::reservation::{fetch_hold_queue_state, fulfill_reservation, refresh_holds};
 use crate::database::{
-    model::checkout::{CheckoutRow, CheckoutStateRow, ReturnedCheckoutRow},
+    model::{
+        checkout::{
+            BookCopyStateRow, BorrowerStandingRow, CheckoutRow, CheckoutStateRow, RenewalStateRow,
+            ReturnedCheckoutRow,
+        },
+        user::UserRoleName,
+    },
+    pagination::take_page,
     ConnectionPool,
 };
 
 #[derive(new)]
 pub struct CheckoutRepositoryImpl {
     db: ConnectionPool,
+    // 返却された冊を予約者のために取り置いておく秒数
+    pickup_window: u64,
+    loan_terms: LoanTerms,
+    policy: CheckoutPolicy,
 }
 
 #[async_trait]
@@ -38,49 +53,131 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
 
         // 事前のチェックとして以下を調べる：
         // - 指定の蔵書IDを持つ蔵書が存在するか
-        // - 存在した場合、蔵書がすでに貸出中でなないか
+        // - 借りようとしている利用者が貸出規則に反していないか
+        // - 冊が指定された場合、その蔵書の冊であり、かつ貸出中でないか
+        // - 冊が指定されなかった場合、貸出中でない冊があるか
+        // - 予約の待ち行列で、借りようとしている利用者より前の予約者に冊が残るか
         //
-        // 上記の両方が YES の場合、このブロックより後の処理に進む
-        {
-            let res = sqlx::query_as!(
-                CheckoutStateRow,
+        // 上記をすべて満たす場合、このブロックより後の処理に進む
+        let (book_copy_id, standing) = {
+            let book_exists = sqlx::query_scalar!(
                 r#"
-                    SELECT
-                        b.book_id,
-                        c.checkout_id AS "checkout_id?: Uuid",
-                        NULL AS "user_id?: Uuid"
-                    FROM books b
-                    LEFT OUTER JOIN checkouts c USING (book_id)
-                    WHERE b.book_id = $1;
+                    SELECT EXISTS (SELECT 1 FROM books WHERE book_id = $1) AS "exists!"
                 "#,
                 event.book_id.inner_ref(),
             )
-            .fetch_optional(&mut *tx)
+            .fetch_one(&mut *tx)
             .await
             .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
 
-            match res {
-                None => return Err(CheckoutRepositoryError::BookNotFound(event.book_id.clone())),
-                Some(CheckoutStateRow {
-                    checkout_id: Some(_),
-                    ..
-                }) => {
-                    return Err(CheckoutRepositoryError::BookAlreadyCheckedOut(
-                        event.book_id.clone(),
-                    ))
+            if !book_exists {
+                return Err(CheckoutRepositoryError::BookNotFound(event.book_id.clone()));
+            }
+
+            let standing = self
+                .fetch_borrower_standing(
+                    &mut tx,
+                    &event.checked_out_by,
+                    &event.book_id,
+                    event.checked_out_at,
+                )
+                .await?;
+            self.policy.check(&standing).map_err(|v| match v {
+                CheckoutPolicyViolation::OwnBook => CheckoutRepositoryError::OwnBook(
+                    event.book_id.clone(),
+                    event.checked_out_by.clone(),
+                ),
+                CheckoutPolicyViolation::HasOverdueLoans(_) => {
+                    CheckoutRepositoryError::HasOverdueLoans(event.checked_out_by.clone())
                 }
-                _ => {}
+                CheckoutPolicyViolation::LoanLimitReached(limit) => {
+                    CheckoutRepositoryError::LoanLimitReached(event.checked_out_by.clone(), limit)
+                }
+            })?;
+
+            let book_copy_id = match &event.book_copy_id {
+                Some(book_copy_id) => {
+                    let res = sqlx::query_as!(
+                        BookCopyStateRow,
+                        r#"
+                            SELECT
+                                bc.book_copy_id,
+                                c.checkout_id AS "checkout_id?: Uuid"
+                            FROM book_copies bc
+                            LEFT OUTER JOIN checkouts c ON c.book_copy_id = bc.book_copy_id
+                            WHERE bc.book_copy_id = $1 AND bc.book_id = $2;
+                        "#,
+                        book_copy_id.inner_ref(),
+                        event.book_id.inner_ref(),
+                    )
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+
+                    match res {
+                        None => {
+                            return Err(CheckoutRepositoryError::BookCopyNotFound(
+                                book_copy_id.clone(),
+                            ))
+                        }
+                        Some(BookCopyStateRow {
+                            checkout_id: Some(_),
+                            ..
+                        }) => {
+                            return Err(CheckoutRepositoryError::BookCopyAlreadyCheckedOut(
+                                book_copy_id.clone(),
+                            ))
+                        }
+                        Some(BookCopyStateRow { book_copy_id, .. }) => book_copy_id,
+                    }
+                }
+                None => sqlx::query_scalar!(
+                    r#"
+                        SELECT bc.book_copy_id
+                        FROM book_copies bc
+                        WHERE bc.book_id = $1
+                            AND NOT EXISTS (
+                                SELECT 1 FROM checkouts c WHERE c.book_copy_id = bc.book_copy_id
+                            )
+                        ORDER BY bc.barcode
+                        LIMIT 1;
+                    "#,
+                    event.book_id.inner_ref(),
+                )
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?
+                .ok_or_else(|| CheckoutRepositoryError::NoAvailableCopy(event.book_id.clone()))?,
+            };
+
+            refresh_holds(&mut tx, &event.book_id, self.pickup_window)
+                .await
+                .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+            let state = fetch_hold_queue_state(&mut tx, &event.book_id, &event.checked_out_by)
+                .await
+                .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+            if !state.allows_checkout() {
+                return Err(CheckoutRepositoryError::ReservedForOthers(
+                    event.book_id.clone(),
+                ));
             }
-        }
+
+            (book_copy_id, standing)
+        };
+
+        // 返却期限は借りる利用者のロールの貸出期間から決める
+        let due_at = event.checked_out_at + self.loan_terms.period_for(&standing.role);
 
         let res = sqlx::query!(
             r#"
-                INSERT INTO checkouts (book_id, user_id, checked_out_at)
-                VALUES ($1, $2, $3)
+                INSERT INTO checkouts (book_id, book_copy_id, user_id, checked_out_at, due_at)
+                VALUES ($1, $2, $3, $4, $5)
             "#,
             event.book_id.inner_ref(),
+            book_copy_id,
             event.checked_out_by.inner_ref(),
             event.checked_out_at,
+            due_at,
         )
         .execute(&mut *tx)
         .await
@@ -92,6 +189,10 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             ));
         }
 
+        fulfill_reservation(&mut tx, &event.book_id, &event.checked_out_by)
+            .await
+            .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+
         tx.commit()
             .await
             .map_err(|e| CheckoutRepositoryError::Transaction(e.into()))?;
@@ -99,32 +200,66 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         Ok(())
     }
 
-    async fn find_unreturned_all(&self) -> CheckoutRepositoryResult<Vec<Checkout>> {
-        let checkouts = sqlx::query_as!(
+    async fn find_unreturned_all(
+        &self,
+        options: CheckoutListOptions,
+    ) -> CheckoutRepositoryResult<CursorPage<Checkout>> {
+        let CheckoutListOptions {
+            limit,
+            after,
+            filter: CheckoutListFilter { overdue },
+        } = options;
+
+        // 貸出日時の昇順に並べ、カーソルより後ろの貸出を取得する
+        let mut checkouts = sqlx::query_as!(
             CheckoutRow,
             r#"
             SELECT
                 c.checkout_id,
                 c.user_id,
                 c.checked_out_at,
+                c.due_at,
+                c.renewal_count,
                 b.book_id,
                 b.title,
                 b.author,
-                b.isbn
+                b.isbn,
+                bc.book_copy_id,
+                bc.barcode
             FROM checkouts c
             INNER JOIN books b ON c.book_id = b.book_id
-            ORDER BY checked_out_at ASC
+            INNER JOIN book_copies bc ON bc.book_copy_id = c.book_copy_id
+            WHERE ($1::text IS NULL
+                OR (c.checked_out_at, c.checkout_id) > (CAST($1::text AS timestamptz), $2::uuid))
+                AND ($4::bool IS NULL OR (c.due_at < now()) = $4)
+            ORDER BY c.checked_out_at ASC, c.checkout_id ASC
+            LIMIT $3
             "#,
+            after.as_ref().and_then(|c| c.sort_key.clone()),
+            after.as_ref().map(|c| c.id),
+            limit.saturating_add(1),
+            overdue,
         )
         .fetch_all(self.db.inner_ref())
         .await
         .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
 
-        checkouts
+        let next_cursor = take_page(&mut checkouts, limit, |row| Cursor {
+            sort_key: Some(row.checked_out_at.to_rfc3339()),
+            id: row.checkout_id,
+        });
+
+        let items = checkouts
             .into_iter()
             .map(Checkout::try_from)
             .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| CheckoutRepositoryError::InvalidSavedEntity(e.into()))
+            .map_err(|e| CheckoutRepositoryError::InvalidSavedEntity(e.into()))?;
+
+        Ok(CursorPage {
+            limit,
+            items,
+            next_cursor,
+        })
     }
 
     async fn find_unreturned_by_user_id(
@@ -138,12 +273,17 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     c.checkout_id,
                     c.user_id,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
                     b.book_id,
                     b.title,
                     b.author,
-                    b.isbn
+                    b.isbn,
+                    bc.book_copy_id,
+                    bc.barcode
                 FROM checkouts c
                 INNER JOIN books b USING (book_id)
+                INNER JOIN book_copies bc ON bc.book_copy_id = c.book_copy_id
                 WHERE c.user_id = $1
                 ORDER BY checked_out_at ASC
             "#,
@@ -171,13 +311,18 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     rc.checkout_id,
                     rc.user_id,
                     rc.checked_out_at,
+                    rc.due_at,
+                    rc.renewal_count,
                     rc.returned_at,
                     b.book_id,
                     b.title,
                     b.author,
-                    b.isbn
+                    b.isbn,
+                    bc.book_copy_id,
+                    bc.barcode
                 FROM returned_checkouts rc
                 INNER JOIN books b USING (book_id)
+                INNER JOIN book_copies bc ON bc.book_copy_id = rc.book_copy_id
                 WHERE rc.book_id = $1
                 ORDER BY checked_out_at DESC
             "#,
@@ -194,21 +339,28 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     c.checkout_id,
                     c.user_id,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
                     b.book_id,
                     b.title,
                     b.author,
-                    b.isbn
+                    b.isbn,
+                    bc.book_copy_id,
+                    bc.barcode
                 FROM books b
                 INNER JOIN checkouts c USING (book_id)
-                WHERE b.book_id = $1;
+                INNER JOIN book_copies bc ON bc.book_copy_id = c.book_copy_id
+                WHERE b.book_id = $1
+                ORDER BY c.checked_out_at DESC;
             "#,
             book_id.inner_ref(),
         )
-        .fetch_optional(self.db.inner_ref())
+        .fetch_all(self.db.inner_ref())
         .await
         .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?
+        .into_iter()
         .map(Checkout::try_from)
-        .transpose()
+        .collect::<Result<Vec<_>, _>>()
         .map_err(|e| CheckoutRepositoryError::InvalidSavedEntity(e.into()))?;
 
         let mut checkouts = checkouts
@@ -217,11 +369,11 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| CheckoutRepositoryError::InvalidSavedEntity(e.into()))?;
 
-        if let Some(checking_out) = checking_out {
-            checkouts.insert(0, checking_out);
-        }
+        // 貸出中のものを先頭に並べる
+        let mut history = checking_out;
+        history.append(&mut checkouts);
 
-        Ok(checkouts)
+        Ok(history)
     }
 
     async fn update_returned(&self, event: UpdateReturned) -> CheckoutRepositoryResult<()> {
@@ -236,8 +388,7 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
 
         // 以下を確認してから後続の処理を行う
         // - 与えられた book_id を持つ蔵書が存在するか
-        // - 仮に存在するとしたら、その蔵書は貸し出し中か（checkouts テーブルにレコードが存在するか）
-        //   - 貸し出し中なら、その貸し出しの ID は与えられた checkout_id に一致するか
+        // - 仮に存在するとしたら、与えられた checkout_id の貸し出しがその蔵書に対するものか
         //   - また、借主は与えられた user_id（returned_by） に一致するか
         {
             let res = sqlx::query_as!(
@@ -248,10 +399,12 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                         c.checkout_id AS "checkout_id?: Uuid",
                         c.user_id AS "user_id?: Uuid"
                     FROM books b
-                    LEFT OUTER JOIN checkouts c USING (book_id)
+                    LEFT OUTER JOIN checkouts c
+                        ON c.book_id = b.book_id AND c.checkout_id = $2
                     WHERE b.book_id = $1;
                 "#,
                 event.book_id.inner_ref(),
+                event.checkout_id.inner_ref(),
             )
             .fetch_optional(&mut *tx)
             .await
@@ -281,15 +434,21 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                 INSERT INTO returned_checkouts (
                     checkout_id,
                     book_id,
+                    book_copy_id,
                     user_id,
                     checked_out_at,
+                    due_at,
+                    renewal_count,
                     returned_at
                 )
                 SELECT
                     c.checkout_id,
                     c.book_id,
+                    c.book_copy_id,
                     c.user_id,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
                     $2
                 FROM checkouts c
                 WHERE c.checkout_id = $1
@@ -324,6 +483,115 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             ));
         }
 
+        // 返却された冊は予約の待ち行列の先頭の利用者のために取り置く
+        refresh_holds(&mut tx, &event.book_id, self.pickup_window)
+            .await
+            .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CheckoutRepositoryError::Transaction(e.into()))?;
+
+        Ok(())
+    }
+
+    async fn renew(&self, event: RenewCheckout) -> CheckoutRepositoryResult<()> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| CheckoutRepositoryError::Transaction(e.into()))?;
+
+        // トランザクション分離レベルをSERIALIZABLEに設定
+        self.set_transaction_serializable(&mut tx).await?;
+
+        // 以下を確認してから返却期限を延長する
+        // - 与えられた checkout_id の貸出がその蔵書に対するもので、借主が延長を求めた利用者であるか
+        // - 延長回数が上限に達していないか
+        // - その蔵書に予約が入っていないか
+        let state = sqlx::query_as!(
+            RenewalStateRow,
+            r#"
+                SELECT
+                    c.user_id,
+                    r.name AS role_name,
+                    c.due_at,
+                    c.renewal_count
+                FROM checkouts c
+                INNER JOIN users u USING (user_id)
+                INNER JOIN roles r USING (role_id)
+                WHERE c.checkout_id = $1 AND c.book_id = $2
+                FOR UPDATE OF c;
+            "#,
+            event.checkout_id.inner_ref(),
+            event.book_id.inner_ref(),
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?
+        .filter(|state| &state.user_id == event.requested_by.inner_ref())
+        .ok_or_else(|| {
+            CheckoutRepositoryError::CannotRenew(
+                event.book_id.clone(),
+                event.requested_by.clone(),
+                event.checkout_id.clone(),
+            )
+        })?;
+
+        if state.renewal_count >= self.loan_terms.max_renewals {
+            return Err(CheckoutRepositoryError::RenewalLimitReached(
+                event.checkout_id.clone(),
+            ));
+        }
+
+        refresh_holds(&mut tx, &event.book_id, self.pickup_window)
+            .await
+            .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+        let reserved = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (SELECT 1 FROM reservations WHERE book_id = $1) AS "exists!"
+            "#,
+            event.book_id.inner_ref(),
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+        if reserved {
+            return Err(CheckoutRepositoryError::RenewalBlockedByReservation(
+                event.book_id.clone(),
+            ));
+        }
+
+        // 延長を求めた時点から貸出期間を数え直す。ただし元の返却期限より早めることはしない
+        let role = state
+            .role_name
+            .parse::<UserRoleName>()
+            .map_err(|e| CheckoutRepositoryError::InvalidSavedEntity(e.into()))?
+            .into();
+        let due_at = std::cmp::max(
+            state.due_at,
+            event.renewed_at + self.loan_terms.period_for(&role),
+        );
+
+        let res = sqlx::query!(
+            r#"
+                UPDATE checkouts
+                SET due_at = $2, renewal_count = renewal_count + 1
+                WHERE checkout_id = $1;
+            "#,
+            event.checkout_id.inner_ref(),
+            due_at,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+
+        if res.rows_affected() < 1 {
+            return Err(CheckoutRepositoryError::NoResourceAffected(
+                "No checkouts record has been updated.".to_string(),
+            ));
+        }
+
         tx.commit()
             .await
             .map_err(|e| CheckoutRepositoryError::Transaction(e.into()))?;
@@ -344,4 +612,314 @@ impl CheckoutRepositoryImpl {
 
         Ok(())
     }
+
+    async fn fetch_borrower_standing(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &UserId,
+        book_id: &BookId,
+        now: DateTime<Utc>,
+    ) -> CheckoutRepositoryResult<BorrowerStanding> {
+        let row = sqlx::query_as!(
+            BorrowerStandingRow,
+            r#"
+                SELECT
+                    r.name AS role_name,
+                    (
+                        SELECT COUNT(*) FROM checkouts c WHERE c.user_id = u.user_id
+                    ) AS "active_loans!",
+                    (
+                        SELECT COUNT(*) FROM checkouts c
+                        WHERE c.user_id = u.user_id AND c.due_at < $3
+                    ) AS "overdue_loans!",
+                    EXISTS (
+                        SELECT 1 FROM books b WHERE b.book_id = $2 AND b.user_id = u.user_id
+                    ) AS "owns_book!"
+                FROM users u
+                INNER JOIN roles r USING (role_id)
+                WHERE u.user_id = $1;
+            "#,
+            user_id.inner_ref(),
+            book_id.inner_ref(),
+            now,
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| CheckoutRepositoryError::Unexpected(e.into()))?;
+
+        row.try_into()
+            .map_err(|e: strum::ParseError| CheckoutRepositoryError::InvalidSavedEntity(e.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use chrono::{Duration, Utc};
+    use kernel::model::{book_copy::BookCopyId, user::UserRole};
+
+    use super::*;
+
+    // 管理者だけ貸出期間を長くし、延長は 1 回まで認める
+    fn loan_terms() -> LoanTerms {
+        LoanTerms {
+            default_period: Duration::days(14),
+            role_periods: [(UserRole::Admin, Duration::days(28))].into(),
+            max_renewals: 1,
+        }
+    }
+
+    fn policy() -> CheckoutPolicy {
+        CheckoutPolicy {
+            default_max_loans: 3,
+            role_max_loans: Default::default(),
+        }
+    }
+
+    fn repository(pool: sqlx::PgPool) -> CheckoutRepositoryImpl {
+        CheckoutRepositoryImpl::new(ConnectionPool::new(pool), 3600, loan_terms(), policy())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "user"))]
+    async fn test_checkout_book_copies(pool: sqlx::PgPool) -> Result<()> {
+        let repo = repository(pool);
+
+        // 2 冊所蔵している蔵書
+        let book_id = BookId::try_from("17afb850-c786-49c5-a303-a3a443a2212c".parse::<Uuid>()?)?;
+        let user_id = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<Uuid>()?)?;
+        let second_copy =
+            BookCopyId::try_from("0a3b1f6e-6a43-4a2b-9d52-0f3a4c1e7b04".parse::<Uuid>()?)?;
+        let checkout = |book_copy_id: Option<BookCopyId>| CreateCheckout {
+            book_id: book_id.clone(),
+            book_copy_id,
+            checked_out_by: user_id.clone(),
+            checked_out_at: Utc::now(),
+        };
+
+        // 冊を指定して貸し出すと、同じ冊は貸し出せない
+        repo.create(checkout(Some(second_copy.clone()))).await?;
+        let res = repo.create(checkout(Some(second_copy.clone()))).await;
+        assert!(matches!(
+            res,
+            Err(CheckoutRepositoryError::BookCopyAlreadyCheckedOut(_))
+        ));
+
+        // 冊を指定しなければ残りの冊が貸し出され、それ以上は貸し出せない
+        repo.create(checkout(None)).await?;
+        let res = repo.create(checkout(None)).await;
+        assert!(matches!(
+            res,
+            Err(CheckoutRepositoryError::NoAvailableCopy(_))
+        ));
+
+        // 別の蔵書の冊は指定できない
+        let res = repo
+            .create(checkout(Some(BookCopyId::try_from(
+                "0a3b1f6e-6a43-4a2b-9d52-0f3a4c1e7b01".parse::<Uuid>()?,
+            )?)))
+            .await;
+        assert!(matches!(
+            res,
+            Err(CheckoutRepositoryError::BookCopyNotFound(_))
+        ));
+
+        let checkouts = repo.find_unreturned_by_user_id(&user_id).await?;
+        assert_eq!(checkouts.len(), 2);
+
+        // 一方を返却しても、もう一方は貸出中のまま
+        let (checkout_id, ..) = checkouts.into_iter().next().unwrap().dissolve();
+        repo.update_returned(UpdateReturned {
+            checkout_id,
+            book_id: book_id.clone(),
+            returned_by: user_id.clone(),
+            returned_at: Utc::now(),
+        })
+        .await?;
+
+        // 貸出中のものが先頭に並ぶ
+        let returned = repo
+            .find_history_by_book_id(&book_id)
+            .await?
+            .into_iter()
+            .map(|c| c.dissolve().5.is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(returned, vec![false, true]);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "user"))]
+    async fn test_renew_checkout(pool: sqlx::PgPool) -> Result<()> {
+        let repo = repository(pool.clone());
+
+        let book_id = BookId::try_from("9890736e-a4e4-461a-a77d-eac3517ef11b".parse::<Uuid>()?)?;
+        // 蔵書の所有者ではない管理者
+        let admin = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02".parse::<Uuid>()?)?;
+        let alice = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<Uuid>()?)?;
+
+        // 返却期限はロールごとの貸出期間で決まる
+        let checked_out_at = Utc::now();
+        repo.create(CreateCheckout {
+            book_id: book_id.clone(),
+            book_copy_id: None,
+            checked_out_by: admin.clone(),
+            checked_out_at,
+        })
+        .await?;
+        let (checkout_id, _, _, due_at, renewal_count, ..) = repo
+            .find_unreturned_by_user_id(&admin)
+            .await?
+            .into_iter()
+            .next()
+            .unwrap()
+            .dissolve();
+        // 日時はミリ秒単位で保存されるため、秒単位で比較する
+        assert_eq!(
+            (due_at - (checked_out_at + Duration::days(28))).num_seconds(),
+            0,
+            "admin loan period should apply"
+        );
+        assert_eq!(renewal_count, 0);
+
+        let renew = |requested_by: &UserId, renewed_at| RenewCheckout {
+            checkout_id: checkout_id.clone(),
+            book_id: book_id.clone(),
+            requested_by: requested_by.clone(),
+            renewed_at,
+        };
+
+        // 借主以外は延長できない
+        let res = repo.renew(renew(&alice, Utc::now())).await;
+        assert!(matches!(res, Err(CheckoutRepositoryError::CannotRenew(..))));
+
+        // 延長すると延長した時点から貸出期間を数え直す
+        let renewed_at = checked_out_at + Duration::days(7);
+        repo.renew(renew(&admin, renewed_at)).await?;
+        let (_, _, _, renewed_due_at, renewal_count, ..) = repo
+            .find_unreturned_by_user_id(&admin)
+            .await?
+            .into_iter()
+            .next()
+            .unwrap()
+            .dissolve();
+        assert_eq!(
+            (renewed_due_at - (renewed_at + Duration::days(28))).num_seconds(),
+            0
+        );
+        assert_eq!(renewal_count, 1);
+
+        // 延長回数の上限に達している
+        let res = repo.renew(renew(&admin, Utc::now())).await;
+        assert!(matches!(
+            res,
+            Err(CheckoutRepositoryError::RenewalLimitReached(_))
+        ));
+
+        // 予約が入っていると延長できない
+        sqlx::query!(
+            "UPDATE checkouts SET renewal_count = 0 WHERE checkout_id = $1",
+            checkout_id.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO reservations (book_id, user_id) VALUES ($1, $2)",
+            book_id.inner_ref(),
+            alice.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+        let res = repo.renew(renew(&admin, Utc::now())).await;
+        assert!(matches!(
+            res,
+            Err(CheckoutRepositoryError::RenewalBlockedByReservation(_))
+        ));
+
+        // 返却期限を過ぎたものだけを絞り込める
+        sqlx::query!(
+            "UPDATE checkouts SET due_at = now() - interval '1 day' WHERE checkout_id = $1",
+            checkout_id.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+        let list = |overdue| {
+            repo.find_unreturned_all(CheckoutListOptions {
+                limit: 10,
+                after: None,
+                filter: CheckoutListFilter { overdue },
+            })
+        };
+        let overdue = list(Some(true)).await?.items;
+        assert_eq!(overdue.len(), 1);
+        assert!(overdue[0].is_overdue(Utc::now()));
+        assert!(list(Some(false)).await?.items.is_empty());
+        assert_eq!(list(None).await?.items.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "user"))]
+    async fn test_checkout_policy(pool: sqlx::PgPool) -> Result<()> {
+        let repo = repository(pool.clone());
+
+        let owner = UserId::try_from("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c".parse::<Uuid>()?)?;
+        let alice = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<Uuid>()?)?;
+        let books = [
+            "9890736e-a4e4-461a-a77d-eac3517ef11b",
+            "f397b83a-dd2a-4a01-9e77-db1eea7de5b6",
+            "17afb850-c786-49c5-a303-a3a443a2212c",
+        ]
+        .into_iter()
+        .map(|id| Ok(BookId::try_from(id.parse::<Uuid>()?)?))
+        .collect::<Result<Vec<_>>>()?;
+        let checkout = |book_id: &BookId, user_id: &UserId| CreateCheckout {
+            book_id: book_id.clone(),
+            book_copy_id: None,
+            checked_out_by: user_id.clone(),
+            checked_out_at: Utc::now(),
+        };
+
+        // 自分が所有する蔵書は借りられない
+        let res = repo.create(checkout(&books[0], &owner)).await;
+        assert!(matches!(res, Err(CheckoutRepositoryError::OwnBook(..))));
+
+        // 同時に借りられる冊数には上限がある
+        for book_id in &books {
+            repo.create(checkout(book_id, &alice)).await?;
+        }
+        let res = repo.create(checkout(&books[2], &alice)).await;
+        assert!(matches!(
+            res,
+            Err(CheckoutRepositoryError::LoanLimitReached(_, 3))
+        ));
+
+        // 返却期限を過ぎた貸出があると、返却するまで新たに借りられない
+        let (checkout_id, ..) = repo
+            .find_unreturned_by_user_id(&alice)
+            .await?
+            .into_iter()
+            .next()
+            .unwrap()
+            .dissolve();
+        sqlx::query!(
+            "UPDATE checkouts SET due_at = now() - interval '1 day' WHERE user_id = $1",
+            alice.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+        repo.update_returned(UpdateReturned {
+            checkout_id,
+            book_id: books[0].clone(),
+            returned_by: alice.clone(),
+            returned_at: Utc::now(),
+        })
+        .await?;
+        let res = repo.create(checkout(&books[0], &alice)).await;
+        assert!(matches!(
+            res,
+            Err(CheckoutRepositoryError::HasOverdueLoans(_))
+        ));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,271 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        mail::Mail,
+        user::{
+            email_change::EmailChangeToken,
+            event::{ConfirmEmailChange, RequestEmailChange},
+            User, UserEmail, UserId,
+        },
+        value_object::ValueObject,
+    },
+    repository::{
+        email_change::{
+            EmailChangeRepository, EmailChangeRepositoryError, EmailChangeRepositoryResult,
+        },
+        mailer::Mailer,
+    },
+};
+use shared::i18n::{self, Locale};
+
+use crate::{
+    database::{
+        model::{
+            email_change::{
+                EmailChangeKey, IssuedEmailChangeToken, PendingEmailChange, UserEmailChangeKey,
+            },
+            user::UserRow,
+        },
+        ConnectionPool,
+    },
+    redis::{RedisClient, RedisClientError},
+};
+
+#[derive(new)]
+pub struct EmailChangeRepositoryImpl {
+    db: ConnectionPool,
+    kvs: Arc<RedisClient>,
+    mailer: Arc<dyn Mailer>,
+    // 確認用のリンクの有効期間の秒数
+    ttl: u64,
+    // メールに載せるリンクの基点となる URL
+    link_base_url: String,
+}
+
+#[async_trait]
+impl EmailChangeRepository for EmailChangeRepositoryImpl {
+    async fn request(&self, event: RequestEmailChange) -> EmailChangeRepositoryResult<()> {
+        // 使われているアドレスにはリンクを送らない。確定するときにも改めて一意制約で確かめる
+        if email_registered(&self.db, &event.new_email).await? {
+            return Err(EmailChangeRepositoryError::EmailAlreadyRegistered);
+        }
+
+        // 最後に送ったリンクだけを有効にする
+        let user_key = UserEmailChangeKey::from(&event.user_id);
+        if let Some(IssuedEmailChangeToken(previous)) =
+            self.kvs.get(&user_key).await.map_err(unexpected)?
+        {
+            self.kvs
+                .delete(&EmailChangeKey::from(&previous))
+                .await
+                .map_err(unexpected)?;
+        }
+        self.kvs
+            .set_ex(
+                &EmailChangeKey::from(&event.token),
+                &PendingEmailChange::new(&event.user_id, &event.new_email),
+                self.ttl,
+            )
+            .await
+            .map_err(unexpected)?;
+        self.kvs
+            .set_ex(
+                &user_key,
+                &IssuedEmailChangeToken(event.token.clone()),
+                self.ttl,
+            )
+            .await
+            .map_err(unexpected)?;
+
+        let mail = confirmation_mail(
+            event.new_email,
+            &self.link_base_url,
+            &event.token,
+            self.ttl,
+            i18n::current(),
+        );
+        self.mailer.send(mail).await?;
+
+        Ok(())
+    }
+
+    async fn confirm(&self, event: ConfirmEmailChange) -> EmailChangeRepositoryResult<User> {
+        // 同じトークンを二度使えないよう、取得と同時に削除する
+        let pending = self
+            .kvs
+            .get_del(&EmailChangeKey::from(&event.token))
+            .await
+            .map_err(unexpected)?
+            .ok_or(EmailChangeRepositoryError::InvalidToken)?;
+        let user_id = UserId::new(pending.user_id);
+        self.kvs
+            .delete(&UserEmailChangeKey::from(&user_id))
+            .await
+            .map_err(unexpected)?;
+
+        let new_email = pending
+            .new_email
+            .parse::<UserEmail>()
+            .map_err(|e| EmailChangeRepositoryError::Unexpected(Box::new(e)))?;
+        update_email(&self.db, &user_id, &new_email).await
+    }
+}
+
+async fn email_registered(
+    db: &ConnectionPool,
+    email: &UserEmail,
+) -> EmailChangeRepositoryResult<bool> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM users WHERE email = $1) AS "exists!""#,
+        email.to_string()
+    )
+    .fetch_one(db.inner_ref())
+    .await
+    .map_err(|e| EmailChangeRepositoryError::Unexpected(Box::new(e)))
+}
+
+async fn update_email(
+    db: &ConnectionPool,
+    user_id: &UserId,
+    new_email: &UserEmail,
+) -> EmailChangeRepositoryResult<User> {
+    let res = sqlx::query_as!(
+        UserRow,
+        r#"
+            UPDATE users u
+            SET email = $1
+            FROM roles r
+            WHERE u.user_id = $2 AND r.role_id = u.role_id
+            RETURNING
+                u.user_id as user_id,
+                u.name as user_name,
+                u.email as user_email,
+                r.name as user_role_name,
+                u.created_at as created_at,
+                u.updated_at as updated_at
+        "#,
+        new_email.to_string(),
+        user_id.inner_ref()
+    )
+    .fetch_optional(db.inner_ref())
+    .await;
+
+    match res {
+        // リンクを送った後に、同じアドレスのユーザーが作られていた
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Err(EmailChangeRepositoryError::EmailAlreadyRegistered)
+        }
+        Err(e) => Err(EmailChangeRepositoryError::Unexpected(Box::new(e))),
+        // リンクを送った後にユーザーが削除された
+        Ok(None) => Err(EmailChangeRepositoryError::InvalidToken),
+        Ok(Some(row)) => {
+            User::try_from(row).map_err(|e| EmailChangeRepositoryError::Unexpected(e.into()))
+        }
+    }
+}
+
+// 変更を確認するメール。リンク先はフロントエンドの画面で、
+// そこから /auth/email-change/confirm にトークンを送る
+fn confirmation_mail(
+    to: UserEmail,
+    link_base_url: &str,
+    token: &EmailChangeToken,
+    ttl: u64,
+    locale: Locale,
+) -> Mail {
+    let url = format!(
+        "{link_base_url}/email-change?token={}",
+        urlencoding::encode(token.inner_ref())
+    );
+    let hours = (ttl / 3600).max(1);
+    let (subject, body) = match locale {
+        Locale::Ja => (
+            "メールアドレスの変更の確認",
+            format!(
+                "メールアドレスをこのアドレスに変更する申し込みがありました。\n\
+                 次のリンクを {hours} 時間以内に開いて変更を完了してください。\n\n\
+                 {url}\n\n\
+                 お心当たりがない場合は、このメールを破棄してください。メールアドレスは変更されません。\n"
+            ),
+        ),
+        Locale::En => (
+            "Confirm your new email address",
+            format!(
+                "We received a request to change your email address to this address.\n\
+                 Open the link below within {hours} hours to complete the change.\n\n\
+                 {url}\n\n\
+                 If you did not request this, you can ignore this email. Your email address will not change.\n"
+            ),
+        ),
+    };
+
+    Mail {
+        to,
+        subject: subject.to_string(),
+        body,
+    }
+}
+
+fn unexpected(e: RedisClientError) -> EmailChangeRepositoryError {
+    EmailChangeRepositoryError::Unexpected(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_update_email(pool: sqlx::PgPool) -> Result<()> {
+        let db = ConnectionPool::new(pool);
+        let alice = UserId::new("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse()?);
+
+        assert!(email_registered(&db, &"carol@example.com".parse()?).await?);
+        assert!(!email_registered(&db, &"alice@library.example.com".parse()?).await?);
+
+        let user = update_email(&db, &alice, &"alice@library.example.com".parse()?).await?;
+        assert_eq!(user.email().to_string(), "alice@library.example.com");
+        assert_eq!(user.user_name().inner_ref(), "Alice");
+
+        // 確認している間に他のユーザーが使い始めたアドレス
+        let res = update_email(&db, &alice, &"carol@example.com".parse()?).await;
+        assert!(matches!(
+            res,
+            Err(EmailChangeRepositoryError::EmailAlreadyRegistered)
+        ));
+
+        let res = update_email(
+            &db,
+            &UserId::new(uuid::Uuid::new_v4()),
+            &"nobody@example.com".parse()?,
+        )
+        .await;
+        assert!(matches!(res, Err(EmailChangeRepositoryError::InvalidToken)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_confirmation_mail_links_to_token() -> Result<()> {
+        let token = EmailChangeToken::new("0123abcd".into());
+        let mail = confirmation_mail(
+            "alice@library.example.com".parse()?,
+            "https://library.example.com",
+            &token,
+            86400,
+            Locale::En,
+        );
+
+        assert_eq!(mail.to.to_string(), "alice@library.example.com");
+        assert!(mail
+            .body
+            .contains("https://library.example.com/email-change?token=0123abcd"));
+        assert!(mail.body.contains("within 24 hours"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+INSERT INTO
+    users (user_id, name, email, password_hash, role_id)
+SELECT
+    v.user_id::uuid, v.name, v.email, '$2b$12$hYF2CCJeGdxhrAv7yAlnyuqNG8kJM7FxfQOrUbxEbG.RIhYusziC2', roles.role_id
+FROM
+    (
+        VALUES
+            ('6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01', 'Alice', 'alice@example.com', 'User'),
+            ('6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02', 'Bob', 'bob@example.com', 'Admin'),
+            ('6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e03', 'Carol', 'carol@example.com', 'User')
+    ) AS v(user_id, name, email, role_name)
+INNER JOIN roles ON roles.name = v.role_name;
@@ -0,0 +1,446 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        invitation::{
+            event::{AcceptInvitation, CreateInvitation, DeleteInvitation},
+            Invitation, InvitationToken,
+        },
+        mail::Mail,
+        user::{password::PasswordPolicy, User, UserEmail, UserId, UserRole},
+        value_object::ValueObject,
+    },
+    repository::{
+        invitation::{InvitationRepository, InvitationRepositoryError, InvitationRepositoryResult},
+        mailer::Mailer,
+    },
+};
+use sha2::{Digest, Sha256};
+use shared::i18n::{self, Locale};
+
+use crate::{
+    database::{
+        model::{invitation::InvitationRow, user::UserRoleName},
+        ConnectionPool,
+    },
+    password,
+};
+
+#[derive(new)]
+pub struct InvitationRepositoryImpl {
+    db: ConnectionPool,
+    mailer: Arc<dyn Mailer>,
+    password_policy: PasswordPolicy,
+    // 招待のリンクの有効期間の秒数
+    ttl: u64,
+    // メールに載せるリンクの基点となる URL
+    link_base_url: String,
+}
+
+#[async_trait]
+impl InvitationRepository for InvitationRepositoryImpl {
+    async fn create(&self, event: CreateInvitation) -> InvitationRepositoryResult<Invitation> {
+        let CreateInvitation {
+            email,
+            role,
+            invited_by,
+            token,
+        } = event;
+        let role_name = UserRoleName::from(role.clone()).to_string();
+        let expires_at =
+            Utc::now() + Duration::seconds(i64::try_from(self.ttl).unwrap_or(i64::MAX));
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        let registered = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM users WHERE email = $1) AS "exists!""#,
+            email.to_string()
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+        if registered {
+            return Err(InvitationRepositoryError::EmailAlreadyRegistered);
+        }
+
+        // 招待し直した場合は、以前に送ったリンクを使えないようにする
+        sqlx::query!(
+            r#"DELETE FROM user_invitations WHERE email = $1"#,
+            email.to_string()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        let row = sqlx::query_as!(
+            InvitationRow,
+            r#"
+                INSERT INTO user_invitations (email, role_id, token_hash, invited_by, expires_at)
+                SELECT $1, r.role_id, $3, $4, $5
+                FROM roles r
+                WHERE r.name = $2
+                RETURNING
+                    invitation_id,
+                    email,
+                    $2 AS "role_name!",
+                    invited_by,
+                    expires_at,
+                    created_at
+            "#,
+            email.to_string(),
+            role_name,
+            hash_token(&token),
+            invited_by.inner_ref(),
+            expires_at,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+        let invitation = Invitation::try_from(row)
+            .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        // メールを送れなかった場合は招待を残さない
+        let mail = invitation_mail(
+            email,
+            &self.link_base_url,
+            &token,
+            &role,
+            *invitation.expires_at(),
+            i18n::current(),
+        );
+        self.mailer.send(mail).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        Ok(invitation)
+    }
+
+    async fn find_pending(&self) -> InvitationRepositoryResult<Vec<Invitation>> {
+        sqlx::query_as!(
+            InvitationRow,
+            r#"
+                SELECT
+                    i.invitation_id,
+                    i.email,
+                    r.name AS role_name,
+                    i.invited_by,
+                    i.expires_at,
+                    i.created_at
+                FROM user_invitations i
+                INNER JOIN roles r ON r.role_id = i.role_id
+                WHERE i.expires_at > CURRENT_TIMESTAMP(3)
+                ORDER BY i.created_at DESC, i.invitation_id DESC
+            "#
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?
+        .into_iter()
+        .map(Invitation::try_from)
+        .collect::<Result<_, _>>()
+        .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))
+    }
+
+    async fn delete(&self, event: DeleteInvitation) -> InvitationRepositoryResult<()> {
+        let res = sqlx::query!(
+            r#"DELETE FROM user_invitations WHERE invitation_id = $1"#,
+            event.invitation_id.inner_ref()
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        if res.rows_affected() < 1 {
+            return Err(InvitationRepositoryError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn accept(&self, event: AcceptInvitation) -> InvitationRepositoryResult<User> {
+        let AcceptInvitation {
+            token,
+            name,
+            password,
+        } = event;
+        // ポリシーに反するパスワードで招待を使い切らないよう、先に確認する
+        password.check(&self.password_policy)?;
+        let password_hash = password::hash(password.inner_ref())
+            .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        // 同じ招待を二度使えないよう、取得と同時に削除する
+        let invitation = sqlx::query!(
+            r#"
+                DELETE FROM user_invitations i
+                USING roles r
+                WHERE i.token_hash = $1
+                    AND i.expires_at > CURRENT_TIMESTAMP(3)
+                    AND r.role_id = i.role_id
+                RETURNING i.email, i.role_id, r.name AS role_name
+            "#,
+            hash_token(&token)
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?
+        .ok_or(InvitationRepositoryError::InvalidToken)?;
+
+        // 招待した後に同じメールアドレスのユーザーが作られていた
+        let user_id = sqlx::query_scalar!(
+            r#"
+                INSERT INTO users (name, email, password_hash, role_id)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (email) DO NOTHING
+                RETURNING user_id
+            "#,
+            name.inner_ref(),
+            invitation.email,
+            password_hash,
+            invitation.role_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?
+        .ok_or(InvitationRepositoryError::EmailAlreadyRegistered)?;
+
+        tx.commit()
+            .await
+            .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        let email = invitation
+            .email
+            .parse::<UserEmail>()
+            .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+        let role = invitation
+            .role_name
+            .parse::<UserRoleName>()
+            .map_err(|e| InvitationRepositoryError::Unexpected(Box::new(e)))?;
+
+        Ok(User::new(UserId::new(user_id), name, role.into(), email))
+    }
+}
+
+// トークンは十分な長さの乱数なので、ソルトなしの SHA-256 で照合する
+fn hash_token(token: &InvitationToken) -> String {
+    format!("{:x}", Sha256::digest(token.inner_ref().as_bytes()))
+}
+
+// 招待を知らせるメール。リンク先はフロントエンドの画面で、そこで名前とパスワードを入力して
+// /auth/invitations/{token}/accept に送る
+fn invitation_mail(
+    to: UserEmail,
+    link_base_url: &str,
+    token: &InvitationToken,
+    role: &UserRole,
+    expires_at: DateTime<Utc>,
+    locale: Locale,
+) -> Mail {
+    let url = format!(
+        "{link_base_url}/invitations/accept?token={}",
+        urlencoding::encode(token.inner_ref())
+    );
+    let expires_at = expires_at.format("%Y-%m-%d %H:%M UTC");
+    let (subject, body) = match locale {
+        Locale::Ja => {
+            let role = match role {
+                UserRole::Admin => "管理者",
+                UserRole::User => "利用者",
+            };
+            (
+                "蔵書管理システムへの招待",
+                format!(
+                    "蔵書管理システムに{role}として招待されました。\n\
+                     次のリンクから名前とパスワードを設定して登録を完了してください。\n\n\
+                     {url}\n\n\
+                     リンクの有効期限は {expires_at} です。\n\
+                     お心当たりがない場合は、このメールを破棄してください。\n"
+                ),
+            )
+        }
+        Locale::En => {
+            let role = match role {
+                UserRole::Admin => "an administrator",
+                UserRole::User => "a user",
+            };
+            (
+                "You're invited to Rusty Book Manager",
+                format!(
+                    "You have been invited to Rusty Book Manager as {role}.\n\
+                     Use the link below to choose your name and password and finish signing up.\n\n\
+                     {url}\n\n\
+                     The link expires at {expires_at}.\n\
+                     If you were not expecting this invitation, you can ignore this email.\n"
+                ),
+            )
+        }
+    };
+
+    Mail {
+        to,
+        subject: subject.to_string(),
+        body,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use anyhow::Result;
+    use kernel::{
+        model::user::{password::PasswordPolicyError, Password},
+        repository::mailer::MockMailer,
+    };
+
+    use super::*;
+
+    fn repository(pool: sqlx::PgPool, mailer: MockMailer) -> InvitationRepositoryImpl {
+        InvitationRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            Arc::new(mailer),
+            PasswordPolicy::default(),
+            3600,
+            "https://library.example.com".into(),
+        )
+    }
+
+    fn accept(token: &InvitationToken, password: &str) -> AcceptInvitation {
+        AcceptInvitation {
+            token: token.clone(),
+            name: "Dave".to_string().try_into().unwrap(),
+            password: Password::new(password.into()),
+        }
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_invitation_lifecycle(pool: sqlx::PgPool) -> Result<()> {
+        let sent = Arc::new(Mutex::new(Vec::<Mail>::new()));
+        let mut mailer = MockMailer::new();
+        let outbox = sent.clone();
+        mailer.expect_send().returning(move |mail| {
+            outbox.lock().unwrap().push(mail);
+            Ok(())
+        });
+        let repo = repository(pool, mailer);
+        let bob = UserId::new("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02".parse()?);
+        let dave: UserEmail = "dave@example.com".parse()?;
+
+        // 登録済みのメールアドレスは招待できない
+        let res = repo
+            .create(CreateInvitation::new(
+                "carol@example.com".parse()?,
+                UserRole::User,
+                bob.clone(),
+            ))
+            .await;
+        assert!(matches!(
+            res,
+            Err(InvitationRepositoryError::EmailAlreadyRegistered)
+        ));
+
+        let first = CreateInvitation::new(dave.clone(), UserRole::User, bob.clone());
+        let first_token = first.token.clone();
+        repo.create(first).await?;
+
+        // 招待し直すと以前のリンクは使えなくなる
+        let second = CreateInvitation::new(dave.clone(), UserRole::Admin, bob.clone());
+        let second_token = second.token.clone();
+        let invitation = repo.create(second).await?;
+        assert_eq!(invitation.invited_by().as_ref(), Some(&bob));
+
+        let pending = repo.find_pending().await?;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].role(), &UserRole::Admin);
+
+        let mails = sent.lock().unwrap().clone();
+        assert_eq!(mails.len(), 2);
+        assert_eq!(mails[1].to, dave);
+        assert!(mails[1].body.contains(&format!(
+            "https://library.example.com/invitations/accept?token={}",
+            second_token.inner_ref()
+        )));
+
+        let res = repo.accept(accept(&first_token, "a-new-password")).await;
+        assert!(matches!(res, Err(InvitationRepositoryError::InvalidToken)));
+        let res = repo.accept(accept(&second_token, "password")).await;
+        assert!(matches!(
+            res,
+            Err(InvitationRepositoryError::WeakPassword(
+                PasswordPolicyError::Common
+            ))
+        ));
+
+        let user = repo.accept(accept(&second_token, "a-new-password")).await?;
+        assert_eq!(user.email(), &dave);
+        assert_eq!(user.role(), &UserRole::Admin);
+        assert!(repo.find_pending().await?.is_empty());
+
+        let res = repo.accept(accept(&second_token, "a-new-password")).await;
+        assert!(matches!(res, Err(InvitationRepositoryError::InvalidToken)));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_delete_invitation(pool: sqlx::PgPool) -> Result<()> {
+        let mut mailer = MockMailer::new();
+        mailer.expect_send().returning(|_| Ok(()));
+        let repo = repository(pool, mailer);
+        let bob = UserId::new("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02".parse()?);
+
+        let event = CreateInvitation::new("dave@example.com".parse()?, UserRole::User, bob);
+        let token = event.token.clone();
+        let invitation = repo.create(event).await?;
+
+        let delete = || DeleteInvitation {
+            invitation_id: invitation.invitation_id().clone(),
+        };
+        repo.delete(delete()).await?;
+        let res = repo.delete(delete()).await;
+        assert!(matches!(res, Err(InvitationRepositoryError::NotFound)));
+
+        let res = repo.accept(accept(&token, "a-new-password")).await;
+        assert!(matches!(res, Err(InvitationRepositoryError::InvalidToken)));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_invitation_is_not_saved_when_mail_fails(pool: sqlx::PgPool) -> Result<()> {
+        let mut mailer = MockMailer::new();
+        mailer.expect_send().returning(|_| {
+            Err(kernel::repository::mailer::MailerError::Transport(
+                "connection refused".into(),
+            ))
+        });
+        let repo = repository(pool, mailer);
+        let bob = UserId::new("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02".parse()?);
+
+        let res = repo
+            .create(CreateInvitation::new(
+                "dave@example.com".parse()?,
+                UserRole::User,
+                bob,
+            ))
+            .await;
+        assert!(matches!(res, Err(InvitationRepositoryError::Mailer(_))));
+        assert!(repo.find_pending().await?.is_empty());
+
+        Ok(())
+    }
+}
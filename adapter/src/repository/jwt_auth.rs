@@ -0,0 +1,275 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{
+            event::{CreateToken, DeleteSession, RotateToken},
+            jwk::PublicJwk,
+            session::Session,
+            AccessToken, AuthTokens, TokenSubject,
+        },
+        user::{Password, UserEmail, UserId},
+        value_object::ValueObject,
+    },
+    repository::auth::{AuthRepository, AuthRepositoryError, AuthRepositoryResult},
+};
+
+use crate::{
+    database::{
+        model::{
+            auth::{Revoked, RevokedTokenKey, TokenFamily, TokenFamilyId},
+            user::UserRoleName,
+        },
+        ConnectionPool,
+    },
+    jwt::{AccessTokenClaims, JwtKeySet},
+    redis::RedisClient,
+    repository::auth::{unexpected, verify_password, Refresh, TokenFamilies},
+};
+
+// アクセストークンに署名付きの JWT を使う AuthRepository。
+// 検証は署名と拒否リストの確認のみで済み、失効させたトークンだけを Redis に保存する。
+// リフレッシュトークンとセッションの扱いは AuthRepositoryImpl と同じ
+#[derive(new)]
+pub struct JwtAuthRepositoryImpl {
+    db: ConnectionPool,
+    kvs: Arc<RedisClient>,
+    keys: Arc<JwtKeySet>,
+    ttl: u64,
+    refresh_ttl: u64,
+}
+
+#[async_trait]
+impl AuthRepository for JwtAuthRepositoryImpl {
+    async fn fetch_token_subject(
+        &self,
+        access_token: &AccessToken,
+    ) -> AuthRepositoryResult<Option<TokenSubject>> {
+        let Ok(claims) = self.keys.verify(access_token.inner_ref()) else {
+            return Ok(None);
+        };
+        let Ok(role) = claims.role.parse::<UserRoleName>() else {
+            return Ok(None);
+        };
+
+        let revoked = self
+            .kvs
+            .exists_any(&[
+                RevokedTokenKey::Token(claims.jti),
+                RevokedTokenKey::Family(claims.sid.into()),
+            ])
+            .await
+            .map_err(unexpected)?;
+        if revoked {
+            return Ok(None);
+        }
+
+        Ok(Some(TokenSubject {
+            user_id: UserId::new(claims.sub),
+            role: Some(role.into()),
+        }))
+    }
+
+    async fn verify_user(
+        &self,
+        email: &UserEmail,
+        password: &Password,
+    ) -> AuthRepositoryResult<UserId> {
+        verify_password(&self.db, email, password).await
+    }
+
+    // イベントの不透明なアクセストークンは使わず、署名付きのトークンに置き換える
+    async fn create_token(&self, event: CreateToken) -> AuthRepositoryResult<AuthTokens> {
+        let family_id = TokenFamilyId::generate();
+        let access_token = self.sign(&event.user_id, family_id).await?;
+
+        let family = TokenFamily {
+            user_id: event.user_id,
+            access_token,
+            refresh_token: event.refresh_token,
+            client: event.client,
+            created_at: Utc::now(),
+        };
+        self.issue_tokens(family_id, family).await
+    }
+
+    async fn rotate_token(&self, event: RotateToken) -> AuthRepositoryResult<AuthTokens> {
+        let RotateToken {
+            refresh_token,
+            new_refresh_token,
+            client,
+            ..
+        } = event;
+
+        let (family_id, current) = match self.families().refresh(&refresh_token).await? {
+            Refresh::Valid(family_id, family) => (family_id, family),
+            Refresh::Reused(family_id, family) => {
+                // 漏洩したトークンが使われた可能性があるため、ファミリーごと失効させる
+                self.revoke_family(family_id, &family.user_id).await?;
+                return Err(AuthRepositoryError::RefreshTokenReused);
+            }
+        };
+
+        // 置き換える前のアクセストークンは有効期限まで拒否する。
+        // ロールが変わっている場合もあるため、新しいトークンのロールは改めて取得する
+        self.deny_token(&current.access_token).await?;
+        let access_token = self.sign(&current.user_id, family_id).await?;
+
+        let family = TokenFamily {
+            access_token,
+            refresh_token: new_refresh_token,
+            client,
+            ..current
+        };
+        self.issue_tokens(family_id, family).await
+    }
+
+    async fn delete_token(&self, access_token: &AccessToken) -> AuthRepositoryResult<()> {
+        let Ok(claims) = self.keys.read_claims(access_token.inner_ref()) else {
+            return Ok(());
+        };
+        self.revoke_family(claims.sid.into(), &UserId::new(claims.sub))
+            .await
+    }
+
+    // 最終利用日時はトークンの発行時とリフレッシュ時にだけ更新する
+    async fn find_sessions(
+        &self,
+        user_id: &UserId,
+        current: &AccessToken,
+    ) -> AuthRepositoryResult<Vec<Session>> {
+        self.families().find_sessions(user_id, current).await
+    }
+
+    async fn delete_session(&self, event: DeleteSession) -> AuthRepositoryResult<()> {
+        let family_id = TokenFamilyId::from(&event.session_id);
+        let family = self.families().find(family_id).await?;
+
+        // 他のユーザーのセッションは存在しないものとして扱う
+        match family {
+            Some(family) if family.user_id == event.user_id => {
+                self.revoke_family(family_id, &event.user_id).await
+            }
+            _ => Err(AuthRepositoryError::SessionNotFound),
+        }
+    }
+
+    async fn delete_all_sessions(&self, user_id: &UserId) -> AuthRepositoryResult<()> {
+        for family_id in self.families().find_ids(user_id).await? {
+            self.revoke_family(family_id, user_id).await?;
+        }
+        self.families().clear(user_id).await
+    }
+
+    async fn delete_other_sessions(
+        &self,
+        user_id: &UserId,
+        current: &AccessToken,
+    ) -> AuthRepositoryResult<()> {
+        for family_id in self.families().find_ids(user_id).await? {
+            let family = self.families().find(family_id).await?;
+            if family.is_some_and(|x| &x.access_token == current) {
+                continue;
+            }
+            self.revoke_family(family_id, user_id).await?;
+        }
+        Ok(())
+    }
+
+    fn public_keys(&self) -> Vec<PublicJwk> {
+        self.keys.public_keys()
+    }
+}
+
+impl JwtAuthRepositoryImpl {
+    fn families(&self) -> TokenFamilies<'_> {
+        TokenFamilies {
+            kvs: &self.kvs,
+            refresh_ttl: self.refresh_ttl,
+        }
+    }
+
+    // 発行時点のロールを含むアクセストークンを作る
+    async fn sign(
+        &self,
+        user_id: &UserId,
+        family_id: TokenFamilyId,
+    ) -> AuthRepositoryResult<AccessToken> {
+        let role = sqlx::query_scalar!(
+            r#"
+                SELECT r.name
+                FROM users u
+                INNER JOIN roles r ON r.role_id = u.role_id
+                WHERE u.user_id = $1
+            "#,
+            user_id.inner_ref()
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))?;
+
+        self.keys
+            .issue(*user_id.inner_ref(), role, family_id.into(), self.ttl)
+            .map(AccessToken::new)
+            .map_err(|e| AuthRepositoryError::Unexpected(Box::new(e)))
+    }
+
+    async fn issue_tokens(
+        &self,
+        family_id: TokenFamilyId,
+        family: TokenFamily,
+    ) -> AuthRepositoryResult<AuthTokens> {
+        self.families().save(family_id, &family).await?;
+
+        let TokenFamily {
+            user_id,
+            access_token,
+            refresh_token,
+            ..
+        } = family;
+        Ok(AuthTokens {
+            user_id,
+            access_token,
+            refresh_token,
+            expires_in: self.ttl,
+        })
+    }
+
+    // セッションの情報を削除し、そのセッションで発行したアクセストークンを拒否する。
+    // 最後に発行したトークンも ttl 秒後には期限が切れるため、拒否リストもその間だけ残す
+    async fn revoke_family(
+        &self,
+        family_id: TokenFamilyId,
+        user_id: &UserId,
+    ) -> AuthRepositoryResult<()> {
+        if self.families().remove(family_id, user_id).await?.is_some() {
+            self.kvs
+                .set_ex(&RevokedTokenKey::Family(family_id), &Revoked, self.ttl)
+                .await
+                .map_err(unexpected)?;
+        }
+        Ok(())
+    }
+
+    async fn deny_token(&self, access_token: &AccessToken) -> AuthRepositoryResult<()> {
+        let Ok(AccessTokenClaims { jti, exp, .. }) =
+            self.keys.read_claims(access_token.inner_ref())
+        else {
+            return Ok(());
+        };
+        // 期限切れのトークンは拒否リストに載せるまでもない
+        let Some(remaining) = u64::try_from(exp - Utc::now().timestamp())
+            .ok()
+            .filter(|remaining| *remaining > 0)
+        else {
+            return Ok(());
+        };
+        self.kvs
+            .set_ex(&RevokedTokenKey::Token(jti), &Revoked, remaining)
+            .await
+            .map_err(unexpected)
+    }
+}
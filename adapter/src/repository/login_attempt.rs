@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{
+            event::RecordLoginFailure,
+            session::SessionClient,
+            throttle::{LoginLockout, LoginThrottle},
+        },
+        user::UserEmail,
+    },
+    repository::login_attempt::{
+        LoginAttemptRepository, LoginAttemptRepositoryError, LoginAttemptRepositoryResult,
+    },
+};
+
+use crate::{
+    database::{
+        model::login_attempt::{
+            LoginFailureCount, LoginFailureCountKey, LoginLockoutKey, LoginSubject,
+        },
+        ConnectionPool,
+    },
+    redis::{RedisClient, RedisClientError},
+};
+
+#[derive(new)]
+pub struct LoginAttemptRepositoryImpl {
+    db: ConnectionPool,
+    kvs: Arc<RedisClient>,
+    throttle: LoginThrottle,
+}
+
+#[async_trait]
+impl LoginAttemptRepository for LoginAttemptRepositoryImpl {
+    async fn find_lockout(
+        &self,
+        email: &UserEmail,
+        client: &SessionClient,
+    ) -> LoginAttemptRepositoryResult<Option<LoginLockout>> {
+        let mut subjects = vec![LoginSubject::email(email)];
+        if let Some(ip_address) = &client.ip_address {
+            subjects.push(LoginSubject::ip_address(ip_address));
+        }
+
+        let mut retry_after = None;
+        for subject in subjects {
+            let ttl = self
+                .kvs
+                .ttl(&LoginLockoutKey::from(subject))
+                .await
+                .map_err(unexpected)?;
+            retry_after = retry_after.max(ttl);
+        }
+
+        Ok(retry_after
+            .filter(|x| *x > 0)
+            .map(|retry_after| LoginLockout { retry_after }))
+    }
+
+    async fn record_failure(&self, event: RecordLoginFailure) -> LoginAttemptRepositoryResult<()> {
+        let RecordLoginFailure { email, client } = event;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO login_failures (email, ip_address, user_agent)
+                VALUES ($1, $2, $3)
+            "#,
+            email.to_string(),
+            client.ip_address,
+            client.user_agent,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| LoginAttemptRepositoryError::Unexpected(Box::new(e)))?;
+
+        self.count_failure(
+            LoginSubject::email(&email),
+            self.throttle.max_failures_per_email,
+        )
+        .await?;
+        if let Some(ip_address) = &client.ip_address {
+            self.count_failure(
+                LoginSubject::ip_address(ip_address),
+                self.throttle.max_failures_per_ip,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    // 同じ IP アドレスから別のアカウントへの攻撃を続けられないよう、IP アドレスの失敗回数は消さない
+    async fn clear_failures(&self, email: &UserEmail) -> LoginAttemptRepositoryResult<()> {
+        self.kvs
+            .delete(&LoginFailureCountKey::from(LoginSubject::email(email)))
+            .await
+            .map_err(unexpected)
+    }
+}
+
+impl LoginAttemptRepositoryImpl {
+    async fn count_failure(
+        &self,
+        subject: LoginSubject,
+        max_failures: u64,
+    ) -> LoginAttemptRepositoryResult<()> {
+        let failures = self
+            .kvs
+            .incr(
+                &LoginFailureCountKey::from(subject.clone()),
+                self.throttle.lockout_max,
+            )
+            .await
+            .map_err(unexpected)?;
+
+        let Some(lockout) = self.throttle.lockout_for(failures, max_failures) else {
+            return Ok(());
+        };
+        self.kvs
+            .set_ex(
+                &LoginLockoutKey::from(subject),
+                &LoginFailureCount(failures),
+                lockout,
+            )
+            .await
+            .map_err(unexpected)
+    }
+}
+
+fn unexpected(e: RedisClientError) -> LoginAttemptRepositoryError {
+    LoginAttemptRepositoryError::Unexpected(Box::new(e))
+}
@@ -0,0 +1,360 @@
+use std::{str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{
+            event::CompleteOidcLogin,
+            oidc::{OidcAuthorization, OidcLogin, OidcState, OIDC_LOGIN_TTL},
+        },
+        user::{UserEmail, UserId, UserName},
+        value_object::ValueObject,
+    },
+    repository::oidc::{OidcRepository, OidcRepositoryError, OidcRepositoryResult},
+};
+
+use crate::{
+    database::{
+        model::{
+            oidc::{OidcLoginKey, OidcLoginState},
+            user::UserRoleName,
+        },
+        ConnectionPool,
+    },
+    oidc::{self, OidcClient, OidcError, OidcIdentity},
+    redis::RedisClient,
+};
+
+#[derive(new)]
+pub struct OidcRepositoryImpl {
+    db: ConnectionPool,
+    kvs: Arc<RedisClient>,
+    // シングルサインオンを設定していない場合は None
+    client: Option<Arc<OidcClient>>,
+}
+
+#[async_trait]
+impl OidcRepository for OidcRepositoryImpl {
+    async fn begin_login(&self) -> OidcRepositoryResult<OidcAuthorization> {
+        let client = self.client()?;
+        let state = OidcState::new(oidc::generate_state());
+        let login = OidcLoginState {
+            nonce: oidc::generate_state(),
+            code_verifier: oidc::generate_code_verifier(),
+        };
+
+        let authorization_url = client
+            .authorization_url(state.inner_ref(), &login.nonce, &login.code_verifier)
+            .await
+            .map_err(oidc_error)?;
+        self.kvs
+            .set_ex(&OidcLoginKey::from(&state), &login, OIDC_LOGIN_TTL)
+            .await
+            .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?;
+
+        Ok(OidcAuthorization { authorization_url })
+    }
+
+    async fn complete_login(&self, event: CompleteOidcLogin) -> OidcRepositoryResult<OidcLogin> {
+        let client = self.client()?;
+
+        // 同じ state でのコールバックを二度受け付けないよう、取得と同時に削除する
+        let login = self
+            .kvs
+            .get_del(&OidcLoginKey::from(&event.state))
+            .await
+            .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?
+            .ok_or(OidcRepositoryError::InvalidState)?;
+
+        let id_token = client
+            .exchange_code(event.code.inner_ref(), &login.code_verifier)
+            .await
+            .map_err(oidc_error)?;
+        let identity = client
+            .validate_id_token(&id_token, &login.nonce)
+            .await
+            .map_err(oidc_error)?;
+
+        self.provision(identity).await
+    }
+}
+
+impl OidcRepositoryImpl {
+    fn client(&self) -> OidcRepositoryResult<&OidcClient> {
+        self.client
+            .as_deref()
+            .ok_or(OidcRepositoryError::NotConfigured)
+    }
+
+    // IdP の利用者に紐づくユーザーを返す。紐づくユーザーがいない場合は、
+    // 同じメールアドレスのユーザーに紐づけるか、パスワードを持たないユーザーを作成する。
+    // ロールを決めるクレームを設定している場合は、ログインのたびにロールを IdP に合わせる
+    async fn provision(&self, identity: OidcIdentity) -> OidcRepositoryResult<OidcLogin> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?;
+
+        let linked = sqlx::query!(
+            r#"
+                SELECT u.user_id, u.email, r.name AS role_name
+                FROM user_oidc_identities i
+                INNER JOIN users u ON u.user_id = i.user_id
+                INNER JOIN roles r ON r.role_id = u.role_id
+                WHERE i.issuer = $1 AND i.subject = $2
+            "#,
+            identity.issuer,
+            identity.subject,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?
+        .map(|row| (row.user_id, row.role_name, row.email));
+
+        let (user_id, current_role, email) = match linked {
+            Some(linked) => linked,
+            // メールアドレスの持ち主であることを IdP が確認していない場合は、既存のユーザーを乗っ取ったり、
+            // 持ち主より先にそのメールアドレスでユーザーを作ったりできてしまうため受け付けない
+            None if !identity.email_verified => return Err(OidcRepositoryError::EmailNotVerified),
+            None => {
+                let existing = sqlx::query!(
+                    r#"
+                        SELECT u.user_id, r.name AS role_name
+                        FROM users u
+                        INNER JOIN roles r ON r.role_id = u.role_id
+                        WHERE u.email = $1
+                    "#,
+                    identity.email.to_string(),
+                )
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?;
+
+                let (user_id, role_name) = match existing {
+                    Some(row) => (row.user_id, row.role_name),
+                    None => {
+                        let role = identity.role.clone().unwrap_or_default();
+                        let role_name = UserRoleName::from(role).to_string();
+                        let name = user_name(&identity)?;
+                        let user_id = sqlx::query_scalar!(
+                            r#"
+                                INSERT INTO users (name, email, password_hash, role_id)
+                                SELECT $1, $2, NULL, r.role_id
+                                FROM roles r
+                                WHERE r.name = $3
+                                RETURNING user_id
+                            "#,
+                            name.inner_ref(),
+                            identity.email.to_string(),
+                            role_name,
+                        )
+                        .fetch_one(&mut *tx)
+                        .await
+                        .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?;
+                        (user_id, role_name)
+                    }
+                };
+
+                sqlx::query!(
+                    r#"
+                        INSERT INTO user_oidc_identities (issuer, subject, user_id)
+                        VALUES ($1, $2, $3)
+                    "#,
+                    identity.issuer,
+                    identity.subject,
+                    user_id,
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?;
+
+                (user_id, role_name, identity.email.to_string())
+            }
+        };
+
+        let new_role = identity
+            .role
+            .map(|role| UserRoleName::from(role).to_string())
+            .filter(|role_name| *role_name != current_role);
+        if let Some(role_name) = &new_role {
+            sqlx::query!(
+                r#"
+                    UPDATE users SET role_id = (SELECT role_id FROM roles WHERE name = $1)
+                    WHERE user_id = $2
+                "#,
+                role_name,
+                user_id,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?;
+
+        Ok(OidcLogin {
+            user_id: UserId::new(user_id),
+            email: UserEmail::from_str(&email)
+                .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))?,
+            role_changed: new_role.is_some(),
+        })
+    }
+}
+
+// name クレームがない、または使えない場合はメールアドレスのローカル部、
+// それも使えない場合はメールアドレス全体を名前にする
+fn user_name(identity: &OidcIdentity) -> OidcRepositoryResult<UserName> {
+    if let Some(name) = identity
+        .name
+        .clone()
+        .and_then(|name| UserName::try_from(name).ok())
+    {
+        return Ok(name);
+    }
+    UserName::try_from(identity.email.inner_ref().local_part().to_string())
+        .or_else(|_| UserName::try_from(identity.email.to_string()))
+        .map_err(|e| OidcRepositoryError::Unexpected(Box::new(e)))
+}
+
+fn oidc_error(e: OidcError) -> OidcRepositoryError {
+    match e {
+        OidcError::InvalidIdToken(_) => OidcRepositoryError::InvalidIdToken(Box::new(e)),
+        OidcError::Request(_) | OidcError::Provider(_) => {
+            OidcRepositoryError::IdentityProvider(Box::new(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use kernel::model::user::UserRole;
+    use shared::config::RedisConfig;
+
+    use super::*;
+
+    const ISSUER: &str = "https://idp.example.com";
+
+    fn repository(pool: sqlx::PgPool) -> Result<OidcRepositoryImpl> {
+        let kvs = RedisClient::new(&RedisConfig {
+            host: "localhost".into(),
+            port: 6379,
+        })?;
+        Ok(OidcRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            Arc::new(kvs),
+            None,
+        ))
+    }
+
+    fn identity(subject: &str, email: &str, role: Option<UserRole>) -> OidcIdentity {
+        OidcIdentity {
+            issuer: ISSUER.into(),
+            subject: subject.into(),
+            email: UserEmail::from_str(email).unwrap(),
+            email_verified: true,
+            name: None,
+            role,
+        }
+    }
+
+    async fn role_of(pool: &sqlx::PgPool, user_id: &UserId) -> Result<String> {
+        Ok(sqlx::query_scalar!(
+            r#"
+                SELECT r.name FROM users u INNER JOIN roles r ON r.role_id = u.role_id
+                WHERE u.user_id = $1
+            "#,
+            user_id.inner_ref()
+        )
+        .fetch_one(pool)
+        .await?)
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_provision_creates_and_reuses_user(pool: sqlx::PgPool) -> Result<()> {
+        let repo = repository(pool.clone())?;
+
+        let created = repo
+            .provision(identity("dave", "dave@example.com", None))
+            .await?;
+        assert!(!created.role_changed);
+        let row = sqlx::query!(
+            r#"SELECT name, password_hash FROM users WHERE user_id = $1"#,
+            created.user_id.inner_ref()
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(row.name, "dave");
+        assert!(row.password_hash.is_none());
+        assert_eq!(role_of(&pool, &created.user_id).await?, "User");
+
+        // 2 回目以降は IdP の利用者で見つけ、クレームに合わせてロールを変える
+        let again = repo
+            .provision(identity("dave", "dave@example.com", Some(UserRole::Admin)))
+            .await?;
+        assert_eq!(again.user_id, created.user_id);
+        assert!(again.role_changed);
+        assert_eq!(role_of(&pool, &created.user_id).await?, "Admin");
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_provision_requires_verified_email(pool: sqlx::PgPool) -> Result<()> {
+        let repo = repository(pool.clone())?;
+        let alice = UserId::new("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse()?);
+
+        let mut unverified = identity("alice", "alice@example.com", None);
+        unverified.email_verified = false;
+        let res = repo.provision(unverified).await;
+        assert!(matches!(res, Err(OidcRepositoryError::EmailNotVerified)));
+
+        // 確認されていないメールアドレスでは新しいユーザーも作らない
+        let mut unverified = identity("mallory", "frank@example.com", None);
+        unverified.email_verified = false;
+        let res = repo.provision(unverified).await;
+        assert!(matches!(res, Err(OidcRepositoryError::EmailNotVerified)));
+        let created = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM users WHERE email = $1",
+            "frank@example.com"
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(created, Some(0));
+
+        let linked = repo
+            .provision(identity("alice", "alice@example.com", Some(UserRole::User)))
+            .await?;
+        assert_eq!(linked.user_id, alice);
+        assert!(!linked.role_changed);
+
+        // 紐づけた後は IdP 側でメールアドレスが変わっても同じユーザーとして扱う
+        let renamed = repo
+            .provision(identity("alice", "alice@corp.example.com", None))
+            .await?;
+        assert_eq!(renamed.user_id, alice);
+        // 二要素認証のチャレンジには登録済みのメールアドレスを使う
+        assert_eq!(renamed.email.to_string(), "alice@example.com");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_name_falls_back_to_validated_email_part() -> Result<()> {
+        let mut identity = identity("erin", "erin@example.com", None);
+        identity.name = Some("  Erin  ".into());
+        assert_eq!(user_name(&identity)?.inner_ref(), "Erin");
+
+        // 使えない name クレームはメールアドレスのローカル部で置き換える
+        identity.name = Some("\u{7}".into());
+        assert_eq!(user_name(&identity)?.inner_ref(), "erin");
+        identity.name = Some("   ".into());
+        assert_eq!(user_name(&identity)?.inner_ref(), "erin");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{
+            event::{RequestPasswordReset, ResetPassword},
+            password_reset::PasswordResetToken,
+        },
+        mail::Mail,
+        user::{password::PasswordPolicy, UserEmail, UserId},
+        value_object::ValueObject,
+    },
+    repository::{
+        mailer::Mailer,
+        password_reset::{
+            PasswordResetRepository, PasswordResetRepositoryError, PasswordResetRepositoryResult,
+        },
+    },
+};
+use shared::i18n::{self, Locale};
+
+use crate::{
+    database::{
+        model::password_reset::{
+            IssuedPasswordResetToken, PasswordResetKey, PasswordResetUserId, UserPasswordResetKey,
+        },
+        ConnectionPool,
+    },
+    password,
+    redis::{RedisClient, RedisClientError},
+};
+
+#[derive(new)]
+pub struct PasswordResetRepositoryImpl {
+    db: ConnectionPool,
+    kvs: Arc<RedisClient>,
+    mailer: Arc<dyn Mailer>,
+    password_policy: PasswordPolicy,
+    // 再設定用のリンクの有効期間の秒数
+    ttl: u64,
+    // メールに載せるリンクの基点となる URL
+    link_base_url: String,
+}
+
+#[async_trait]
+impl PasswordResetRepository for PasswordResetRepositoryImpl {
+    async fn request(&self, event: RequestPasswordReset) -> PasswordResetRepositoryResult<()> {
+        let user_id = sqlx::query_scalar!(
+            r#"SELECT user_id FROM users WHERE email = $1"#,
+            event.email.to_string()
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(|e| PasswordResetRepositoryError::Unexpected(Box::new(e)))?;
+        let Some(user_id) = user_id.map(UserId::new) else {
+            return Ok(());
+        };
+
+        // 最後に送ったリンクだけを有効にする
+        let user_key = UserPasswordResetKey::from(&user_id);
+        if let Some(IssuedPasswordResetToken(previous)) =
+            self.kvs.get(&user_key).await.map_err(unexpected)?
+        {
+            self.kvs
+                .delete(&PasswordResetKey::from(&previous))
+                .await
+                .map_err(unexpected)?;
+        }
+        self.kvs
+            .set_ex(
+                &PasswordResetKey::from(&event.token),
+                &PasswordResetUserId(user_id),
+                self.ttl,
+            )
+            .await
+            .map_err(unexpected)?;
+        self.kvs
+            .set_ex(
+                &user_key,
+                &IssuedPasswordResetToken(event.token.clone()),
+                self.ttl,
+            )
+            .await
+            .map_err(unexpected)?;
+
+        let mail = reset_mail(
+            event.email,
+            &self.link_base_url,
+            &event.token,
+            self.ttl,
+            i18n::current(),
+        );
+        self.mailer.send(mail).await?;
+
+        Ok(())
+    }
+
+    async fn confirm(&self, event: ResetPassword) -> PasswordResetRepositoryResult<UserId> {
+        // ポリシーに反するパスワードでトークンを使い切らないよう、先に確認する
+        event.new_password.check(&self.password_policy)?;
+
+        // 同じトークンを二度使えないよう、取得と同時に削除する
+        let PasswordResetUserId(user_id) = self
+            .kvs
+            .get_del(&PasswordResetKey::from(&event.token))
+            .await
+            .map_err(unexpected)?
+            .ok_or(PasswordResetRepositoryError::InvalidToken)?;
+        self.kvs
+            .delete(&UserPasswordResetKey::from(&user_id))
+            .await
+            .map_err(unexpected)?;
+
+        let password_hash = password::hash(event.new_password.inner_ref())
+            .map_err(|e| PasswordResetRepositoryError::Unexpected(Box::new(e)))?;
+        let res = sqlx::query!(
+            r#"UPDATE users SET password_hash = $1 WHERE user_id = $2"#,
+            password_hash,
+            user_id.inner_ref()
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| PasswordResetRepositoryError::Unexpected(Box::new(e)))?;
+
+        // リンクを送った後にユーザーが削除された
+        if res.rows_affected() < 1 {
+            return Err(PasswordResetRepositoryError::InvalidToken);
+        }
+
+        Ok(user_id)
+    }
+}
+
+// 再設定用のリンクを知らせるメール。リンク先はフロントエンドの画面で、
+// そこから /auth/password-reset/confirm にトークンと新しいパスワードを送る
+fn reset_mail(
+    to: UserEmail,
+    link_base_url: &str,
+    token: &PasswordResetToken,
+    ttl: u64,
+    locale: Locale,
+) -> Mail {
+    let url = format!(
+        "{link_base_url}/password-reset?token={}",
+        urlencoding::encode(token.inner_ref())
+    );
+    let minutes = (ttl / 60).max(1);
+    let (subject, body) = match locale {
+        Locale::Ja => (
+            "パスワードの再設定",
+            format!(
+                "パスワードの再設定が申し込まれました。\n\
+                 次のリンクから {minutes} 分以内に新しいパスワードを設定してください。\n\n\
+                 {url}\n\n\
+                 お心当たりがない場合は、このメールを破棄してください。パスワードは変更されません。\n"
+            ),
+        ),
+        Locale::En => (
+            "Reset your password",
+            format!(
+                "We received a request to reset your password.\n\
+                 Use the link below within {minutes} minutes to set a new password.\n\n\
+                 {url}\n\n\
+                 If you did not request this, you can ignore this email. Your password will not change.\n"
+            ),
+        ),
+    };
+
+    Mail {
+        to,
+        subject: subject.to_string(),
+        body,
+    }
+}
+
+fn unexpected(e: RedisClientError) -> PasswordResetRepositoryError {
+    PasswordResetRepositoryError::Unexpected(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn test_reset_mail_links_to_token() -> Result<()> {
+        let token = PasswordResetToken::new("0123abcd".into());
+        let mail = reset_mail(
+            "alice@example.com".parse()?,
+            "https://library.example.com",
+            &token,
+            1800,
+            Locale::En,
+        );
+
+        assert_eq!(mail.to.to_string(), "alice@example.com");
+        assert!(mail
+            .body
+            .contains("https://library.example.com/password-reset?token=0123abcd"));
+        assert!(mail.body.contains("within 30 minutes"));
+
+        let mail = reset_mail(
+            "alice@example.com".parse()?,
+            "https://library.example.com",
+            &token,
+            1800,
+            Locale::Ja,
+        );
+        assert!(mail.body.contains("30 分以内"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,484 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        book::BookId,
+        reservation::{
+            event::{CreateReservation, DeleteReservation},
+            Reservation,
+        },
+        user::UserId,
+        value_object::ValueObject,
+    },
+    repository::reservation::{
+        ReservationRepository, ReservationRepositoryError, ReservationRepositoryResult,
+    },
+};
+
+use crate::database::{
+    model::reservation::{HoldQueueStateRow, ReservationRow},
+    ConnectionPool,
+};
+
+#[derive(new)]
+pub struct ReservationRepositoryImpl {
+    db: ConnectionPool,
+    // 返却された冊を取り置いておく秒数
+    pickup_window: u64,
+}
+
+#[async_trait]
+impl ReservationRepository for ReservationRepositoryImpl {
+    async fn create(&self, event: CreateReservation) -> ReservationRepositoryResult<()> {
+        let mut tx = self.begin_serializable().await?;
+
+        self.ensure_book_exists(&mut tx, &event.book_id).await?;
+        refresh_holds(&mut tx, &event.book_id, self.pickup_window)
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        let state = fetch_hold_queue_state(&mut tx, &event.book_id, &event.reserved_by)
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+        if state.reserved {
+            return Err(ReservationRepositoryError::AlreadyReserved(
+                event.book_id,
+                event.reserved_by,
+            ));
+        }
+
+        let checked_out = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM checkouts WHERE book_id = $1 AND user_id = $2
+                ) AS "exists!"
+            "#,
+            event.book_id.inner_ref(),
+            event.reserved_by.inner_ref(),
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+        if checked_out {
+            return Err(ReservationRepositoryError::AlreadyCheckedOut(
+                event.book_id,
+                event.reserved_by,
+            ));
+        }
+
+        // 今すぐ借りられる場合は予約する必要がない
+        if state.allows_checkout() {
+            return Err(ReservationRepositoryError::BookAvailable(event.book_id));
+        }
+
+        sqlx::query!(
+            r#"
+                INSERT INTO reservations (book_id, user_id, reserved_at)
+                VALUES ($1, $2, $3)
+            "#,
+            event.book_id.inner_ref(),
+            event.reserved_by.inner_ref(),
+            event.reserved_at,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        Ok(())
+    }
+
+    async fn find_by_book_id(
+        &self,
+        book_id: &BookId,
+    ) -> ReservationRepositoryResult<Vec<Reservation>> {
+        let mut tx = self.begin_serializable().await?;
+
+        self.ensure_book_exists(&mut tx, book_id).await?;
+        refresh_holds(&mut tx, book_id, self.pickup_window)
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        let rows = sqlx::query_as!(
+            ReservationRow,
+            r#"
+                SELECT
+                    reservation_id,
+                    book_id,
+                    user_id,
+                    reserved_at,
+                    ROW_NUMBER() OVER (ORDER BY reserved_at, reservation_id) AS "position!",
+                    ready_at,
+                    expires_at
+                FROM reservations
+                WHERE book_id = $1
+                ORDER BY reserved_at, reservation_id
+            "#,
+            book_id.inner_ref(),
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        rows.into_iter()
+            .map(Reservation::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ReservationRepositoryError::InvalidSavedEntity(e.into()))
+    }
+
+    async fn delete(&self, event: DeleteReservation) -> ReservationRepositoryResult<()> {
+        let mut tx = self.begin_serializable().await?;
+
+        let user_id = sqlx::query_scalar!(
+            r#"
+                SELECT user_id FROM reservations WHERE reservation_id = $1 AND book_id = $2
+            "#,
+            event.reservation_id.inner_ref(),
+            event.book_id.inner_ref(),
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        match user_id {
+            None => return Err(ReservationRepositoryError::NotFound(event.reservation_id)),
+            Some(user_id) if &user_id != event.requested_by.inner_ref() => {
+                return Err(ReservationRepositoryError::CannotCancel(
+                    event.reservation_id,
+                    event.requested_by,
+                ))
+            }
+            Some(_) => {}
+        }
+
+        sqlx::query!(
+            r#"
+                DELETE FROM reservations WHERE reservation_id = $1
+            "#,
+            event.reservation_id.inner_ref(),
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        // 取り置かれていた冊は次の予約者に回す
+        refresh_holds(&mut tx, &event.book_id, self.pickup_window)
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        Ok(())
+    }
+}
+
+impl ReservationRepositoryImpl {
+    async fn begin_serializable(
+        &self,
+    ) -> ReservationRepositoryResult<sqlx::Transaction<'_, sqlx::Postgres>> {
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        Ok(tx)
+    }
+
+    async fn ensure_book_exists(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        book_id: &BookId,
+    ) -> ReservationRepositoryResult<()> {
+        let exists = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS (SELECT 1 FROM books WHERE book_id = $1) AS "exists!"
+            "#,
+            book_id.inner_ref(),
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| ReservationRepositoryError::Unexpected(e.into()))?;
+
+        if exists {
+            Ok(())
+        } else {
+            Err(ReservationRepositoryError::BookNotFound(book_id.clone()))
+        }
+    }
+}
+
+// 受け取り期限を過ぎた予約を取り除き、貸出中でない冊を待ち行列の先頭から順に取り置く
+// 貸し出し・返却・予約の取り消しなど、待ち行列が動きうる処理のたびに呼び出す
+pub(crate) async fn refresh_holds(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: &BookId,
+    pickup_window: u64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+            DELETE FROM reservations WHERE book_id = $1 AND expires_at < now()
+        "#,
+        book_id.inner_ref(),
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+            WITH head AS (
+                SELECT reservation_id
+                FROM reservations
+                WHERE book_id = $1
+                ORDER BY reserved_at, reservation_id
+                LIMIT (
+                    SELECT COUNT(*)
+                    FROM book_copies bc
+                    WHERE bc.book_id = $1
+                        AND NOT EXISTS (
+                            SELECT 1 FROM checkouts c WHERE c.book_copy_id = bc.book_copy_id
+                        )
+                )
+            )
+            UPDATE reservations r
+            SET ready_at = now(), expires_at = now() + $2::bigint * interval '1 second'
+            FROM head
+            WHERE r.reservation_id = head.reservation_id AND r.ready_at IS NULL
+        "#,
+        book_id.inner_ref(),
+        i64::try_from(pickup_window).unwrap_or(i64::MAX),
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn fetch_hold_queue_state(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: &BookId,
+    user_id: &UserId,
+) -> Result<HoldQueueStateRow, sqlx::Error> {
+    sqlx::query_as!(
+        HoldQueueStateRow,
+        r#"
+            WITH own AS (
+                SELECT reserved_at, reservation_id
+                FROM reservations
+                WHERE book_id = $1 AND user_id = $2
+            )
+            SELECT
+                (
+                    SELECT COUNT(*)
+                    FROM book_copies bc
+                    WHERE bc.book_id = $1
+                        AND NOT EXISTS (
+                            SELECT 1 FROM checkouts c WHERE c.book_copy_id = bc.book_copy_id
+                        )
+                ) AS "available!",
+                (SELECT COUNT(*) FROM reservations WHERE book_id = $1) AS "queued!",
+                (
+                    SELECT COUNT(*)
+                    FROM reservations r, own
+                    WHERE r.book_id = $1
+                        AND (r.reserved_at, r.reservation_id) < (own.reserved_at, own.reservation_id)
+                ) AS "ahead!",
+                EXISTS (SELECT 1 FROM own) AS "reserved!"
+        "#,
+        book_id.inner_ref(),
+        user_id.inner_ref(),
+    )
+    .fetch_one(&mut **tx)
+    .await
+}
+
+// 予約者が貸し出しを受けたら、その予約は完了とする
+pub(crate) async fn fulfill_reservation(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: &BookId,
+    user_id: &UserId,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+            DELETE FROM reservations WHERE book_id = $1 AND user_id = $2
+        "#,
+        book_id.inner_ref(),
+        user_id.inner_ref(),
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use chrono::{Duration, Utc};
+    use kernel::{
+        model::{
+            checkout::{
+                event::{CreateCheckout, UpdateReturned},
+                policy::CheckoutPolicy,
+                LoanTerms,
+            },
+            reservation::ReservationStatus,
+        },
+        repository::checkout::{CheckoutRepository, CheckoutRepositoryError},
+    };
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::repository::checkout::CheckoutRepositoryImpl;
+
+    #[sqlx::test(fixtures("common", "book", "user"))]
+    async fn test_reservation_queue(pool: sqlx::PgPool) -> Result<()> {
+        let reservations = ReservationRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3600);
+        let checkouts = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            3600,
+            LoanTerms {
+                default_period: Duration::days(14),
+                role_periods: Default::default(),
+                max_renewals: 1,
+            },
+            CheckoutPolicy {
+                default_max_loans: 10,
+                role_max_loans: Default::default(),
+            },
+        );
+
+        // 1 冊だけ所蔵している蔵書
+        let book_id = BookId::try_from("9890736e-a4e4-461a-a77d-eac3517ef11b".parse::<Uuid>()?)?;
+        let carol = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e03".parse::<Uuid>()?)?;
+        let alice = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<Uuid>()?)?;
+        let bob = UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02".parse::<Uuid>()?)?;
+        let reserve = |user_id: &UserId| CreateReservation {
+            book_id: book_id.clone(),
+            reserved_by: user_id.clone(),
+            reserved_at: Utc::now(),
+        };
+        let checkout = |user_id: &UserId| CreateCheckout {
+            book_id: book_id.clone(),
+            book_copy_id: None,
+            checked_out_by: user_id.clone(),
+            checked_out_at: Utc::now(),
+        };
+
+        // 貸出可能な冊があるうちは予約できない
+        let res = reservations.create(reserve(&alice)).await;
+        assert!(matches!(
+            res,
+            Err(ReservationRepositoryError::BookAvailable(_))
+        ));
+
+        checkouts.create(checkout(&carol)).await?;
+
+        // 借りている本人は予約できず、同じ利用者は二重に予約できない
+        let res = reservations.create(reserve(&carol)).await;
+        assert!(matches!(
+            res,
+            Err(ReservationRepositoryError::AlreadyCheckedOut(..))
+        ));
+        reservations.create(reserve(&alice)).await?;
+        reservations.create(reserve(&bob)).await?;
+        let res = reservations.create(reserve(&alice)).await;
+        assert!(matches!(
+            res,
+            Err(ReservationRepositoryError::AlreadyReserved(..))
+        ));
+
+        let queue = reservations.find_by_book_id(&book_id).await?;
+        assert_eq!(
+            queue
+                .iter()
+                .map(|r| (r.reserved_by().clone(), *r.position(), *r.status()))
+                .collect::<Vec<_>>(),
+            vec![
+                (alice.clone(), 1, ReservationStatus::Waiting),
+                (bob.clone(), 2, ReservationStatus::Waiting),
+            ]
+        );
+
+        // 返却されると先頭の予約者のために取り置かれ、他の利用者は借りられない
+        let (checkout_id, ..) = checkouts
+            .find_unreturned_by_user_id(&carol)
+            .await?
+            .into_iter()
+            .next()
+            .unwrap()
+            .dissolve();
+        checkouts
+            .update_returned(UpdateReturned {
+                checkout_id,
+                book_id: book_id.clone(),
+                returned_by: carol.clone(),
+                returned_at: Utc::now(),
+            })
+            .await?;
+
+        let queue = reservations.find_by_book_id(&book_id).await?;
+        assert!(matches!(
+            queue[0].status(),
+            ReservationStatus::ReadyForPickup { .. }
+        ));
+        assert_eq!(queue[1].status(), &ReservationStatus::Waiting);
+        for user_id in [&carol, &bob] {
+            let res = checkouts.create(checkout(user_id)).await;
+            assert!(matches!(
+                res,
+                Err(CheckoutRepositoryError::ReservedForOthers(_))
+            ));
+        }
+
+        // 受け取り期限を過ぎると、取り置きは次の予約者に移る
+        sqlx::query!(
+            "UPDATE reservations SET expires_at = now() - interval '1 minute' WHERE user_id = $1",
+            alice.inner_ref(),
+        )
+        .execute(&pool)
+        .await?;
+
+        let queue = reservations.find_by_book_id(&book_id).await?;
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].reserved_by(), &bob);
+        assert_eq!(*queue[0].position(), 1);
+        assert!(matches!(
+            queue[0].status(),
+            ReservationStatus::ReadyForPickup { .. }
+        ));
+
+        // 予約を取り消せるのは予約者本人のみ
+        let res = reservations
+            .delete(DeleteReservation {
+                reservation_id: queue[0].reservation_id().clone(),
+                book_id: book_id.clone(),
+                requested_by: alice.clone(),
+            })
+            .await;
+        assert!(matches!(
+            res,
+            Err(ReservationRepositoryError::CannotCancel(..))
+        ));
+
+        // 取り置かれた予約者が借りると、その予約は完了する
+        checkouts.create(checkout(&bob)).await?;
+        assert!(reservations.find_by_book_id(&book_id).await?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,541 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use derive_new::new;
+use kernel::{
+    model::{
+        auth::{
+            event::{CreateLoginChallenge, DisableTotp, EnableTotp},
+            totp::{
+                LoginChallenge, LoginChallengeToken, SecondFactor, TotpCode, TotpRequirement,
+                TotpSetup, LOGIN_CHALLENGE_MAX_FAILURES, LOGIN_CHALLENGE_TTL,
+            },
+        },
+        user::{UserId, UserRole},
+        value_object::ValueObject,
+    },
+    repository::totp::{TotpRepository, TotpRepositoryError, TotpRepositoryResult},
+};
+
+use crate::{
+    database::{
+        model::{
+            totp::{LoginChallengeFailureKey, LoginChallengeKey, StoredLoginChallenge},
+            user::UserRoleName,
+        },
+        ConnectionPool,
+    },
+    redis::{RedisClient, RedisClientError},
+    totp,
+};
+
+#[derive(new)]
+pub struct TotpRepositoryImpl {
+    db: ConnectionPool,
+    kvs: Arc<RedisClient>,
+    // otpauth URI に含めるサービス名
+    issuer: String,
+    // true の場合、管理者は二要素認証を無効にできず、未登録ならログイン時に登録させる
+    required_for_admin: bool,
+}
+
+#[async_trait]
+impl TotpRepository for TotpRepositoryImpl {
+    async fn find_requirement(&self, user_id: &UserId) -> TotpRepositoryResult<TotpRequirement> {
+        let row = sqlx::query!(
+            r#"
+                SELECT r.name AS role_name, t.enabled_at AS "enabled_at?"
+                FROM users u
+                INNER JOIN roles r ON u.role_id = r.role_id
+                LEFT JOIN user_totp t ON t.user_id = u.user_id
+                WHERE u.user_id = $1
+            "#,
+            user_id.inner_ref()
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+
+        let Some(row) = row else {
+            return Ok(TotpRequirement::Disabled);
+        };
+        if row.enabled_at.is_some() {
+            return Ok(TotpRequirement::Enabled);
+        }
+        if self.is_required_for(&row.role_name)? {
+            return Ok(TotpRequirement::SetupRequired);
+        }
+        Ok(TotpRequirement::Disabled)
+    }
+
+    async fn create_secret(&self, user_id: &UserId) -> TotpRepositoryResult<TotpSetup> {
+        let row = sqlx::query!(
+            r#"
+                SELECT u.email, t.enabled_at AS "enabled_at?"
+                FROM users u
+                LEFT JOIN user_totp t ON t.user_id = u.user_id
+                WHERE u.user_id = $1
+            "#,
+            user_id.inner_ref()
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+        if row.enabled_at.is_some() {
+            return Err(TotpRepositoryError::AlreadyEnabled);
+        }
+
+        let secret = totp::generate_secret();
+        // 有効化と同時に実行された場合に、有効なシークレットを上書きしないようにする
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO user_totp (user_id, secret)
+                VALUES ($1, $2)
+                ON CONFLICT (user_id) DO UPDATE
+                SET secret = EXCLUDED.secret,
+                    last_used_step = NULL,
+                    created_at = CURRENT_TIMESTAMP(3)
+                WHERE user_totp.enabled_at IS NULL
+            "#,
+            user_id.inner_ref(),
+            secret,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+        if res.rows_affected() < 1 {
+            return Err(TotpRepositoryError::AlreadyEnabled);
+        }
+
+        let otpauth_uri = totp::otpauth_uri(&secret, &self.issuer, &row.email);
+        Ok(TotpSetup {
+            secret,
+            otpauth_uri,
+        })
+    }
+
+    async fn enable(&self, event: EnableTotp) -> TotpRepositoryResult<Vec<String>> {
+        let EnableTotp { user_id, code } = event;
+
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+
+        let row = sqlx::query!(
+            r#"
+                SELECT secret, enabled_at, last_used_step
+                FROM user_totp
+                WHERE user_id = $1
+                FOR UPDATE
+            "#,
+            user_id.inner_ref()
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?
+        .ok_or(TotpRepositoryError::NotEnabled)?;
+        if row.enabled_at.is_some() {
+            return Err(TotpRepositoryError::AlreadyEnabled);
+        }
+        let step = verify_code(&row.secret, &code, row.last_used_step)?;
+
+        sqlx::query!(
+            r#"
+                UPDATE user_totp
+                SET enabled_at = CURRENT_TIMESTAMP(3), last_used_step = $2
+                WHERE user_id = $1
+            "#,
+            user_id.inner_ref(),
+            step,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+
+        // 以前に発行したリカバリーコードは使えなくする
+        sqlx::query!(
+            "DELETE FROM user_recovery_codes WHERE user_id = $1",
+            user_id.inner_ref()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+
+        let recovery_codes = totp::generate_recovery_codes();
+        let code_hashes = recovery_codes
+            .iter()
+            .map(|code| totp::hash_recovery_code(code))
+            .collect::<Vec<_>>();
+        sqlx::query!(
+            r#"
+                INSERT INTO user_recovery_codes (user_id, code_hash)
+                SELECT $1, code_hash FROM UNNEST($2::varchar[]) AS code_hash
+            "#,
+            user_id.inner_ref(),
+            &code_hashes,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+
+        Ok(recovery_codes)
+    }
+
+    async fn disable(&self, event: DisableTotp) -> TotpRepositoryResult<()> {
+        let DisableTotp {
+            user_id,
+            second_factor,
+        } = event;
+
+        let role_name = sqlx::query_scalar!(
+            r#"
+                SELECT r.name
+                FROM users u
+                INNER JOIN roles r ON u.role_id = r.role_id
+                WHERE u.user_id = $1
+            "#,
+            user_id.inner_ref()
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+        if self.is_required_for(&role_name)? {
+            return Err(TotpRepositoryError::Required);
+        }
+
+        self.verify(&user_id, &second_factor).await?;
+
+        // リカバリーコードは user_totp とは関連付けていないため、あわせて削除する
+        let mut tx = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+        sqlx::query!(
+            "DELETE FROM user_recovery_codes WHERE user_id = $1",
+            user_id.inner_ref()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+        sqlx::query!(
+            "DELETE FROM user_totp WHERE user_id = $1",
+            user_id.inner_ref()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+        tx.commit()
+            .await
+            .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+
+        Ok(())
+    }
+
+    async fn verify(
+        &self,
+        user_id: &UserId,
+        second_factor: &SecondFactor,
+    ) -> TotpRepositoryResult<()> {
+        match second_factor {
+            SecondFactor::Totp(code) => {
+                let row = sqlx::query!(
+                    r#"
+                        SELECT secret, last_used_step
+                        FROM user_totp
+                        WHERE user_id = $1 AND enabled_at IS NOT NULL
+                    "#,
+                    user_id.inner_ref()
+                )
+                .fetch_optional(self.db.inner_ref())
+                .await
+                .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?
+                .ok_or(TotpRepositoryError::NotEnabled)?;
+                let step = verify_code(&row.secret, code, row.last_used_step)?;
+
+                // 同時に同じコードが送られた場合は、先に記録した方だけを受け付ける
+                let res = sqlx::query!(
+                    r#"
+                        UPDATE user_totp
+                        SET last_used_step = $2
+                        WHERE user_id = $1
+                            AND (last_used_step IS NULL OR last_used_step < $2)
+                    "#,
+                    user_id.inner_ref(),
+                    step,
+                )
+                .execute(self.db.inner_ref())
+                .await
+                .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+                if res.rows_affected() < 1 {
+                    return Err(TotpRepositoryError::InvalidCode);
+                }
+            }
+            SecondFactor::RecoveryCode(code) => {
+                let res = sqlx::query!(
+                    r#"
+                        UPDATE user_recovery_codes c
+                        SET used_at = CURRENT_TIMESTAMP(3)
+                        WHERE c.user_id = $1
+                            AND c.code_hash = $2
+                            AND c.used_at IS NULL
+                            AND EXISTS (
+                                SELECT 1 FROM user_totp t
+                                WHERE t.user_id = c.user_id AND t.enabled_at IS NOT NULL
+                            )
+                    "#,
+                    user_id.inner_ref(),
+                    totp::hash_recovery_code(code.inner_ref()),
+                )
+                .execute(self.db.inner_ref())
+                .await
+                .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+                if res.rows_affected() < 1 {
+                    return Err(TotpRepositoryError::InvalidCode);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_challenge(&self, event: CreateLoginChallenge) -> TotpRepositoryResult<()> {
+        let CreateLoginChallenge {
+            challenge_token,
+            user_id,
+            email,
+            setup_required,
+        } = event;
+        let challenge = LoginChallenge {
+            user_id,
+            email,
+            setup_required,
+        };
+
+        self.kvs
+            .set_ex(
+                &LoginChallengeKey::from(&challenge_token),
+                &StoredLoginChallenge(challenge),
+                LOGIN_CHALLENGE_TTL,
+            )
+            .await
+            .map_err(unexpected)
+    }
+
+    async fn find_challenge(
+        &self,
+        challenge_token: &LoginChallengeToken,
+    ) -> TotpRepositoryResult<Option<LoginChallenge>> {
+        self.kvs
+            .get(&LoginChallengeKey::from(challenge_token))
+            .await
+            .map(|challenge| challenge.map(|StoredLoginChallenge(challenge)| challenge))
+            .map_err(unexpected)
+    }
+
+    async fn fail_challenge(
+        &self,
+        challenge_token: &LoginChallengeToken,
+    ) -> TotpRepositoryResult<()> {
+        let failures = self
+            .kvs
+            .incr(
+                &LoginChallengeFailureKey::from(challenge_token),
+                LOGIN_CHALLENGE_TTL,
+            )
+            .await
+            .map_err(unexpected)?;
+        if failures >= LOGIN_CHALLENGE_MAX_FAILURES {
+            self.delete_challenge(challenge_token).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_challenge(
+        &self,
+        challenge_token: &LoginChallengeToken,
+    ) -> TotpRepositoryResult<()> {
+        self.kvs
+            .delete(&LoginChallengeKey::from(challenge_token))
+            .await
+            .map_err(unexpected)?;
+        self.kvs
+            .delete(&LoginChallengeFailureKey::from(challenge_token))
+            .await
+            .map_err(unexpected)
+    }
+}
+
+impl TotpRepositoryImpl {
+    fn is_required_for(&self, role_name: &str) -> TotpRepositoryResult<bool> {
+        let role = role_name
+            .parse::<UserRoleName>()
+            .map_err(|e| TotpRepositoryError::Unexpected(Box::new(e)))?;
+        Ok(self.required_for_admin && UserRole::from(role) == UserRole::Admin)
+    }
+}
+
+// コードが一致したステップを返す
+fn verify_code(
+    secret: &str,
+    code: &TotpCode,
+    last_used_step: Option<i64>,
+) -> TotpRepositoryResult<i64> {
+    totp::verify(
+        secret,
+        code.inner_ref(),
+        Utc::now().timestamp(),
+        last_used_step,
+    )
+    .ok_or(TotpRepositoryError::InvalidCode)
+}
+
+fn unexpected(e: RedisClientError) -> TotpRepositoryError {
+    TotpRepositoryError::Unexpected(Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use shared::config::RedisConfig;
+
+    use super::*;
+
+    // 二要素認証の設定はデータベースのみを使うため、Redis には接続しない
+    fn repository(pool: sqlx::PgPool, required_for_admin: bool) -> Result<TotpRepositoryImpl> {
+        let kvs = RedisClient::new(&RedisConfig {
+            host: "localhost".into(),
+            port: 6379,
+        })?;
+        Ok(TotpRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            Arc::new(kvs),
+            "Book Manager".into(),
+            required_for_admin,
+        ))
+    }
+
+    fn current_code(setup: &TotpSetup) -> Result<TotpCode> {
+        let step = totp::step_at(Utc::now().timestamp());
+        let code = totp::code_at(&setup.secret, step).expect("secret must be base32");
+        Ok(TotpCode::try_from(code)?)
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_enable_and_verify_totp(pool: sqlx::PgPool) -> Result<()> {
+        let repo = repository(pool, false)?;
+        let user_id =
+            UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<uuid::Uuid>()?)?;
+
+        assert_eq!(
+            repo.find_requirement(&user_id).await?,
+            TotpRequirement::Disabled
+        );
+
+        let setup = repo.create_secret(&user_id).await?;
+        assert!(setup
+            .otpauth_uri
+            .starts_with("otpauth://totp/Book%20Manager%3Aalice%40example.com?"));
+        // 登録中の間は二要素目を求めない
+        assert_eq!(
+            repo.find_requirement(&user_id).await?,
+            TotpRequirement::Disabled
+        );
+
+        let code = current_code(&setup)?;
+        let recovery_codes = repo
+            .enable(EnableTotp {
+                user_id: user_id.clone(),
+                code: code.clone(),
+            })
+            .await?;
+        assert_eq!(recovery_codes.len(), 10);
+        assert_eq!(
+            repo.find_requirement(&user_id).await?,
+            TotpRequirement::Enabled
+        );
+        assert!(matches!(
+            repo.create_secret(&user_id).await,
+            Err(TotpRepositoryError::AlreadyEnabled)
+        ));
+
+        // 登録の確認に使ったコードは再利用できない
+        let res = repo
+            .verify(&user_id, &SecondFactor::Totp(code.clone()))
+            .await;
+        assert!(matches!(res, Err(TotpRepositoryError::InvalidCode)));
+
+        // リカバリーコードは一度だけ使える
+        let recovery_code = SecondFactor::RecoveryCode(recovery_codes[0].clone().try_into()?);
+        repo.verify(&user_id, &recovery_code).await?;
+        let res = repo.verify(&user_id, &recovery_code).await;
+        assert!(matches!(res, Err(TotpRepositoryError::InvalidCode)));
+
+        repo.disable(DisableTotp {
+            user_id: user_id.clone(),
+            second_factor: SecondFactor::RecoveryCode(recovery_codes[1].clone().try_into()?),
+        })
+        .await?;
+        assert_eq!(
+            repo.find_requirement(&user_id).await?,
+            TotpRequirement::Disabled
+        );
+        let res = repo.verify(&user_id, &SecondFactor::Totp(code)).await;
+        assert!(matches!(res, Err(TotpRepositoryError::NotEnabled)));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_totp_required_for_admin(pool: sqlx::PgPool) -> Result<()> {
+        let repo = repository(pool, true)?;
+        let admin_id =
+            UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e02".parse::<uuid::Uuid>()?)?;
+        let user_id =
+            UserId::try_from("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse::<uuid::Uuid>()?)?;
+
+        assert_eq!(
+            repo.find_requirement(&admin_id).await?,
+            TotpRequirement::SetupRequired
+        );
+        assert_eq!(
+            repo.find_requirement(&user_id).await?,
+            TotpRequirement::Disabled
+        );
+
+        // 誤ったコードでは有効にならない
+        let setup = repo.create_secret(&admin_id).await?;
+        let res = repo
+            .enable(EnableTotp {
+                user_id: admin_id.clone(),
+                code: TotpCode::try_from("abcdef".to_string())?,
+            })
+            .await;
+        assert!(matches!(res, Err(TotpRepositoryError::InvalidCode)));
+
+        let recovery_codes = repo
+            .enable(EnableTotp {
+                user_id: admin_id.clone(),
+                code: current_code(&setup)?,
+            })
+            .await?;
+
+        // 管理者は二要素認証を無効にできない
+        let res = repo
+            .disable(DisableTotp {
+                user_id: admin_id.clone(),
+                second_factor: SecondFactor::RecoveryCode(recovery_codes[0].clone().try_into()?),
+            })
+            .await;
+        assert!(matches!(res, Err(TotpRepositoryError::Required)));
+
+        Ok(())
+    }
+}
@@ -2,8 +2,10 @@ use async_trait::async_trait;
 use derive_new::new;
 use kernel::{
     model::{
+        list::{Cursor, CursorOptions, CursorPage},
         user::{
-            event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
+            event::{CreateUser, DeleteUser, UpdateUserName, UpdateUserPassword, UpdateUserRole},
+            password::PasswordPolicy,
             Password, User, UserId, UserIdError, UserRole,
         },
         value_object::ValueObject,
@@ -11,14 +13,20 @@ use kernel::{
     repository::user::{UserRepository, UserRepositoryError, UserRepositoryResult},
 };
 
-use crate::database::{
-    model::user::{UserRoleName, UserRow},
-    ConnectionPool,
+use crate::{
+    database::{
+        model::user::{UserRoleName, UserRow},
+        pagination::take_page,
+        ConnectionPool,
+    },
+    password::{self, Verification},
 };
 
 #[derive(new)]
 pub struct UserRepositoryImpl {
     db: ConnectionPool,
+    // 新しく設定するパスワードに求める条件
+    password_policy: PasswordPolicy,
 }
 
 #[async_trait]
@@ -50,8 +58,11 @@ impl UserRepository for UserRepositoryImpl {
             .map_err(|e| UserRepositoryError::InvalidSavedEntity(e.into()))
     }
 
-    async fn find_all(&self) -> UserRepositoryResult<Vec<User>> {
-        let users = sqlx::query_as!(
+    async fn find_all(&self, options: CursorOptions) -> UserRepositoryResult<CursorPage<User>> {
+        let CursorOptions { limit, after } = options;
+
+        // 作成日時の降順に並べ、カーソルより後ろのユーザーを取得する
+        let mut users = sqlx::query_as!(
             UserRow,
             r#"
                 SELECT
@@ -63,26 +74,44 @@ impl UserRepository for UserRepositoryImpl {
                     u.updated_at as updated_at
                 FROM users u
                 INNER JOIN roles r ON u.role_id = r.role_id
-                ORDER BY u.created_at DESC;
-            "#
+                WHERE $1::text IS NULL
+                    OR (u.created_at, u.user_id) < (CAST($1::text AS timestamptz), $2::uuid)
+                ORDER BY u.created_at DESC, u.user_id DESC
+                LIMIT $3;
+            "#,
+            after.as_ref().and_then(|c| c.sort_key.clone()),
+            after.as_ref().map(|c| c.id),
+            limit.saturating_add(1),
         )
         .fetch_all(self.db.inner_ref())
         .await
         .map_err(|e| UserRepositoryError::Unexpected(e.into()))?;
 
-        users
+        let next_cursor = take_page(&mut users, limit, |row| Cursor {
+            sort_key: Some(row.created_at.to_rfc3339()),
+            id: row.user_id,
+        });
+
+        let items = users
             .into_iter()
             .map(User::try_from)
             .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| UserRepositoryError::InvalidSavedEntity(e.into()))
+            .map_err(|e| UserRepositoryError::InvalidSavedEntity(e.into()))?;
+
+        Ok(CursorPage {
+            limit,
+            items,
+            next_cursor,
+        })
     }
 
     async fn create(&self, event: CreateUser) -> UserRepositoryResult<User> {
+        event.password.check(&self.password_policy)?;
         let role: UserRoleName = UserRole::User.into();
-        let hashed_password = hash_password(&event.password)
+        let hashed_password = password::hash(event.password.inner_ref())
             .map_err(|e| UserRepositoryError::PasswordHash(e.into()))?;
 
-        let user_id = sqlx::query_scalar!(
+        let res = sqlx::query_scalar!(
             r#"
                 INSERT INTO users (name, email, password_hash, role_id)
                 SELECT $1, $2, $3, r.role_id
@@ -96,8 +125,15 @@ impl UserRepository for UserRepositoryImpl {
             role.to_string()
         )
         .fetch_one(self.db.inner_ref())
-        .await
-        .map_err(|e| UserRepositoryError::Unexpected(e.into()))?;
+        .await;
+
+        let user_id = match res {
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                return Err(UserRepositoryError::EmailAlreadyRegistered)
+            }
+            Err(e) => return Err(UserRepositoryError::Unexpected(e.into())),
+            Ok(user_id) => user_id,
+        };
 
         let user_id = user_id
             .try_into()
@@ -106,7 +142,36 @@ impl UserRepository for UserRepositoryImpl {
         Ok(User::new(user_id, event.name, role.into(), event.email))
     }
 
+    async fn update_name(&self, event: UpdateUserName) -> UserRepositoryResult<User> {
+        let user_row = sqlx::query_as!(
+            UserRow,
+            r#"
+                UPDATE users u
+                SET name = $1
+                FROM roles r
+                WHERE u.user_id = $2 AND r.role_id = u.role_id
+                RETURNING
+                    u.user_id as user_id,
+                    u.name as user_name,
+                    u.email as user_email,
+                    r.name as user_role_name,
+                    u.created_at as created_at,
+                    u.updated_at as updated_at;
+            "#,
+            event.name.inner_ref(),
+            event.user_id.inner_ref()
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(|e| UserRepositoryError::Unexpected(e.into()))?
+        .ok_or_else(|| UserRepositoryError::NotFound(event.user_id.clone()))?;
+
+        User::try_from(user_row).map_err(|e| UserRepositoryError::InvalidSavedEntity(e.into()))
+    }
+
     async fn update_password(&self, event: UpdateUserPassword) -> UserRepositoryResult<()> {
+        event.new_password.check(&self.password_policy)?;
+
         let mut tx = self
             .db
             .begin()
@@ -122,11 +187,13 @@ impl UserRepository for UserRepositoryImpl {
         .fetch_one(&mut *tx)
         .await
         .map_err(|e| UserRepositoryError::Unexpected(e.into()))?
-        .password_hash;
+        .password_hash
+        // シングルサインオンだけで利用するユーザーには確認できるパスワードがない
+        .ok_or(UserRepositoryError::InvalidPassword)?;
 
         verify_password(&event.current_password, &original_password_hash)?;
 
-        let new_password_hash = hash_password(&event.new_password)
+        let new_password_hash = password::hash(event.new_password.inner_ref())
             .map_err(|e| UserRepositoryError::PasswordHash(e.into()))?;
 
         sqlx::query!(
@@ -160,9 +227,7 @@ impl UserRepository for UserRepositoryImpl {
         .map_err(|e| UserRepositoryError::Unexpected(e.into()))?;
 
         if res.rows_affected() < 1 {
-            return Err(UserRepositoryError::NoResourceAffected(
-                "No users record has been updated.".to_string(),
-            ));
+            return Err(UserRepositoryError::NotFound(event.user_id));
         }
 
         Ok(())
@@ -187,17 +252,129 @@ impl UserRepository for UserRepositoryImpl {
     }
 }
 
-fn hash_password(password: &Password) -> Result<String, bcrypt::BcryptError> {
-    bcrypt::hash(password.inner_ref(), bcrypt::DEFAULT_COST)
-}
-
 fn verify_password(password: &Password, hash: &str) -> Result<(), UserRepositoryError> {
-    let valid = bcrypt::verify(password.inner_ref(), hash)
+    let verification = password::verify(password.inner_ref(), hash)
         .map_err(|e| UserRepositoryError::PasswordHash(e.into()))?;
 
-    if !valid {
+    if verification == Verification::Invalid {
         return Err(UserRepositoryError::InvalidPassword);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use kernel::model::user::{UserEmail, UserRole};
+
+    use super::*;
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_find_all_by_cursor(pool: sqlx::PgPool) -> Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool), PasswordPolicy::default());
+
+        for i in 0..4 {
+            repo.create(CreateUser {
+                name: format!("user {i}").try_into()?,
+                email: format!("user{i}@example.com").parse::<UserEmail>()?,
+                password: "a-new-password".to_string().try_into()?,
+            })
+            .await?;
+        }
+
+        // 2 件ずつたどると 3 ページ目で終わる（fixture のユーザーと合わせて 5 件）
+        let mut pages = vec![];
+        let mut after = None;
+        loop {
+            let page = repo.find_all(CursorOptions { limit: 2, after }).await?;
+            pages.push(
+                page.items
+                    .iter()
+                    .map(|u| u.user_id().clone())
+                    .collect::<Vec<_>>(),
+            );
+            match page.next_cursor {
+                Some(cursor) => after = Some(cursor),
+                None => break,
+            }
+        }
+
+        assert_eq!(
+            pages.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        let ids = pages
+            .into_iter()
+            .flatten()
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(ids.len(), 5);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_update_role(pool: sqlx::PgPool) -> Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool), PasswordPolicy::default());
+        let alice = UserId::new("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse()?);
+
+        repo.update_role(UpdateUserRole {
+            user_id: alice.clone(),
+            role: UserRole::Admin,
+        })
+        .await?;
+        let user = repo
+            .find_current_user(&alice)
+            .await?
+            .ok_or(anyhow::anyhow!("user not found"))?;
+        assert_eq!(user.role(), &UserRole::Admin);
+
+        let missing = UserId::new(uuid::Uuid::new_v4());
+        let res = repo
+            .update_role(UpdateUserRole {
+                user_id: missing.clone(),
+                role: UserRole::Admin,
+            })
+            .await;
+        assert!(matches!(res, Err(UserRepositoryError::NotFound(id)) if id == missing));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "user"))]
+    async fn test_update_name_and_duplicate_email(pool: sqlx::PgPool) -> Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool), PasswordPolicy::default());
+        let alice = UserId::new("6d1d3a0c-6f0e-4a8e-9b3e-2f8a1c5d7e01".parse()?);
+
+        let user = repo
+            .update_name(UpdateUserName {
+                user_id: alice.clone(),
+                name: "Alice Liddell".to_string().try_into()?,
+            })
+            .await?;
+        assert_eq!(user.user_name().inner_ref(), "Alice Liddell");
+        assert_eq!(user.email().to_string(), "alice@example.com");
+
+        let res = repo
+            .update_name(UpdateUserName {
+                user_id: UserId::new(uuid::Uuid::new_v4()),
+                name: "Nobody".to_string().try_into()?,
+            })
+            .await;
+        assert!(matches!(res, Err(UserRepositoryError::NotFound(_))));
+
+        let res = repo
+            .create(CreateUser {
+                name: "Another Alice".to_string().try_into()?,
+                email: "alice@example.com".parse::<UserEmail>()?,
+                password: "a-new-password".to_string().try_into()?,
+            })
+            .await;
+        assert!(matches!(
+            res,
+            Err(UserRepositoryError::EmailAlreadyRegistered)
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+// RFC 6238 の TOTP（HMAC-SHA1, 6 桁, 30 秒）と、リカバリーコードの生成・ハッシュ化
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::{distributions::Slice, Rng, RngCore};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+const DIGITS: u32 = 6;
+const PERIOD: i64 = 30;
+const SECRET_BYTES: usize = 20;
+// 端末の時計のずれを考慮して前後 1 ステップのコードも受け付ける
+const SKEW_STEPS: i64 = 1;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+// 読み間違えやすい 0, 1, o, l を除いた文字
+const RECOVERY_CODE_CHARS: &[u8] = b"23456789abcdefghijkmnpqrstuvwxyz";
+
+// 認証アプリに登録する Base32 のシークレットを生成する
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut secret);
+    BASE32_NOPAD.encode(&secret)
+}
+
+// 認証アプリの QR コードに埋め込む URI を組み立てる
+pub fn otpauth_uri(secret: &str, issuer: &str, account: &str) -> String {
+    let label = urlencoding::encode(&format!("{issuer}:{account}")).into_owned();
+    format!(
+        "otpauth://totp/{label}?secret={secret}&issuer={}&algorithm=SHA1&digits={DIGITS}&period={PERIOD}",
+        urlencoding::encode(issuer)
+    )
+}
+
+pub fn step_at(unix_time: i64) -> i64 {
+    unix_time.div_euclid(PERIOD)
+}
+
+// 指定したステップのコードを返す。シークレットが Base32 として不正な場合は None
+pub fn code_at(secret: &str, step: i64) -> Option<String> {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).ok()?;
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = usize::from(hash[hash.len() - 1] & 0x0f);
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    Some(format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    ))
+}
+
+// コードが一致したステップを返す。last_used_step 以前のステップは再利用とみなして受け付けない
+pub fn verify(
+    secret: &str,
+    code: &str,
+    unix_time: i64,
+    last_used_step: Option<i64>,
+) -> Option<i64> {
+    let current = step_at(unix_time);
+    (current - SKEW_STEPS..=current + SKEW_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_at(secret, *step).is_some_and(|expected| expected == code))
+}
+
+// 利用者に一度だけ表示するリカバリーコードを生成する
+pub fn generate_recovery_codes() -> Vec<String> {
+    let chars = Slice::new(RECOVERY_CODE_CHARS).expect("recovery code chars must not be empty");
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code: String = (&mut rng)
+                .sample_iter(&chars)
+                .take(10)
+                .map(|c| char::from(*c))
+                .collect();
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+// リカバリーコードは十分な長さの乱数なので、ソルトなしの SHA-256 で保存する
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 Appendix B のテストベクター（SHA1, シークレットは "12345678901234567890"）
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn matches_rfc6238_test_vectors() {
+        for (time, expected) in [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            assert_eq!(code_at(RFC_SECRET, step_at(time)).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn accepts_adjacent_steps_but_not_reused_ones() {
+        let now = 1111111111;
+        let previous = code_at(RFC_SECRET, step_at(now) - 1).unwrap();
+
+        assert_eq!(
+            verify(RFC_SECRET, &previous, now, None),
+            Some(step_at(now) - 1)
+        );
+        assert_eq!(verify(RFC_SECRET, &previous, now, Some(step_at(now))), None);
+        assert_eq!(verify(RFC_SECRET, "000000", now, None), None);
+    }
+
+    #[test]
+    fn builds_otpauth_uri() {
+        assert_eq!(
+            otpauth_uri("ABC", "Book Manager", "alice@example.com"),
+            "otpauth://totp/Book%20Manager%3Aalice%40example.com?secret=ABC&issuer=Book%20Manager&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn generates_decodable_secret_and_distinct_recovery_codes() {
+        assert!(code_at(&generate_secret(), 0).is_some());
+
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(codes[0].len(), 11);
+        assert_eq!(
+            hash_recovery_code(&codes[0]),
+            hash_recovery_code(&codes[0].to_uppercase().replace('-', ""))
+        );
+    }
+}
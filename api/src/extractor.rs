@@ -1,8 +1,17 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
 use axum::{
     async_trait,
-    extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
-    response::IntoResponse,
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        ConnectInfo, FromRequest, FromRequestParts,
+    },
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
     RequestPartsExt,
 };
 use axum_extra::{
@@ -11,66 +20,235 @@ use axum_extra::{
 };
 use kernel::{
     model::{
-        auth::{AccessToken, AccessTokenError},
-        user::{User, UserId, UserRole},
+        api_key::{ApiKeyGrant, ApiKeyScope, API_KEY_PREFIX},
+        auth::{session::SessionClient, AccessToken, AccessTokenError, TokenSubject},
+        user::{UserId, UserRole},
+    },
+    repository::{
+        api_key::ApiKeyRepositoryError, auth::AuthRepositoryError, user::UserRepositoryError,
     },
-    repository::{auth::AuthRepositoryError, user::UserRepositoryError},
 };
 use registry::AppRegistry;
+use shared::problem::ProblemDetails;
+
+// 認証に使った資格情報
+pub enum Credential {
+    AccessToken(AccessToken),
+    ApiKey(ApiKeyGrant),
+}
+
+// ハンドラが求める操作の範囲。API キーで認証したリクエストは、キーにその範囲が無ければ拒否する
+pub trait RequiredScope: Send + Sync + 'static {
+    // ハンドラが受け取る資格情報の型
+    type Credential: Send + Sync;
+
+    fn accept(credential: Credential) -> Result<Self::Credential, AuthorizedUserError>;
+}
 
-pub struct AuthorizedUser {
-    pub access_token: AccessToken,
-    pub user: User,
+// セッションやパスワード、ユーザーの管理など、API キーでは呼び出せないハンドラ
+pub struct SessionOnly;
+
+impl RequiredScope for SessionOnly {
+    type Credential = AccessToken;
+
+    fn accept(credential: Credential) -> Result<Self::Credential, AuthorizedUserError> {
+        match credential {
+            Credential::AccessToken(access_token) => Ok(access_token),
+            Credential::ApiKey(_) => Err(AuthorizedUserError::ApiKeyNotAllowed),
+        }
+    }
 }
 
-impl AuthorizedUser {
+pub mod scope {
+    use kernel::model::api_key::ApiKeyScope;
+
+    use super::{AuthorizedUserError, Credential, RequiredScope};
+
+    macro_rules! define_scopes {
+        ($($name:ident),+ $(,)?) => {
+            $(
+                pub struct $name;
+
+                impl RequiredScope for $name {
+                    type Credential = Credential;
+
+                    fn accept(credential: Credential) -> Result<Self::Credential, AuthorizedUserError> {
+                        match &credential {
+                            Credential::ApiKey(grant) if !grant.scopes.contains(&ApiKeyScope::$name) => {
+                                Err(AuthorizedUserError::InsufficientScope(ApiKeyScope::$name))
+                            }
+                            _ => Ok(credential),
+                        }
+                    }
+                }
+            )+
+        };
+    }
+
+    define_scopes!(
+        BooksRead,
+        BooksWrite,
+        CheckoutsRead,
+        CheckoutsWrite,
+        ReservationsRead,
+        ReservationsWrite,
+        UsersRead,
+        UsersWrite,
+    );
+}
+
+// 範囲を指定しない場合は、ログインして得たアクセストークンのみを受け付ける
+pub struct AuthorizedUser<S: RequiredScope = SessionOnly> {
+    pub credential: S::Credential,
+    pub user_id: UserId,
+    pub role: UserRole,
+}
+
+impl<S: RequiredScope> AuthorizedUser<S> {
     pub fn user_id(&self) -> &UserId {
-        self.user.user_id()
+        &self.user_id
     }
 
     pub fn is_admin(&self) -> bool {
-        self.user.role() == &UserRole::Admin
+        self.role == UserRole::Admin
     }
+}
 
-    pub fn user(self) -> User {
-        self.user
+impl AuthorizedUser<SessionOnly> {
+    pub fn access_token(&self) -> &AccessToken {
+        &self.credential
     }
 }
 
 #[async_trait]
-impl FromRequestParts<AppRegistry> for AuthorizedUser {
+impl<S: RequiredScope> FromRequestParts<AppRegistry> for AuthorizedUser<S> {
     type Rejection = AuthorizedUserError;
 
     async fn from_request_parts(
         parts: &mut Parts,
         registry: &AppRegistry,
     ) -> Result<Self, Self::Rejection> {
-        // HTTP ヘッダーからアクセストークンを取得
+        // HTTP ヘッダーからアクセストークンか API キーを取得
         let TypedHeader(Authorization(bearer)) = parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
             .await
             .map_err(|_| AuthorizedUserError::Unauthenticated)?;
 
-        let access_token: AccessToken = bearer.token().to_string().try_into()?;
+        let (subject, credential) = if bearer.token().starts_with(API_KEY_PREFIX) {
+            let grant = registry
+                .api_key_repository()
+                .fetch_grant(bearer.token())
+                .await?
+                .ok_or(AuthorizedUserError::Unauthenticated)?;
+            let subject = TokenSubject {
+                user_id: grant.user_id.clone(),
+                role: None,
+            };
+            (subject, Credential::ApiKey(grant))
+        } else {
+            let access_token: AccessToken = bearer.token().to_string().try_into()?;
 
-        // アクセストークンに紐づくユーザーIDを取得
-        let user_id = registry
-            .auth_repository()
-            .fetch_user_id_from_token(&access_token)
-            .await?
-            .ok_or(AuthorizedUserError::Unauthenticated)?;
+            // アクセストークンに紐づくユーザーIDを取得
+            let subject = registry
+                .auth_repository()
+                .fetch_token_subject(&access_token)
+                .await?
+                .ok_or(AuthorizedUserError::Unauthenticated)?;
+            (subject, Credential::AccessToken(access_token))
+        };
+        let credential = S::accept(credential)?;
 
-        // ユーザーIDに紐づくユーザーを取得
-        let user = registry
-            .user_repository()
-            .find_current_user(&user_id)
-            .await?
-            .ok_or(AuthorizedUserError::Unauthenticated)?;
+        // 署名付きトークンはロールを含むため、ユーザーの取得を省く
+        let role = match subject.role {
+            Some(role) => role,
+            None => registry
+                .user_repository()
+                .find_current_user(&subject.user_id)
+                .await?
+                .ok_or(AuthorizedUserError::Unauthenticated)?
+                .role()
+                .clone(),
+        };
 
-        Ok(AuthorizedUser { access_token, user })
+        Ok(AuthorizedUser {
+            credential,
+            user_id: subject.user_id,
+            role,
+        })
     }
 }
 
+// ログイン・リフレッシュを行ったクライアントの情報。
+// IP アドレスは接続元のものとし、信頼するリバースプロキシからの接続に限って
+// X-Forwarded-For の末尾（そのプロキシが付け加えた値）を使う
+pub struct ClientInfo(pub SessionClient);
+
+// X-Forwarded-For を付け加えるリバースプロキシの IP アドレス。
+// ルーターに Extension として登録しない場合は、どの接続元も信頼しない
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Arc<[IpAddr]>);
+
+impl TrustedProxies {
+    pub fn new(addrs: impl IntoIterator<Item = IpAddr>) -> Self {
+        Self(addrs.into_iter().collect())
+    }
+
+    fn contains(&self, addr: &IpAddr) -> bool {
+        self.0.contains(addr)
+    }
+}
+
+// 極端に長い User-Agent をそのまま保存しないよう切り詰める
+const MAX_USER_AGENT_CHARS: usize = 512;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(MAX_USER_AGENT_CHARS).collect());
+
+        let peer = parts
+            .extract::<ConnectInfo<SocketAddr>>()
+            .await
+            .ok()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let trusted = parts
+            .extensions
+            .get::<TrustedProxies>()
+            .is_some_and(|proxies| peer.is_some_and(|peer| proxies.contains(&peer)));
+        let ip_address = if trusted {
+            forwarded_for(&parts.headers).or(peer)
+        } else {
+            peer
+        };
+
+        Ok(ClientInfo(SessionClient {
+            user_agent,
+            ip_address: ip_address.map(|v| v.to_string()),
+        }))
+    }
+}
+
+// 先頭側の値はクライアントが自由に書けるため、最後の X-Forwarded-For の末尾だけを読む
+fn forwarded_for(headers: &header::HeaderMap) -> Option<IpAddr> {
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .next_back()?
+        .to_str()
+        .ok()?
+        .rsplit(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum AuthorizedUserError {
     #[error("unauthorized")]
@@ -82,32 +260,102 @@ pub enum AuthorizedUserError {
     #[error("invalid access token")]
     InvalidAccessToken(#[from] AccessTokenError),
 
+    #[error("api keys cannot be used for this operation")]
+    ApiKeyNotAllowed,
+
+    #[error("api key does not have the {0} scope")]
+    InsufficientScope(ApiKeyScope),
+
     #[error("auth repository error")]
     AuthRepositoryError(#[from] AuthRepositoryError),
 
+    #[error("api key repository error")]
+    ApiKeyRepositoryError(#[from] ApiKeyRepositoryError),
+
     #[error("user repository error")]
     UserRepositoryError(#[from] UserRepositoryError),
 }
 
 impl IntoResponse for AuthorizedUserError {
     fn into_response(self) -> axum::response::Response {
-        let status = match self {
-            Self::Unauthorized => StatusCode::FORBIDDEN,
-            Self::Unauthenticated => StatusCode::UNAUTHORIZED,
-            Self::InvalidAccessToken(_) => StatusCode::UNAUTHORIZED,
+        let (status, code) = match &self {
+            Self::Unauthorized => (StatusCode::FORBIDDEN, "forbidden"),
+            Self::Unauthenticated => (StatusCode::UNAUTHORIZED, "unauthenticated"),
+            Self::InvalidAccessToken(_) => (StatusCode::UNAUTHORIZED, "invalid_access_token"),
             Self::AuthRepositoryError(AuthRepositoryError::InvalidPassword) => {
-                StatusCode::UNAUTHORIZED
+                (StatusCode::UNAUTHORIZED, "invalid_credentials")
+            }
+            Self::ApiKeyNotAllowed => (StatusCode::FORBIDDEN, "api_key_not_allowed"),
+            Self::InsufficientScope(_) => (StatusCode::FORBIDDEN, "insufficient_scope"),
+            Self::AuthRepositoryError(_)
+            | Self::ApiKeyRepositoryError(_)
+            | Self::UserRepositoryError(_) => {
+                return ProblemDetails::internal(&self).into_response()
+            }
+        };
+
+        ProblemDetails::new(status, code, &self).into_response()
+    }
+}
+
+// axum の Json・Path・Query と同じく使えるが、値を取り出せなかった場合は
+// 他のエラーと同じく application/problem+json で返す
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(RequestRejection))]
+pub struct Json<T>(pub T);
+
+impl<T: serde::Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(RequestRejection))]
+pub struct Path<T>(pub T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(RequestRejection))]
+pub struct Query<T>(pub T);
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestRejection {
+    #[error(transparent)]
+    Json(#[from] JsonRejection),
+
+    #[error(transparent)]
+    Path(#[from] PathRejection),
+
+    #[error(transparent)]
+    Query(#[from] QueryRejection),
+}
+
+impl IntoResponse for RequestRejection {
+    fn into_response(self) -> Response {
+        // ステータスコードは axum が決めたものをそのまま使う
+        let (status, code) = match &self {
+            Self::Json(JsonRejection::MissingJsonContentType(e)) => {
+                (e.status(), "unsupported_media_type")
             }
-            e @ (Self::AuthRepositoryError(_) | Self::UserRepositoryError(_)) => {
-                tracing::error!(
-                    error.cause_chain = ?e,
-                    error.message = %e,
-                    "unexpected error happened"
-                );
-                StatusCode::INTERNAL_SERVER_ERROR
+            Self::Json(e) => (e.status(), "invalid_request_body"),
+            Self::Path(PathRejection::FailedToDeserializePathParams(e)) => {
+                (e.status(), "invalid_id")
             }
+            Self::Query(e) => (e.status(), "invalid_query"),
+            // ルートの定義とハンドラの引数が食い違っている
+            Self::Path(_) => return ProblemDetails::internal(&self).into_response(),
         };
 
-        status.into_response()
+        ProblemDetails::new(status, code, self.body_text()).into_response()
+    }
+}
+
+impl RequestRejection {
+    fn body_text(&self) -> String {
+        match self {
+            Self::Json(e) => e.body_text(),
+            Self::Path(e) => e.body_text(),
+            Self::Query(e) => e.body_text(),
+        }
     }
 }
@@ -0,0 +1,139 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use garde::Validate;
+use kernel::{
+    model::api_key::{event::DeleteApiKey, ApiKeyIdError, ApiKeyNameError},
+    repository::api_key::ApiKeyRepositoryError,
+};
+use registry::AppRegistry;
+use shared::problem::{FieldError, ProblemDetails};
+use uuid::Uuid;
+
+use crate::{
+    extractor::{AuthorizedUser, Json, Path},
+    model::{
+        api_key::{
+            ApiKeysResponse, CreateApiKeyRequest, CreateApiKeyRequestWithUserId,
+            CreatedApiKeyResponse,
+        },
+        violation::ToViolation,
+    },
+};
+
+// ユーザーが自身の API キーを取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/api-keys",
+    tag = "users",
+    responses(
+        (status = 200, body = ApiKeysResponse),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn list_api_keys(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> Result<Json<ApiKeysResponse>, ApiKeyHandlerError> {
+    registry
+        .api_key_repository()
+        .find_all(user.user_id())
+        .await
+        .map(ApiKeysResponse::from)
+        .map(Json)
+        .map_err(ApiKeyHandlerError::from)
+}
+
+// ユーザーが API キーを発行する。キーそのものはこの応答でしか返さない
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/api-keys",
+    tag = "users",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, body = CreatedApiKeyResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn create_api_key(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKeyResponse>), ApiKeyHandlerError> {
+    req.validate()?;
+
+    let event = CreateApiKeyRequestWithUserId::new(user.user_id().clone(), req).try_into()?;
+
+    registry
+        .api_key_repository()
+        .create(event)
+        .await
+        .map(|created| (StatusCode::CREATED, Json(created.into())))
+        .map_err(ApiKeyHandlerError::from)
+}
+
+// ユーザーが自身の API キーを失効させる
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/api-keys/{api_key_id}",
+    tag = "users",
+    params(("api_key_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "API キーを失効させた"),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn delete_api_key(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Path(api_key_id): Path<Uuid>,
+) -> Result<StatusCode, ApiKeyHandlerError> {
+    let event = DeleteApiKey {
+        api_key_id: api_key_id.try_into()?,
+        requested_by: user.user_id().clone(),
+    };
+
+    registry.api_key_repository().delete(event).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiKeyHandlerError {
+    #[error("validation error: {0}")]
+    ValidationError(#[from] garde::Report),
+
+    #[error("invalid api key name: {0}")]
+    InvalidName(#[from] ApiKeyNameError),
+
+    #[error("invalid api key id: {0}")]
+    InvalidApiKeyId(#[from] ApiKeyIdError),
+
+    #[error("api key repository error: {0}")]
+    ApiKeyRepositoryError(#[from] ApiKeyRepositoryError),
+}
+
+impl IntoResponse for ApiKeyHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, code) = match &self {
+            ApiKeyHandlerError::ValidationError(report) => {
+                return ProblemDetails::from(report).into_response()
+            }
+            ApiKeyHandlerError::InvalidName(e) => {
+                return ProblemDetails::from(FieldError::new("name", e.violation())).into_response()
+            }
+            ApiKeyHandlerError::InvalidApiKeyId(_) => (StatusCode::BAD_REQUEST, "invalid_id"),
+            ApiKeyHandlerError::ApiKeyRepositoryError(ApiKeyRepositoryError::NotFound) => {
+                (StatusCode::NOT_FOUND, "api_key_not_found")
+            }
+            ApiKeyHandlerError::ApiKeyRepositoryError(_) => {
+                return ProblemDetails::internal(&self).into_response()
+            }
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
+    }
+}
@@ -1,53 +1,292 @@
 use std::str::FromStr;
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, StatusCode},
+    response::IntoResponse,
+};
 use kernel::{
     model::{
-        auth::event::CreateToken,
+        auth::{
+            event::{
+                CreateLoginChallenge, CreateToken, EnableTotp, RecordLoginFailure, RotateToken,
+            },
+            throttle::LoginLockout,
+            totp::{LoginChallengeToken, SecondFactor, TotpRequirement, LOGIN_CHALLENGE_TTL},
+            RefreshToken, RefreshTokenError,
+        },
         user::{Password, PasswordError, UserEmail, UserEmailError},
         value_object::ValueObject,
     },
-    repository::auth::AuthRepositoryError,
+    repository::{
+        auth::AuthRepositoryError, login_attempt::LoginAttemptRepositoryError,
+        totp::TotpRepositoryError,
+    },
 };
 use registry::AppRegistry;
+use shared::problem::{FieldError, ProblemDetails};
 use thiserror::Error;
 
 use crate::{
-    extractor::AuthorizedUser,
-    model::auth::{AccessTokenResponse, LoginRequest},
+    extractor::{AuthorizedUser, ClientInfo, Json},
+    model::{
+        auth::{
+            AccessTokenResponse, JwksResponse, LoginRequest, LoginResponse, RefreshTokenRequest,
+            TotpChallengeResponse, TotpLoginRequest, TotpLoginResponse,
+        },
+        violation::ToViolation,
+    },
 };
 
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    security(()),
+    responses(
+        (
+            status = 200,
+            description = "二要素認証を有効にしている場合は、トークンの代わりに mfaToken を返す",
+            body = LoginResponse,
+        ),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (
+            status = 429,
+            description = "ログインの失敗が続いたためロックされている",
+            body = ProblemDetails,
+            content_type = "application/problem+json",
+            headers(("Retry-After" = u64, description = "ログインを再び試せるまでの秒数")),
+        ),
+    )
+)]
 pub(crate) async fn login(
+    ClientInfo(client): ClientInfo,
     State(registry): State<AppRegistry>,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<AccessTokenResponse>, AuthHandlerError> {
+) -> Result<Json<LoginResponse>, AuthHandlerError> {
     let email = UserEmail::from_str(&req.email).map_err(AuthHandlerError::from)?;
     let password = Password::try_from(req.password).map_err(AuthHandlerError::from)?;
 
-    let user_id = registry
+    let login_attempts = registry.login_attempt_repository();
+    if let Some(lockout) = login_attempts.find_lockout(&email, &client).await? {
+        return Err(AuthHandlerError::LoginLocked(lockout));
+    }
+
+    let user_id = match registry
         .auth_repository()
         .verify_user(&email, &password)
-        .await?;
+        .await
+    {
+        Ok(user_id) => user_id,
+        Err(AuthRepositoryError::InvalidPassword) => {
+            login_attempts
+                .record_failure(RecordLoginFailure { email, client })
+                .await?;
+            return Err(AuthRepositoryError::InvalidPassword.into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let totp = registry.totp_repository();
+    let setup = match totp.find_requirement(&user_id).await? {
+        TotpRequirement::Disabled => {
+            login_attempts.clear_failures(&email).await?;
+            let tokens = registry
+                .auth_repository()
+                .create_token(CreateToken::new(user_id, client))
+                .await?;
+            return Ok(Json(LoginResponse::Tokens(tokens.into())));
+        }
+        TotpRequirement::Enabled => None,
+        // 二要素認証が必須で未登録の場合は、ここで登録を始めて最初のコードを求める
+        TotpRequirement::SetupRequired => Some(totp.create_secret(&user_id).await?),
+    };
+
+    let create_challenge = CreateLoginChallenge::new(user_id, email, setup.is_some());
+    let mfa_token = create_challenge.challenge_token.clone();
+    totp.create_challenge(create_challenge).await?;
+
+    Ok(Json(LoginResponse::TotpRequired(TotpChallengeResponse {
+        mfa_token: mfa_token.into_inner(),
+        expires_in: LOGIN_CHALLENGE_TTL,
+        setup: setup.map(Into::into),
+    })))
+}
+
+// パスワードを確認したログインに、TOTP のコードかリカバリーコードを送ってトークンを受け取る
+#[utoipa::path(
+    post,
+    path = "/auth/login/totp",
+    tag = "auth",
+    request_body = TotpLoginRequest,
+    security(()),
+    responses(
+        (status = 200, body = TotpLoginResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (
+            status = 429,
+            description = "ログインの失敗が続いたためロックされている",
+            body = ProblemDetails,
+            content_type = "application/problem+json",
+            headers(("Retry-After" = u64, description = "ログインを再び試せるまでの秒数")),
+        ),
+    )
+)]
+pub(crate) async fn login_totp(
+    ClientInfo(client): ClientInfo,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<TotpLoginRequest>,
+) -> Result<Json<TotpLoginResponse>, AuthHandlerError> {
+    let challenge_token = LoginChallengeToken::new(req.mfa_token);
+    let second_factor =
+        SecondFactor::try_from(req.second_factor).map_err(AuthHandlerError::InvalidSecondFactor)?;
 
-    let create_token = CreateToken::new(user_id.clone());
-    let access_token = registry
+    let totp = registry.totp_repository();
+    let challenge = totp
+        .find_challenge(&challenge_token)
+        .await?
+        .ok_or(AuthHandlerError::InvalidMfaToken)?;
+
+    let login_attempts = registry.login_attempt_repository();
+    if let Some(lockout) = login_attempts
+        .find_lockout(&challenge.email, &client)
+        .await?
+    {
+        return Err(AuthHandlerError::LoginLocked(lockout));
+    }
+
+    let result = match (challenge.setup_required, second_factor) {
+        (false, second_factor) => totp
+            .verify(&challenge.user_id, &second_factor)
+            .await
+            .map(|_| None),
+        (true, SecondFactor::Totp(code)) => totp
+            .enable(EnableTotp {
+                user_id: challenge.user_id.clone(),
+                code,
+            })
+            .await
+            .map(Some),
+        // 登録を完了するまではリカバリーコードを発行していない
+        (true, SecondFactor::RecoveryCode(_)) => Err(TotpRepositoryError::InvalidCode),
+    };
+    let recovery_codes = match result {
+        Ok(recovery_codes) => recovery_codes,
+        Err(TotpRepositoryError::InvalidCode) => {
+            totp.fail_challenge(&challenge_token).await?;
+            login_attempts
+                .record_failure(RecordLoginFailure {
+                    email: challenge.email,
+                    client,
+                })
+                .await?;
+            return Err(TotpRepositoryError::InvalidCode.into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    totp.delete_challenge(&challenge_token).await?;
+    login_attempts.clear_failures(&challenge.email).await?;
+
+    let tokens = registry
         .auth_repository()
-        .create_token(create_token)
+        .create_token(CreateToken::new(challenge.user_id, client))
         .await?;
-
-    Ok(Json(AccessTokenResponse {
-        access_token: access_token.into_inner(),
-        user_id: user_id.into_inner(),
+    Ok(Json(TotpLoginResponse {
+        tokens: tokens.into(),
+        recovery_codes,
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshTokenRequest,
+    security(()),
+    responses(
+        (status = 200, body = AccessTokenResponse),
+        (status = 401, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn refresh(
+    ClientInfo(client): ClientInfo,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<RefreshTokenRequest>,
+) -> Result<Json<AccessTokenResponse>, AuthHandlerError> {
+    let refresh_token = RefreshToken::try_from(req.refresh_token)?;
+
+    registry
+        .auth_repository()
+        .rotate_token(RotateToken::new(refresh_token, client))
+        .await
+        .map(AccessTokenResponse::from)
+        .map(Json)
+        .map_err(AuthHandlerError::from)
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 204, description = "アクセストークンを無効にした"),
+        (status = 401, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn logout(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
 ) -> Result<StatusCode, AuthHandlerError> {
     registry
         .auth_repository()
-        .delete_token(&user.access_token)
+        .delete_token(user.access_token())
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// アクセストークンの署名を検証するための公開鍵を JWKS として公開する。
+// アクセストークンに JWT を使わない設定では空の一覧を返す
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "auth",
+    security(()),
+    responses((status = 200, body = JwksResponse))
+)]
+pub(crate) async fn jwks(State(registry): State<AppRegistry>) -> impl IntoResponse {
+    let keys = JwksResponse::from(registry.auth_repository().public_keys());
+
+    // 鍵の入れ替え時は新しい鍵を先に公開しておくため、短時間のキャッシュを許可する
+    (
+        [(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=300"),
+        )],
+        Json(keys),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout-all",
+    tag = "auth",
+    responses(
+        (status = 204, description = "自身のすべてのセッションを無効にした"),
+        (status = 401, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn logout_all(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> Result<StatusCode, AuthHandlerError> {
+    registry
+        .auth_repository()
+        .delete_all_sessions(user.user_id())
         .await?;
 
     Ok(StatusCode::NO_CONTENT)
@@ -63,26 +302,76 @@ pub enum AuthHandlerError {
 
     #[error("invalid password: {0}")]
     InvalidPassword(#[from] PasswordError),
+
+    #[error("invalid refresh token: {0}")]
+    InvalidRefreshToken(#[from] RefreshTokenError),
+
+    #[error("too many failed login attempts; retry after {} seconds", .0.retry_after)]
+    LoginLocked(LoginLockout),
+
+    #[error("login attempt repository error: {0}")]
+    LoginAttemptRepositoryError(#[from] LoginAttemptRepositoryError),
+
+    #[error("invalid second factor")]
+    InvalidSecondFactor(FieldError),
+
+    #[error("mfa token is invalid or expired")]
+    InvalidMfaToken,
+
+    #[error("totp repository error: {0}")]
+    TotpRepositoryError(#[from] TotpRepositoryError),
 }
 
 impl IntoResponse for AuthHandlerError {
     fn into_response(self) -> axum::response::Response {
-        let status_code = match self {
-            AuthHandlerError::InvalidEmail(_email_error) => StatusCode::BAD_REQUEST,
-            AuthHandlerError::InvalidPassword(_password_error) => StatusCode::BAD_REQUEST,
+        match &self {
+            AuthHandlerError::InvalidEmail(e) => {
+                ProblemDetails::from(FieldError::new("email", e.violation())).into_response()
+            }
+            AuthHandlerError::InvalidPassword(e) => {
+                ProblemDetails::from(FieldError::new("password", e.violation())).into_response()
+            }
+            AuthHandlerError::InvalidSecondFactor(e) => {
+                ProblemDetails::from(e.clone()).into_response()
+            }
             AuthHandlerError::AuthRepositoryError(AuthRepositoryError::InvalidPassword) => {
-                StatusCode::UNAUTHORIZED
+                ProblemDetails::new(StatusCode::UNAUTHORIZED, "invalid_credentials", &self)
+                    .into_response()
             }
-            AuthHandlerError::AuthRepositoryError(_) => {
-                tracing::error!(
-                    error.cause_chain = ?self,
-                    error.message = %self,
-                    "unexpected error happened"
-                );
-                StatusCode::INTERNAL_SERVER_ERROR
+            AuthHandlerError::InvalidRefreshToken(_)
+            | AuthHandlerError::AuthRepositoryError(AuthRepositoryError::InvalidRefreshToken) => {
+                ProblemDetails::new(StatusCode::UNAUTHORIZED, "invalid_refresh_token", &self)
+                    .into_response()
             }
-        };
-
-        status_code.into_response()
+            AuthHandlerError::AuthRepositoryError(AuthRepositoryError::RefreshTokenReused) => {
+                ProblemDetails::new(StatusCode::UNAUTHORIZED, "refresh_token_reused", &self)
+                    .into_response()
+            }
+            AuthHandlerError::LoginLocked(lockout) => {
+                let mut response = ProblemDetails::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "too_many_login_attempts",
+                    &self,
+                )
+                .into_response();
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(lockout.retry_after));
+                response
+            }
+            AuthHandlerError::InvalidMfaToken => {
+                ProblemDetails::new(StatusCode::UNAUTHORIZED, "invalid_mfa_token", &self)
+                    .into_response()
+            }
+            AuthHandlerError::TotpRepositoryError(TotpRepositoryError::InvalidCode) => {
+                ProblemDetails::new(StatusCode::UNAUTHORIZED, "invalid_totp_code", &self)
+                    .into_response()
+            }
+            AuthHandlerError::AuthRepositoryError(_)
+            | AuthHandlerError::LoginAttemptRepositoryError(_)
+            | AuthHandlerError::TotpRepositoryError(_) => {
+                ProblemDetails::internal(&self).into_response()
+            }
+        }
     }
 }
@@ -1,28 +1,40 @@
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 use garde::Validate;
 use kernel::{
     model::book::{event::DeleteBook, BookIdError},
     repository::book::BookRepositoryError,
 };
 use registry::AppRegistry;
+use shared::problem::ProblemDetails;
 use thiserror::Error;
 use uuid::Uuid;
 
 use crate::{
-    extractor::AuthorizedUser,
-    model::book::{
-        BookListQuery, BookResponse, CreateBookRequest, CreateBookRequestError,
-        PaginatedBookResponse, UpdateBookRequest, UpdateBookRequestError, UpdateBookRequestWithIds,
+    extractor::{scope, AuthorizedUser, Json, Path, Query},
+    model::{
+        book::{
+            book_cursor_scope, BookListParams, BookListQuery, BookListQueryWithUserId,
+            BookListResponse, BookResponse, CreateBookRequest, CreateBookRequestError,
+            PaginatedBookResponse, UpdateBookRequest, UpdateBookRequestError,
+            UpdateBookRequestWithIds,
+        },
+        list::{CursorError, CursorPageResponse},
     },
 };
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/books",
+    tag = "books",
+    request_body = CreateBookRequest,
+    responses(
+        (status = 201, description = "蔵書を登録した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn register_book(
-    user: AuthorizedUser,
+    user: AuthorizedUser<scope::BooksWrite>,
     State(registry): State<AppRegistry>,
     Json(req): Json<CreateBookRequest>,
 ) -> Result<StatusCode, BookHandlerError> {
@@ -36,20 +48,45 @@ pub(crate) async fn register_book(
         .map_err(BookHandlerError::from)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/books",
+    tag = "books",
+    params(BookListQuery),
+    responses(
+        (status = 200, body = BookListResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn show_book_list(
-    _user: AuthorizedUser,
+    user: AuthorizedUser<scope::BooksRead>,
     Query(req): Query<BookListQuery>,
     State(registry): State<AppRegistry>,
-) -> Result<Json<PaginatedBookResponse>, BookHandlerError> {
+) -> Result<Json<BookListResponse>, BookHandlerError> {
     req.validate()?;
 
-    registry
-        .book_repository()
-        .find_all(req.into())
-        .await
-        .map(PaginatedBookResponse::from)
-        .map(Json)
-        .map_err(BookHandlerError::from)
+    let params = BookListQueryWithUserId::new(user.user_id().clone(), req).try_into()?;
+
+    let res = match params {
+        BookListParams::Offset(options) => registry
+            .book_repository()
+            .find_all(options)
+            .await
+            .map(PaginatedBookResponse::from)
+            .map(BookListResponse::Paginated)?,
+        BookListParams::Cursor(options) => {
+            let scope = book_cursor_scope(&options.sort, &options.filter);
+            registry
+                .book_repository()
+                .find_all_by_cursor(options)
+                .await
+                .map(|page| CursorPageResponse::from_page(page, &scope))
+                .map(BookListResponse::Cursor)?
+        }
+    };
+
+    Ok(Json(res))
 }
 
 #[tracing::instrument(
@@ -58,8 +95,20 @@ pub(crate) async fn show_book_list(
         user_id = %_user.user_id(),
     )
 )]
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    params(("book_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = BookResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn show_book(
-    _user: AuthorizedUser,
+    _user: AuthorizedUser<scope::BooksRead>,
     State(registry): State<AppRegistry>,
     Path(book_id): Path<Uuid>,
 ) -> Result<Json<BookResponse>, BookHandlerError> {
@@ -76,8 +125,22 @@ pub(crate) async fn show_book(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    params(("book_id" = Uuid, Path)),
+    request_body = UpdateBookRequest,
+    responses(
+        (status = 204, description = "蔵書を更新した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn update_book(
-    user: AuthorizedUser,
+    user: AuthorizedUser<scope::BooksWrite>,
     Path(book_id): Path<Uuid>,
     State(registry): State<AppRegistry>,
     Json(req): Json<UpdateBookRequest>,
@@ -95,8 +158,21 @@ pub(crate) async fn update_book(
         .map_err(BookHandlerError::from)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    params(("book_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "蔵書を削除した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn delete_book(
-    user: AuthorizedUser,
+    user: AuthorizedUser<scope::BooksWrite>,
     Path(book_id): Path<Uuid>,
     State(registry): State<AppRegistry>,
 ) -> Result<StatusCode, BookHandlerError> {
@@ -132,26 +208,37 @@ pub enum BookHandlerError {
 
     #[error("invalid book id: {0}")]
     InvalidBookId(#[from] BookIdError),
+
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(#[from] CursorError),
 }
 
 impl IntoResponse for BookHandlerError {
     fn into_response(self) -> axum::response::Response {
-        let status_code = match self {
-            BookHandlerError::ValidationError(_) => StatusCode::BAD_REQUEST,
-            BookHandlerError::NotFound => StatusCode::NOT_FOUND,
-            e @ BookHandlerError::RepositoryError(_) => {
-                tracing::error!(
-                    error.cause_chain = ?e,
-                    error.message = %e,
-                    "unexpected error happened"
-                );
-                StatusCode::INTERNAL_SERVER_ERROR
+        let (status_code, code) = match &self {
+            BookHandlerError::ValidationError(report) => {
+                return ProblemDetails::from(report).into_response()
+            }
+            BookHandlerError::InvalidCreateBookRequest(e) => {
+                return ProblemDetails::from(e.field_error()).into_response()
+            }
+            BookHandlerError::InvalidUpdateBookRequest(e) => {
+                return ProblemDetails::from(e.field_error()).into_response()
+            }
+            BookHandlerError::NotFound
+            | BookHandlerError::RepositoryError(BookRepositoryError::NotFound(_)) => {
+                (StatusCode::NOT_FOUND, "book_not_found")
+            }
+            BookHandlerError::RepositoryError(BookRepositoryError::NotBookOwner(..)) => {
+                (StatusCode::FORBIDDEN, "forbidden")
+            }
+            BookHandlerError::InvalidBookId(_) => (StatusCode::BAD_REQUEST, "invalid_id"),
+            BookHandlerError::InvalidCursor(_) => (StatusCode::BAD_REQUEST, "invalid_cursor"),
+            BookHandlerError::RepositoryError(_) => {
+                return ProblemDetails::internal(&self).into_response()
             }
-            BookHandlerError::InvalidCreateBookRequest(_) => StatusCode::BAD_REQUEST,
-            BookHandlerError::InvalidBookId(_) => StatusCode::BAD_REQUEST,
-            BookHandlerError::InvalidUpdateBookRequest(_) => StatusCode::BAD_REQUEST,
         };
 
-        status_code.into_response()
+        ProblemDetails::new(status_code, code, &self).into_response()
     }
 }
@@ -0,0 +1,212 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use garde::Validate;
+use kernel::{
+    model::{
+        book::BookIdError,
+        book_copy::{event::DeleteBookCopy, BookCopyIdError},
+    },
+    repository::book_copy::BookCopyRepositoryError,
+};
+use registry::AppRegistry;
+use shared::problem::ProblemDetails;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    extractor::{scope, AuthorizedUser, Json, Path},
+    model::book_copy::{
+        BookCopiesResponse, BookCopyRequestError, CreateBookCopyRequest,
+        CreateBookCopyRequestWithIds, UpdateBookCopyRequest, UpdateBookCopyRequestWithIds,
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}/copies",
+    tag = "book-copies",
+    params(("book_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = BookCopiesResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn show_book_copy_list(
+    _user: AuthorizedUser<scope::BooksRead>,
+    State(registry): State<AppRegistry>,
+    Path(book_id): Path<Uuid>,
+) -> Result<Json<BookCopiesResponse>, BookCopyHandlerError> {
+    registry
+        .book_copy_repository()
+        .find_by_book_id(&book_id.try_into()?)
+        .await
+        .map(BookCopiesResponse::from)
+        .map(Json)
+        .map_err(BookCopyHandlerError::from)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/copies",
+    tag = "book-copies",
+    params(("book_id" = Uuid, Path)),
+    request_body = CreateBookCopyRequest,
+    responses(
+        (status = 201, description = "冊を追加した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn register_book_copy(
+    user: AuthorizedUser<scope::BooksWrite>,
+    State(registry): State<AppRegistry>,
+    Path(book_id): Path<Uuid>,
+    Json(req): Json<CreateBookCopyRequest>,
+) -> Result<StatusCode, BookCopyHandlerError> {
+    req.validate()?;
+
+    registry
+        .book_copy_repository()
+        .create(
+            CreateBookCopyRequestWithIds::new(book_id.try_into()?, user.user_id().clone(), req)
+                .try_into()?,
+        )
+        .await
+        .map(|_| StatusCode::CREATED)
+        .map_err(BookCopyHandlerError::from)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/copies/{book_copy_id}",
+    tag = "book-copies",
+    params(("book_id" = Uuid, Path), ("book_copy_id" = Uuid, Path)),
+    request_body = UpdateBookCopyRequest,
+    responses(
+        (status = 204, description = "冊の状態・配架場所を更新した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn update_book_copy(
+    user: AuthorizedUser<scope::BooksWrite>,
+    State(registry): State<AppRegistry>,
+    Path((book_id, book_copy_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<UpdateBookCopyRequest>,
+) -> Result<StatusCode, BookCopyHandlerError> {
+    req.validate()?;
+
+    registry
+        .book_copy_repository()
+        .update(
+            UpdateBookCopyRequestWithIds::new(
+                book_id.try_into()?,
+                book_copy_id.try_into()?,
+                user.user_id().clone(),
+                req,
+            )
+            .try_into()?,
+        )
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(BookCopyHandlerError::from)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/books/{book_id}/copies/{book_copy_id}",
+    tag = "book-copies",
+    params(("book_id" = Uuid, Path), ("book_copy_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "冊を削除した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn delete_book_copy(
+    user: AuthorizedUser<scope::BooksWrite>,
+    State(registry): State<AppRegistry>,
+    Path((book_id, book_copy_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, BookCopyHandlerError> {
+    let delete_book_copy = DeleteBookCopy {
+        book_copy_id: book_copy_id.try_into()?,
+        book_id: book_id.try_into()?,
+        requested_by: user.user_id().clone(),
+    };
+
+    registry
+        .book_copy_repository()
+        .delete(delete_book_copy)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(BookCopyHandlerError::from)
+}
+
+#[derive(Debug, Error)]
+pub enum BookCopyHandlerError {
+    #[error("validation error: {0}")]
+    ValidationError(#[from] garde::Report),
+
+    #[error("invalid book copy request: {0}")]
+    InvalidRequest(#[from] BookCopyRequestError),
+
+    #[error("invalid book id: {0}")]
+    InvalidBookId(#[from] BookIdError),
+
+    #[error("invalid book copy id: {0}")]
+    InvalidBookCopyId(#[from] BookCopyIdError),
+
+    #[error("repository error: {0}")]
+    RepositoryError(#[from] BookCopyRepositoryError),
+}
+
+impl IntoResponse for BookCopyHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, code) = match &self {
+            BookCopyHandlerError::ValidationError(report) => {
+                return ProblemDetails::from(report).into_response()
+            }
+            BookCopyHandlerError::InvalidRequest(e) => {
+                return ProblemDetails::from(e.field_error()).into_response()
+            }
+            BookCopyHandlerError::InvalidBookId(_) | BookCopyHandlerError::InvalidBookCopyId(_) => {
+                (StatusCode::BAD_REQUEST, "invalid_id")
+            }
+            BookCopyHandlerError::RepositoryError(e) => match e {
+                BookCopyRepositoryError::BookNotFound(_) => {
+                    (StatusCode::NOT_FOUND, "book_not_found")
+                }
+                BookCopyRepositoryError::NotFound(_) => {
+                    (StatusCode::NOT_FOUND, "book_copy_not_found")
+                }
+                BookCopyRepositoryError::NotBookOwner(..) => {
+                    (StatusCode::FORBIDDEN, "not_book_owner")
+                }
+                BookCopyRepositoryError::DuplicateBarcode(_) => {
+                    (StatusCode::CONFLICT, "duplicate_barcode")
+                }
+                BookCopyRepositoryError::CheckedOut(_) => {
+                    (StatusCode::CONFLICT, "book_copy_checked_out")
+                }
+                BookCopyRepositoryError::HasCheckoutHistory(_) => {
+                    (StatusCode::CONFLICT, "book_copy_has_history")
+                }
+                BookCopyRepositoryError::Unexpected(_)
+                | BookCopyRepositoryError::InvalidSavedEntity(_) => {
+                    return ProblemDetails::internal(&self).into_response()
+                }
+            },
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
+    }
+}
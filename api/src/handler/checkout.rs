@@ -1,32 +1,86 @@
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 use chrono::Utc;
+use garde::Validate;
 use kernel::{
     model::{
         book::BookIdError,
+        book_copy::BookCopyIdError,
         checkout::{
-            event::{CreateCheckout, UpdateReturned},
+            event::{CreateCheckout, RenewCheckout, UpdateReturned},
             CheckoutIdError,
         },
     },
     repository::checkout::CheckoutRepositoryError,
 };
 use registry::AppRegistry;
+use shared::problem::ProblemDetails;
 use uuid::Uuid;
 
-use crate::{extractor::AuthorizedUser, model::checkout::CheckoutsResponse};
+use crate::{
+    extractor::{scope, AuthorizedUser, Json, Path, Query},
+    model::{
+        checkout::{CheckoutListQuery, CheckoutResponse, CheckoutsResponse, CHECKOUT_CURSOR_SCOPE},
+        list::{CursorError, CursorPageResponse},
+    },
+};
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/checkouts",
+    tag = "checkouts",
+    params(("book_id" = Uuid, Path)),
+    responses(
+        (status = 201, description = "貸出可能な冊のいずれかを貸し出した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 422, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn checkout_book(
-    user: AuthorizedUser,
+    user: AuthorizedUser<scope::CheckoutsWrite>,
     State(registry): State<AppRegistry>,
     Path(book_id): Path<Uuid>,
+) -> Result<StatusCode, CheckoutHandlerError> {
+    // 冊を指定しない場合は貸出可能な冊のいずれかを貸し出す
+    let create_checkout = CreateCheckout {
+        book_id: book_id.try_into()?,
+        book_copy_id: None,
+        checked_out_by: user.user_id().clone(),
+        checked_out_at: Utc::now(),
+    };
+
+    registry
+        .checkout_repository()
+        .create(create_checkout)
+        .await
+        .map(|_| StatusCode::CREATED)
+        .map_err(CheckoutHandlerError::from)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/copies/{book_copy_id}/checkouts",
+    tag = "checkouts",
+    params(("book_id" = Uuid, Path), ("book_copy_id" = Uuid, Path)),
+    responses(
+        (status = 201, description = "指定した冊を貸し出した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 422, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn checkout_book_copy(
+    user: AuthorizedUser<scope::CheckoutsWrite>,
+    State(registry): State<AppRegistry>,
+    Path((book_id, book_copy_id)): Path<(Uuid, Uuid)>,
 ) -> Result<StatusCode, CheckoutHandlerError> {
     let create_checkout = CreateCheckout {
         book_id: book_id.try_into()?,
+        book_copy_id: Some(book_copy_id.try_into()?),
         checked_out_by: user.user_id().clone(),
         checked_out_at: Utc::now(),
     };
@@ -39,8 +93,21 @@ pub(crate) async fn checkout_book(
         .map_err(CheckoutHandlerError::from)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/checkouts/{checkout_id}/returned",
+    tag = "checkouts",
+    params(("book_id" = Uuid, Path), ("checkout_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "返却した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn return_book(
-    user: AuthorizedUser,
+    user: AuthorizedUser<scope::CheckoutsWrite>,
     State(registry): State<AppRegistry>,
     Path((book_id, checkout_id)): Path<(Uuid, Uuid)>,
 ) -> Result<StatusCode, CheckoutHandlerError> {
@@ -59,8 +126,53 @@ pub(crate) async fn return_book(
         .map_err(CheckoutHandlerError::from)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/checkouts/{checkout_id}/renewal",
+    tag = "checkouts",
+    params(("book_id" = Uuid, Path), ("checkout_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "返却期限を延長した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn renew_checkout(
+    user: AuthorizedUser<scope::CheckoutsWrite>,
+    State(registry): State<AppRegistry>,
+    Path((book_id, checkout_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, CheckoutHandlerError> {
+    let renew_checkout = RenewCheckout {
+        checkout_id: checkout_id.try_into()?,
+        book_id: book_id.try_into()?,
+        requested_by: user.user_id().clone(),
+        renewed_at: Utc::now(),
+    };
+
+    registry
+        .checkout_repository()
+        .renew(renew_checkout)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(CheckoutHandlerError::from)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}/checkout-history",
+    tag = "checkouts",
+    params(("book_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = CheckoutsResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn checkout_history(
-    _user: AuthorizedUser,
+    _user: AuthorizedUser<scope::CheckoutsRead>,
     State(registry): State<AppRegistry>,
     Path(book_id): Path<Uuid>,
 ) -> Result<Json<CheckoutsResponse>, CheckoutHandlerError> {
@@ -73,16 +185,32 @@ pub(crate) async fn checkout_history(
     Ok(Json(checkout_history))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/checkouts",
+    tag = "checkouts",
+    params(CheckoutListQuery),
+    responses(
+        (status = 200, body = CursorPageResponse<CheckoutResponse>),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn show_checked_out_list(
-    _user: AuthorizedUser,
+    _user: AuthorizedUser<scope::CheckoutsRead>,
     State(registry): State<AppRegistry>,
-) -> Result<Json<CheckoutsResponse>, CheckoutHandlerError> {
-    let checkout_history = registry
+    Query(req): Query<CheckoutListQuery>,
+) -> Result<Json<CursorPageResponse<CheckoutResponse>>, CheckoutHandlerError> {
+    req.validate()?;
+
+    let page = registry
         .checkout_repository()
-        .find_unreturned_all()
-        .await?
-        .into();
-    Ok(Json(checkout_history))
+        .find_unreturned_all(req.into_options()?)
+        .await?;
+    Ok(Json(CursorPageResponse::from_page(
+        page,
+        CHECKOUT_CURSOR_SCOPE,
+    )))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -93,12 +221,78 @@ pub(crate) enum CheckoutHandlerError {
     #[error("invalid checkout id: {0}")]
     InvalidCheckoutId(#[from] CheckoutIdError),
 
+    #[error("invalid book copy id: {0}")]
+    InvalidBookCopyId(#[from] BookCopyIdError),
+
+    #[error("validation error: {0}")]
+    ValidationError(#[from] garde::Report),
+
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(#[from] CursorError),
+
     #[error("checkout repository error: {0}")]
     CheckoutRepositoryError(#[from] CheckoutRepositoryError),
 }
 
 impl IntoResponse for CheckoutHandlerError {
     fn into_response(self) -> axum::response::Response {
-        todo!()
+        let (status_code, code) = match &self {
+            CheckoutHandlerError::ValidationError(report) => {
+                return ProblemDetails::from(report).into_response()
+            }
+            CheckoutHandlerError::InvalidBookId(_)
+            | CheckoutHandlerError::InvalidCheckoutId(_)
+            | CheckoutHandlerError::InvalidBookCopyId(_) => (StatusCode::BAD_REQUEST, "invalid_id"),
+            CheckoutHandlerError::InvalidCursor(_) => (StatusCode::BAD_REQUEST, "invalid_cursor"),
+            CheckoutHandlerError::CheckoutRepositoryError(e) => match e {
+                CheckoutRepositoryError::BookNotFound(_) => {
+                    (StatusCode::NOT_FOUND, "book_not_found")
+                }
+                CheckoutRepositoryError::BookCopyNotFound(_) => {
+                    (StatusCode::NOT_FOUND, "book_copy_not_found")
+                }
+                // 返却・延長の対象となる貸出が見つからない場合
+                CheckoutRepositoryError::NoResourceAffected(_) => {
+                    (StatusCode::NOT_FOUND, "checkout_not_found")
+                }
+                CheckoutRepositoryError::NoAvailableCopy(_) => {
+                    (StatusCode::CONFLICT, "no_available_copy")
+                }
+                CheckoutRepositoryError::BookCopyAlreadyCheckedOut(_) => {
+                    (StatusCode::CONFLICT, "book_copy_already_checked_out")
+                }
+                CheckoutRepositoryError::ReservedForOthers(_) => {
+                    (StatusCode::CONFLICT, "reserved_for_others")
+                }
+                CheckoutRepositoryError::RenewalLimitReached(_) => {
+                    (StatusCode::CONFLICT, "renewal_limit_reached")
+                }
+                CheckoutRepositoryError::RenewalBlockedByReservation(_) => {
+                    (StatusCode::CONFLICT, "renewal_blocked_by_reservation")
+                }
+                // 借主以外は返却・延長できない
+                CheckoutRepositoryError::CannotReturn(..)
+                | CheckoutRepositoryError::CannotRenew(..) => {
+                    (StatusCode::FORBIDDEN, "not_borrower")
+                }
+                // 貸出規則に反する場合
+                CheckoutRepositoryError::LoanLimitReached(..) => {
+                    (StatusCode::CONFLICT, "loan_limit_reached")
+                }
+                CheckoutRepositoryError::HasOverdueLoans(_) => {
+                    (StatusCode::CONFLICT, "overdue_loans")
+                }
+                CheckoutRepositoryError::OwnBook(..) => {
+                    (StatusCode::UNPROCESSABLE_ENTITY, "own_book")
+                }
+                CheckoutRepositoryError::Unexpected(_)
+                | CheckoutRepositoryError::InvalidSavedEntity(_)
+                | CheckoutRepositoryError::Transaction(_) => {
+                    return ProblemDetails::internal(&self).into_response()
+                }
+            },
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
     }
 }
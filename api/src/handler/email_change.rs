@@ -0,0 +1,105 @@
+use std::str::FromStr;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use kernel::{
+    model::user::{
+        email_change::EmailChangeToken,
+        event::{ConfirmEmailChange, RequestEmailChange},
+        UserEmail, UserEmailError,
+    },
+    repository::email_change::EmailChangeRepositoryError,
+};
+use registry::AppRegistry;
+use shared::problem::{FieldError, ProblemDetails};
+use thiserror::Error;
+
+use crate::{
+    extractor::{AuthorizedUser, Json},
+    model::{
+        email_change::{ConfirmEmailChangeRequest, EmailChangeRequest},
+        violation::ToViolation,
+    },
+};
+
+// ユーザーが自身のメールアドレスの変更を申し込む。
+// 新しいアドレスに確認用のリンクを送り、リンクを開くまではメールアドレスを変更しない
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/email",
+    tag = "users",
+    request_body = EmailChangeRequest,
+    responses(
+        (status = 202, description = "確認用のリンクを新しいアドレスに送った"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn request_email_change(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<EmailChangeRequest>,
+) -> Result<StatusCode, EmailChangeHandlerError> {
+    let event = RequestEmailChange::new(user.user_id().clone(), UserEmail::from_str(&req.email)?);
+
+    registry.email_change_repository().request(event).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+// 新しいアドレスで受け取ったトークンを確認してメールアドレスを変更する
+#[utoipa::path(
+    post,
+    path = "/auth/email-change/confirm",
+    tag = "auth",
+    request_body = ConfirmEmailChangeRequest,
+    security(()),
+    responses(
+        (status = 204, description = "メールアドレスを変更した"),
+        (status = 400, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn confirm_email_change(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ConfirmEmailChangeRequest>,
+) -> Result<StatusCode, EmailChangeHandlerError> {
+    let event = ConfirmEmailChange {
+        token: EmailChangeToken::new(req.token),
+    };
+
+    registry.email_change_repository().confirm(event).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Error)]
+pub enum EmailChangeHandlerError {
+    #[error("invalid email: {0}")]
+    InvalidEmail(#[from] UserEmailError),
+
+    #[error("email change repository error: {0}")]
+    EmailChangeRepositoryError(#[from] EmailChangeRepositoryError),
+}
+
+impl IntoResponse for EmailChangeHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, code) = match &self {
+            EmailChangeHandlerError::InvalidEmail(e) => {
+                return ProblemDetails::from(FieldError::new("email", e.violation()))
+                    .into_response()
+            }
+            EmailChangeHandlerError::EmailChangeRepositoryError(
+                EmailChangeRepositoryError::InvalidToken,
+            ) => (StatusCode::BAD_REQUEST, "invalid_email_change_token"),
+            EmailChangeHandlerError::EmailChangeRepositoryError(
+                EmailChangeRepositoryError::EmailAlreadyRegistered,
+            ) => (StatusCode::CONFLICT, "email_already_registered"),
+            EmailChangeHandlerError::EmailChangeRepositoryError(_) => {
+                return ProblemDetails::internal(&self).into_response()
+            }
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
+    }
+}
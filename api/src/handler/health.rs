@@ -1,10 +1,27 @@
 use axum::{extract::State, http::StatusCode};
 use registry::AppRegistry;
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/health",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "API サーバーが稼働している"))
+)]
 pub async fn health_check() -> StatusCode {
     StatusCode::OK
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/health/db",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "データベースに接続できる"),
+        (status = 500, description = "データベースに接続できない"),
+    )
+)]
 pub async fn health_check_db(State(registry): State<AppRegistry>) -> StatusCode {
     let result = registry.health_check_repository().check_db().await;
     match result {
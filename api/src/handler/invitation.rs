@@ -0,0 +1,201 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use garde::Validate;
+use kernel::{
+    model::invitation::{event::DeleteInvitation, InvitationIdError},
+    repository::invitation::InvitationRepositoryError,
+};
+use registry::AppRegistry;
+use shared::problem::{FieldError, ProblemDetails};
+use uuid::Uuid;
+
+use crate::{
+    extractor::{scope, AuthorizedUser, Json, Path},
+    model::{
+        invitation::{
+            AcceptInvitationRequest, AcceptInvitationRequestWithToken, CreateInvitationRequest,
+            CreateInvitationRequestWithUserId, InvitationResponse, InvitationsResponse,
+        },
+        user::{UserModelError, UserResponse},
+        violation::ToViolation,
+    },
+};
+
+// 管理者がメールアドレスとロールを指定して招待し、登録用のリンクをメールで送る。
+// 同じメールアドレスに招待し直すと、以前のリンクは使えなくなる
+#[utoipa::path(
+    post,
+    path = "/api/v1/invitations",
+    tag = "users",
+    request_body = CreateInvitationRequest,
+    responses(
+        (status = 201, body = InvitationResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn create_invitation(
+    user: AuthorizedUser<scope::UsersWrite>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateInvitationRequest>,
+) -> Result<(StatusCode, Json<InvitationResponse>), InvitationHandlerError> {
+    if !user.is_admin() {
+        return Err(InvitationHandlerError::Forbidden);
+    }
+
+    req.validate()?;
+
+    let event = CreateInvitationRequestWithUserId::new(user.user_id().clone(), req).try_into()?;
+
+    registry
+        .invitation_repository()
+        .create(event)
+        .await
+        .map(|invitation| (StatusCode::CREATED, Json(invitation.into())))
+        .map_err(InvitationHandlerError::from)
+}
+
+// 管理者が受け入れられていない有効な招待を取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/invitations",
+    tag = "users",
+    responses(
+        (status = 200, body = InvitationsResponse),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn list_invitations(
+    user: AuthorizedUser<scope::UsersRead>,
+    State(registry): State<AppRegistry>,
+) -> Result<Json<InvitationsResponse>, InvitationHandlerError> {
+    if !user.is_admin() {
+        return Err(InvitationHandlerError::Forbidden);
+    }
+
+    registry
+        .invitation_repository()
+        .find_pending()
+        .await
+        .map(InvitationsResponse::from)
+        .map(Json)
+        .map_err(InvitationHandlerError::from)
+}
+
+// 管理者が招待を取り消す
+#[utoipa::path(
+    delete,
+    path = "/api/v1/invitations/{invitation_id}",
+    tag = "users",
+    params(("invitation_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "招待を取り消した"),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn delete_invitation(
+    user: AuthorizedUser<scope::UsersWrite>,
+    State(registry): State<AppRegistry>,
+    Path(invitation_id): Path<Uuid>,
+) -> Result<StatusCode, InvitationHandlerError> {
+    if !user.is_admin() {
+        return Err(InvitationHandlerError::Forbidden);
+    }
+
+    let event = DeleteInvitation {
+        invitation_id: invitation_id.try_into()?,
+    };
+    registry.invitation_repository().delete(event).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 招待された人がメールのリンクのトークンを使い、名前とパスワードを決めてユーザーになる
+#[utoipa::path(
+    post,
+    path = "/auth/invitations/{token}/accept",
+    tag = "auth",
+    params(("token" = String, Path)),
+    request_body = AcceptInvitationRequest,
+    security(()),
+    responses(
+        (status = 201, body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn accept_invitation(
+    State(registry): State<AppRegistry>,
+    Path(token): Path<String>,
+    Json(req): Json<AcceptInvitationRequest>,
+) -> Result<(StatusCode, Json<UserResponse>), InvitationHandlerError> {
+    req.validate()?;
+
+    let event = AcceptInvitationRequestWithToken::new(token, req).try_into()?;
+
+    registry
+        .invitation_repository()
+        .accept(event)
+        .await
+        .map(|user| (StatusCode::CREATED, Json(user.into())))
+        .map_err(InvitationHandlerError::from)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvitationHandlerError {
+    #[error("forbidden")]
+    Forbidden,
+
+    #[error("validation error: {0}")]
+    ValidationError(#[from] garde::Report),
+
+    #[error("model error: {0}")]
+    ModelError(#[from] UserModelError),
+
+    #[error("invalid invitation id: {0}")]
+    InvalidInvitationId(#[from] InvitationIdError),
+
+    #[error("invitation repository error: {0}")]
+    InvitationRepositoryError(#[from] InvitationRepositoryError),
+}
+
+impl IntoResponse for InvitationHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, code) = match &self {
+            InvitationHandlerError::ValidationError(report) => {
+                return ProblemDetails::from(report).into_response()
+            }
+            InvitationHandlerError::ModelError(e) => {
+                return ProblemDetails::from(e.field_error()).into_response()
+            }
+            InvitationHandlerError::InvitationRepositoryError(
+                InvitationRepositoryError::WeakPassword(e),
+            ) => {
+                return ProblemDetails::from(FieldError::new("password", e.violation()))
+                    .into_response()
+            }
+            InvitationHandlerError::Forbidden => (StatusCode::FORBIDDEN, "forbidden"),
+            InvitationHandlerError::InvalidInvitationId(_) => {
+                (StatusCode::BAD_REQUEST, "invalid_id")
+            }
+            InvitationHandlerError::InvitationRepositoryError(
+                InvitationRepositoryError::NotFound,
+            ) => (StatusCode::NOT_FOUND, "invitation_not_found"),
+            InvitationHandlerError::InvitationRepositoryError(
+                InvitationRepositoryError::InvalidToken,
+            ) => (StatusCode::BAD_REQUEST, "invalid_invitation_token"),
+            InvitationHandlerError::InvitationRepositoryError(
+                InvitationRepositoryError::EmailAlreadyRegistered,
+            ) => (StatusCode::CONFLICT, "email_already_registered"),
+            InvitationHandlerError::InvitationRepositoryError(_) => {
+                return ProblemDetails::internal(&self).into_response()
+            }
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
+    }
+}
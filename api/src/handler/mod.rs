@@ -1,5 +1,15 @@
+pub mod api_key;
 pub mod auth;
 pub mod book;
+pub mod book_copy;
 pub mod checkout;
+pub mod email_change;
 pub mod health;
+pub mod invitation;
+pub mod oidc;
+pub mod openapi;
+pub mod password_reset;
+pub mod reservation;
+pub mod session;
+pub mod totp;
 pub mod user;
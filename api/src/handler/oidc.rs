@@ -0,0 +1,169 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Redirect},
+};
+use kernel::{
+    model::{
+        auth::{
+            event::{CompleteOidcLogin, CreateLoginChallenge, CreateToken},
+            oidc::{AuthorizationCode, AuthorizationCodeError, OidcState},
+            totp::{TotpRequirement, LOGIN_CHALLENGE_TTL},
+        },
+        value_object::ValueObject,
+    },
+    repository::{auth::AuthRepositoryError, oidc::OidcRepositoryError, totp::TotpRepositoryError},
+};
+use registry::AppRegistry;
+use shared::problem::{FieldError, ProblemDetails};
+
+use crate::{
+    extractor::{ClientInfo, Json, Query},
+    model::{
+        auth::{LoginResponse, TotpChallengeResponse},
+        oidc::OidcCallbackQuery,
+        violation::ToViolation,
+    },
+};
+
+// IdP の認可エンドポイントへリダイレクトしてシングルサインオンを始める
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/login",
+    tag = "auth",
+    security(()),
+    responses(
+        (
+            status = 303,
+            description = "IdP の認可エンドポイントへリダイレクトする",
+            headers(("Location" = String, description = "IdP の認可エンドポイントの URL")),
+        ),
+        (status = 404, response = ProblemDetails),
+        (status = 502, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn oidc_login(
+    State(registry): State<AppRegistry>,
+) -> Result<Redirect, OidcHandlerError> {
+    let authorization = registry.oidc_repository().begin_login().await?;
+
+    Ok(Redirect::to(&authorization.authorization_url))
+}
+
+// IdP からのリダイレクトを受け取り、ID トークンを確認してトークンを発行する。
+// IdP 側の二要素認証は確認できないため、パスワードでのログインと同じく TOTP の入力を求める
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/callback",
+    tag = "auth",
+    params(OidcCallbackQuery),
+    security(()),
+    responses(
+        (status = 200, body = LoginResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 502, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn oidc_callback(
+    ClientInfo(client): ClientInfo,
+    State(registry): State<AppRegistry>,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<Json<LoginResponse>, OidcHandlerError> {
+    if let Some(error) = query.error {
+        return Err(OidcHandlerError::Denied {
+            error,
+            description: query.error_description,
+        });
+    }
+
+    let event = CompleteOidcLogin {
+        state: OidcState::new(query.state.unwrap_or_default()),
+        code: AuthorizationCode::try_from(query.code.unwrap_or_default())?,
+    };
+    let login = registry.oidc_repository().complete_login(event).await?;
+
+    // IdP でロールが変わっていた場合は、変更前の権限で発行したトークンを使えないようにする
+    let auth = registry.auth_repository();
+    if login.role_changed {
+        auth.delete_all_sessions(&login.user_id).await?;
+    }
+
+    let totp = registry.totp_repository();
+    let setup = match totp.find_requirement(&login.user_id).await? {
+        TotpRequirement::Disabled => {
+            let tokens = auth
+                .create_token(CreateToken::new(login.user_id, client))
+                .await?;
+            return Ok(Json(LoginResponse::Tokens(tokens.into())));
+        }
+        TotpRequirement::Enabled => None,
+        TotpRequirement::SetupRequired => Some(totp.create_secret(&login.user_id).await?),
+    };
+
+    let create_challenge = CreateLoginChallenge::new(login.user_id, login.email, setup.is_some());
+    let mfa_token = create_challenge.challenge_token.clone();
+    totp.create_challenge(create_challenge).await?;
+
+    Ok(Json(LoginResponse::TotpRequired(TotpChallengeResponse {
+        mfa_token: mfa_token.into_inner(),
+        expires_in: LOGIN_CHALLENGE_TTL,
+        setup: setup.map(Into::into),
+    })))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcHandlerError {
+    #[error("identity provider returned an error: {error} ({})", .description.as_deref().unwrap_or_default())]
+    Denied {
+        error: String,
+        description: Option<String>,
+    },
+
+    #[error("invalid authorization code: {0}")]
+    InvalidCode(#[from] AuthorizationCodeError),
+
+    #[error("oidc repository error: {0}")]
+    OidcRepositoryError(#[from] OidcRepositoryError),
+
+    #[error("auth repository error: {0}")]
+    AuthRepositoryError(#[from] AuthRepositoryError),
+
+    #[error("totp repository error: {0}")]
+    TotpRepositoryError(#[from] TotpRepositoryError),
+}
+
+impl IntoResponse for OidcHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, code) = match &self {
+            OidcHandlerError::InvalidCode(e) => {
+                return ProblemDetails::from(FieldError::new("code", e.violation())).into_response()
+            }
+            OidcHandlerError::Denied { .. } => (StatusCode::UNAUTHORIZED, "oidc_login_denied"),
+            OidcHandlerError::OidcRepositoryError(OidcRepositoryError::NotConfigured) => {
+                (StatusCode::NOT_FOUND, "oidc_not_configured")
+            }
+            OidcHandlerError::OidcRepositoryError(OidcRepositoryError::InvalidState) => {
+                (StatusCode::UNAUTHORIZED, "invalid_oidc_state")
+            }
+            OidcHandlerError::OidcRepositoryError(OidcRepositoryError::InvalidIdToken(_)) => {
+                (StatusCode::UNAUTHORIZED, "invalid_id_token")
+            }
+            OidcHandlerError::OidcRepositoryError(OidcRepositoryError::EmailNotVerified) => {
+                (StatusCode::FORBIDDEN, "oidc_email_not_verified")
+            }
+            OidcHandlerError::OidcRepositoryError(OidcRepositoryError::IdentityProvider(_)) => {
+                (StatusCode::BAD_GATEWAY, "oidc_provider_error")
+            }
+            OidcHandlerError::OidcRepositoryError(OidcRepositoryError::Unexpected(_))
+            | OidcHandlerError::AuthRepositoryError(_)
+            | OidcHandlerError::TotpRepositoryError(_) => {
+                return ProblemDetails::internal(&self).into_response()
+            }
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
+    }
+}
@@ -0,0 +1,8 @@
+use axum::Json;
+use utoipa::OpenApi;
+
+use crate::openapi::ApiDoc;
+
+pub async fn show_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
@@ -0,0 +1,143 @@
+use std::str::FromStr;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use kernel::{
+    model::{
+        auth::{
+            event::{RequestPasswordReset, ResetPassword},
+            password_reset::PasswordResetToken,
+        },
+        user::{Password, PasswordError, UserEmail, UserEmailError},
+    },
+    repository::{
+        api_key::ApiKeyRepositoryError, auth::AuthRepositoryError,
+        password_reset::PasswordResetRepositoryError,
+    },
+};
+use registry::AppRegistry;
+use shared::{
+    i18n,
+    problem::{FieldError, ProblemDetails},
+};
+use thiserror::Error;
+
+use crate::{
+    extractor::Json,
+    model::{
+        password_reset::{ConfirmPasswordResetRequest, PasswordResetRequest},
+        violation::ToViolation,
+    },
+};
+
+// パスワードの再設定用のリンクをメールで送る。
+// アカウントの有無を推測されないよう、該当するユーザーがいなくても同じ応答を返す
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/request",
+    tag = "auth",
+    request_body = PasswordResetRequest,
+    security(()),
+    responses(
+        (status = 202, description = "該当するユーザーがいればメールを送る"),
+        (status = 400, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn request_password_reset(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<PasswordResetRequest>,
+) -> Result<StatusCode, PasswordResetHandlerError> {
+    let event = RequestPasswordReset::new(UserEmail::from_str(&req.email)?);
+
+    // メールの送信を待つとアカウントの有無が応答時間に表れるため、送信の完了を待たずに応答する。
+    // メールの文面はリクエストの言語に合わせる
+    let repository = registry.password_reset_repository();
+    tokio::spawn(i18n::scope(i18n::current(), async move {
+        if let Err(e) = repository.request(event).await {
+            tracing::error!(error = %e, "failed to request password reset");
+        }
+    }));
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+// メールで受け取ったトークンを確認してパスワードを変更する。
+// 以前のパスワードで作られたセッションはすべて無効にする
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/confirm",
+    tag = "auth",
+    request_body = ConfirmPasswordResetRequest,
+    security(()),
+    responses(
+        (status = 204, description = "パスワードを変更した"),
+        (status = 400, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn confirm_password_reset(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ConfirmPasswordResetRequest>,
+) -> Result<StatusCode, PasswordResetHandlerError> {
+    let event = ResetPassword {
+        token: PasswordResetToken::new(req.token),
+        new_password: Password::try_from(req.new_password)?,
+    };
+    let user_id = registry.password_reset_repository().confirm(event).await?;
+
+    // 乗っ取られたアカウントを取り戻す場合もあるため、API キーも無効にする
+    registry
+        .auth_repository()
+        .delete_all_sessions(&user_id)
+        .await?;
+    registry.api_key_repository().delete_all(&user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Error)]
+pub enum PasswordResetHandlerError {
+    #[error("invalid email: {0}")]
+    InvalidEmail(#[from] UserEmailError),
+
+    #[error("invalid password: {0}")]
+    InvalidPassword(#[from] PasswordError),
+
+    #[error("password reset repository error: {0}")]
+    PasswordResetRepositoryError(#[from] PasswordResetRepositoryError),
+
+    #[error("auth repository error: {0}")]
+    AuthRepositoryError(#[from] AuthRepositoryError),
+
+    #[error("api key repository error: {0}")]
+    ApiKeyRepositoryError(#[from] ApiKeyRepositoryError),
+}
+
+impl IntoResponse for PasswordResetHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        match &self {
+            PasswordResetHandlerError::InvalidEmail(e) => {
+                ProblemDetails::from(FieldError::new("email", e.violation())).into_response()
+            }
+            PasswordResetHandlerError::InvalidPassword(e) => {
+                ProblemDetails::from(FieldError::new("newPassword", e.violation())).into_response()
+            }
+            PasswordResetHandlerError::PasswordResetRepositoryError(
+                PasswordResetRepositoryError::WeakPassword(e),
+            ) => {
+                ProblemDetails::from(FieldError::new("newPassword", e.violation())).into_response()
+            }
+            PasswordResetHandlerError::PasswordResetRepositoryError(
+                PasswordResetRepositoryError::InvalidToken,
+            ) => ProblemDetails::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_password_reset_token",
+                &self,
+            )
+            .into_response(),
+            PasswordResetHandlerError::PasswordResetRepositoryError(_)
+            | PasswordResetHandlerError::AuthRepositoryError(_)
+            | PasswordResetHandlerError::ApiKeyRepositoryError(_) => {
+                ProblemDetails::internal(&self).into_response()
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::Utc;
+use kernel::{
+    model::{
+        book::BookIdError,
+        reservation::{
+            event::{CreateReservation, DeleteReservation},
+            ReservationIdError,
+        },
+    },
+    repository::reservation::ReservationRepositoryError,
+};
+use registry::AppRegistry;
+use shared::problem::ProblemDetails;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    extractor::{scope, AuthorizedUser, Json, Path},
+    model::reservation::ReservationsResponse,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}/reservations",
+    tag = "reservations",
+    params(("book_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = ReservationsResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn show_reservation_list(
+    _user: AuthorizedUser<scope::ReservationsRead>,
+    State(registry): State<AppRegistry>,
+    Path(book_id): Path<Uuid>,
+) -> Result<Json<ReservationsResponse>, ReservationHandlerError> {
+    registry
+        .reservation_repository()
+        .find_by_book_id(&book_id.try_into()?)
+        .await
+        .map(ReservationsResponse::from)
+        .map(Json)
+        .map_err(ReservationHandlerError::from)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/reservations",
+    tag = "reservations",
+    params(("book_id" = Uuid, Path)),
+    responses(
+        (status = 201, description = "予約した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn reserve_book(
+    user: AuthorizedUser<scope::ReservationsWrite>,
+    State(registry): State<AppRegistry>,
+    Path(book_id): Path<Uuid>,
+) -> Result<StatusCode, ReservationHandlerError> {
+    let create_reservation = CreateReservation {
+        book_id: book_id.try_into()?,
+        reserved_by: user.user_id().clone(),
+        reserved_at: Utc::now(),
+    };
+
+    registry
+        .reservation_repository()
+        .create(create_reservation)
+        .await
+        .map(|_| StatusCode::CREATED)
+        .map_err(ReservationHandlerError::from)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/books/{book_id}/reservations/{reservation_id}",
+    tag = "reservations",
+    params(("book_id" = Uuid, Path), ("reservation_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "予約を取り消した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn cancel_reservation(
+    user: AuthorizedUser<scope::ReservationsWrite>,
+    State(registry): State<AppRegistry>,
+    Path((book_id, reservation_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ReservationHandlerError> {
+    let delete_reservation = DeleteReservation {
+        reservation_id: reservation_id.try_into()?,
+        book_id: book_id.try_into()?,
+        requested_by: user.user_id().clone(),
+    };
+
+    registry
+        .reservation_repository()
+        .delete(delete_reservation)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(ReservationHandlerError::from)
+}
+
+#[derive(Debug, Error)]
+pub enum ReservationHandlerError {
+    #[error("invalid book id: {0}")]
+    InvalidBookId(#[from] BookIdError),
+
+    #[error("invalid reservation id: {0}")]
+    InvalidReservationId(#[from] ReservationIdError),
+
+    #[error("repository error: {0}")]
+    RepositoryError(#[from] ReservationRepositoryError),
+}
+
+impl IntoResponse for ReservationHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, code) = match &self {
+            ReservationHandlerError::InvalidBookId(_)
+            | ReservationHandlerError::InvalidReservationId(_) => {
+                (StatusCode::BAD_REQUEST, "invalid_id")
+            }
+            ReservationHandlerError::RepositoryError(e) => match e {
+                ReservationRepositoryError::BookNotFound(_) => {
+                    (StatusCode::NOT_FOUND, "book_not_found")
+                }
+                ReservationRepositoryError::NotFound(_) => {
+                    (StatusCode::NOT_FOUND, "reservation_not_found")
+                }
+                ReservationRepositoryError::AlreadyReserved(..) => {
+                    (StatusCode::CONFLICT, "already_reserved")
+                }
+                ReservationRepositoryError::AlreadyCheckedOut(..) => {
+                    (StatusCode::CONFLICT, "already_checked_out")
+                }
+                ReservationRepositoryError::BookAvailable(_) => {
+                    (StatusCode::CONFLICT, "book_available")
+                }
+                ReservationRepositoryError::CannotCancel(..) => {
+                    (StatusCode::FORBIDDEN, "not_reserver")
+                }
+                ReservationRepositoryError::Unexpected(_)
+                | ReservationRepositoryError::InvalidSavedEntity(_) => {
+                    return ProblemDetails::internal(&self).into_response()
+                }
+            },
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
+    }
+}
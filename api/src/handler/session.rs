@@ -0,0 +1,139 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use kernel::{
+    model::{
+        auth::{event::DeleteSession, session::SessionIdError},
+        user::UserIdError,
+    },
+    repository::{api_key::ApiKeyRepositoryError, auth::AuthRepositoryError},
+};
+use registry::AppRegistry;
+use shared::problem::ProblemDetails;
+use uuid::Uuid;
+
+use crate::{
+    extractor::{scope, AuthorizedUser, Json, Path},
+    model::session::SessionsResponse,
+};
+
+// ユーザーが自身のログイン中のセッションを取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/sessions",
+    tag = "users",
+    responses(
+        (status = 200, body = SessionsResponse),
+        (status = 401, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn list_sessions(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> Result<Json<SessionsResponse>, SessionHandlerError> {
+    registry
+        .auth_repository()
+        .find_sessions(user.user_id(), user.access_token())
+        .await
+        .map(SessionsResponse::from)
+        .map(Json)
+        .map_err(SessionHandlerError::from)
+}
+
+// ユーザーが自身のセッションを 1 つ無効にする
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/sessions/{session_id}",
+    tag = "users",
+    params(("session_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "セッションを無効にした"),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn delete_session(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Path(session_id): Path<Uuid>,
+) -> Result<StatusCode, SessionHandlerError> {
+    let delete_session = DeleteSession {
+        user_id: user.user_id().clone(),
+        session_id: session_id.try_into()?,
+    };
+
+    registry
+        .auth_repository()
+        .delete_session(delete_session)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 管理者がユーザーのすべてのセッションを無効にする
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{user_id}/sessions",
+    tag = "users",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "ユーザーのすべてのセッションと API キーを無効にした"),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn delete_user_sessions(
+    user: AuthorizedUser<scope::UsersWrite>,
+    State(registry): State<AppRegistry>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, SessionHandlerError> {
+    if !user.is_admin() {
+        return Err(SessionHandlerError::Forbidden);
+    }
+
+    // ログインし直させるだけでなく、API キーによる操作も止める
+    let user_id = user_id.try_into()?;
+    registry
+        .auth_repository()
+        .delete_all_sessions(&user_id)
+        .await?;
+    registry.api_key_repository().delete_all(&user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionHandlerError {
+    #[error("forbidden")]
+    Forbidden,
+
+    #[error("invalid user id: {0}")]
+    InvalidUserId(#[from] UserIdError),
+
+    #[error("invalid session id: {0}")]
+    InvalidSessionId(#[from] SessionIdError),
+
+    #[error("auth repository error: {0}")]
+    AuthRepositoryError(#[from] AuthRepositoryError),
+
+    #[error("api key repository error: {0}")]
+    ApiKeyRepositoryError(#[from] ApiKeyRepositoryError),
+}
+
+impl IntoResponse for SessionHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, code) = match &self {
+            SessionHandlerError::Forbidden => (StatusCode::FORBIDDEN, "forbidden"),
+            SessionHandlerError::InvalidUserId(_) | SessionHandlerError::InvalidSessionId(_) => {
+                (StatusCode::BAD_REQUEST, "invalid_id")
+            }
+            SessionHandlerError::AuthRepositoryError(AuthRepositoryError::SessionNotFound) => {
+                (StatusCode::NOT_FOUND, "session_not_found")
+            }
+            SessionHandlerError::AuthRepositoryError(_)
+            | SessionHandlerError::ApiKeyRepositoryError(_) => {
+                return ProblemDetails::internal(&self).into_response()
+            }
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
+    }
+}
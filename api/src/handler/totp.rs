@@ -0,0 +1,148 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use kernel::{
+    model::auth::{
+        event::{DisableTotp, EnableTotp},
+        totp::{SecondFactor, TotpCode, TotpCodeError},
+    },
+    repository::totp::TotpRepositoryError,
+};
+use registry::AppRegistry;
+use shared::problem::{FieldError, ProblemDetails};
+
+use crate::{
+    extractor::{AuthorizedUser, Json},
+    model::{
+        totp::{ConfirmTotpRequest, RecoveryCodesResponse, SecondFactorRequest, TotpSetupResponse},
+        violation::ToViolation,
+    },
+};
+
+// ユーザーが二要素認証の登録を始める
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/totp",
+    tag = "users",
+    responses(
+        (status = 200, body = TotpSetupResponse),
+        (status = 401, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn setup_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> Result<Json<TotpSetupResponse>, TotpHandlerError> {
+    registry
+        .totp_repository()
+        .create_secret(user.user_id())
+        .await
+        .map(TotpSetupResponse::from)
+        .map(Json)
+        .map_err(TotpHandlerError::from)
+}
+
+// 認証アプリに表示された最初のコードで登録を完了し、リカバリーコードを受け取る
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/totp/confirm",
+    tag = "users",
+    request_body = ConfirmTotpRequest,
+    responses(
+        (status = 200, body = RecoveryCodesResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 422, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn confirm_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ConfirmTotpRequest>,
+) -> Result<Json<RecoveryCodesResponse>, TotpHandlerError> {
+    let event = EnableTotp {
+        user_id: user.user_id().clone(),
+        code: TotpCode::try_from(req.code)?,
+    };
+
+    registry
+        .totp_repository()
+        .enable(event)
+        .await
+        .map(|recovery_codes| RecoveryCodesResponse { recovery_codes })
+        .map(Json)
+        .map_err(TotpHandlerError::from)
+}
+
+// ユーザーが TOTP のコードかリカバリーコードを入力して二要素認証を無効にする
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/totp",
+    tag = "users",
+    request_body = SecondFactorRequest,
+    responses(
+        (status = 204, description = "二要素認証を無効にした"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 422, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn disable_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<SecondFactorRequest>,
+) -> Result<StatusCode, TotpHandlerError> {
+    let event = DisableTotp {
+        user_id: user.user_id().clone(),
+        second_factor: SecondFactor::try_from(req)
+            .map_err(TotpHandlerError::InvalidSecondFactor)?,
+    };
+
+    registry.totp_repository().disable(event).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TotpHandlerError {
+    #[error("invalid totp code: {0}")]
+    InvalidCode(#[from] TotpCodeError),
+
+    #[error("invalid second factor")]
+    InvalidSecondFactor(FieldError),
+
+    #[error("totp repository error: {0}")]
+    TotpRepositoryError(#[from] TotpRepositoryError),
+}
+
+impl IntoResponse for TotpHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, code) = match &self {
+            TotpHandlerError::InvalidCode(e) => {
+                return ProblemDetails::from(FieldError::new("code", e.violation())).into_response()
+            }
+            TotpHandlerError::InvalidSecondFactor(e) => {
+                return ProblemDetails::from(e.clone()).into_response()
+            }
+            TotpHandlerError::TotpRepositoryError(TotpRepositoryError::InvalidCode) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "invalid_totp_code")
+            }
+            TotpHandlerError::TotpRepositoryError(TotpRepositoryError::AlreadyEnabled) => {
+                (StatusCode::CONFLICT, "totp_already_enabled")
+            }
+            TotpHandlerError::TotpRepositoryError(TotpRepositoryError::NotEnabled) => {
+                (StatusCode::CONFLICT, "totp_not_enabled")
+            }
+            TotpHandlerError::TotpRepositoryError(TotpRepositoryError::Required) => {
+                (StatusCode::FORBIDDEN, "totp_required")
+            }
+            TotpHandlerError::TotpRepositoryError(TotpRepositoryError::Unexpected(_)) => {
+                return ProblemDetails::internal(&self).into_response()
+            }
+        };
+
+        ProblemDetails::new(status_code, code, &self).into_response()
+    }
+}
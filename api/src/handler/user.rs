@@ -1,31 +1,48 @@
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 use garde::Validate;
 use kernel::{
-    model::user::{event::DeleteUser, UserIdError},
-    repository::{checkout::CheckoutRepositoryError, user::UserRepositoryError},
+    model::user::{event::DeleteUser, password::PasswordPolicyError, UserId, UserIdError},
+    repository::{
+        api_key::ApiKeyRepositoryError, auth::AuthRepositoryError,
+        checkout::CheckoutRepositoryError, user::UserRepositoryError,
+    },
 };
 use registry::AppRegistry;
+use shared::{
+    message::Violation,
+    problem::{FieldError, ProblemDetails},
+};
 use uuid::Uuid;
 
 use crate::{
-    extractor::AuthorizedUser,
+    extractor::{scope, AuthorizedUser, Json, Path, Query},
     model::{
         checkout::CheckoutsResponse,
+        list::{CursorError, CursorPageResponse, CursorQuery},
         user::{
-            CreateUserRequest, UpdateUserPasswordRequest, UpdateUserPasswordRequestWithUserId,
-            UpdateUserRoleRequest, UpdateUserRoleRequestWithUserId, UserModelError, UserResponse,
-            UsersResponse,
+            CreateUserRequest, UpdateUserNameRequest, UpdateUserNameRequestWithUserId,
+            UpdateUserPasswordRequest, UpdateUserPasswordRequestWithUserId, UpdateUserRoleRequest,
+            UpdateUserRoleRequestWithUserId, UserModelError, UserResponse, USER_CURSOR_SCOPE,
         },
+        violation::ToViolation,
     },
 };
 
 // 管理者がユーザーを登録する
 #[axum::debug_handler]
+#[utoipa::path(
+    post,
+    path = "/api/v1/users",
+    tag = "users",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn register_user(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -37,20 +54,56 @@ pub(crate) async fn register_user(
 
     req.validate()?;
 
-    let registered_user = registry.user_repository().create(req.try_into()?).await?;
+    let registered_user = registry
+        .user_repository()
+        .create(req.try_into()?)
+        .await
+        .map_err(weak_password("password"))?;
 
     Ok(Json(registered_user.into()))
 }
 
 // ユーザー一覧を取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/users",
+    tag = "users",
+    params(CursorQuery),
+    responses(
+        (status = 200, body = CursorPageResponse<UserResponse>),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn list_users(
-    _user: AuthorizedUser,
+    _user: AuthorizedUser<scope::UsersRead>,
     State(registry): State<AppRegistry>,
-) -> Result<Json<UsersResponse>, UserHandlerError> {
-    Ok(Json(registry.user_repository().find_all().await?.into()))
+    Query(req): Query<CursorQuery>,
+) -> Result<Json<CursorPageResponse<UserResponse>>, UserHandlerError> {
+    req.validate()?;
+
+    let page = registry
+        .user_repository()
+        .find_all(req.into_options(USER_CURSOR_SCOPE)?)
+        .await?;
+
+    Ok(Json(CursorPageResponse::from_page(page, USER_CURSOR_SCOPE)))
 }
 
 // 管理者がユーザーを削除する
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{user_id}",
+    tag = "users",
+    params(("user_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "ユーザーを削除した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn delete_user(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -63,13 +116,34 @@ pub(crate) async fn delete_user(
     let delete_user = DeleteUser {
         user_id: user_id.try_into()?,
     };
+    let user_id = delete_user.user_id.clone();
 
     registry.user_repository().delete(delete_user).await?;
 
+    // 削除したユーザーのトークンを使えないようにする
+    registry
+        .auth_repository()
+        .delete_all_sessions(&user_id)
+        .await?;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
 // 管理者がユーザーの権限を変更する
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/{user_id}/role",
+    tag = "users",
+    params(("user_id" = Uuid, Path)),
+    request_body = UpdateUserRoleRequest,
+    responses(
+        (status = 204, description = "ユーザーのロールを変更した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn change_user_role(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -82,24 +156,85 @@ pub(crate) async fn change_user_role(
 
     req.validate()?;
 
-    let update_user_role = UpdateUserRoleRequestWithUserId::new(user_id.try_into()?, req);
+    let user_id: UserId = user_id.try_into()?;
+    let update_user_role = UpdateUserRoleRequestWithUserId::new(user_id.clone(), req);
 
     registry
         .user_repository()
         .update_role(update_user_role.into())
         .await?;
 
+    // 変更前の権限で発行したトークンを使えないようにし、再ログインさせる
+    registry
+        .auth_repository()
+        .delete_all_sessions(&user_id)
+        .await?;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
 // ユーザーが自身の情報を取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me",
+    tag = "users",
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn get_current_user(
+    user: AuthorizedUser<scope::UsersRead>,
+    State(registry): State<AppRegistry>,
+) -> Result<Json<UserResponse>, UserHandlerError> {
+    let current_user = registry
+        .user_repository()
+        .find_current_user(user.user_id())
+        .await?
+        .ok_or_else(|| UserRepositoryError::NotFound(user.user_id().clone()))?;
+
+    Ok(Json(current_user.into()))
+}
+
+// ユーザーが自身の表示名を変更する
+#[utoipa::path(
+    patch,
+    path = "/api/v1/users/me",
+    tag = "users",
+    request_body = UpdateUserNameRequest,
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+    )
+)]
+pub(crate) async fn update_current_user(
     user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateUserNameRequest>,
 ) -> Result<Json<UserResponse>, UserHandlerError> {
-    Ok(Json(user.user.into()))
+    req.validate()?;
+
+    let event = UpdateUserNameRequestWithUserId::new(user.user_id().clone(), req).try_into()?;
+    let updated_user = registry.user_repository().update_name(event).await?;
+
+    Ok(Json(updated_user.into()))
 }
 
 // ユーザーが自身のパスワードを変更する
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/password",
+    tag = "users",
+    request_body = UpdateUserPasswordRequest,
+    responses(
+        (status = 204, description = "パスワードを変更した"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn change_password(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -113,13 +248,33 @@ pub(crate) async fn change_password(
     registry
         .user_repository()
         .update_password(update_user_password.try_into()?)
+        .await
+        .map_err(weak_password("newPassword"))?;
+
+    // パスワードを変更したセッションは残し、他の端末のセッションと API キーを無効にする
+    registry
+        .auth_repository()
+        .delete_other_sessions(user.user_id(), user.access_token())
+        .await?;
+    registry
+        .api_key_repository()
+        .delete_all(user.user_id())
         .await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/checkouts",
+    tag = "users",
+    responses(
+        (status = 200, body = CheckoutsResponse),
+        (status = 401, response = ProblemDetails),
+    )
+)]
 pub(crate) async fn get_checkouts(
-    user: AuthorizedUser,
+    user: AuthorizedUser<scope::CheckoutsRead>,
     State(registry): State<AppRegistry>,
 ) -> Result<Json<CheckoutsResponse>, UserHandlerError> {
     let checkouts = registry
@@ -143,38 +298,70 @@ pub enum UserHandlerError {
     #[error("model error: {0}")]
     ModelError(#[from] UserModelError),
 
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(#[from] CursorError),
+
     #[error("repository error: {0}")]
     UserRepositoryError(#[from] UserRepositoryError),
 
+    // パスワードポリシーに反する。どのフィールドの値かを添える
+    #[error("weak password: {1}")]
+    WeakPassword(&'static str, PasswordPolicyError),
+
     #[error("checkout repository error: {0}")]
     CheckoutRepositoryError(#[from] CheckoutRepositoryError),
+
+    #[error("auth repository error: {0}")]
+    AuthRepositoryError(#[from] AuthRepositoryError),
+
+    #[error("api key repository error: {0}")]
+    ApiKeyRepositoryError(#[from] ApiKeyRepositoryError),
+}
+
+// パスワードポリシーに反した場合に、その値を受け取ったフィールドを示すエラーにする
+fn weak_password(field: &'static str) -> impl FnOnce(UserRepositoryError) -> UserHandlerError {
+    move |e| match e {
+        UserRepositoryError::WeakPassword(e) => UserHandlerError::WeakPassword(field, e),
+        e => e.into(),
+    }
 }
 
 impl IntoResponse for UserHandlerError {
     fn into_response(self) -> axum::response::Response {
-        let status_code = match self {
-            UserHandlerError::Forbidden => StatusCode::FORBIDDEN,
-            UserHandlerError::InvalidUserId(_) => StatusCode::BAD_REQUEST,
-            UserHandlerError::ValidationError(_) => StatusCode::BAD_REQUEST,
-            UserHandlerError::ModelError(_) => StatusCode::BAD_REQUEST,
-            UserHandlerError::UserRepositoryError(e) => {
-                tracing::error!(
-                    error.cause_chain = ?e,
-                    error.message = %e,
-                    "unexpected error happened"
-                );
-                StatusCode::INTERNAL_SERVER_ERROR
+        let (status_code, code) = match &self {
+            UserHandlerError::ValidationError(report) => {
+                return ProblemDetails::from(report).into_response()
+            }
+            UserHandlerError::ModelError(e) => {
+                return ProblemDetails::from(e.field_error()).into_response()
+            }
+            UserHandlerError::Forbidden => (StatusCode::FORBIDDEN, "forbidden"),
+            UserHandlerError::InvalidUserId(_) => (StatusCode::BAD_REQUEST, "invalid_id"),
+            UserHandlerError::InvalidCursor(_) => (StatusCode::BAD_REQUEST, "invalid_cursor"),
+            UserHandlerError::UserRepositoryError(UserRepositoryError::NotFound(_)) => {
+                (StatusCode::NOT_FOUND, "user_not_found")
+            }
+            UserHandlerError::UserRepositoryError(UserRepositoryError::EmailAlreadyRegistered) => {
+                (StatusCode::CONFLICT, "email_already_registered")
+            }
+            UserHandlerError::UserRepositoryError(UserRepositoryError::InvalidPassword) => {
+                return ProblemDetails::from(FieldError::new(
+                    "currentPassword",
+                    Violation::IncorrectPassword,
+                ))
+                .into_response()
+            }
+            UserHandlerError::WeakPassword(field, e) => {
+                return ProblemDetails::from(FieldError::new(*field, e.violation())).into_response()
             }
-            UserHandlerError::CheckoutRepositoryError(e) => {
-                tracing::error!(
-                    error.cause_chain = ?e,
-                    error.message = %e,
-                    "unexpected error happened"
-                );
-                StatusCode::INTERNAL_SERVER_ERROR
+            UserHandlerError::UserRepositoryError(_)
+            | UserHandlerError::CheckoutRepositoryError(_)
+            | UserHandlerError::AuthRepositoryError(_)
+            | UserHandlerError::ApiKeyRepositoryError(_) => {
+                return ProblemDetails::internal(&self).into_response()
             }
         };
 
-        status_code.into_response()
+        ProblemDetails::new(status_code, code, &self).into_response()
     }
 }
@@ -0,0 +1,51 @@
+use axum::{
+    extract::Request,
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use shared::{
+    i18n::{self, Locale},
+    request_id,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+// 受け取る x-request-id の最大長。これを超える値は使わずに採番し直す
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// リクエスト ID を決めて処理中に参照できるようにし、レスポンスヘッダにも付与する
+// クライアントから x-request-id が送られてきた場合はそれを引き継ぐ
+pub async fn request_id(req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(request_id::HEADER_NAME)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= MAX_REQUEST_ID_LEN)
+        .map(ToString::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let span = tracing::info_span!("request", request_id = %id);
+    let mut res = request_id::scope(id.clone(), next.run(req))
+        .instrument(span)
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        res.headers_mut()
+            .insert(HeaderName::from_static(request_id::HEADER_NAME), value);
+    }
+    res
+}
+
+// Accept-Language からエラーメッセージの言語を決める
+// 対応している言語が指定されていなければ既定の言語とする
+pub async fn locale(req: Request, next: Next) -> Response {
+    let locale = req
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Locale::from_accept_language)
+        .unwrap_or_default();
+
+    i18n::scope(locale, next.run(req)).await
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Duration, Utc};
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    api_key::{
+        event::CreateApiKey, ApiKey, ApiKeyNameError, ApiKeyScope, CreatedApiKey,
+        API_KEY_MAX_LIFETIME_DAYS,
+    },
+    user::UserId,
+    value_object::ValueObject,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub enum ApiKeyScopeName {
+    #[serde(rename = "books:read")]
+    BooksRead,
+    #[serde(rename = "books:write")]
+    BooksWrite,
+    #[serde(rename = "checkouts:read")]
+    CheckoutsRead,
+    #[serde(rename = "checkouts:write")]
+    CheckoutsWrite,
+    #[serde(rename = "reservations:read")]
+    ReservationsRead,
+    #[serde(rename = "reservations:write")]
+    ReservationsWrite,
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "users:write")]
+    UsersWrite,
+}
+
+impl From<ApiKeyScopeName> for ApiKeyScope {
+    fn from(value: ApiKeyScopeName) -> Self {
+        match value {
+            ApiKeyScopeName::BooksRead => ApiKeyScope::BooksRead,
+            ApiKeyScopeName::BooksWrite => ApiKeyScope::BooksWrite,
+            ApiKeyScopeName::CheckoutsRead => ApiKeyScope::CheckoutsRead,
+            ApiKeyScopeName::CheckoutsWrite => ApiKeyScope::CheckoutsWrite,
+            ApiKeyScopeName::ReservationsRead => ApiKeyScope::ReservationsRead,
+            ApiKeyScopeName::ReservationsWrite => ApiKeyScope::ReservationsWrite,
+            ApiKeyScopeName::UsersRead => ApiKeyScope::UsersRead,
+            ApiKeyScopeName::UsersWrite => ApiKeyScope::UsersWrite,
+        }
+    }
+}
+
+impl From<ApiKeyScope> for ApiKeyScopeName {
+    fn from(value: ApiKeyScope) -> Self {
+        match value {
+            ApiKeyScope::BooksRead => ApiKeyScopeName::BooksRead,
+            ApiKeyScope::BooksWrite => ApiKeyScopeName::BooksWrite,
+            ApiKeyScope::CheckoutsRead => ApiKeyScopeName::CheckoutsRead,
+            ApiKeyScope::CheckoutsWrite => ApiKeyScopeName::CheckoutsWrite,
+            ApiKeyScope::ReservationsRead => ApiKeyScopeName::ReservationsRead,
+            ApiKeyScope::ReservationsWrite => ApiKeyScopeName::ReservationsWrite,
+            ApiKeyScope::UsersRead => ApiKeyScopeName::UsersRead,
+            ApiKeyScope::UsersWrite => ApiKeyScopeName::UsersWrite,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    #[garde(skip)]
+    #[schema(min_length = 1, max_length = 100)]
+    pub name: String,
+    #[garde(length(min = 1))]
+    #[schema(min_items = 1)]
+    pub scopes: Vec<ApiKeyScopeName>,
+    // 発行から 365 日後までの日時を指定する
+    #[garde(custom(within_max_lifetime))]
+    pub expires_at: DateTime<Utc>,
+}
+
+fn within_max_lifetime(expires_at: &DateTime<Utc>, _: &()) -> garde::Result {
+    let now = Utc::now();
+    if *expires_at <= now {
+        return Err(garde::Error::new("expires_at_in_past"));
+    }
+    if *expires_at > now + Duration::days(API_KEY_MAX_LIFETIME_DAYS) {
+        return Err(garde::Error::new("expires_at_too_far"));
+    }
+    Ok(())
+}
+
+#[derive(new)]
+pub struct CreateApiKeyRequestWithUserId(UserId, CreateApiKeyRequest);
+
+impl TryFrom<CreateApiKeyRequestWithUserId> for CreateApiKey {
+    type Error = ApiKeyNameError;
+
+    fn try_from(value: CreateApiKeyRequestWithUserId) -> Result<Self, Self::Error> {
+        let CreateApiKeyRequestWithUserId(user_id, request) = value;
+        let CreateApiKeyRequest {
+            name,
+            scopes,
+            expires_at,
+        } = request;
+
+        // 同じスコープが重複して指定されても 1 つにまとめる
+        let mut deduped = Vec::with_capacity(scopes.len());
+        for scope in scopes.into_iter().map(ApiKeyScope::from) {
+            if !deduped.contains(&scope) {
+                deduped.push(scope);
+            }
+        }
+
+        Ok(CreateApiKey {
+            user_id,
+            name: name.try_into()?,
+            scopes: deduped,
+            expires_at,
+        })
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeysResponse {
+    pub items: Vec<ApiKeyResponse>,
+}
+
+impl From<Vec<ApiKey>> for ApiKeysResponse {
+    fn from(value: Vec<ApiKey>) -> Self {
+        Self {
+            items: value.into_iter().map(ApiKeyResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    // キーを見分けるための先頭の数文字
+    pub prefix: String,
+    pub scopes: Vec<ApiKeyScopeName>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(value: ApiKey) -> Self {
+        let (api_key_id, name, key_prefix, scopes, expires_at, last_used_at, created_at) =
+            value.dissolve();
+
+        Self {
+            id: api_key_id.into_inner(),
+            name: name.into_inner(),
+            prefix: key_prefix,
+            scopes: scopes.into_iter().map(ApiKeyScopeName::from).collect(),
+            expires_at,
+            last_used_at,
+            created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    // 発行したキー。この応答でしか受け取れない
+    pub key: String,
+}
+
+impl From<CreatedApiKey> for CreatedApiKeyResponse {
+    fn from(value: CreatedApiKey) -> Self {
+        let CreatedApiKey { api_key, key } = value;
+        Self {
+            api_key: api_key.into(),
+            key,
+        }
+    }
+}
@@ -1,16 +1,140 @@
+use kernel::model::{
+    auth::{
+        jwk::{PublicJwk, PublicKey},
+        AuthTokens,
+    },
+    value_object::ValueObject,
+};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Deserialize)]
+use crate::model::totp::{SecondFactorRequest, TotpSetupResponse};
+
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LoginRequest {
+    #[schema(format = Email)]
     pub email: String,
     pub password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AccessTokenResponse {
     pub user_id: Uuid,
     pub access_token: String,
+    // アクセストークンの有効期間（秒）
+    pub expires_in: u64,
+    pub refresh_token: String,
+}
+
+impl From<AuthTokens> for AccessTokenResponse {
+    fn from(value: AuthTokens) -> Self {
+        let AuthTokens {
+            user_id,
+            access_token,
+            refresh_token,
+            expires_in,
+        } = value;
+
+        Self {
+            user_id: user_id.into_inner(),
+            access_token: access_token.into_inner(),
+            expires_in,
+            refresh_token: refresh_token.into_inner(),
+        }
+    }
+}
+
+// 二要素認証を有効にしているユーザーには、トークンの代わりに二要素目の入力を求める
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Tokens(AccessTokenResponse),
+    TotpRequired(TotpChallengeResponse),
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpChallengeResponse {
+    // POST /auth/login/totp に二要素目と一緒に送る
+    pub mfa_token: String,
+    // mfaToken の有効期間（秒）
+    pub expires_in: u64,
+    // 二要素認証が必須で未登録の場合に、認証アプリに登録するシークレット
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub setup: Option<TotpSetupResponse>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpLoginRequest {
+    pub mfa_token: String,
+    #[serde(flatten)]
+    pub second_factor: SecondFactorRequest,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpLoginResponse {
+    #[serde(flatten)]
+    pub tokens: AccessTokenResponse,
+    // ログイン時に二要素認証の登録を完了した場合のみ返す
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+// アクセストークンの署名を検証するための公開鍵の一覧（RFC 7517 の JWK Set）
+#[derive(Serialize, ToSchema)]
+pub struct JwksResponse {
+    pub keys: Vec<JwkResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JwkResponse {
+    // Ed25519 は "OKP"、P-256 は "EC"
+    pub kty: &'static str,
+    pub crv: &'static str,
+    pub x: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+    pub kid: String,
+    pub alg: &'static str,
+    #[serde(rename = "use")]
+    pub use_: &'static str,
+}
+
+impl From<Vec<PublicJwk>> for JwksResponse {
+    fn from(value: Vec<PublicJwk>) -> Self {
+        Self {
+            keys: value.into_iter().map(JwkResponse::from).collect(),
+        }
+    }
+}
+
+impl From<PublicJwk> for JwkResponse {
+    fn from(value: PublicJwk) -> Self {
+        let PublicJwk { kid, key } = value;
+        let (kty, crv, alg, x, y) = match key {
+            PublicKey::Ed25519 { x } => ("OKP", "Ed25519", "EdDSA", x, None),
+            PublicKey::P256 { x, y } => ("EC", "P-256", "ES256", x, Some(y)),
+        };
+
+        Self {
+            kty,
+            crv,
+            x,
+            y,
+            kid,
+            alg,
+            use_: "sig",
+        }
+    }
 }
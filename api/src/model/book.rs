@@ -1,32 +1,47 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::{DateTime, Utc};
 use derive_new::new;
 use garde::Validate;
 use kernel::model::{
     book::{
         event::{CreateBook, UpdateBook},
-        Author, AuthorError, Book, BookId, BookListOptions, Checkout, Description,
-        DescriptionError, Isbn, IsbnError, Title, TitleError,
+        Author, AuthorError, Book, BookAvailability, BookId, BookListCursorOptions, BookListFilter,
+        BookListOptions, BookSort, BookSortKey, Checkout, Description, DescriptionError, Isbn,
+        IsbnError, Title, TitleError,
     },
-    list::PaginatedList,
+    book_copy::BookCopyCount,
+    list::{PaginatedList, SortOrder},
     user::{CheckoutUser, UserId},
     value_object::ValueObject,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared::problem::FieldError;
 use thiserror::Error;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-use super::user::BookOwner;
+use super::{
+    list::{decode_cursor, default_limit, CursorError, CursorPageResponse},
+    user::BookOwner,
+    violation::ToViolation,
+};
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateBookRequest {
     #[garde(length(min = 1))]
+    #[schema(min_length = 1, max_length = 255)]
     pub title: String,
     #[garde(length(min = 1))]
+    #[schema(min_length = 1, max_length = 255)]
     pub author: String,
+    // ISBN-10 / ISBN-13 のいずれか。ハイフンや空白で区切られていてもよい
     #[garde(length(min = 1))]
+    #[schema(min_length = 1, example = "978-4-06-536957-9")]
     pub isbn: String,
     #[garde(skip)]
+    #[schema(max_length = 1024)]
     pub description: String,
 }
 
@@ -58,16 +73,35 @@ pub enum CreateBookRequestError {
     InvalidDescription(#[from] DescriptionError),
 }
 
-#[derive(Debug, Deserialize, Validate)]
+impl CreateBookRequestError {
+    // どのフィールドの値が不正かを返す
+    pub fn field_error(&self) -> FieldError {
+        match self {
+            CreateBookRequestError::InvalidTitle(e) => FieldError::new("title", e.violation()),
+            CreateBookRequestError::InvalidAuthor(e) => FieldError::new("author", e.violation()),
+            CreateBookRequestError::InvalidIsbn(e) => FieldError::new("isbn", e.violation()),
+            CreateBookRequestError::InvalidDescription(e) => {
+                FieldError::new("description", e.violation())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateBookRequest {
     #[garde(length(min = 1))]
+    #[schema(min_length = 1, max_length = 255)]
     pub title: String,
     #[garde(length(min = 1))]
+    #[schema(min_length = 1, max_length = 255)]
     pub author: String,
+    // ISBN-10 / ISBN-13 のいずれか。ハイフンや空白で区切られていてもよい
     #[garde(length(min = 1))]
+    #[schema(min_length = 1, example = "978-4-06-536957-9")]
     pub isbn: String,
     #[garde(skip)]
+    #[schema(max_length = 1024)]
     pub description: String,
 }
 
@@ -114,57 +148,294 @@ pub enum UpdateBookRequestError {
     InvalidDescription(#[from] DescriptionError),
 }
 
-#[derive(Deserialize, Validate)]
+impl UpdateBookRequestError {
+    // どのフィールドの値が不正かを返す
+    pub fn field_error(&self) -> FieldError {
+        match self {
+            UpdateBookRequestError::InvalidTitle(e) => FieldError::new("title", e.violation()),
+            UpdateBookRequestError::InvalidAuthor(e) => FieldError::new("author", e.violation()),
+            UpdateBookRequestError::InvalidIsbn(e) => FieldError::new("isbn", e.violation()),
+            UpdateBookRequestError::InvalidDescription(e) => {
+                FieldError::new("description", e.violation())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, IntoParams)]
 #[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 pub struct BookListQuery {
-    #[garde(range(min = 1))]
+    #[garde(range(min = 1, max = 100))]
     #[serde(default = "default_limit")]
+    #[param(minimum = 1, maximum = 100, default = 20)]
     pub limit: i64,
-    #[garde(range(min = 0))]
+    // after と同時には指定できない
+    #[garde(range(min = 0), custom(not_combined_with_cursor(&self.after)))]
     #[serde(default)]
+    #[param(minimum = 0, default = 0)]
     pub offset: i64,
+    // 指定された場合はカーソル方式でページングする（空文字列なら先頭から）
+    #[garde(skip)]
+    pub after: Option<String>,
+    // タイトル・著者・ISBN・説明のいずれかに対する部分一致検索
+    #[garde(length(chars, min = 1, max = 255))]
+    #[param(min_length = 1, max_length = 255)]
+    pub q: Option<String>,
+    #[garde(length(chars, min = 1, max = 255))]
+    #[param(min_length = 1, max_length = 255)]
+    pub title: Option<String>,
+    #[garde(length(chars, min = 1, max = 255))]
+    #[param(min_length = 1, max_length = 255)]
+    pub author: Option<String>,
+    #[garde(length(chars, min = 1, max = 255))]
+    #[param(min_length = 1, max_length = 255)]
+    pub isbn: Option<String>,
+    #[garde(length(chars, min = 1, max = 1024))]
+    #[param(min_length = 1, max_length = 1024)]
+    pub description: Option<String>,
+    #[garde(skip)]
+    pub owner_id: Option<Uuid>,
+    // true なら貸出可能な蔵書のみ、false なら貸出中の蔵書のみを返す
+    #[garde(skip)]
+    pub available: Option<bool>,
+    #[garde(skip)]
+    #[serde(default)]
+    pub checked_out_by_me: bool,
+    // relevance は q を指定したときのみ使える
+    #[garde(custom(requires_keyword_for_relevance(&self.q)))]
+    #[serde(default)]
+    #[param(inline)]
+    pub sort: BookSortKeyName,
+    #[garde(skip)]
+    #[param(inline)]
+    pub order: Option<SortOrderName>,
 }
 
-const DEFAULT_LIMIT: i64 = 20;
-const fn default_limit() -> i64 {
-    DEFAULT_LIMIT
+// オフセット方式とカーソル方式は同時に使えない
+fn not_combined_with_cursor(
+    after: &Option<String>,
+) -> impl FnOnce(&i64, &()) -> garde::Result + '_ {
+    move |offset, _| match (offset, after) {
+        (1.., Some(_)) => Err(garde::Error::new("offset_with_after")),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum BookSortKeyName {
+    Title,
+    Author,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    LastCheckedOutAt,
+    CheckoutCount,
+    Relevance,
 }
 
-impl From<BookListQuery> for BookListOptions {
-    fn from(BookListQuery { limit, offset }: BookListQuery) -> Self {
-        BookListOptions { limit, offset }
+impl From<BookSortKeyName> for BookSortKey {
+    fn from(value: BookSortKeyName) -> Self {
+        match value {
+            BookSortKeyName::Title => BookSortKey::Title,
+            BookSortKeyName::Author => BookSortKey::Author,
+            BookSortKeyName::CreatedAt => BookSortKey::CreatedAt,
+            BookSortKeyName::UpdatedAt => BookSortKey::UpdatedAt,
+            BookSortKeyName::LastCheckedOutAt => BookSortKey::LastCheckedOutAt,
+            BookSortKeyName::CheckoutCount => BookSortKey::CheckoutCount,
+            BookSortKeyName::Relevance => BookSortKey::Relevance,
+        }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrderName {
+    Asc,
+    Desc,
+}
+
+impl From<SortOrderName> for SortOrder {
+    fn from(value: SortOrderName) -> Self {
+        match value {
+            SortOrderName::Asc => SortOrder::Asc,
+            SortOrderName::Desc => SortOrder::Desc,
+        }
+    }
+}
+
+// 関連度順の並び替えはキーワード検索と組み合わせたときのみ受け付ける
+fn requires_keyword_for_relevance(
+    q: &Option<String>,
+) -> impl FnOnce(&BookSortKeyName, &()) -> garde::Result + '_ {
+    move |sort, _| match (sort, q) {
+        (BookSortKeyName::Relevance, None) => Err(garde::Error::new("relevance_without_keyword")),
+        _ => Ok(()),
+    }
+}
+
+#[derive(new)]
+pub struct BookListQueryWithUserId(UserId, BookListQuery);
+
+// オフセット方式・カーソル方式のどちらで一覧を取得するか
+pub enum BookListParams {
+    Offset(BookListOptions),
+    Cursor(BookListCursorOptions),
+}
+
+impl TryFrom<BookListQueryWithUserId> for BookListParams {
+    type Error = CursorError;
+
+    fn try_from(value: BookListQueryWithUserId) -> Result<Self, Self::Error> {
+        let BookListQueryWithUserId(
+            user_id,
+            BookListQuery {
+                limit,
+                offset,
+                after,
+                q,
+                title,
+                author,
+                isbn,
+                description,
+                owner_id,
+                available,
+                checked_out_by_me,
+                sort,
+                order,
+            },
+        ) = value;
+
+        let filter = BookListFilter {
+            keyword: q,
+            title,
+            author,
+            // 保存値は区切りのない ISBN-13 なので、区切り文字を除いて比較する
+            isbn: isbn.map(|isbn| isbn.replace(['-', ' '], "")),
+            description,
+            owner_id: owner_id.map(UserId::new),
+            availability: available.map(|available| {
+                if available {
+                    BookAvailability::Available
+                } else {
+                    BookAvailability::CheckedOut
+                }
+            }),
+            checked_out_by: checked_out_by_me.then_some(user_id),
+        };
+        let sort = BookSort::new(sort.into(), order.map(SortOrder::from));
+
+        match after {
+            None => Ok(BookListParams::Offset(BookListOptions {
+                limit,
+                offset,
+                filter,
+                sort,
+            })),
+            Some(after) => Ok(BookListParams::Cursor(BookListCursorOptions {
+                limit,
+                after: decode_cursor(&book_cursor_scope(&sort, &filter), Some(&after))?,
+                filter,
+                sort,
+            })),
+        }
+    }
+}
+
+// 並び順と絞り込み条件ごとにカーソルを区別する。
+// 条件は長くなりうるため、カーソルにはハッシュ値だけを埋め込む
+pub fn book_cursor_scope(sort: &BookSort, filter: &BookListFilter) -> String {
+    format!(
+        "books:{:?}:{:?}:{}",
+        sort.key,
+        sort.order,
+        book_filter_digest(filter)
+    )
+}
+
+// 正規化した後の（リポジトリに渡す）絞り込み条件から求める
+fn book_filter_digest(filter: &BookListFilter) -> String {
+    let BookListFilter {
+        keyword,
+        title,
+        author,
+        isbn,
+        description,
+        owner_id,
+        availability,
+        checked_out_by,
+    } = filter;
+    let normalized = format!(
+        "{:?}",
+        (
+            keyword,
+            title,
+            author,
+            isbn,
+            description,
+            owner_id.as_ref().map(UserId::inner_ref),
+            availability,
+            checked_out_by.as_ref().map(UserId::inner_ref),
+        )
+    );
+    URL_SAFE_NO_PAD.encode(&Sha256::digest(normalized)[..12])
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookResponse {
     pub id: Uuid,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    // 旧データで正しい ISBN に直せなかった蔵書では null になる
+    pub isbn: Option<String>,
+    pub isbn13: Option<String>,
+    pub isbn10: Option<String>,
     pub description: String,
     pub owner: BookOwner,
-    pub checkout: Option<BookCheckoutResponse>,
+    pub copies: BookCopyCountResponse,
+    pub checkouts: Vec<BookCheckoutResponse>,
 }
 
 impl From<Book> for BookResponse {
     fn from(book: Book) -> Self {
-        let (book_id, title, author, isbn, description, owner, checkout) = book.dissolve();
+        let (book_id, title, author, isbn, description, owner, copy_count, checkouts) =
+            book.dissolve();
 
         BookResponse {
             id: book_id.into_inner(),
             title: title.into_inner(),
             author: author.into_inner(),
-            isbn: isbn.into_inner(),
+            isbn13: isbn.as_ref().map(|isbn| isbn.isbn13().to_string()),
+            isbn10: isbn.as_ref().and_then(Isbn::isbn10),
+            isbn: isbn.map(Isbn::into_inner),
             description: description.into_inner(),
             owner: owner.into(),
-            checkout: checkout.map(BookCheckoutResponse::from),
+            copies: copy_count.into(),
+            checkouts: checkouts
+                .into_iter()
+                .map(BookCheckoutResponse::from)
+                .collect(),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// 所蔵数と貸出可能数
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookCopyCountResponse {
+    pub total: i64,
+    pub available: i64,
+}
+
+impl From<BookCopyCount> for BookCopyCountResponse {
+    fn from(BookCopyCount { total, available }: BookCopyCount) -> Self {
+        Self { total, available }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PaginatedBookResponse {
     pub total: i64,
@@ -173,6 +444,14 @@ pub struct PaginatedBookResponse {
     pub items: Vec<BookResponse>,
 }
 
+// offset 方式ならページ番号付き、after 方式ならカーソル付きの一覧を返す
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum BookListResponse {
+    Paginated(PaginatedBookResponse),
+    Cursor(CursorPageResponse<BookResponse>),
+}
+
 impl From<PaginatedList<Book>> for PaginatedBookResponse {
     fn from(paginated_book: PaginatedList<Book>) -> Self {
         let PaginatedList {
@@ -191,27 +470,32 @@ impl From<PaginatedList<Book>> for PaginatedBookResponse {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookCheckoutResponse {
     pub id: Uuid,
+    pub copy_id: Uuid,
     pub checked_out_by: CheckoutUserResponse,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
 }
 
 impl From<Checkout> for BookCheckoutResponse {
     fn from(checkout: Checkout) -> Self {
-        let (checkout_id, checkout_user, checked_out_at) = checkout.dissolve();
+        let (checkout_id, book_copy_id, checkout_user, checked_out_at, due_at) =
+            checkout.dissolve();
 
         BookCheckoutResponse {
             id: checkout_id.into_inner(),
+            copy_id: book_copy_id.into_inner(),
             checked_out_by: checkout_user.into(),
             checked_out_at,
+            due_at,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutUserResponse {
     pub id: Uuid,
@@ -0,0 +1,188 @@
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    book::BookId,
+    book_copy::{
+        event::{CreateBookCopy, UpdateBookCopy},
+        BarcodeError, BookCopy, BookCopyId, CopyCondition, CopyLocationError,
+    },
+    user::UserId,
+    value_object::ValueObject,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use shared::problem::FieldError;
+
+use super::{book::BookCheckoutResponse, violation::ToViolation};
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CopyConditionName {
+    New,
+    #[default]
+    Good,
+    Worn,
+    Damaged,
+}
+
+impl From<CopyCondition> for CopyConditionName {
+    fn from(value: CopyCondition) -> Self {
+        match value {
+            CopyCondition::New => CopyConditionName::New,
+            CopyCondition::Good => CopyConditionName::Good,
+            CopyCondition::Worn => CopyConditionName::Worn,
+            CopyCondition::Damaged => CopyConditionName::Damaged,
+        }
+    }
+}
+
+impl From<CopyConditionName> for CopyCondition {
+    fn from(value: CopyConditionName) -> Self {
+        match value {
+            CopyConditionName::New => CopyCondition::New,
+            CopyConditionName::Good => CopyCondition::Good,
+            CopyConditionName::Worn => CopyCondition::Worn,
+            CopyConditionName::Damaged => CopyCondition::Damaged,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBookCopyRequest {
+    // 省略した場合は採番する
+    #[garde(length(chars, min = 1))]
+    #[schema(min_length = 1, max_length = 64)]
+    pub barcode: Option<String>,
+    #[garde(skip)]
+    #[serde(default)]
+    pub condition: CopyConditionName,
+    #[garde(skip)]
+    #[serde(default)]
+    #[schema(max_length = 255)]
+    pub location: String,
+}
+
+#[derive(new)]
+pub struct CreateBookCopyRequestWithIds(BookId, UserId, CreateBookCopyRequest);
+
+impl TryFrom<CreateBookCopyRequestWithIds> for CreateBookCopy {
+    type Error = BookCopyRequestError;
+
+    fn try_from(value: CreateBookCopyRequestWithIds) -> Result<Self, Self::Error> {
+        let CreateBookCopyRequestWithIds(
+            book_id,
+            user_id,
+            CreateBookCopyRequest {
+                barcode,
+                condition,
+                location,
+            },
+        ) = value;
+
+        Ok(CreateBookCopy {
+            book_id,
+            barcode: barcode.map(TryInto::try_into).transpose()?,
+            condition: condition.into(),
+            location: location.try_into()?,
+            requested_by: user_id,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateBookCopyRequest {
+    #[garde(skip)]
+    pub condition: CopyConditionName,
+    #[garde(skip)]
+    #[schema(max_length = 255)]
+    pub location: String,
+}
+
+#[derive(new)]
+pub struct UpdateBookCopyRequestWithIds(BookId, BookCopyId, UserId, UpdateBookCopyRequest);
+
+impl TryFrom<UpdateBookCopyRequestWithIds> for UpdateBookCopy {
+    type Error = BookCopyRequestError;
+
+    fn try_from(value: UpdateBookCopyRequestWithIds) -> Result<Self, Self::Error> {
+        let UpdateBookCopyRequestWithIds(
+            book_id,
+            book_copy_id,
+            user_id,
+            UpdateBookCopyRequest {
+                condition,
+                location,
+            },
+        ) = value;
+
+        Ok(UpdateBookCopy {
+            book_copy_id,
+            book_id,
+            condition: condition.into(),
+            location: location.try_into()?,
+            requested_by: user_id,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum BookCopyRequestError {
+    #[error("invalid barcode: {0}")]
+    InvalidBarcode(#[from] BarcodeError),
+
+    #[error("invalid location: {0}")]
+    InvalidLocation(#[from] CopyLocationError),
+}
+
+impl BookCopyRequestError {
+    // どのフィールドの値が不正かを返す
+    pub fn field_error(&self) -> FieldError {
+        match self {
+            BookCopyRequestError::InvalidBarcode(e) => FieldError::new("barcode", e.violation()),
+            BookCopyRequestError::InvalidLocation(e) => FieldError::new("location", e.violation()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookCopiesResponse {
+    pub items: Vec<BookCopyResponse>,
+}
+
+impl From<Vec<BookCopy>> for BookCopiesResponse {
+    fn from(copies: Vec<BookCopy>) -> Self {
+        Self {
+            items: copies.into_iter().map(BookCopyResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookCopyResponse {
+    pub id: Uuid,
+    pub barcode: String,
+    pub condition: CopyConditionName,
+    pub location: String,
+    pub checkout: Option<BookCheckoutResponse>,
+}
+
+impl From<BookCopy> for BookCopyResponse {
+    fn from(copy: BookCopy) -> Self {
+        let (book_copy_id, _book_id, barcode, condition, location, checkout) = copy.dissolve();
+
+        Self {
+            id: book_copy_id.into_inner(),
+            barcode: barcode.into_inner(),
+            condition: condition.into(),
+            location: location.into_inner(),
+            checkout: checkout.map(BookCheckoutResponse::from),
+        }
+    }
+}
@@ -1,12 +1,51 @@
 use chrono::{DateTime, Utc};
+use garde::Validate;
 use kernel::model::{
-    checkout::{Checkout, CheckoutBook},
+    book::Isbn,
+    checkout::{Checkout, CheckoutBook, CheckoutListFilter, CheckoutListOptions},
     value_object::ValueObject,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Serialize)]
+use super::list::{decode_cursor, default_limit, CursorError};
+
+// 貸出一覧のカーソルを他の一覧のカーソルと区別するための値
+pub const CHECKOUT_CURSOR_SCOPE: &str = "checkouts";
+
+// 貸出中の一覧のクエリパラメータ
+#[derive(Deserialize, Validate, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct CheckoutListQuery {
+    #[garde(range(min = 1, max = 100))]
+    #[serde(default = "default_limit")]
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    pub limit: i64,
+    #[garde(skip)]
+    pub after: Option<String>,
+    // true なら返却期限を過ぎたもののみ、false なら過ぎていないもののみ
+    #[garde(skip)]
+    pub overdue: Option<bool>,
+}
+
+impl CheckoutListQuery {
+    pub fn into_options(self) -> Result<CheckoutListOptions, CursorError> {
+        let CheckoutListQuery {
+            limit,
+            after,
+            overdue,
+        } = self;
+        Ok(CheckoutListOptions {
+            limit,
+            after: decode_cursor(CHECKOUT_CURSOR_SCOPE, after.as_deref())?,
+            filter: CheckoutListFilter { overdue },
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutsResponse {
     pub items: Vec<CheckoutResponse>,
@@ -20,46 +59,55 @@ impl From<Vec<Checkout>> for CheckoutsResponse {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutResponse {
     pub id: Uuid,
     pub checked_out_by: Uuid,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
     pub returned_at: Option<DateTime<Utc>>,
     pub book: CheckoutBookResponse,
 }
 
 impl From<Checkout> for CheckoutResponse {
     fn from(checkout: Checkout) -> Self {
-        let (checkout_id, checked_out_by, checked_out_at, returned_at, book) = checkout.dissolve();
+        let (checkout_id, checked_out_by, checked_out_at, due_at, renewal_count, returned_at, book) =
+            checkout.dissolve();
         CheckoutResponse {
             id: checkout_id.into_inner(),
             checked_out_by: checked_out_by.into_inner(),
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at,
             book: book.into(),
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutBookResponse {
     pub id: Uuid,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Option<String>,
+    pub copy_id: Uuid,
+    pub barcode: String,
 }
 
 impl From<CheckoutBook> for CheckoutBookResponse {
     fn from(book: CheckoutBook) -> Self {
-        let (book_id, title, author, isbn) = book.dissolve();
+        let (book_id, title, author, isbn, book_copy_id, barcode) = book.dissolve();
         CheckoutBookResponse {
             id: book_id.into_inner(),
             title: title.into_inner(),
             author: author.into_inner(),
-            isbn: isbn.into_inner(),
+            isbn: isbn.map(Isbn::into_inner),
+            copy_id: book_copy_id.into_inner(),
+            barcode: barcode.into_inner(),
         }
     }
 }
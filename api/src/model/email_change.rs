@@ -0,0 +1,17 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailChangeRequest {
+    // 変更後のメールアドレス。確認用のリンクをこのアドレスに送る
+    #[schema(format = Email)]
+    pub email: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmEmailChangeRequest {
+    // 新しいアドレスで受け取ったリンクに含まれるトークン
+    pub token: String,
+}
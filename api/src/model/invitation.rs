@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    invitation::{
+        event::{AcceptInvitation, CreateInvitation},
+        Invitation, InvitationToken,
+    },
+    user::UserId,
+    value_object::ValueObject,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::user::{UserModelError, UserRoleName};
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateInvitationRequest {
+    #[garde(email)]
+    #[schema(format = Email)]
+    pub email: String,
+    // 招待を受け入れたときに作成するユーザーのロール
+    #[garde(skip)]
+    pub role: UserRoleName,
+}
+
+#[derive(new)]
+pub struct CreateInvitationRequestWithUserId(UserId, CreateInvitationRequest);
+
+impl TryFrom<CreateInvitationRequestWithUserId> for CreateInvitation {
+    type Error = UserModelError;
+
+    fn try_from(value: CreateInvitationRequestWithUserId) -> Result<Self, Self::Error> {
+        let CreateInvitationRequestWithUserId(invited_by, CreateInvitationRequest { email, role }) =
+            value;
+
+        Ok(CreateInvitation::new(
+            email.parse()?,
+            role.into(),
+            invited_by,
+        ))
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationResponse {
+    pub invitation_id: Uuid,
+    pub email: String,
+    pub role: UserRoleName,
+    // 招待した管理者。削除されている場合は含めない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Invitation> for InvitationResponse {
+    fn from(value: Invitation) -> Self {
+        let (invitation_id, email, role, invited_by, expires_at, created_at) = value.dissolve();
+        Self {
+            invitation_id: invitation_id.into_inner(),
+            email: email.to_string(),
+            role: role.into(),
+            invited_by: invited_by.map(UserId::into_inner),
+            expires_at,
+            created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationsResponse {
+    pub items: Vec<InvitationResponse>,
+}
+
+impl From<Vec<Invitation>> for InvitationsResponse {
+    fn from(value: Vec<Invitation>) -> Self {
+        Self {
+            items: value.into_iter().map(InvitationResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptInvitationRequest {
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1, max_length = 255)]
+    pub name: String,
+    // 文字数などの条件はサーバーのパスワードポリシーで確認する
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1)]
+    pub password: String,
+}
+
+#[derive(new)]
+pub struct AcceptInvitationRequestWithToken(String, AcceptInvitationRequest);
+
+impl TryFrom<AcceptInvitationRequestWithToken> for AcceptInvitation {
+    type Error = UserModelError;
+
+    fn try_from(value: AcceptInvitationRequestWithToken) -> Result<Self, Self::Error> {
+        let AcceptInvitationRequestWithToken(token, AcceptInvitationRequest { name, password }) =
+            value;
+
+        Ok(AcceptInvitation {
+            token: InvitationToken::new(token),
+            name: name.try_into()?,
+            password: password.try_into()?,
+        })
+    }
+}
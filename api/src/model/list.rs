@@ -0,0 +1,115 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use garde::Validate;
+use kernel::model::list::{Cursor, CursorOptions, CursorPage};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+pub(crate) const DEFAULT_LIMIT: i64 = 20;
+pub(crate) const fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+// カーソル方式でページングする一覧のクエリパラメータ
+#[derive(Deserialize, Validate, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct CursorQuery {
+    #[garde(range(min = 1, max = 100))]
+    #[serde(default = "default_limit")]
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    pub limit: i64,
+    #[garde(skip)]
+    pub after: Option<String>,
+}
+
+impl CursorQuery {
+    pub fn into_options(self, scope: &str) -> Result<CursorOptions, CursorError> {
+        let CursorQuery { limit, after } = self;
+        Ok(CursorOptions {
+            limit,
+            after: decode_cursor(scope, after.as_deref())?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPageResponse<T> {
+    pub limit: i64,
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> CursorPageResponse<T> {
+    pub fn from_page<U>(page: CursorPage<U>, scope: &str) -> Self
+    where
+        T: From<U>,
+    {
+        let CursorPage {
+            limit,
+            items,
+            next_cursor,
+        } = page;
+
+        Self {
+            limit,
+            items: items.into_iter().map(T::from).collect(),
+            next_cursor: next_cursor.map(|cursor| encode_cursor(scope, &cursor)),
+        }
+    }
+}
+
+// カーソルはクライアントからは中身を解釈できない文字列として扱う。
+// 別の一覧や別の並び順で発行されたカーソルを取り違えないよう、
+// 発行元を表す scope を埋め込んでおき、復元時に照合する。
+pub fn encode_cursor(scope: &str, cursor: &Cursor) -> String {
+    let sort_key = match &cursor.sort_key {
+        Some(sort_key) => format!("+{sort_key}"),
+        None => "-".to_string(),
+    };
+    URL_SAFE_NO_PAD.encode(format!("{scope}\n{}\n{sort_key}", cursor.id))
+}
+
+// 空文字列は「先頭から」を表す
+pub fn decode_cursor(scope: &str, token: Option<&str>) -> Result<Option<Cursor>, CursorError> {
+    let token = match token {
+        None | Some("") => return Ok(None),
+        Some(token) => token,
+    };
+
+    let decoded = URL_SAFE_NO_PAD
+        .decode(token)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or(CursorError::Malformed)?;
+
+    let mut parts = decoded.splitn(3, '\n');
+    let (Some(cursor_scope), Some(id), Some(sort_key)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(CursorError::Malformed);
+    };
+
+    if cursor_scope != scope {
+        return Err(CursorError::ScopeMismatch);
+    }
+
+    let id = id.parse::<Uuid>().map_err(|_| CursorError::Malformed)?;
+    let sort_key = match (sort_key.strip_prefix('+'), sort_key) {
+        (Some(sort_key), _) => Some(sort_key.to_string()),
+        (None, "-") => None,
+        _ => return Err(CursorError::Malformed),
+    };
+
+    Ok(Some(Cursor { sort_key, id }))
+}
+
+#[derive(Debug, Error)]
+pub enum CursorError {
+    #[error("malformed cursor")]
+    Malformed,
+
+    #[error("cursor was issued for a different list or sort order")]
+    ScopeMismatch,
+}
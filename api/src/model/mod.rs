@@ -1,4 +1,15 @@
+pub mod api_key;
 pub mod auth;
 pub mod book;
+pub mod book_copy;
 pub mod checkout;
+pub mod email_change;
+pub mod invitation;
+pub mod list;
+pub mod oidc;
+pub mod password_reset;
+pub mod reservation;
+pub mod session;
+pub mod totp;
 pub mod user;
+pub mod violation;
@@ -0,0 +1,13 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+// IdP が認証を終えた利用者をリダイレクトするときのクエリパラメータ
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    // 利用者が同意しなかった場合などは、code の代わりに error を受け取る
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
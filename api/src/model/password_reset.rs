@@ -0,0 +1,17 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordResetRequest {
+    #[schema(format = Email)]
+    pub email: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmPasswordResetRequest {
+    // メールで受け取ったリンクに含まれるトークン
+    pub token: String,
+    pub new_password: String,
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    reservation::{Reservation, ReservationStatus},
+    value_object::ValueObject,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ReservationStatusName {
+    Waiting,
+    ReadyForPickup,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationsResponse {
+    pub items: Vec<ReservationResponse>,
+}
+
+impl From<Vec<Reservation>> for ReservationsResponse {
+    fn from(reservations: Vec<Reservation>) -> Self {
+        Self {
+            items: reservations
+                .into_iter()
+                .map(ReservationResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationResponse {
+    pub id: Uuid,
+    pub reserved_by: Uuid,
+    pub reserved_at: DateTime<Utc>,
+    pub position: i64,
+    pub status: ReservationStatusName,
+    // 冊が取り置かれている場合のみ値が入る
+    pub ready_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<Reservation> for ReservationResponse {
+    fn from(reservation: Reservation) -> Self {
+        let (reservation_id, _book_id, reserved_by, reserved_at, position, status) =
+            reservation.dissolve();
+
+        let (status, ready_at, expires_at) = match status {
+            ReservationStatus::Waiting => (ReservationStatusName::Waiting, None, None),
+            ReservationStatus::ReadyForPickup {
+                ready_at,
+                expires_at,
+            } => (
+                ReservationStatusName::ReadyForPickup,
+                Some(ready_at),
+                Some(expires_at),
+            ),
+        };
+
+        Self {
+            id: reservation_id.into_inner(),
+            reserved_by: reserved_by.into_inner(),
+            reserved_at,
+            position,
+            status,
+            ready_at,
+            expires_at,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{auth::session::Session, value_object::ValueObject};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsResponse {
+    pub items: Vec<SessionResponse>,
+}
+
+impl From<Vec<Session>> for SessionsResponse {
+    fn from(value: Vec<Session>) -> Self {
+        Self {
+            items: value.into_iter().map(SessionResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    // このリクエストで使ったアクセストークンのセッションか
+    pub current: bool,
+}
+
+impl From<Session> for SessionResponse {
+    fn from(value: Session) -> Self {
+        let Session {
+            session_id,
+            client,
+            created_at,
+            last_used_at,
+            current,
+        } = value;
+
+        Self {
+            id: session_id.into_inner(),
+            user_agent: client.user_agent,
+            ip_address: client.ip_address,
+            created_at,
+            last_used_at,
+            current,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use kernel::model::auth::totp::{RecoveryCode, SecondFactor, TotpCode, TotpSetup};
+use serde::{Deserialize, Serialize};
+use shared::{message::Violation, problem::FieldError};
+use utoipa::ToSchema;
+
+use crate::model::violation::ToViolation;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpSetupResponse {
+    // Base32 のシークレット。QR コードを読み込めない場合に手で入力する
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+impl From<TotpSetup> for TotpSetupResponse {
+    fn from(value: TotpSetup) -> Self {
+        let TotpSetup {
+            secret,
+            otpauth_uri,
+        } = value;
+        Self {
+            secret,
+            otpauth_uri,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmTotpRequest {
+    pub code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesResponse {
+    // 一度しか表示しないため、利用者に控えてもらう
+    pub recovery_codes: Vec<String>,
+}
+
+// code と recoveryCode のどちらか一方を指定する
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SecondFactorRequest {
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+impl TryFrom<SecondFactorRequest> for SecondFactor {
+    type Error = FieldError;
+
+    fn try_from(value: SecondFactorRequest) -> Result<Self, Self::Error> {
+        match (value.code, value.recovery_code) {
+            (Some(code), None) => TotpCode::try_from(code)
+                .map(SecondFactor::Totp)
+                .map_err(|e| FieldError::new("code", e.violation())),
+            (None, Some(recovery_code)) => RecoveryCode::try_from(recovery_code)
+                .map(SecondFactor::RecoveryCode)
+                .map_err(|e| FieldError::new("recoveryCode", e.violation())),
+            _ => Err(FieldError::new("code", Violation::Required)),
+        }
+    }
+}
@@ -2,16 +2,21 @@ use derive_new::new;
 use garde::Validate;
 use kernel::model::{
     user::{
-        event::{CreateUser, UpdateUserPassword, UpdateUserRole},
+        event::{CreateUser, UpdateUserName, UpdateUserPassword, UpdateUserRole},
         PasswordError, User, UserEmailError, UserId, UserNameError, UserRole,
     },
     value_object::ValueObject,
 };
 use serde::{Deserialize, Serialize};
 use strum::VariantNames;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Serialize, VariantNames, Deserialize)]
+use shared::problem::FieldError;
+
+use super::violation::ToViolation;
+
+#[derive(Serialize, VariantNames, Deserialize, ToSchema)]
 #[strum(serialize_all = "kebab-case")]
 pub enum UserRoleName {
     Admin,
@@ -36,7 +41,7 @@ impl From<UserRoleName> for UserRole {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookOwner {
     pub id: Uuid,
@@ -52,21 +57,10 @@ impl From<kernel::model::user::BookOwner> for BookOwner {
     }
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct UsersResponse {
-    pub items: Vec<UserResponse>,
-}
-
-impl From<Vec<User>> for UsersResponse {
-    fn from(value: Vec<User>) -> Self {
-        Self {
-            items: value.into_iter().map(UserResponse::from).collect(),
-        }
-    }
-}
+// ユーザー一覧のカーソルを他の一覧のカーソルと区別するための値
+pub const USER_CURSOR_SCOPE: &str = "users";
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UserResponse {
     pub id: Uuid,
@@ -88,12 +82,39 @@ impl From<User> for UserResponse {
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserNameRequest {
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1, max_length = 255)]
+    pub name: String,
+}
+
+#[derive(new)]
+pub struct UpdateUserNameRequestWithUserId(UserId, UpdateUserNameRequest);
+
+impl TryFrom<UpdateUserNameRequestWithUserId> for UpdateUserName {
+    type Error = UserModelError;
+
+    fn try_from(value: UpdateUserNameRequestWithUserId) -> Result<Self, Self::Error> {
+        let UpdateUserNameRequestWithUserId(user_id, UpdateUserNameRequest { name }) = value;
+
+        Ok(UpdateUserName {
+            user_id,
+            name: name.try_into()?,
+        })
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserPasswordRequest {
-    #[garde(length(min = 8))]
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1)]
     pub current_password: String,
-    #[garde(length(min = 8))]
+    // 文字数などの条件はサーバーのパスワードポリシーで確認する
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1)]
     pub new_password: String,
 }
 
@@ -118,14 +139,18 @@ impl TryFrom<UpdateUserPasswordRequestWithUserId> for UpdateUserPassword {
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateUserRequest {
     #[garde(length(min = 1))]
+    #[schema(min_length = 1, max_length = 255)]
     pub name: String,
     #[garde(email)]
+    #[schema(format = Email)]
     pub email: String,
-    #[garde(length(min = 8))]
+    // 文字数などの条件はサーバーのパスワードポリシーで確認する
+    #[garde(length(min = 1))]
+    #[schema(min_length = 1)]
     pub password: String,
 }
 
@@ -147,7 +172,7 @@ impl TryFrom<CreateUserRequest> for CreateUser {
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserRoleRequest {
     #[garde(skip)]
@@ -180,3 +205,14 @@ pub enum UserModelError {
     #[error("Invalid email: {0}")]
     InvalidEmail(#[from] UserEmailError),
 }
+
+impl UserModelError {
+    // どのフィールドの値が不正かを返す
+    pub fn field_error(&self) -> FieldError {
+        match self {
+            UserModelError::InvalidName(e) => FieldError::new("name", e.violation()),
+            UserModelError::InvalidCurrentPassword(e) => FieldError::new("password", e.violation()),
+            UserModelError::InvalidEmail(e) => FieldError::new("email", e.violation()),
+        }
+    }
+}
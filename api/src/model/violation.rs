@@ -0,0 +1,87 @@
+use kernel::model::{
+    api_key::ApiKeyNameError,
+    auth::{
+        oidc::AuthorizationCodeError,
+        totp::{RecoveryCodeError, TotpCodeError},
+    },
+    book::{AuthorError, DescriptionError, IsbnError, TitleError},
+    book_copy::{BarcodeError, CopyLocationError},
+    user::{password::PasswordPolicyError, PasswordError, UserEmailError, UserNameError},
+    value_object::StringInvariant,
+};
+use shared::message::Violation;
+
+// kernel の値オブジェクトのエラーを、文言を引くための入力値の誤りの種類に変換する
+pub trait ToViolation {
+    fn violation(&self) -> Violation;
+}
+
+fn string_violation(invariant: &StringInvariant) -> Violation {
+    match invariant {
+        StringInvariant::Trimmed => Violation::SurroundingWhitespace,
+        StringInvariant::NonEmpty => Violation::Required,
+        StringInvariant::MaxChars(max) => Violation::TooLong(*max),
+        StringInvariant::NoControlChars | StringInvariant::NoControlCharsExceptLineBreaks => {
+            Violation::ControlCharacters
+        }
+    }
+}
+
+macro_rules! impl_string_violation {
+    ($($error:ident),+ $(,)?) => {
+        $(
+            impl ToViolation for $error {
+                fn violation(&self) -> Violation {
+                    match self {
+                        $error::InvalidValue(invariant) => string_violation(invariant),
+                        $error::ParseError(_) => Violation::InvalidFormat,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_string_violation!(
+    TitleError,
+    AuthorError,
+    DescriptionError,
+    UserNameError,
+    BarcodeError,
+    CopyLocationError,
+    TotpCodeError,
+    RecoveryCodeError,
+    ApiKeyNameError,
+    AuthorizationCodeError,
+);
+
+impl ToViolation for IsbnError {
+    fn violation(&self) -> Violation {
+        Violation::InvalidFormat
+    }
+}
+
+impl ToViolation for PasswordError {
+    fn violation(&self) -> Violation {
+        Violation::InvalidFormat
+    }
+}
+
+impl ToViolation for PasswordPolicyError {
+    fn violation(&self) -> Violation {
+        match self {
+            PasswordPolicyError::TooShort(min) => Violation::TooShort(*min),
+            PasswordPolicyError::TooLong(max) => Violation::TooLong(*max),
+            PasswordPolicyError::TooFewCharacterClasses(min) => {
+                Violation::TooFewCharacterClasses(*min)
+            }
+            PasswordPolicyError::Common => Violation::CommonPassword,
+        }
+    }
+}
+
+impl ToViolation for UserEmailError {
+    fn violation(&self) -> Violation {
+        Violation::InvalidEmail
+    }
+}
@@ -0,0 +1,95 @@
+use shared::problem::{FieldError, ProblemDetails};
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::handler;
+
+// ルーティングに追加したハンドラは paths にも追加する
+// （追加漏れは api/tests/api/openapi.rs のテストで検出する）
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Rusty Book Manager API"),
+    paths(
+        handler::health::health_check,
+        handler::health::health_check_db,
+        handler::auth::login,
+        handler::auth::login_totp,
+        handler::oidc::oidc_login,
+        handler::oidc::oidc_callback,
+        handler::auth::refresh,
+        handler::auth::logout,
+        handler::auth::logout_all,
+        handler::auth::jwks,
+        handler::password_reset::request_password_reset,
+        handler::password_reset::confirm_password_reset,
+        handler::email_change::confirm_email_change,
+        handler::invitation::accept_invitation,
+        handler::book::register_book,
+        handler::book::show_book_list,
+        handler::book::show_book,
+        handler::book::update_book,
+        handler::book::delete_book,
+        handler::book_copy::show_book_copy_list,
+        handler::book_copy::register_book_copy,
+        handler::book_copy::update_book_copy,
+        handler::book_copy::delete_book_copy,
+        handler::checkout::checkout_book,
+        handler::checkout::checkout_book_copy,
+        handler::checkout::return_book,
+        handler::checkout::renew_checkout,
+        handler::checkout::checkout_history,
+        handler::checkout::show_checked_out_list,
+        handler::reservation::show_reservation_list,
+        handler::reservation::reserve_book,
+        handler::reservation::cancel_reservation,
+        handler::user::register_user,
+        handler::user::list_users,
+        handler::user::delete_user,
+        handler::user::change_user_role,
+        handler::user::get_current_user,
+        handler::user::update_current_user,
+        handler::email_change::request_email_change,
+        handler::user::change_password,
+        handler::user::get_checkouts,
+        handler::session::list_sessions,
+        handler::session::delete_session,
+        handler::session::delete_user_sessions,
+        handler::totp::setup_totp,
+        handler::totp::confirm_totp,
+        handler::totp::disable_totp,
+        handler::api_key::list_api_keys,
+        handler::api_key::create_api_key,
+        handler::api_key::delete_api_key,
+        handler::invitation::create_invitation,
+        handler::invitation::list_invitations,
+        handler::invitation::delete_invitation,
+    ),
+    components(schemas(ProblemDetails, FieldError), responses(ProblemDetails)),
+    modifiers(&BearerSecurity),
+    security(("bearer" = [])),
+    tags(
+        (name = "health"),
+        (name = "auth"),
+        (name = "books"),
+        (name = "book-copies"),
+        (name = "checkouts"),
+        (name = "reservations"),
+        (name = "users"),
+    )
+)]
+pub struct ApiDoc;
+
+// ログインで発行したアクセストークンか API キーを Authorization: Bearer で送る
+struct BearerSecurity;
+
+impl Modify for BearerSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use axum::{routing::post, Router};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 use registry::AppRegistry;
 
 use crate::handler;
@@ -6,6 +9,27 @@ use crate::handler;
 pub fn build_auth_routers() -> Router<AppRegistry> {
     let routers = Router::new()
         .route("/login", post(handler::auth::login))
-        .route("/logout", post(handler::auth::logout));
+        .route("/login/totp", post(handler::auth::login_totp))
+        .route("/refresh", post(handler::auth::refresh))
+        .route("/logout", post(handler::auth::logout))
+        .route("/logout-all", post(handler::auth::logout_all))
+        .route(
+            "/password-reset/request",
+            post(handler::password_reset::request_password_reset),
+        )
+        .route(
+            "/password-reset/confirm",
+            post(handler::password_reset::confirm_password_reset),
+        )
+        .route(
+            "/email-change/confirm",
+            post(handler::email_change::confirm_email_change),
+        )
+        .route(
+            "/invitations/:token/accept",
+            post(handler::invitation::accept_invitation),
+        )
+        .route("/oidc/login", get(handler::oidc::oidc_login))
+        .route("/oidc/callback", get(handler::oidc::oidc_callback));
     Router::new().nest("/auth", routers)
 }
@@ -17,10 +17,46 @@ pub fn build_book_routers() -> Router<AppRegistry> {
             "/:book_id/checkouts",
             post(handler::checkout::checkout_book),
         )
+        .route(
+            "/:book_id/copies",
+            get(handler::book_copy::show_book_copy_list),
+        )
+        .route(
+            "/:book_id/copies",
+            post(handler::book_copy::register_book_copy),
+        )
+        .route(
+            "/:book_id/copies/:book_copy_id",
+            put(handler::book_copy::update_book_copy),
+        )
+        .route(
+            "/:book_id/copies/:book_copy_id",
+            delete(handler::book_copy::delete_book_copy),
+        )
+        .route(
+            "/:book_id/copies/:book_copy_id/checkouts",
+            post(handler::checkout::checkout_book_copy),
+        )
         .route(
             "/:book_id/checkouts/:checkout_id/returned",
             put(handler::checkout::return_book),
         )
+        .route(
+            "/:book_id/checkouts/:checkout_id/renewal",
+            put(handler::checkout::renew_checkout),
+        )
+        .route(
+            "/:book_id/reservations",
+            get(handler::reservation::show_reservation_list),
+        )
+        .route(
+            "/:book_id/reservations",
+            post(handler::reservation::reserve_book),
+        )
+        .route(
+            "/:book_id/reservations/:reservation_id",
+            delete(handler::reservation::cancel_reservation),
+        )
         .route(
             "/:book_id/checkout-history",
             get(handler::checkout::checkout_history),
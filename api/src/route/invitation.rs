@@ -0,0 +1,20 @@
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+use registry::AppRegistry;
+
+use crate::handler;
+
+pub fn build_invitation_routers() -> Router<AppRegistry> {
+    let routers = Router::new()
+        .route(
+            "/",
+            get(handler::invitation::list_invitations).post(handler::invitation::create_invitation),
+        )
+        .route(
+            "/:invitation_id",
+            delete(handler::invitation::delete_invitation),
+        );
+    Router::new().nest("/invitations", routers)
+}
@@ -1,5 +1,8 @@
 pub mod auth;
 pub mod book;
 pub mod health;
+pub mod invitation;
+pub mod openapi;
 pub mod user;
 pub mod v1;
+pub mod well_known;
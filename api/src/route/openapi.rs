@@ -0,0 +1,17 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler;
+
+pub fn build_openapi_routers() -> Router<AppRegistry> {
+    let routers = Router::new().route("/openapi.json", get(handler::openapi::show_openapi));
+
+    // swagger-ui フィーチャーが有効な場合のみ、同梱の Swagger UI を /docs で配信する
+    #[cfg(feature = "swagger-ui")]
+    let routers = routers.merge(
+        utoipa_swagger_ui::SwaggerUi::new("/docs")
+            .config(utoipa_swagger_ui::Config::from("/api/v1/openapi.json")),
+    );
+
+    routers
+}
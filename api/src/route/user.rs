@@ -8,12 +8,41 @@ use crate::handler;
 
 pub fn build_user_routers() -> Router<AppRegistry> {
     let routers = Router::new()
-        .route("/me", get(handler::user::get_current_user))
+        .route(
+            "/me",
+            get(handler::user::get_current_user).patch(handler::user::update_current_user),
+        )
+        .route(
+            "/me/email",
+            post(handler::email_change::request_email_change),
+        )
         .route("/me/password", put(handler::user::change_password))
         .route("/me/checkouts", get(handler::user::get_checkouts))
+        .route("/me/sessions", get(handler::session::list_sessions))
+        .route(
+            "/me/sessions/:session_id",
+            delete(handler::session::delete_session),
+        )
+        .route(
+            "/me/totp",
+            post(handler::totp::setup_totp).delete(handler::totp::disable_totp),
+        )
+        .route("/me/totp/confirm", post(handler::totp::confirm_totp))
+        .route(
+            "/me/api-keys",
+            get(handler::api_key::list_api_keys).post(handler::api_key::create_api_key),
+        )
+        .route(
+            "/me/api-keys/:api_key_id",
+            delete(handler::api_key::delete_api_key),
+        )
         .route("/", post(handler::user::register_user))
         .route("/", get(handler::user::list_users))
         .route("/:user_id", delete(handler::user::delete_user))
-        .route("/:user_id/role", put(handler::user::change_user_role));
+        .route("/:user_id/role", put(handler::user::change_user_role))
+        .route(
+            "/:user_id/sessions",
+            delete(handler::session::delete_user_sessions),
+        );
     Router::new().nest("/users", routers)
 }
@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler;
+
+pub fn build_well_known_routers() -> Router<AppRegistry> {
+    let routers = Router::new().route("/jwks.json", get(handler::auth::jwks));
+    Router::new().nest("/.well-known", routers)
+}
@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::{Duration, Utc};
+use kernel::{
+    model::{
+        api_key::{ApiKey, ApiKeyGrant, ApiKeyId, ApiKeyScope, CreatedApiKey},
+        user::{UserId, UserRole},
+        value_object::ValueObject,
+    },
+    repository::{
+        api_key::{ApiKeyRepositoryError, MockApiKeyRepository},
+        book::MockBookRepository,
+    },
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use serde_json::Value;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    deserialize_json,
+    helper::{expect_current_user, fixture, make_router, v1, TestRequestExt},
+};
+
+const API_KEY: &str = "rbm_0123456789abcdefghijklmnopqrstuvwxyzABCD";
+
+fn api_key(name: &str) -> ApiKey {
+    ApiKey::new(
+        ApiKeyId::new(Uuid::new_v4()),
+        name.to_string().try_into().unwrap(),
+        API_KEY[..12].to_string(),
+        vec![ApiKeyScope::BooksRead],
+        Utc::now() + Duration::days(30),
+        None,
+        Utc::now(),
+    )
+}
+
+// API キーで認証するリクエスト用に、指定したスコープを持つキーを返すモックを登録する
+fn expect_api_key_grant(registry: &mut MockAppRegistryExt, scopes: Vec<ApiKeyScope>) {
+    registry.expect_api_key_repository().returning(move || {
+        let scopes = scopes.clone();
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_fetch_grant()
+            .withf(|key| key == API_KEY)
+            .returning(move |_| {
+                Ok(Some(ApiKeyGrant {
+                    api_key_id: ApiKeyId::new(Uuid::new_v4()),
+                    user_id: UserId::new(Uuid::new_v4()),
+                    scopes: scopes.clone(),
+                }))
+            });
+        Arc::new(mock)
+    });
+    expect_current_user(registry, UserRole::User);
+}
+
+#[rstest]
+#[tokio::test]
+async fn create_api_key_201(mut fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_api_key_repository().returning(|| {
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_create()
+            .withf(|event| {
+                event.name.inner_ref() == "inventory script"
+                    && event.scopes == vec![ApiKeyScope::BooksRead, ApiKeyScope::CheckoutsWrite]
+            })
+            .returning(|_| {
+                Ok(CreatedApiKey {
+                    api_key: api_key("inventory script"),
+                    key: API_KEY.to_string(),
+                })
+            });
+        Arc::new(mock)
+    });
+    let app = make_router(fixture);
+
+    let body = serde_json::json!({
+        "name": " inventory script ",
+        "scopes": ["books:read", "checkouts:write", "books:read"],
+        "expiresAt": Utc::now() + Duration::days(30),
+    });
+    let req = Request::post(&v1("/users/me/api-keys"))
+        .bearer()
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["key"], API_KEY);
+    assert_eq!(body["prefix"], "rbm_01234567");
+    assert_eq!(body["scopes"][0], "books:read");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(Duration::days(-1), "expiresAt")]
+#[case(Duration::days(366), "expiresAt")]
+#[tokio::test]
+async fn create_api_key_400(
+    fixture: MockAppRegistryExt,
+    #[case] lifetime: Duration,
+    #[case] expected_field: &str,
+) -> anyhow::Result<()> {
+    let app = make_router(fixture);
+
+    let body = serde_json::json!({
+        "name": "script",
+        "scopes": ["books:read"],
+        "expiresAt": Utc::now() + lifetime,
+    });
+    let req = Request::post(&v1("/users/me/api-keys"))
+        .bearer()
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "validation_failed");
+    assert_eq!(body.errors[0].field, expected_field);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn list_api_keys_200(mut fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_api_key_repository().returning(|| {
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_find_all()
+            .returning(|_| Ok(vec![api_key("first"), api_key("second")]));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture);
+
+    let req = Request::get(&v1("/users/me/api-keys"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // 一覧にはキーそのものを含めない
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["items"].as_array().map(Vec::len), Some(2));
+    assert!(body["items"][0].get("key").is_none());
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_api_key_404(mut fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_api_key_repository().returning(|| {
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_delete()
+            .returning(|_| Err(ApiKeyRepositoryError::NotFound));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture);
+
+    let req = Request::delete(&v1(&format!("/users/me/api-keys/{}", Uuid::new_v4())))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "api_key_not_found");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn api_key_with_scope_is_accepted() -> anyhow::Result<()> {
+    let mut registry = MockAppRegistryExt::new();
+    expect_api_key_grant(&mut registry, vec![ApiKeyScope::BooksRead]);
+    registry.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().returning(|_| Ok(None));
+        Arc::new(mock)
+    });
+    let app = make_router(registry);
+
+    let req = Request::get(&v1(&format!("/books/{}", Uuid::new_v4())))
+        .header("Authorization", format!("Bearer {API_KEY}"))
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    Request::post(v1("/books")),
+    vec![ApiKeyScope::BooksRead],
+    "insufficient_scope"
+)]
+#[case(
+    Request::get(v1("/users/me/api-keys")),
+    vec![ApiKeyScope::UsersRead, ApiKeyScope::UsersWrite],
+    "api_key_not_allowed"
+)]
+// 管理者によるユーザーの登録・削除・ロール変更は、長期間有効な API キーだけでは行えない
+#[case(
+    Request::post(v1("/users")),
+    vec![ApiKeyScope::UsersWrite],
+    "api_key_not_allowed"
+)]
+#[case(
+    Request::delete(v1(&format!("/users/{}", Uuid::new_v4()))),
+    vec![ApiKeyScope::UsersWrite],
+    "api_key_not_allowed"
+)]
+#[case(
+    Request::put(v1(&format!("/users/{}/role", Uuid::new_v4()))),
+    vec![ApiKeyScope::UsersWrite],
+    "api_key_not_allowed"
+)]
+#[tokio::test]
+async fn api_key_403(
+    #[case] builder: axum::http::request::Builder,
+    #[case] scopes: Vec<ApiKeyScope>,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    let mut registry = MockAppRegistryExt::new();
+    expect_api_key_grant(&mut registry, scopes);
+    let app = make_router(registry);
+
+    let req = builder
+        .header("Authorization", format!("Bearer {API_KEY}"))
+        .application_json()
+        .body(Body::from(
+            r#"{"title":"t","author":"a","isbn":"9784065369579","description":""}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, expected_code);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn unknown_api_key_401() -> anyhow::Result<()> {
+    let mut registry = MockAppRegistryExt::new();
+    registry.expect_api_key_repository().returning(|| {
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_fetch_grant().returning(|_| Ok(None));
+        Arc::new(mock)
+    });
+    let app = make_router(registry);
+
+    let req = Request::get(&v1("/books"))
+        .header("Authorization", format!("Bearer {API_KEY}"))
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
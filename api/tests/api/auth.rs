@@ -0,0 +1,292 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use api::extractor::TrustedProxies;
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Extension,
+};
+use kernel::{
+    model::{
+        auth::{
+            throttle::LoginLockout, totp::TotpRequirement, AccessToken, AuthTokens, RefreshToken,
+        },
+        user::UserId,
+        value_object::ValueObject,
+    },
+    repository::{
+        auth::{AuthRepositoryError, MockAuthRepository},
+        login_attempt::MockLoginAttemptRepository,
+        totp::MockTotpRepository,
+    },
+};
+use rstest::rstest;
+use serde_json::Value;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router, TestRequestExt},
+};
+
+#[rstest]
+#[tokio::test]
+async fn login_200(mut fixture_registry: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_verify_user()
+            .returning(|_, _| Ok(UserId::new(Uuid::new_v4())));
+        // ログインしたクライアントの情報をセッションに記録する
+        mock.expect_create_token()
+            .withf(|event| {
+                event.client.user_agent.as_deref() == Some("curl/8.5.0")
+                    && event.client.ip_address.as_deref() == Some("192.0.2.10")
+            })
+            .returning(|event| {
+                Ok(AuthTokens {
+                    user_id: event.user_id,
+                    access_token: AccessToken::new("dummy".to_string()),
+                    refresh_token: RefreshToken::new("dummy-refresh".to_string()),
+                    expires_in: 900,
+                })
+            });
+        Arc::new(mock)
+    });
+    fixture_registry
+        .expect_login_attempt_repository()
+        .returning(|| {
+            let mut mock = MockLoginAttemptRepository::new();
+            mock.expect_find_lockout().returning(|_, _| Ok(None));
+            mock.expect_clear_failures().returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+    fixture_registry.expect_totp_repository().returning(|| {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_find_requirement()
+            .returning(|_| Ok(TotpRequirement::Disabled));
+        Arc::new(mock)
+    });
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login")
+        .application_json()
+        .header(header::USER_AGENT, "curl/8.5.0")
+        .body(Body::from(
+            r#"{"email":"alice@example.com","password":"password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["accessToken"], "dummy");
+    assert_eq!(body["refreshToken"], "dummy-refresh");
+    assert_eq!(body["expiresIn"], 900);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_401_records_failure(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_verify_user()
+            .returning(|_, _| Err(AuthRepositoryError::InvalidPassword));
+        Arc::new(mock)
+    });
+    fixture_registry
+        .expect_login_attempt_repository()
+        .returning(move || {
+            let counter = counter.clone();
+            let mut mock = MockLoginAttemptRepository::new();
+            mock.expect_find_lockout().returning(|_, _| Ok(None));
+            mock.expect_record_failure()
+                .withf(|event| event.email.to_string() == "alice@example.com")
+                .returning(move |_| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                });
+            Arc::new(mock)
+        });
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login")
+        .application_json()
+        .body(Body::from(
+            r#"{"email":"alice@example.com","password":"wrong-password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "invalid_credentials");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_429(mut fixture_registry: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    // ロック中はパスワードを照合しない
+    fixture_registry
+        .expect_auth_repository()
+        .returning(|| Arc::new(MockAuthRepository::new()));
+    fixture_registry
+        .expect_login_attempt_repository()
+        .returning(|| {
+            let mut mock = MockLoginAttemptRepository::new();
+            mock.expect_find_lockout()
+                .returning(|_, _| Ok(Some(LoginLockout { retry_after: 120 })));
+            Arc::new(mock)
+        });
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login")
+        .application_json()
+        .body(Body::from(
+            r#"{"email":"alice@example.com","password":"password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers()[header::RETRY_AFTER], "120");
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "too_many_login_attempts");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn refresh_200(mut fixture_registry: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    let user_id = UserId::new(Uuid::new_v4());
+    let expected_user_id = user_id.inner_ref().to_string();
+
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let user_id = user_id.clone();
+            let mut mock = MockAuthRepository::new();
+            mock.expect_rotate_token()
+                .withf(|event| event.refresh_token.inner_ref() == "old-refresh")
+                .returning(move |event| {
+                    Ok(AuthTokens {
+                        user_id: user_id.clone(),
+                        access_token: AccessToken::new("new-access".to_string()),
+                        refresh_token: event.new_refresh_token,
+                        expires_in: 900,
+                    })
+                });
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/refresh")
+        .application_json()
+        .body(Body::from(r#"{"refreshToken":"old-refresh"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["userId"], expected_user_id);
+    assert_eq!(body["accessToken"], "new-access");
+    assert_ne!(body["refreshToken"], "old-refresh");
+
+    Ok(())
+}
+
+// X-Forwarded-For は信頼するリバースプロキシからの接続に限り、末尾の値だけを使う
+#[rstest]
+#[case(None, Some("203.0.113.7"), "192.0.2.10")]
+#[case(Some("198.51.100.1"), Some("203.0.113.7"), "192.0.2.10")]
+#[case(Some("192.0.2.10"), Some("203.0.113.7"), "203.0.113.7")]
+#[case(Some("192.0.2.10"), Some("10.0.0.1, 203.0.113.7"), "203.0.113.7")]
+#[case(Some("192.0.2.10"), Some("203.0.113.7, not-an-ip"), "192.0.2.10")]
+#[case(Some("192.0.2.10"), None, "192.0.2.10")]
+#[tokio::test]
+async fn refresh_records_client_ip(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] trusted_proxy: Option<&str>,
+    #[case] forwarded_for: Option<&str>,
+    #[case] expected_ip: &'static str,
+) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let mut mock = MockAuthRepository::new();
+            mock.expect_rotate_token()
+                .withf(move |event| event.client.ip_address.as_deref() == Some(expected_ip))
+                .returning(|event| {
+                    Ok(AuthTokens {
+                        user_id: UserId::new(Uuid::new_v4()),
+                        access_token: AccessToken::new("new-access".to_string()),
+                        refresh_token: event.new_refresh_token,
+                        expires_in: 900,
+                    })
+                });
+            Arc::new(mock)
+        });
+
+    let mut app: axum::Router = make_router(fixture_registry);
+    if let Some(trusted_proxy) = trusted_proxy {
+        app = app.layer(Extension(TrustedProxies::new([trusted_proxy.parse()?])));
+    }
+
+    let mut req = Request::post("/auth/refresh").application_json();
+    if let Some(forwarded_for) = forwarded_for {
+        req = req.header("X-Forwarded-For", forwarded_for);
+    }
+    let resp = app
+        .oneshot(req.body(Body::from(r#"{"refreshToken":"old-refresh"}"#))?)
+        .await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(AuthRepositoryError::InvalidRefreshToken, "invalid_refresh_token")]
+#[case(AuthRepositoryError::RefreshTokenReused, "refresh_token_reused")]
+#[tokio::test]
+async fn refresh_401(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] error: AuthRepositoryError,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_auth_repository()
+        .return_once(move || {
+            let mut mock = MockAuthRepository::new();
+            mock.expect_rotate_token().return_once(move |_| Err(error));
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/refresh")
+        .application_json()
+        .body(Body::from(r#"{"refreshToken":"used-refresh"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        resp.headers()[header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, expected_code);
+
+    Ok(())
+}
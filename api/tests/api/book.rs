@@ -6,19 +6,24 @@ use tower::util::ServiceExt;
 
 use kernel::{
     model::{
-        book::{Author, Book, BookId, Description, Isbn, Title},
-        list::PaginatedList,
+        book::{Author, Book, BookAvailability, BookId, BookSortKey, Description, Isbn, Title},
+        book_copy::BookCopyCount,
+        list::{Cursor, CursorPage, PaginatedList, SortOrder},
         user::{BookOwner, UserId, UserName},
     },
-    repository::book::MockBookRepository,
+    repository::book::{BookRepositoryError, MockBookRepository},
 };
 use uuid::Uuid;
 
-use api::model::book::PaginatedBookResponse;
+use api::model::{
+    book::{BookResponse, PaginatedBookResponse},
+    list::CursorPageResponse,
+};
 use axum::{
     body::Body,
-    http::{Request, StatusCode},
+    http::{header, Request, StatusCode},
 };
+use shared::problem::ProblemDetails;
 
 use crate::{
     deserialize_json,
@@ -49,15 +54,20 @@ async fn show_book_list_with_query_200(
             let book_id = book_id.clone();
             let items = vec![Book {
                 book_id,
-                title: Title::new("RustによるWebアプリケーション開発".to_string()),
-                author: Author::new("Yuki Toyoda".to_string()),
-                isbn: Isbn::new("978-4-06-536957-9".to_string()),
-                description: Description::new("RustによるWebアプリケーション開発".to_string()),
+                title: Title::try_from("RustによるWebアプリケーション開発".to_string()).unwrap(),
+                author: Author::try_from("Yuki Toyoda".to_string()).unwrap(),
+                isbn: Some(Isbn::try_from("978-4-06-536957-9".to_string()).unwrap()),
+                description: Description::try_from("RustによるWebアプリケーション開発".to_string())
+                    .unwrap(),
                 owner: BookOwner {
                     user_id: UserId::new(Uuid::new_v4()),
-                    user_name: UserName::new("Yuki Toyoda".to_string()),
+                    user_name: UserName::try_from("Yuki Toyoda".to_string()).unwrap(),
+                },
+                copy_count: BookCopyCount {
+                    total: 1,
+                    available: 1,
                 },
-                checkout: None,
+                checkouts: vec![],
             }];
             Ok(PaginatedList {
                 total: 1,
@@ -88,6 +98,8 @@ async fn show_book_list_with_query_200(
 
 #[rstest]
 #[case("/books?limit=-1")]
+#[case("/books?limit=101")]
+#[case("/books?limit=9223372036854775807&after=")]
 #[case("/books?offset=aaa")]
 #[tokio::test]
 async fn show_book_list_with_query_400(
@@ -105,15 +117,20 @@ async fn show_book_list_with_query_400(
             let book_id = book_id.clone();
             let items = vec![Book {
                 book_id,
-                title: Title::new("RustによるWebアプリケーション開発".to_string()),
-                author: Author::new("Yuki Toyoda".to_string()),
-                isbn: Isbn::new("978-4-06-536957-9".to_string()),
-                description: Description::new("RustによるWebアプリケーション開発".to_string()),
+                title: Title::try_from("RustによるWebアプリケーション開発".to_string()).unwrap(),
+                author: Author::try_from("Yuki Toyoda".to_string()).unwrap(),
+                isbn: Some(Isbn::try_from("978-4-06-536957-9".to_string()).unwrap()),
+                description: Description::try_from("RustによるWebアプリケーション開発".to_string())
+                    .unwrap(),
                 owner: BookOwner {
                     user_id: UserId::new(Uuid::new_v4()),
-                    user_name: UserName::new("Yuki Toyoda".to_string()),
+                    user_name: UserName::try_from("Yuki Toyoda".to_string()).unwrap(),
+                },
+                copy_count: BookCopyCount {
+                    total: 1,
+                    available: 1,
                 },
-                checkout: None,
+                checkouts: vec![],
             }];
             Ok(PaginatedList {
                 total: 1,
@@ -136,3 +153,424 @@ async fn show_book_list_with_query_400(
 
     Ok(())
 }
+
+#[rstest]
+#[tokio::test]
+async fn show_book_list_with_search_query_200(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let owner_id = Uuid::new_v4();
+
+    // クエリパラメータが絞り込み条件として repository に渡されることを検証する
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+
+        mock.expect_find_all()
+            .withf(move |opt| {
+                let filter = &opt.filter;
+                filter.keyword.as_deref() == Some("Rust")
+                    && filter.author.as_deref() == Some("豊田")
+                    && filter.owner_id == Some(UserId::new(owner_id))
+                    && filter.availability == Some(BookAvailability::CheckedOut)
+                    && filter.checked_out_by.is_some()
+                    && opt.sort.key == BookSortKey::Relevance
+                    && opt.sort.order == SortOrder::Desc
+            })
+            .returning(|opt| {
+                Ok(PaginatedList {
+                    total: 0,
+                    limit: opt.limit,
+                    offset: opt.offset,
+                    items: vec![],
+                })
+            });
+
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let path = format!(
+        "/books?q=Rust&author=%E8%B1%8A%E7%94%B0&ownerId={owner_id}&available=false&checkedOutByMe=true&sort=relevance"
+    );
+    let req = Request::get(&v1(&path)).bearer().body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/books?q=")]
+#[case("/books?sort=unknown")]
+#[case("/books?available=maybe")]
+#[case("/books?sort=relevance")]
+#[case("/books?sort=title&order=sideways")]
+#[tokio::test]
+async fn show_book_list_with_search_query_400(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] path: &str,
+) -> anyhow::Result<()> {
+    fixture
+        .expect_book_repository()
+        .returning(|| Arc::new(MockBookRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/books?sort=title", BookSortKey::Title, SortOrder::Asc)]
+#[case("/books?sort=author&order=desc", BookSortKey::Author, SortOrder::Desc)]
+#[case("/books?sort=updatedAt", BookSortKey::UpdatedAt, SortOrder::Desc)]
+#[case(
+    "/books?sort=lastCheckedOutAt&order=asc",
+    BookSortKey::LastCheckedOutAt,
+    SortOrder::Asc
+)]
+#[case(
+    "/books?sort=checkoutCount",
+    BookSortKey::CheckoutCount,
+    SortOrder::Desc
+)]
+#[case("/books", BookSortKey::CreatedAt, SortOrder::Desc)]
+#[tokio::test]
+async fn show_book_list_with_sort_query_200(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] path: &str,
+    #[case] expected_key: BookSortKey,
+    #[case] expected_order: SortOrder,
+) -> anyhow::Result<()> {
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+
+        mock.expect_find_all()
+            .withf(move |opt| opt.sort.key == expected_key && opt.sort.order == expected_order)
+            .returning(|opt| {
+                Ok(PaginatedList {
+                    total: 0,
+                    limit: opt.limit,
+                    offset: opt.offset,
+                    items: vec![],
+                })
+            });
+
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_book_list_with_cursor_200(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let last_id = Uuid::new_v4();
+
+    // 先頭ページ（after が空文字列）では after なしで、
+    // 2 ページ目では 1 ページ目で返したカーソルがそのまま渡される
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+
+        mock.expect_find_all_by_cursor()
+            .withf(|opt| opt.after.is_none() && opt.sort.key == BookSortKey::Title)
+            .returning(move |opt| {
+                Ok(CursorPage {
+                    limit: opt.limit,
+                    items: vec![],
+                    next_cursor: Some(Cursor {
+                        sort_key: Some("Rust\nの本".to_string()),
+                        id: last_id,
+                    }),
+                })
+            });
+        mock.expect_find_all_by_cursor()
+            .withf(move |opt| {
+                opt.after
+                    == Some(Cursor {
+                        sort_key: Some("Rust\nの本".to_string()),
+                        id: last_id,
+                    })
+            })
+            .returning(|opt| {
+                Ok(CursorPage {
+                    limit: opt.limit,
+                    items: vec![],
+                    next_cursor: None,
+                })
+            });
+
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1("/books?sort=title&limit=5&after="))
+        .bearer()
+        .body(Body::empty())?;
+    let res = app.clone().oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let result = deserialize_json!(res, CursorPageResponse<BookResponse>);
+    assert_eq!(result.limit, 5);
+    let next_cursor = result
+        .next_cursor
+        .ok_or(anyhow::anyhow!("next cursor not found"))?;
+
+    let req = Request::get(&v1(&format!("/books?sort=title&after={next_cursor}")))
+        .bearer()
+        .body(Body::empty())?;
+    let res = app.clone().oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let result = deserialize_json!(res, CursorPageResponse<BookResponse>);
+    assert!(result.next_cursor.is_none());
+
+    // 別の並び順や絞り込み条件で発行されたカーソルは受け付けない
+    for query in [
+        "sort=author",
+        "sort=title&q=rust",
+        "sort=title&available=true",
+    ] {
+        let req = Request::get(&v1(&format!("/books?{query}&after={next_cursor}")))
+            .bearer()
+            .body(Body::empty())?;
+        let res = app.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let result = deserialize_json!(res, ProblemDetails);
+        assert_eq!(result.code, "invalid_cursor");
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/books?after=not-a-cursor")]
+#[case("/books?after=&offset=20")]
+#[tokio::test]
+async fn show_book_list_with_cursor_400(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] path: &str,
+) -> anyhow::Result<()> {
+    fixture
+        .expect_book_repository()
+        .returning(|| Arc::new(MockBookRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    r#"{"title":"   ","author":"Yuki Toyoda","isbn":"9784065369579","description":""}"#,
+    "title",
+    "required"
+)]
+#[case(
+    r#"{"title":"Rust","author":"Yuki\u0007","isbn":"9784065369579","description":""}"#,
+    "author",
+    "control_characters"
+)]
+#[case(
+    r#"{"title":"Rust","author":"Yuki Toyoda","isbn":"978-4-06-536957-0","description":""}"#,
+    "isbn",
+    "invalid_format"
+)]
+#[case(
+    &format!(
+        r#"{{"title":"Rust","author":"Yuki Toyoda","isbn":"9784065369579","description":"{}"}}"#,
+        "a".repeat(1025)
+    ),
+    "description",
+    "too_long"
+)]
+#[case(
+    r#"{"title":"","author":"Yuki Toyoda","isbn":"9784065369579","description":""}"#,
+    "title",
+    "required"
+)]
+#[tokio::test]
+async fn register_book_400(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] body: &str,
+    #[case] expected_field: &str,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    fixture
+        .expect_book_repository()
+        .returning(|| Arc::new(MockBookRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(&v1("/books"))
+        .bearer()
+        .application_json()
+        .body(Body::from(body.to_string()))?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let result = deserialize_json!(res, ProblemDetails);
+    assert_eq!(result.code, "validation_failed");
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].field, expected_field);
+    assert_eq!(result.errors[0].code, expected_code);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_book_404(mut fixture: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().returning(|_| Ok(None));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let book_id = Uuid::new_v4();
+    let req = Request::get(&v1(&format!("/books/{book_id}")))
+        .bearer()
+        .header("x-request-id", "test-request-id")
+        .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        res.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/problem+json"
+    );
+    assert_eq!(
+        res.headers().get("x-request-id").unwrap(),
+        "test-request-id"
+    );
+
+    // クライアントは code でエラーの種類を判別し、requestId でリクエストを辿る
+    let result = deserialize_json!(res, ProblemDetails);
+    assert_eq!(result.status, 404);
+    assert_eq!(result.code, "book_not_found");
+    assert_eq!(result.request_id.as_deref(), Some("test-request-id"));
+
+    Ok(())
+}
+
+// 存在しない蔵書と、他人の蔵書の変更・削除を区別して返す
+#[rstest]
+#[case("PUT", false, StatusCode::NOT_FOUND, "book_not_found")]
+#[case("PUT", true, StatusCode::FORBIDDEN, "forbidden")]
+#[case("DELETE", false, StatusCode::NOT_FOUND, "book_not_found")]
+#[case("DELETE", true, StatusCode::FORBIDDEN, "forbidden")]
+#[tokio::test]
+async fn update_or_delete_book_error(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] method: &str,
+    #[case] exists: bool,
+    #[case] expected_status: StatusCode,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    let error = move |book_id: BookId, user_id: UserId| {
+        if exists {
+            BookRepositoryError::NotBookOwner(book_id, user_id)
+        } else {
+            BookRepositoryError::NotFound(book_id)
+        }
+    };
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_update()
+            .returning(move |event| Err(error(event.book_id, event.requested_by)));
+        mock.expect_delete()
+            .returning(move |event| Err(error(event.book_id, event.requested_by)));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let book_id = Uuid::new_v4();
+    let req = Request::builder()
+        .method(method)
+        .uri(v1(&format!("/books/{book_id}")))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            r#"{"title":"Rust","author":"Yuki Toyoda","isbn":"9784065369579","description":""}"#,
+        ))?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), expected_status);
+
+    let result = deserialize_json!(res, ProblemDetails);
+    assert_eq!(result.code, expected_code);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    None,
+    "ja",
+    "入力内容に誤りがあります。",
+    "255 文字以内で入力してください。"
+)]
+#[case(
+    Some("en-US,en;q=0.9"),
+    "en",
+    "The request has invalid fields.",
+    "Must be at most 255 characters."
+)]
+#[case(
+    Some("fr, ja;q=0.5"),
+    "ja",
+    "入力内容に誤りがあります。",
+    "255 文字以内で入力してください。"
+)]
+#[tokio::test]
+async fn register_book_400_localized(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] accept_language: Option<&str>,
+    #[case] expected_language: &str,
+    #[case] expected_detail: &str,
+    #[case] expected_message: &str,
+) -> anyhow::Result<()> {
+    fixture
+        .expect_book_repository()
+        .returning(|| Arc::new(MockBookRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let body = format!(
+        r#"{{"title":"{}","author":"Yuki Toyoda","isbn":"9784065369579","description":""}}"#,
+        "a".repeat(256)
+    );
+    let mut req = Request::post(&v1("/books")).bearer().application_json();
+    if let Some(accept_language) = accept_language {
+        req = req.header(header::ACCEPT_LANGUAGE, accept_language);
+    }
+    let res = app.oneshot(req.body(Body::from(body))?).await?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        res.headers().get(header::CONTENT_LANGUAGE).unwrap(),
+        expected_language
+    );
+
+    let result = deserialize_json!(res, ProblemDetails);
+    assert_eq!(result.detail, expected_detail);
+    assert_eq!(result.errors[0].code, "too_long");
+    assert_eq!(result.errors[0].message, expected_message);
+
+    Ok(())
+}
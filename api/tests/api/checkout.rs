@@ -0,0 +1,398 @@
+use rstest::rstest;
+
+use std::sync::Arc;
+
+use tower::util::ServiceExt;
+
+use chrono::{Duration, Utc};
+use kernel::{
+    model::{
+        book::{Author, BookId, Isbn, Title},
+        book_copy::{Barcode, BookCopyId},
+        checkout::{Checkout, CheckoutBook, CheckoutId},
+        list::CursorPage,
+        user::UserId,
+    },
+    repository::checkout::{CheckoutRepositoryError, MockCheckoutRepository},
+};
+use uuid::Uuid;
+
+use api::model::{
+    checkout::{CheckoutResponse, CheckoutsResponse},
+    list::CursorPageResponse,
+};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+
+use crate::{
+    deserialize_json,
+    helper::{fixture, make_router, v1, TestRequestExt},
+};
+
+fn checkout(book_id: BookId, overdue: bool) -> Checkout {
+    let checked_out_at = Utc::now() - Duration::days(20);
+    let due_at = if overdue {
+        checked_out_at + Duration::days(14)
+    } else {
+        checked_out_at + Duration::days(28)
+    };
+
+    Checkout::new(
+        CheckoutId::new(Uuid::new_v4()),
+        UserId::new(Uuid::new_v4()),
+        checked_out_at,
+        due_at,
+        0,
+        None,
+        CheckoutBook::new(
+            book_id,
+            Title::try_from("RustによるWebアプリケーション開発".to_string()).unwrap(),
+            Author::try_from("Yuki Toyoda".to_string()).unwrap(),
+            Some(Isbn::try_from("978-4-06-536957-9".to_string()).unwrap()),
+            BookCopyId::new(Uuid::new_v4()),
+            Barcode::try_from("C000000001".to_string()).unwrap(),
+        ),
+    )
+}
+
+#[rstest]
+#[case(None, StatusCode::CREATED)]
+#[case(
+    Some(CheckoutRepositoryError::BookNotFound(BookId::new(Uuid::new_v4()))),
+    StatusCode::NOT_FOUND
+)]
+#[case(
+    Some(CheckoutRepositoryError::NoAvailableCopy(BookId::new(Uuid::new_v4()))),
+    StatusCode::CONFLICT
+)]
+#[case(
+    Some(CheckoutRepositoryError::ReservedForOthers(BookId::new(Uuid::new_v4()))),
+    StatusCode::CONFLICT
+)]
+#[case(
+    Some(CheckoutRepositoryError::LoanLimitReached(UserId::new(Uuid::new_v4()), 5)),
+    StatusCode::CONFLICT
+)]
+#[case(
+    Some(CheckoutRepositoryError::HasOverdueLoans(UserId::new(Uuid::new_v4()))),
+    StatusCode::CONFLICT
+)]
+#[case(
+    Some(CheckoutRepositoryError::OwnBook(
+        BookId::new(Uuid::new_v4()),
+        UserId::new(Uuid::new_v4())
+    )),
+    StatusCode::UNPROCESSABLE_ENTITY
+)]
+#[case(
+    Some(CheckoutRepositoryError::Unexpected("connection reset".into())),
+    StatusCode::INTERNAL_SERVER_ERROR
+)]
+#[tokio::test]
+async fn checkout_book(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] error: Option<CheckoutRepositoryError>,
+    #[case] expected: StatusCode,
+) -> anyhow::Result<()> {
+    let book_id = Uuid::new_v4();
+
+    // 冊を指定しない貸出として repository に渡されることを検証する
+    fixture.expect_checkout_repository().return_once(move || {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_create()
+            .withf(move |event| {
+                event.book_id == BookId::new(book_id) && event.book_copy_id.is_none()
+            })
+            .return_once(move |_| error.map_or(Ok(()), Err));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(&v1(&format!("/books/{book_id}/checkouts")))
+        .bearer()
+        .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), expected);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(None, StatusCode::CREATED)]
+#[case(
+    Some(CheckoutRepositoryError::BookCopyNotFound(BookCopyId::new(Uuid::new_v4()))),
+    StatusCode::NOT_FOUND
+)]
+#[case(
+    Some(CheckoutRepositoryError::BookCopyAlreadyCheckedOut(BookCopyId::new(Uuid::new_v4()))),
+    StatusCode::CONFLICT
+)]
+#[tokio::test]
+async fn checkout_book_copy(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] error: Option<CheckoutRepositoryError>,
+    #[case] expected: StatusCode,
+) -> anyhow::Result<()> {
+    let book_id = Uuid::new_v4();
+    let book_copy_id = Uuid::new_v4();
+
+    fixture.expect_checkout_repository().return_once(move || {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_create()
+            .withf(move |event| event.book_copy_id == Some(BookCopyId::new(book_copy_id)))
+            .return_once(move |_| error.map_or(Ok(()), Err));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(&v1(&format!(
+        "/books/{book_id}/copies/{book_copy_id}/checkouts"
+    )))
+    .bearer()
+    .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), expected);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(None, StatusCode::NO_CONTENT)]
+#[case(
+    Some(CheckoutRepositoryError::BookNotFound(BookId::new(Uuid::new_v4()))),
+    StatusCode::NOT_FOUND
+)]
+#[case(
+    Some(CheckoutRepositoryError::NoResourceAffected("no checkout".to_string())),
+    StatusCode::NOT_FOUND
+)]
+#[case(
+    Some(CheckoutRepositoryError::CannotReturn(
+        BookId::new(Uuid::new_v4()),
+        UserId::new(Uuid::new_v4()),
+        CheckoutId::new(Uuid::new_v4())
+    )),
+    StatusCode::FORBIDDEN
+)]
+#[tokio::test]
+async fn return_book(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] error: Option<CheckoutRepositoryError>,
+    #[case] expected: StatusCode,
+) -> anyhow::Result<()> {
+    let book_id = Uuid::new_v4();
+    let checkout_id = Uuid::new_v4();
+
+    fixture.expect_checkout_repository().return_once(move || {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_update_returned()
+            .withf(move |event| {
+                event.book_id == BookId::new(book_id)
+                    && event.checkout_id == CheckoutId::new(checkout_id)
+            })
+            .return_once(move |_| error.map_or(Ok(()), Err));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::put(&v1(&format!(
+        "/books/{book_id}/checkouts/{checkout_id}/returned"
+    )))
+    .bearer()
+    .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), expected);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(None, StatusCode::NO_CONTENT)]
+#[case(
+    Some(CheckoutRepositoryError::RenewalLimitReached(CheckoutId::new(Uuid::new_v4()))),
+    StatusCode::CONFLICT
+)]
+#[case(
+    Some(CheckoutRepositoryError::RenewalBlockedByReservation(BookId::new(Uuid::new_v4()))),
+    StatusCode::CONFLICT
+)]
+#[case(
+    Some(CheckoutRepositoryError::CannotRenew(
+        BookId::new(Uuid::new_v4()),
+        UserId::new(Uuid::new_v4()),
+        CheckoutId::new(Uuid::new_v4())
+    )),
+    StatusCode::FORBIDDEN
+)]
+#[tokio::test]
+async fn renew_checkout(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] error: Option<CheckoutRepositoryError>,
+    #[case] expected: StatusCode,
+) -> anyhow::Result<()> {
+    let book_id = Uuid::new_v4();
+    let checkout_id = Uuid::new_v4();
+
+    fixture.expect_checkout_repository().return_once(move || {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_renew()
+            .withf(move |event| event.checkout_id == CheckoutId::new(checkout_id))
+            .return_once(move |_| error.map_or(Ok(()), Err));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::put(&v1(&format!(
+        "/books/{book_id}/checkouts/{checkout_id}/renewal"
+    )))
+    .bearer()
+    .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), expected);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn checkout_history_200(mut fixture: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    let book_id = Uuid::new_v4();
+
+    fixture.expect_checkout_repository().returning(move || {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_find_history_by_book_id()
+            .withf(move |id| id == &BookId::new(book_id))
+            .returning(|id| Ok(vec![checkout(id.clone(), false)]));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1(&format!("/books/{book_id}/checkout-history")))
+        .bearer()
+        .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let result = deserialize_json!(res, CheckoutsResponse);
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(result.items[0].book.id, book_id);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn checkout_history_404(mut fixture: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_checkout_repository().returning(|| {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_find_history_by_book_id()
+            .returning(|id| Err(CheckoutRepositoryError::BookNotFound(id.clone())));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1(&format!("/books/{}/checkout-history", Uuid::new_v4())))
+        .bearer()
+        .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/books/checkouts", None)]
+#[case("/books/checkouts?overdue=true", Some(true))]
+#[case("/books/checkouts?overdue=false", Some(false))]
+#[tokio::test]
+async fn show_checked_out_list_200(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] path: &str,
+    #[case] expected_overdue: Option<bool>,
+) -> anyhow::Result<()> {
+    // クエリパラメータが絞り込み条件として repository に渡されることを検証する
+    fixture.expect_checkout_repository().returning(move || {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_find_unreturned_all()
+            .withf(move |opt| opt.filter.overdue == expected_overdue && opt.after.is_none())
+            .returning(|opt| {
+                Ok(CursorPage {
+                    limit: opt.limit,
+                    items: vec![checkout(BookId::new(Uuid::new_v4()), true)],
+                    next_cursor: None,
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let result = deserialize_json!(res, CursorPageResponse<CheckoutResponse>);
+    assert_eq!(result.limit, 20);
+    assert_eq!(result.items.len(), 1);
+    assert!(result.items[0].due_at < Utc::now());
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/books/checkouts?limit=0")]
+#[case("/books/checkouts?limit=101")]
+#[case("/books/checkouts?overdue=maybe")]
+#[case("/books/checkouts?after=not-a-cursor")]
+#[tokio::test]
+async fn show_checked_out_list_400(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] path: &str,
+) -> anyhow::Result<()> {
+    fixture.expect_checkout_repository().returning(|| {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_find_unreturned_all().never();
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_my_checkouts_200(mut fixture: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_checkout_repository().returning(|| {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_find_unreturned_by_user_id()
+            .returning(|_| Ok(vec![checkout(BookId::new(Uuid::new_v4()), false)]));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(&v1("/users/me/checkouts"))
+        .bearer()
+        .body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let result = deserialize_json!(res, CheckoutsResponse);
+    assert_eq!(result.items.len(), 1);
+    assert!(result.items[0].due_at > Utc::now());
+
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use kernel::{
+    model::{
+        user::{User, UserId, UserRole},
+        value_object::ValueObject,
+    },
+    repository::{
+        email_change::{EmailChangeRepositoryError, MockEmailChangeRepository},
+        user::UserRepositoryError,
+    },
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use serde_json::Value;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{
+        fixture, fixture_auth, fixture_registry, make_router, mock_user_repository, v1,
+        TestRequestExt,
+    },
+};
+
+#[rstest]
+#[tokio::test]
+async fn update_current_user_name(mut fixture_auth: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture_auth.expect_user_repository().returning(|| {
+        let mut mock = mock_user_repository(UserRole::User);
+        mock.expect_update_name()
+            .withf(|event| event.name.inner_ref() == "Alice Liddell")
+            .returning(|event| {
+                Ok(User::new(
+                    event.user_id,
+                    event.name,
+                    UserRole::User,
+                    "alice@example.com".parse().unwrap(),
+                ))
+            });
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::patch(&v1("/users/me"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"name":"  Alice Liddell  "}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["name"], "Alice Liddell");
+    assert_eq!(body["email"], "alice@example.com");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn update_current_user_blank_name_400(fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    let app = make_router(fixture);
+
+    let req = Request::patch(&v1("/users/me"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"name":"   "}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.errors.first().map(|e| e.field.as_str()), Some("name"));
+
+    Ok(())
+}
+
+#[rstest]
+#[case(Ok(()), StatusCode::ACCEPTED)]
+#[case(
+    Err(EmailChangeRepositoryError::EmailAlreadyRegistered),
+    StatusCode::CONFLICT
+)]
+#[tokio::test]
+async fn request_email_change(
+    mut fixture: MockAppRegistryExt,
+    #[case] result: Result<(), EmailChangeRepositoryError>,
+    #[case] expected: StatusCode,
+) -> anyhow::Result<()> {
+    let result = std::sync::Mutex::new(Some(result));
+    let mut mock = MockEmailChangeRepository::new();
+    mock.expect_request()
+        .withf(|event| event.new_email.to_string() == "alice@library.example.com")
+        .times(1)
+        .returning(move |_| result.lock().unwrap().take().unwrap());
+    let mock = Arc::new(mock);
+    fixture
+        .expect_email_change_repository()
+        .returning(move || mock.clone());
+    let app = make_router(fixture);
+
+    let req = Request::post(&v1("/users/me/email"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"email":"alice@library.example.com"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected);
+
+    if expected == StatusCode::CONFLICT {
+        let body = deserialize_json!(resp, ProblemDetails);
+        assert_eq!(body.code, "email_already_registered");
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[case(None, StatusCode::NO_CONTENT, None)]
+#[case(
+    Some(EmailChangeRepositoryError::InvalidToken),
+    StatusCode::BAD_REQUEST,
+    Some("invalid_email_change_token")
+)]
+#[case(
+    Some(EmailChangeRepositoryError::EmailAlreadyRegistered),
+    StatusCode::CONFLICT,
+    Some("email_already_registered")
+)]
+#[tokio::test]
+async fn confirm_email_change(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] error: Option<EmailChangeRepositoryError>,
+    #[case] expected_status: StatusCode,
+    #[case] expected_code: Option<&str>,
+) -> anyhow::Result<()> {
+    let error = std::sync::Mutex::new(error);
+    let mut mock = MockEmailChangeRepository::new();
+    mock.expect_confirm()
+        .withf(|event| event.token.inner_ref() == "change-token")
+        .returning(move |_| match error.lock().unwrap().take() {
+            Some(e) => Err(e),
+            None => Ok(User::new(
+                UserId::new(uuid::Uuid::new_v4()),
+                "Alice".to_string().try_into().unwrap(),
+                UserRole::User,
+                "alice@library.example.com".parse().unwrap(),
+            )),
+        });
+    let mock = Arc::new(mock);
+    fixture_registry
+        .expect_email_change_repository()
+        .returning(move || mock.clone());
+    let app = make_router(fixture_registry);
+
+    let req = Request::post("/auth/email-change/confirm")
+        .application_json()
+        .body(Body::from(r#"{"token":"change-token"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    if let Some(expected_code) = expected_code {
+        let body = deserialize_json!(resp, ProblemDetails);
+        assert_eq!(body.code, expected_code);
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn register_user_with_registered_email_409(
+    mut fixture_auth: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_auth.expect_user_repository().returning(|| {
+        let mut mock = mock_user_repository(UserRole::Admin);
+        mock.expect_create()
+            .returning(|_| Err(UserRepositoryError::EmailAlreadyRegistered));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post(&v1("/users"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            r#"{"name":"Alice","email":"alice@example.com","password":"a-new-password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "email_already_registered");
+
+    Ok(())
+}
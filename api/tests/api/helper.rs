@@ -1,10 +1,17 @@
-use std::{str::FromStr, sync::Arc};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
 
-use api::route::{auth, v1};
-use axum::{http::request::Builder, Router};
+use api::{
+    middleware::{locale, request_id},
+    route::{auth, v1, well_known},
+};
+use axum::{extract::connect_info::MockConnectInfo, http::request::Builder, middleware, Router};
 use kernel::{
     model::{
-        auth::AccessToken,
+        auth::{AccessToken, AuthTokens, RefreshToken, TokenSubject},
         user::{User, UserEmail, UserId, UserName, UserRole},
     },
     repository::{auth::MockAuthRepository, user::MockUserRepository},
@@ -17,10 +24,17 @@ pub fn v1(endpoint: &str) -> String {
     format!("/api/v1{}", endpoint)
 }
 
+// テストのリクエストの接続元
+pub const PEER_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+
 pub fn make_router(registry: MockAppRegistryExt) -> Router {
     Router::new()
         .merge(v1::routes())
         .merge(auth::build_auth_routers())
+        .merge(well_known::build_well_known_routers())
+        .layer(middleware::from_fn(locale))
+        .layer(middleware::from_fn(request_id))
+        .layer(MockConnectInfo(SocketAddr::new(PEER_IP, 50000)))
         .with_state(Arc::new(registry))
 }
 
@@ -29,40 +43,67 @@ pub fn fixture_registry() -> MockAppRegistryExt {
     MockAppRegistryExt::new()
 }
 
+// 任意のアクセストークンを認証済みとして扱う AuthRepository のモック。
+// テストごとに期待値を追加して使う
+pub fn mock_auth_repository() -> MockAuthRepository {
+    let mut mock_auth_repository = MockAuthRepository::new();
+    mock_auth_repository
+        .expect_fetch_token_subject()
+        .returning(|_| {
+            Ok(Some(TokenSubject {
+                user_id: UserId::new(Uuid::new_v4()),
+                role: None,
+            }))
+        });
+    mock_auth_repository
+        .expect_verify_user()
+        .returning(|_, _| Ok(UserId::new(Uuid::new_v4())));
+    mock_auth_repository
+        .expect_create_token()
+        .returning(|event| {
+            Ok(AuthTokens {
+                user_id: event.user_id,
+                access_token: AccessToken::new("dummy".to_string()),
+                refresh_token: RefreshToken::new("dummy-refresh".to_string()),
+                expires_in: 900,
+            })
+        });
+    mock_auth_repository
+}
+
+// アクセストークンに紐づくユーザーを指定したロールのユーザーとして返す UserRepository のモック
+pub fn mock_user_repository(role: UserRole) -> MockUserRepository {
+    let mut mock_user_repository = MockUserRepository::new();
+    mock_user_repository
+        .expect_find_current_user()
+        .returning(move |id| {
+            Ok(Some(User::new(
+                id.clone(),
+                UserName::try_from("dummy-user".to_string()).unwrap(),
+                role.clone(),
+                UserEmail::from_str("dummy@example.com").unwrap(),
+            )))
+        });
+    mock_user_repository
+}
+
+pub fn expect_current_user(registry: &mut MockAppRegistryExt, role: UserRole) {
+    registry
+        .expect_user_repository()
+        .returning(move || Arc::new(mock_user_repository(role.clone())));
+}
+
 #[fixture]
 pub fn fixture_auth(mut fixture_registry: MockAppRegistryExt) -> MockAppRegistryExt {
-    fixture_registry.expect_auth_repository().returning(|| {
-        let mut mock_auth_repository = MockAuthRepository::new();
-        mock_auth_repository
-            .expect_fetch_user_id_from_token()
-            .returning(|_| Ok(Some(UserId::new(Uuid::new_v4()))));
-        mock_auth_repository
-            .expect_verify_user()
-            .returning(|_, _| Ok(UserId::new(Uuid::new_v4())));
-        mock_auth_repository
-            .expect_create_token()
-            .returning(|_| Ok(AccessToken::new("dummy".to_string())));
-        Arc::new(mock_auth_repository)
-    });
+    fixture_registry
+        .expect_auth_repository()
+        .returning(|| Arc::new(mock_auth_repository()));
     fixture_registry
 }
 
 #[fixture]
 pub fn fixture(mut fixture_auth: MockAppRegistryExt) -> MockAppRegistryExt {
-    fixture_auth.expect_user_repository().returning(|| {
-        let mut mock_user_repository = MockUserRepository::new();
-        mock_user_repository
-            .expect_find_current_user()
-            .returning(|id| {
-                Ok(Some(User::new(
-                    id.clone(),
-                    UserName::new("dummy-user".to_string()),
-                    UserRole::User,
-                    UserEmail::from_str("dummy@example.com").unwrap(),
-                )))
-            });
-        Arc::new(mock_user_repository)
-    });
+    expect_current_user(&mut fixture_auth, UserRole::User);
     fixture_auth
 }
 
@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::{Duration, Utc};
+use kernel::{
+    model::{
+        invitation::{Invitation, InvitationId},
+        user::{password::PasswordPolicyError, User, UserId, UserRole},
+        value_object::ValueObject,
+    },
+    repository::invitation::{InvitationRepositoryError, MockInvitationRepository},
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use serde_json::Value;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    deserialize_json,
+    helper::{
+        expect_current_user, fixture_auth, fixture_registry, make_router, v1, TestRequestExt,
+    },
+};
+
+fn invitation(role: UserRole) -> Invitation {
+    Invitation::new(
+        InvitationId::new(Uuid::new_v4()),
+        "dave@example.com".parse().unwrap(),
+        role,
+        Some(UserId::new(Uuid::new_v4())),
+        Utc::now() + Duration::days(7),
+        Utc::now(),
+    )
+}
+
+#[rstest]
+#[case(UserRole::Admin, StatusCode::CREATED)]
+#[case(UserRole::User, StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn create_invitation_requires_admin(
+    mut fixture_auth: MockAppRegistryExt,
+    #[case] role: UserRole,
+    #[case] expected: StatusCode,
+) -> anyhow::Result<()> {
+    expect_current_user(&mut fixture_auth, role);
+    fixture_auth.expect_invitation_repository().returning(|| {
+        let mut mock = MockInvitationRepository::new();
+        mock.expect_create()
+            .withf(|event| {
+                event.email.to_string() == "dave@example.com" && event.role == UserRole::Admin
+            })
+            .returning(|event| Ok(invitation(event.role)));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post(&v1("/invitations"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"email":"dave@example.com","role":"Admin"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected);
+
+    if expected == StatusCode::CREATED {
+        let body = deserialize_json!(resp, Value);
+        assert_eq!(body["email"], "dave@example.com");
+        assert_eq!(body["role"], "Admin");
+        assert!(body.get("token").is_none());
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn create_invitation_for_registered_email_409(
+    mut fixture_auth: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    expect_current_user(&mut fixture_auth, UserRole::Admin);
+    fixture_auth.expect_invitation_repository().returning(|| {
+        let mut mock = MockInvitationRepository::new();
+        mock.expect_create()
+            .returning(|_| Err(InvitationRepositoryError::EmailAlreadyRegistered));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post(&v1("/invitations"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"email":"carol@example.com","role":"User"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "email_already_registered");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn list_and_delete_invitations(mut fixture_auth: MockAppRegistryExt) -> anyhow::Result<()> {
+    expect_current_user(&mut fixture_auth, UserRole::Admin);
+    let pending = invitation(UserRole::User);
+    let invitation_id = *pending.invitation_id().inner_ref();
+    fixture_auth
+        .expect_invitation_repository()
+        .returning(move || {
+            let mut mock = MockInvitationRepository::new();
+            mock.expect_find_pending()
+                .returning(|| Ok(vec![invitation(UserRole::User)]));
+            mock.expect_delete()
+                .withf(move |event| *event.invitation_id.inner_ref() == invitation_id)
+                .returning(|_| Ok(()));
+            mock.expect_delete()
+                .returning(|_| Err(InvitationRepositoryError::NotFound));
+            Arc::new(mock)
+        });
+    let app = make_router(fixture_auth);
+
+    let req = Request::get(&v1("/invitations"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.clone().oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["items"].as_array().map(Vec::len), Some(1));
+
+    let req = Request::delete(&v1(&format!("/invitations/{invitation_id}")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.clone().oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let req = Request::delete(&v1(&format!("/invitations/{}", Uuid::new_v4())))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "invitation_not_found");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn accept_invitation_201(mut fixture_registry: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_invitation_repository()
+        .returning(|| {
+            let mut mock = MockInvitationRepository::new();
+            mock.expect_accept()
+                .withf(|event| {
+                    event.token.to_string() == "invite-token" && event.name.inner_ref() == "Dave"
+                })
+                .returning(|event| {
+                    Ok(User::new(
+                        UserId::new(Uuid::new_v4()),
+                        event.name,
+                        UserRole::User,
+                        "dave@example.com".parse().unwrap(),
+                    ))
+                });
+            Arc::new(mock)
+        });
+    let app = make_router(fixture_registry);
+
+    let req = Request::post("/auth/invitations/invite-token/accept")
+        .application_json()
+        .body(Body::from(r#"{"name":"Dave","password":"a-new-password"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["email"], "dave@example.com");
+    assert_eq!(body["name"], "Dave");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    InvitationRepositoryError::InvalidToken,
+    StatusCode::BAD_REQUEST,
+    "invalid_invitation_token",
+    None
+)]
+#[case(
+    InvitationRepositoryError::WeakPassword(PasswordPolicyError::Common),
+    StatusCode::BAD_REQUEST,
+    "validation_failed",
+    Some("password")
+)]
+#[case(
+    InvitationRepositoryError::EmailAlreadyRegistered,
+    StatusCode::CONFLICT,
+    "email_already_registered",
+    None
+)]
+#[tokio::test]
+async fn accept_invitation_error(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] error: InvitationRepositoryError,
+    #[case] expected_status: StatusCode,
+    #[case] expected_code: &str,
+    #[case] expected_field: Option<&str>,
+) -> anyhow::Result<()> {
+    let error = std::sync::Mutex::new(Some(error));
+    let mut mock = MockInvitationRepository::new();
+    mock.expect_accept()
+        .returning(move |_| Err(error.lock().unwrap().take().unwrap()));
+    let mock = Arc::new(mock);
+    fixture_registry
+        .expect_invitation_repository()
+        .returning(move || mock.clone());
+    let app = make_router(fixture_registry);
+
+    let req = Request::post("/auth/invitations/invite-token/accept")
+        .application_json()
+        .body(Body::from(r#"{"name":"Dave","password":"password"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, expected_code);
+    assert_eq!(
+        body.errors.first().map(|e| e.field.as_str()),
+        expected_field
+    );
+
+    Ok(())
+}
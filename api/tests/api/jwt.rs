@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use kernel::{
+    model::{
+        auth::{
+            jwk::{PublicJwk, PublicKey},
+            TokenSubject,
+        },
+        user::{UserId, UserRole},
+    },
+    repository::{
+        api_key::MockApiKeyRepository, auth::MockAuthRepository, user::MockUserRepository,
+    },
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use serde_json::{json, Value};
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router, v1, TestRequestExt},
+};
+
+// ロールを含む署名付きトークンとして認証する AuthRepository のモック
+fn signed_token(registry: &mut MockAppRegistryExt, role: UserRole) {
+    registry.expect_auth_repository().returning(move || {
+        let role = role.clone();
+        let mut mock = MockAuthRepository::new();
+        mock.expect_fetch_token_subject().returning(move |_| {
+            Ok(Some(TokenSubject {
+                user_id: UserId::new(Uuid::new_v4()),
+                role: Some(role.clone()),
+            }))
+        });
+        mock.expect_delete_all_sessions().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+    registry.expect_api_key_repository().returning(|| {
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_delete_all().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+}
+
+#[rstest]
+#[case(UserRole::Admin, StatusCode::NO_CONTENT)]
+#[case(UserRole::User, StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn signed_token_role_is_used_without_user_lookup(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] role: UserRole,
+    #[case] expected_status: StatusCode,
+) -> anyhow::Result<()> {
+    // ユーザーを取得しようとするとモックの期待値がなく失敗する
+    signed_token(&mut fixture_registry, role);
+    fixture_registry.expect_user_repository().never();
+    let app = make_router(fixture_registry);
+
+    let req = Request::delete(&v1(&format!("/users/{}/sessions", Uuid::new_v4())))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn get_current_user_404_when_user_was_deleted(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    signed_token(&mut fixture_registry, UserRole::User);
+    fixture_registry.expect_user_repository().returning(|| {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(|_| Ok(None));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_registry);
+
+    let req = Request::get(&v1("/users/me"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "user_not_found");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn jwks_200(mut fixture_registry: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_public_keys().returning(|| {
+            vec![
+                PublicJwk {
+                    kid: "2025-01".into(),
+                    key: PublicKey::Ed25519 { x: "ed-x".into() },
+                },
+                PublicJwk {
+                    kid: "2024-07".into(),
+                    key: PublicKey::P256 {
+                        x: "ec-x".into(),
+                        y: "ec-y".into(),
+                    },
+                },
+            ]
+        });
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_registry);
+
+    let req = Request::get("/.well-known/jwks.json").body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().contains_key(header::CACHE_CONTROL));
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(
+        body,
+        json!({
+            "keys": [
+                {"kty": "OKP", "crv": "Ed25519", "x": "ed-x", "kid": "2025-01", "alg": "EdDSA", "use": "sig"},
+                {"kty": "EC", "crv": "P-256", "x": "ec-x", "y": "ec-y", "kid": "2024-07", "alg": "ES256", "use": "sig"},
+            ]
+        })
+    );
+
+    Ok(())
+}
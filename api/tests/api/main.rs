@@ -1,2 +1,15 @@
+mod api_key;
+mod auth;
 mod book;
+mod checkout;
+mod email_change;
 mod helper;
+mod invitation;
+mod jwt;
+mod oidc;
+mod openapi;
+mod password;
+mod password_reset;
+mod rejection;
+mod session;
+mod totp;
@@ -0,0 +1,276 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use kernel::{
+    model::{
+        auth::{
+            oidc::{OidcAuthorization, OidcLogin},
+            totp::{TotpRequirement, TotpSetup},
+        },
+        user::{UserEmail, UserId},
+        value_object::ValueObject,
+    },
+    repository::{
+        oidc::{MockOidcRepository, OidcRepositoryError},
+        totp::MockTotpRepository,
+    },
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use serde_json::Value;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router, mock_auth_repository},
+};
+
+#[rstest]
+#[tokio::test]
+async fn oidc_login_303(mut fixture_registry: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture_registry.expect_oidc_repository().returning(|| {
+        let mut mock = MockOidcRepository::new();
+        mock.expect_begin_login().returning(|| {
+            Ok(OidcAuthorization {
+                authorization_url: "https://idp.example.com/authorize?state=abc".into(),
+            })
+        });
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_registry);
+
+    let req = Request::get("/auth/oidc/login").body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+    assert_eq!(
+        resp.headers()[header::LOCATION],
+        "https://idp.example.com/authorize?state=abc"
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn oidc_login_404_when_not_configured(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_registry.expect_oidc_repository().returning(|| {
+        let mut mock = MockOidcRepository::new();
+        mock.expect_begin_login()
+            .returning(|| Err(OidcRepositoryError::NotConfigured));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_registry);
+
+    let req = Request::get("/auth/oidc/login").body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "oidc_not_configured");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(false, 0)]
+#[case(true, 1)]
+#[tokio::test]
+async fn oidc_callback_200(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] role_changed: bool,
+    #[case] expected_revocations: usize,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new(Uuid::new_v4());
+    let revocations = Arc::new(AtomicUsize::new(0));
+
+    let login_user_id = user_id.clone();
+    fixture_registry
+        .expect_oidc_repository()
+        .returning(move || {
+            let user_id = login_user_id.clone();
+            let mut mock = MockOidcRepository::new();
+            mock.expect_complete_login()
+                .withf(|event| {
+                    event.state.inner_ref() == "abc" && event.code.inner_ref() == "code-1"
+                })
+                .returning(move |_| {
+                    Ok(OidcLogin {
+                        user_id: user_id.clone(),
+                        email: UserEmail::from_str("alice@example.com").unwrap(),
+                        role_changed,
+                    })
+                });
+            Arc::new(mock)
+        });
+    let counter = revocations.clone();
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let counter = counter.clone();
+            let mut mock = mock_auth_repository();
+            mock.expect_delete_all_sessions().returning(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+            Arc::new(mock)
+        });
+    fixture_registry.expect_totp_repository().returning(|| {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_find_requirement()
+            .returning(|_| Ok(TotpRequirement::Disabled));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_registry);
+
+    let req = Request::get("/auth/oidc/callback?code=code-1&state=abc").body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(revocations.load(Ordering::SeqCst), expected_revocations);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["userId"], user_id.inner_ref().to_string());
+    assert_eq!(body["accessToken"], "dummy");
+
+    Ok(())
+}
+
+// 二要素認証を有効にしているユーザーや必須の管理者には、IdP でのログイン後も TOTP を求める
+#[rstest]
+#[case(TotpRequirement::Enabled, false)]
+#[case(TotpRequirement::SetupRequired, true)]
+#[tokio::test]
+async fn oidc_callback_200_requires_second_factor(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] requirement: TotpRequirement,
+    #[case] setup_required: bool,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new(Uuid::new_v4());
+
+    let login_user_id = user_id.clone();
+    fixture_registry
+        .expect_oidc_repository()
+        .returning(move || {
+            let user_id = login_user_id.clone();
+            let mut mock = MockOidcRepository::new();
+            mock.expect_complete_login().returning(move |_| {
+                Ok(OidcLogin {
+                    user_id: user_id.clone(),
+                    email: UserEmail::from_str("alice@example.com").unwrap(),
+                    role_changed: false,
+                })
+            });
+            Arc::new(mock)
+        });
+    fixture_registry
+        .expect_auth_repository()
+        .returning(|| Arc::new(mock_auth_repository()));
+    fixture_registry
+        .expect_totp_repository()
+        .returning(move || {
+            let user_id = user_id.clone();
+            let mut mock = MockTotpRepository::new();
+            mock.expect_find_requirement()
+                .returning(move |_| Ok(requirement));
+            mock.expect_create_secret().returning(|_| {
+                Ok(TotpSetup {
+                    secret: "JBSWY3DPEHPK3PXP".into(),
+                    otpauth_uri: "otpauth://totp/dummy".into(),
+                })
+            });
+            mock.expect_create_challenge()
+                .withf(move |event| {
+                    event.user_id == user_id
+                        && event.email.to_string() == "alice@example.com"
+                        && event.setup_required == setup_required
+                })
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+    let app = make_router(fixture_registry);
+
+    let req = Request::get("/auth/oidc/callback?code=code-1&state=abc").body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = deserialize_json!(resp, Value);
+    assert!(body.get("accessToken").is_none());
+    assert!(body["mfaToken"].is_string());
+    assert_eq!(body.get("setup").is_some(), setup_required);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    "error=access_denied&state=abc",
+    None,
+    StatusCode::UNAUTHORIZED,
+    "oidc_login_denied"
+)]
+#[case("state=abc", None, StatusCode::BAD_REQUEST, "validation_failed")]
+#[case(
+    "code=code-1&state=expired",
+    Some(OidcRepositoryError::InvalidState),
+    StatusCode::UNAUTHORIZED,
+    "invalid_oidc_state"
+)]
+#[case(
+    "code=code-1&state=abc",
+    Some(OidcRepositoryError::InvalidIdToken("nonce does not match".into())),
+    StatusCode::UNAUTHORIZED,
+    "invalid_id_token"
+)]
+#[case(
+    "code=code-1&state=abc",
+    Some(OidcRepositoryError::EmailNotVerified),
+    StatusCode::FORBIDDEN,
+    "oidc_email_not_verified"
+)]
+#[case(
+    "code=code-1&state=abc",
+    Some(OidcRepositoryError::IdentityProvider("connection refused".into())),
+    StatusCode::BAD_GATEWAY,
+    "oidc_provider_error"
+)]
+#[tokio::test]
+async fn oidc_callback_error(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] query: &str,
+    #[case] error: Option<OidcRepositoryError>,
+    #[case] expected_status: StatusCode,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    if let Some(error) = error {
+        fixture_registry
+            .expect_oidc_repository()
+            .return_once(move || {
+                let mut mock = MockOidcRepository::new();
+                mock.expect_complete_login()
+                    .return_once(move |_| Err(error));
+                Arc::new(mock)
+            });
+    }
+    let app = make_router(fixture_registry);
+
+    let req = Request::get(format!("/auth/oidc/callback?{query}")).body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, expected_code);
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+
+use api::openapi::ApiDoc;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use rstest::rstest;
+use tower::util::ServiceExt;
+use utoipa::OpenApi;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router, v1},
+};
+
+// OpenAPI の定義そのものを配信するルートは定義に含めない
+const UNDOCUMENTED_PATHS: &[&str] = &["/api/v1/openapi.json", "/api/v1/docs"];
+
+#[rstest]
+#[tokio::test]
+async fn show_openapi_200(fixture_registry: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    let app: Router = make_router(fixture_registry);
+
+    let req = Request::get(&v1("/openapi.json")).body(Body::empty())?;
+    let res = app.oneshot(req).await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let doc = deserialize_json!(res, serde_json::Value);
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    assert_eq!(
+        doc["components"]["securitySchemes"]["bearer"]["scheme"],
+        "bearer"
+    );
+    assert!(doc["components"]["schemas"]["CreateBookRequest"].is_object());
+    assert!(doc["components"]["responses"]["ProblemDetails"].is_object());
+    assert!(doc["components"]["schemas"]["FieldError"].is_object());
+
+    Ok(())
+}
+
+#[rstest]
+fn every_route_is_documented(fixture_registry: registry::MockAppRegistryExt) {
+    let routes = registered_routes(&make_router(fixture_registry));
+    assert!(!routes.is_empty(), "failed to list routes from the router");
+
+    let documented = documented_routes();
+
+    let undocumented = routes.difference(&documented).collect::<Vec<_>>();
+    assert!(
+        undocumented.is_empty(),
+        "routes missing from the OpenAPI document: {undocumented:?}"
+    );
+    let unknown = documented.difference(&routes).collect::<Vec<_>>();
+    assert!(
+        unknown.is_empty(),
+        "OpenAPI document has routes the router does not serve: {unknown:?}"
+    );
+}
+
+// ハンドラーが返すエラーのステータスを定義にも載せる
+#[rstest]
+#[case("/api/v1/books/{book_id}", "put", &["400", "401", "403", "404"])]
+#[case("/api/v1/books/{book_id}", "delete", &["400", "401", "403", "404"])]
+#[case("/api/v1/users/{user_id}/role", "put", &["400", "401", "403", "404"])]
+fn error_responses_are_documented(
+    #[case] path: &str,
+    #[case] method: &str,
+    #[case] expected_statuses: &[&str],
+) -> anyhow::Result<()> {
+    let doc = serde_json::to_value(ApiDoc::openapi())?;
+    let responses = doc["paths"][path][method]["responses"]
+        .as_object()
+        .ok_or(anyhow::anyhow!("{method} {path} is not documented"))?;
+    for status in expected_statuses {
+        assert!(
+            responses.contains_key(*status),
+            "{method} {path} does not document {status}"
+        );
+    }
+
+    Ok(())
+}
+
+// OpenAPI の定義に含まれる (メソッド, パス) の一覧
+fn documented_routes() -> BTreeSet<(String, String)> {
+    ApiDoc::openapi()
+        .paths
+        .paths
+        .iter()
+        .flat_map(|(path, item)| {
+            [
+                ("GET", &item.get),
+                ("POST", &item.post),
+                ("PUT", &item.put),
+                ("DELETE", &item.delete),
+                ("PATCH", &item.patch),
+            ]
+            .into_iter()
+            .filter(|(_, operation)| operation.is_some())
+            .map(|(method, _)| (method.to_string(), path.clone()))
+        })
+        .collect()
+}
+
+// ルーターに登録されている (メソッド, パス) の一覧
+// axum のルーターには登録済みのルートを列挙する API が無いため、Debug 表現から取り出す
+fn registered_routes(router: &Router) -> BTreeSet<(String, String)> {
+    let debug = format!("{router:?}");
+    // フォールバック用のルートは対象外
+    let debug = debug.split("fallback_router").next().unwrap();
+
+    let mut paths = std::collections::HashMap::new();
+    let mut methods = std::collections::HashMap::new();
+    for segment in debug.split("RouteId(").skip(1) {
+        let (id, rest) = segment.split_once(')').unwrap();
+        if let Some(path) = rest.strip_prefix(": \"") {
+            paths.insert(id.to_string(), path.split('"').next().unwrap().to_string());
+        } else if let Some((_, allow)) = rest.split_once("allow_header: Bytes(b\"") {
+            methods.insert(id.to_string(), allow.split('"').next().unwrap().to_string());
+        }
+    }
+
+    paths
+        .into_iter()
+        .filter(|(_, path)| !UNDOCUMENTED_PATHS.iter().any(|p| path.starts_with(p)))
+        .flat_map(|(id, path)| {
+            let path = to_openapi_path(&path);
+            methods[&id]
+                .split(',')
+                // HEAD は GET を登録すると自動的に受け付ける
+                .filter(|method| *method != "HEAD")
+                .map(|method| (method.to_string(), path.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// /books/:book_id を /books/{book_id} の形にそろえる
+fn to_openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(param) => format!("{{{param}}}"),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
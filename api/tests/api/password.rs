@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use kernel::{
+    model::user::{password::PasswordPolicyError, UserRole},
+    repository::user::UserRepositoryError,
+};
+use rstest::rstest;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_auth, make_router, mock_user_repository, v1, TestRequestExt},
+};
+
+// パスワードポリシーに反する場合は、値を受け取ったフィールドの誤りとして返す
+#[rstest]
+#[case(
+    "POST",
+    "/users",
+    r#"{"name":"dummy","email":"dummy@example.com","password":"password123"}"#,
+    "password"
+)]
+#[case(
+    "PUT",
+    "/users/me/password",
+    r#"{"currentPassword":"current-password","newPassword":"password123"}"#,
+    "newPassword"
+)]
+#[tokio::test]
+async fn weak_password_400(
+    mut fixture_auth: registry::MockAppRegistryExt,
+    #[case] method: &str,
+    #[case] path: &str,
+    #[case] body: &'static str,
+    #[case] expected_field: &str,
+) -> anyhow::Result<()> {
+    fixture_auth.expect_user_repository().returning(|| {
+        let mut mock = mock_user_repository(UserRole::Admin);
+        mock.expect_create().returning(|_| {
+            Err(UserRepositoryError::WeakPassword(
+                PasswordPolicyError::Common,
+            ))
+        });
+        mock.expect_update_password().returning(|_| {
+            Err(UserRepositoryError::WeakPassword(
+                PasswordPolicyError::Common,
+            ))
+        });
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::builder()
+        .method(method)
+        .uri(v1(path))
+        .bearer()
+        .application_json()
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.errors.len(), 1);
+    assert_eq!(body.errors[0].field, expected_field);
+    assert_eq!(body.errors[0].code, "common_password");
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use kernel::{
+    model::user::{password::PasswordPolicyError, UserId},
+    repository::{
+        api_key::MockApiKeyRepository,
+        auth::MockAuthRepository,
+        password_reset::{MockPasswordResetRepository, PasswordResetRepositoryError},
+    },
+};
+use rstest::rstest;
+use shared::problem::ProblemDetails;
+use tokio::sync::Notify;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router, TestRequestExt},
+};
+
+#[rstest]
+#[tokio::test]
+async fn request_password_reset_202(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 応答した後にメールの送信を始める
+    let requested = Arc::new(Notify::new());
+    let notify = requested.clone();
+    fixture_registry
+        .expect_password_reset_repository()
+        .returning(move || {
+            let notify = notify.clone();
+            let mut mock = MockPasswordResetRepository::new();
+            mock.expect_request()
+                .withf(|event| event.email.to_string() == "alice@example.com")
+                .returning(move |_| {
+                    notify.notify_one();
+                    Ok(())
+                });
+            Arc::new(mock)
+        });
+    let app = make_router(fixture_registry);
+
+    let req = Request::post("/auth/password-reset/request")
+        .application_json()
+        .body(Body::from(r#"{"email":"alice@example.com"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    tokio::time::timeout(Duration::from_secs(1), requested.notified()).await?;
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn confirm_password_reset_revokes_all_sessions(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = Uuid::new_v4();
+    fixture_registry
+        .expect_password_reset_repository()
+        .returning(move || {
+            let mut mock = MockPasswordResetRepository::new();
+            mock.expect_confirm()
+                .withf(|event| event.token.to_string() == "reset-token")
+                .returning(move |_| Ok(UserId::new(user_id)));
+            Arc::new(mock)
+        });
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let mut mock = MockAuthRepository::new();
+            mock.expect_delete_all_sessions()
+                .withf(move |id| id.to_string() == user_id.to_string())
+                .times(1)
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+    // 乗っ取られていた場合に備え、API キーも削除する
+    fixture_registry
+        .expect_api_key_repository()
+        .returning(move || {
+            let mut mock = MockApiKeyRepository::new();
+            mock.expect_delete_all()
+                .withf(move |id| id.to_string() == user_id.to_string())
+                .times(1)
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+    let app = make_router(fixture_registry);
+
+    let req = Request::post("/auth/password-reset/confirm")
+        .application_json()
+        .body(Body::from(
+            r#"{"token":"reset-token","newPassword":"a-new-password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    PasswordResetRepositoryError::InvalidToken,
+    "invalid_password_reset_token",
+    None
+)]
+#[case(
+    PasswordResetRepositoryError::WeakPassword(PasswordPolicyError::TooShort(8)),
+    "validation_failed",
+    Some("newPassword")
+)]
+#[tokio::test]
+async fn confirm_password_reset_400(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] error: PasswordResetRepositoryError,
+    #[case] expected_code: &str,
+    #[case] expected_field: Option<&str>,
+) -> anyhow::Result<()> {
+    let error = std::sync::Mutex::new(Some(error));
+    let mut mock = MockPasswordResetRepository::new();
+    mock.expect_confirm()
+        .returning(move |_| Err(error.lock().unwrap().take().unwrap()));
+    let mock = Arc::new(mock);
+    fixture_registry
+        .expect_password_reset_repository()
+        .returning(move || mock.clone());
+    let app = make_router(fixture_registry);
+
+    let req = Request::post("/auth/password-reset/confirm")
+        .application_json()
+        .body(Body::from(
+            r#"{"token":"reset-token","newPassword":"short"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, expected_code);
+    assert_eq!(
+        body.errors.first().map(|e| e.field.as_str()),
+        expected_field
+    );
+
+    Ok(())
+}
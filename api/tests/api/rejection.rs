@@ -0,0 +1,71 @@
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture, make_router, v1, TestRequestExt},
+};
+
+// 抽出器が値を取り出せなかった場合も application/problem+json で返す
+#[rstest]
+#[case(
+    Request::get(&v1("/books?sort=unknown")).bearer().body(Body::empty()),
+    StatusCode::BAD_REQUEST,
+    "invalid_query"
+)]
+#[case(
+    Request::get(&v1("/books/not-a-uuid")).bearer().body(Body::empty()),
+    StatusCode::BAD_REQUEST,
+    "invalid_id"
+)]
+#[case(
+    Request::post(&v1("/books"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"title":"#)),
+    StatusCode::BAD_REQUEST,
+    "invalid_request_body"
+)]
+#[case(
+    Request::post(&v1("/books"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"title":"Rust"}"#)),
+    StatusCode::UNPROCESSABLE_ENTITY,
+    "invalid_request_body"
+)]
+#[case(
+    Request::post(&v1("/books"))
+        .bearer()
+        .body(Body::from(r#"{"title":"Rust","author":"Yuki Toyoda","isbn":"9784065369579","description":""}"#)),
+    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+    "unsupported_media_type"
+)]
+#[tokio::test]
+async fn extractor_rejection_is_problem_details(
+    fixture: MockAppRegistryExt,
+    #[case] req: Result<Request<Body>, axum::http::Error>,
+    #[case] expected_status: StatusCode,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    let app = make_router(fixture);
+
+    let resp = app.oneshot(req?).await?;
+    assert_eq!(resp.status(), expected_status);
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE),
+        Some(&"application/problem+json".parse()?)
+    );
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.status, expected_status.as_u16());
+    assert_eq!(body.code, expected_code);
+
+    Ok(())
+}
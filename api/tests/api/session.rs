@@ -0,0 +1,267 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use kernel::{
+    model::{
+        auth::session::{Session, SessionClient, SessionId},
+        user::{UserId, UserRole},
+    },
+    repository::{
+        api_key::MockApiKeyRepository,
+        auth::{AuthRepositoryError, MockAuthRepository},
+    },
+};
+use rstest::rstest;
+use serde_json::Value;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    deserialize_json,
+    helper::{
+        expect_current_user, fixture_registry, make_router, mock_auth_repository,
+        mock_user_repository, v1, TestRequestExt,
+    },
+};
+
+// API キーをすべて削除したユーザーの ID を記録する
+fn record_api_key_revocation(
+    registry: &mut registry::MockAppRegistryExt,
+) -> Arc<std::sync::Mutex<Vec<String>>> {
+    let revoked = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorder = revoked.clone();
+    registry.expect_api_key_repository().returning(move || {
+        let recorder = recorder.clone();
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_delete_all().returning(move |user_id| {
+            recorder.lock().unwrap().push(user_id.to_string());
+            Ok(())
+        });
+        Arc::new(mock)
+    });
+    revoked
+}
+
+fn with_auth_repository(
+    registry: &mut registry::MockAppRegistryExt,
+    setup: impl Fn(&mut MockAuthRepository) + Send + Sync + 'static,
+) {
+    registry.expect_auth_repository().returning(move || {
+        let mut mock = mock_auth_repository();
+        setup(&mut mock);
+        Arc::new(mock)
+    });
+}
+
+#[rstest]
+#[tokio::test]
+async fn list_sessions_200(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    with_auth_repository(&mut fixture_registry, |mock| {
+        mock.expect_find_sessions()
+            .withf(|_, current| current.to_string() == "dummy")
+            .returning(|_, _| {
+                Ok(vec![Session {
+                    session_id: SessionId::new(Uuid::new_v4()),
+                    client: SessionClient {
+                        user_agent: Some("curl/8.5.0".to_string()),
+                        ip_address: Some("192.0.2.1".to_string()),
+                    },
+                    created_at: Utc::now(),
+                    last_used_at: Utc::now(),
+                    current: true,
+                }])
+            });
+    });
+    expect_current_user(&mut fixture_registry, UserRole::User);
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::get(v1("/users/me/sessions"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["items"][0]["userAgent"], "curl/8.5.0");
+    assert_eq!(body["items"][0]["ipAddress"], "192.0.2.1");
+    assert_eq!(body["items"][0]["current"], true);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_session_404(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    with_auth_repository(&mut fixture_registry, |mock| {
+        mock.expect_delete_session()
+            .returning(|_| Err(AuthRepositoryError::SessionNotFound));
+    });
+    expect_current_user(&mut fixture_registry, UserRole::User);
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1(&format!("/users/me/sessions/{}", Uuid::new_v4())))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "session_not_found");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(UserRole::Admin, StatusCode::NO_CONTENT)]
+#[case(UserRole::User, StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn delete_user_sessions(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] role: UserRole,
+    #[case] expected: StatusCode,
+) -> anyhow::Result<()> {
+    let target = Uuid::new_v4();
+    with_auth_repository(&mut fixture_registry, move |mock| {
+        mock.expect_delete_all_sessions()
+            .withf(move |user_id| user_id.to_string() == target.to_string())
+            .returning(|_| Ok(()));
+    });
+    expect_current_user(&mut fixture_registry, role);
+    let revoked = record_api_key_revocation(&mut fixture_registry);
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1(&format!("/users/{target}/sessions")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected);
+
+    // セッションとともに API キーも無効にする
+    let expected_revoked = if expected == StatusCode::NO_CONTENT {
+        vec![UserId::new(target).to_string()]
+    } else {
+        vec![]
+    };
+    assert_eq!(*revoked.lock().unwrap(), expected_revoked);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn logout_all_204(mut fixture_registry: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    with_auth_repository(&mut fixture_registry, |mock| {
+        mock.expect_delete_all_sessions().returning(|_| Ok(()));
+    });
+    expect_current_user(&mut fixture_registry, UserRole::User);
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/logout-all")
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn change_password_revokes_other_sessions(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    with_auth_repository(&mut fixture_registry, move |mock| {
+        let counter = counter.clone();
+        // パスワードを変更したリクエストのセッションは残す
+        mock.expect_delete_other_sessions()
+            .withf(|_, current| current.to_string() == "dummy")
+            .returning(move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+    });
+    fixture_registry.expect_user_repository().returning(|| {
+        let mut mock = mock_user_repository(UserRole::User);
+        mock.expect_update_password().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+    let revoked = record_api_key_revocation(&mut fixture_registry);
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::put(v1("/users/me/password"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            r#"{"currentPassword":"password","newPassword":"new-password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    // 他の端末に渡した API キーも使えなくする
+    assert_eq!(revoked.lock().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("PUT", "/role", r#"{"role":"Admin"}"#)]
+#[case("DELETE", "", "")]
+#[tokio::test]
+async fn user_lifecycle_revokes_all_sessions(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] method: &str,
+    #[case] suffix: &str,
+    #[case] body: &'static str,
+) -> anyhow::Result<()> {
+    let target = Uuid::new_v4();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    with_auth_repository(&mut fixture_registry, move |mock| {
+        let counter = counter.clone();
+        mock.expect_delete_all_sessions()
+            .withf(move |user_id| user_id.to_string() == target.to_string())
+            .returning(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+    });
+    fixture_registry.expect_user_repository().returning(|| {
+        let mut mock = mock_user_repository(UserRole::Admin);
+        mock.expect_update_role().returning(|_| Ok(()));
+        mock.expect_delete().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::builder()
+        .method(method)
+        .uri(v1(&format!("/users/{target}{suffix}")))
+        .bearer()
+        .application_json()
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
@@ -0,0 +1,330 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use kernel::{
+    model::{
+        auth::totp::{LoginChallenge, SecondFactor, TotpRequirement, TotpSetup},
+        user::{UserEmail, UserId, UserRole},
+        value_object::ValueObject,
+    },
+    repository::{
+        login_attempt::MockLoginAttemptRepository,
+        totp::{MockTotpRepository, TotpRepositoryError},
+    },
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use serde_json::Value;
+use shared::problem::ProblemDetails;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::{
+    deserialize_json,
+    helper::{expect_current_user, fixture_auth, make_router, v1, TestRequestExt},
+};
+
+fn login_attempts(registry: &mut MockAppRegistryExt, failures: Arc<AtomicUsize>) {
+    registry
+        .expect_login_attempt_repository()
+        .returning(move || {
+            let failures = failures.clone();
+            let mut mock = MockLoginAttemptRepository::new();
+            mock.expect_find_lockout().returning(|_, _| Ok(None));
+            mock.expect_clear_failures().returning(|_| Ok(()));
+            mock.expect_record_failure().returning(move |_| {
+                failures.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+            Arc::new(mock)
+        });
+}
+
+fn challenge(setup_required: bool) -> LoginChallenge {
+    LoginChallenge {
+        user_id: UserId::new(Uuid::new_v4()),
+        email: UserEmail::from_str("alice@example.com").unwrap(),
+        setup_required,
+    }
+}
+
+#[rstest]
+#[case(TotpRequirement::Enabled, false)]
+#[case(TotpRequirement::SetupRequired, true)]
+#[tokio::test]
+async fn login_200_requires_second_factor(
+    mut fixture_auth: MockAppRegistryExt,
+    #[case] requirement: TotpRequirement,
+    #[case] setup_required: bool,
+) -> anyhow::Result<()> {
+    login_attempts(&mut fixture_auth, Default::default());
+    fixture_auth.expect_totp_repository().returning(move || {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_find_requirement()
+            .returning(move |_| Ok(requirement));
+        mock.expect_create_secret().returning(|_| {
+            Ok(TotpSetup {
+                secret: "JBSWY3DPEHPK3PXP".into(),
+                otpauth_uri: "otpauth://totp/dummy".into(),
+            })
+        });
+        mock.expect_create_challenge()
+            .withf(move |event| event.setup_required == setup_required)
+            .returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post("/auth/login")
+        .application_json()
+        .body(Body::from(
+            r#"{"email":"alice@example.com","password":"password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // パスワードだけではトークンを発行しない
+    let body = deserialize_json!(resp, Value);
+    assert!(body.get("accessToken").is_none());
+    assert!(body["mfaToken"].is_string());
+    assert_eq!(body["expiresIn"], 300);
+    assert_eq!(body.get("setup").is_some(), setup_required);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_totp_200(mut fixture_auth: MockAppRegistryExt) -> anyhow::Result<()> {
+    login_attempts(&mut fixture_auth, Default::default());
+    fixture_auth.expect_totp_repository().returning(|| {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_find_challenge()
+            .withf(|token| token.inner_ref() == "mfa")
+            .returning(|_| Ok(Some(challenge(false))));
+        mock.expect_verify()
+            .withf(|_, second_factor| {
+                matches!(second_factor, SecondFactor::RecoveryCode(code) if code.inner_ref() == "abcde-fghij")
+            })
+            .returning(|_, _| Ok(()));
+        mock.expect_delete_challenge().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post("/auth/login/totp")
+        .application_json()
+        .body(Body::from(
+            r#"{"mfaToken":"mfa","recoveryCode":"abcde-fghij"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["accessToken"], "dummy");
+    assert!(body.get("recoveryCodes").is_none());
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_totp_200_completes_setup(
+    mut fixture_auth: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    login_attempts(&mut fixture_auth, Default::default());
+    fixture_auth.expect_totp_repository().returning(|| {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_find_challenge()
+            .returning(|_| Ok(Some(challenge(true))));
+        mock.expect_enable()
+            .withf(|event| event.code.inner_ref() == "123456")
+            .returning(|_| Ok(vec!["abcde-fghij".into()]));
+        mock.expect_delete_challenge().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post("/auth/login/totp")
+        .application_json()
+        .body(Body::from(r#"{"mfaToken":"mfa","code":"123456"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["accessToken"], "dummy");
+    assert_eq!(body["recoveryCodes"][0], "abcde-fghij");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_totp_401_records_failure(
+    mut fixture_auth: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let failures = Arc::new(AtomicUsize::new(0));
+    let challenge_failures = Arc::new(AtomicUsize::new(0));
+    let counter = challenge_failures.clone();
+
+    login_attempts(&mut fixture_auth, failures.clone());
+    fixture_auth.expect_totp_repository().returning(move || {
+        let counter = counter.clone();
+        let mut mock = MockTotpRepository::new();
+        mock.expect_find_challenge()
+            .returning(|_| Ok(Some(challenge(false))));
+        mock.expect_verify()
+            .returning(|_, _| Err(TotpRepositoryError::InvalidCode));
+        mock.expect_fail_challenge().returning(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post("/auth/login/totp")
+        .application_json()
+        .body(Body::from(r#"{"mfaToken":"mfa","code":"000000"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(challenge_failures.load(Ordering::SeqCst), 1);
+    assert_eq!(failures.load(Ordering::SeqCst), 1);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "invalid_totp_code");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    r#"{"mfaToken":"expired","code":"123456"}"#,
+    StatusCode::UNAUTHORIZED,
+    "invalid_mfa_token"
+)]
+#[case(r#"{"mfaToken":"mfa"}"#, StatusCode::BAD_REQUEST, "validation_failed")]
+#[tokio::test]
+async fn login_totp_rejects_request(
+    mut fixture_auth: MockAppRegistryExt,
+    #[case] body: &'static str,
+    #[case] expected_status: StatusCode,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    fixture_auth.expect_totp_repository().returning(|| {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_find_challenge().returning(|_| Ok(None));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post("/auth/login/totp")
+        .application_json()
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, expected_code);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn setup_totp_409(mut fixture_auth: MockAppRegistryExt) -> anyhow::Result<()> {
+    expect_current_user(&mut fixture_auth, UserRole::User);
+    fixture_auth.expect_totp_repository().returning(|| {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_create_secret()
+            .returning(|_| Err(TotpRepositoryError::AlreadyEnabled));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post(&v1("/users/me/totp"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, "totp_already_enabled");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn confirm_totp_200(mut fixture_auth: MockAppRegistryExt) -> anyhow::Result<()> {
+    expect_current_user(&mut fixture_auth, UserRole::User);
+    fixture_auth.expect_totp_repository().returning(|| {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_enable()
+            .withf(|event| event.code.inner_ref() == "123456")
+            .returning(|_| Ok(vec!["abcde-fghij".into(), "klmno-pqrst".into()]));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::post(&v1("/users/me/totp/confirm"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"code":" 123456 "}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = deserialize_json!(resp, Value);
+    assert_eq!(body["recoveryCodes"].as_array().map(Vec::len), Some(2));
+
+    Ok(())
+}
+
+#[rstest]
+#[case(TotpRepositoryError::Required, StatusCode::FORBIDDEN, "totp_required")]
+#[case(
+    TotpRepositoryError::InvalidCode,
+    StatusCode::UNPROCESSABLE_ENTITY,
+    "invalid_totp_code"
+)]
+#[case(
+    TotpRepositoryError::NotEnabled,
+    StatusCode::CONFLICT,
+    "totp_not_enabled"
+)]
+#[tokio::test]
+async fn disable_totp_error(
+    mut fixture_auth: MockAppRegistryExt,
+    #[case] error: TotpRepositoryError,
+    #[case] expected_status: StatusCode,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    expect_current_user(&mut fixture_auth, UserRole::Admin);
+    fixture_auth.expect_totp_repository().return_once(move || {
+        let mut mock = MockTotpRepository::new();
+        mock.expect_disable().return_once(move |_| Err(error));
+        Arc::new(mock)
+    });
+    let app = make_router(fixture_auth);
+
+    let req = Request::delete(&v1("/users/me/totp"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"code":"123456"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    let body = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(body.code, expected_code);
+
+    Ok(())
+}
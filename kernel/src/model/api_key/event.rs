@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+
+use crate::model::user::UserId;
+
+use super::{ApiKeyId, ApiKeyName, ApiKeyScope};
+
+pub struct CreateApiKey {
+    pub user_id: UserId,
+    pub name: ApiKeyName,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub struct DeleteApiKey {
+    pub api_key_id: ApiKeyId,
+    pub requested_by: UserId,
+}
@@ -0,0 +1,123 @@
+pub mod event;
+
+use chrono::{DateTime, Utc};
+use derive_getters::{Dissolve, Getters};
+
+use crate::{impl_entity, tuple_value_object_with_simple_error};
+
+use super::user::UserId;
+
+tuple_value_object_with_simple_error!(ApiKeyId, uuid::Uuid, ApiKeyIdError);
+tuple_value_object_with_simple_error!(
+    ApiKeyName,
+    String,
+    ApiKeyNameError,
+    [Trimmed, NonEmpty, MaxChars(100), NoControlChars]
+);
+
+// API キーの先頭に付ける文字列。アクセストークンと区別するために使う
+pub const API_KEY_PREFIX: &str = "rbm_";
+// API キーを発行できる最長の有効期間（日）
+pub const API_KEY_MAX_LIFETIME_DAYS: i64 = 365;
+
+// API キーで操作できる範囲。ログインして得たアクセストークンはすべての範囲を操作できる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiKeyScope {
+    BooksRead,
+    BooksWrite,
+    CheckoutsRead,
+    CheckoutsWrite,
+    ReservationsRead,
+    ReservationsWrite,
+    UsersRead,
+    UsersWrite,
+}
+
+impl ApiKeyScope {
+    pub const ALL: [ApiKeyScope; 8] = [
+        ApiKeyScope::BooksRead,
+        ApiKeyScope::BooksWrite,
+        ApiKeyScope::CheckoutsRead,
+        ApiKeyScope::CheckoutsWrite,
+        ApiKeyScope::ReservationsRead,
+        ApiKeyScope::ReservationsWrite,
+        ApiKeyScope::UsersRead,
+        ApiKeyScope::UsersWrite,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::BooksRead => "books:read",
+            ApiKeyScope::BooksWrite => "books:write",
+            ApiKeyScope::CheckoutsRead => "checkouts:read",
+            ApiKeyScope::CheckoutsWrite => "checkouts:write",
+            ApiKeyScope::ReservationsRead => "reservations:read",
+            ApiKeyScope::ReservationsWrite => "reservations:write",
+            ApiKeyScope::UsersRead => "users:read",
+            ApiKeyScope::UsersWrite => "users:write",
+        }
+    }
+}
+
+impl std::fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown api key scope: {0}")]
+pub struct ApiKeyScopeError(String);
+
+impl std::str::FromStr for ApiKeyScope {
+    type Err = ApiKeyScopeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| ApiKeyScopeError(s.to_string()))
+    }
+}
+
+#[derive(Debug, derive_new::new, Getters, Dissolve)]
+pub struct ApiKey {
+    api_key_id: ApiKeyId,
+    name: ApiKeyName,
+    // 一覧でキーを見分けるための、キーの先頭の数文字
+    key_prefix: String,
+    scopes: Vec<ApiKeyScope>,
+    expires_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl_entity!(ApiKey, api_key_id, ApiKeyId);
+
+// 発行した API キー。key は発行時に一度だけ返し、保存しない
+#[derive(Debug)]
+pub struct CreatedApiKey {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+// API キーで認証したリクエストの持ち主と操作できる範囲
+#[derive(Debug, Clone)]
+pub struct ApiKeyGrant {
+    pub api_key_id: ApiKeyId,
+    pub user_id: UserId,
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scope_names() {
+        for scope in ApiKeyScope::ALL {
+            assert_eq!(scope.as_str().parse::<ApiKeyScope>().unwrap(), scope);
+        }
+        assert!("books:delete".parse::<ApiKeyScope>().is_err());
+    }
+}
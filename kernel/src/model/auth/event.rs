@@ -1,18 +1,118 @@
-use crate::model::user::UserId;
+use crate::model::user::{Password, UserEmail, UserId};
 
-use super::AccessToken;
+use super::{
+    oidc::{AuthorizationCode, OidcState},
+    password_reset::PasswordResetToken,
+    session::{SessionClient, SessionId},
+    totp::{LoginChallengeToken, SecondFactor, TotpCode},
+    AccessToken, RefreshToken,
+};
 
 pub struct CreateToken {
     pub user_id: UserId,
     pub access_token: AccessToken,
+    pub refresh_token: RefreshToken,
+    pub client: SessionClient,
 }
 
 impl CreateToken {
-    pub fn new(user_id: UserId) -> Self {
-        let access_token = AccessToken(uuid::Uuid::new_v4().simple().to_string());
+    pub fn new(user_id: UserId, client: SessionClient) -> Self {
         Self {
             user_id,
-            access_token,
+            access_token: AccessToken(generate_token()),
+            refresh_token: RefreshToken(generate_token()),
+            client,
         }
     }
 }
+
+// リフレッシュトークンを使って新しいトークンの組を発行する
+pub struct RotateToken {
+    pub refresh_token: RefreshToken,
+    pub new_access_token: AccessToken,
+    pub new_refresh_token: RefreshToken,
+    pub client: SessionClient,
+}
+
+impl RotateToken {
+    pub fn new(refresh_token: RefreshToken, client: SessionClient) -> Self {
+        Self {
+            refresh_token,
+            new_access_token: AccessToken(generate_token()),
+            new_refresh_token: RefreshToken(generate_token()),
+            client,
+        }
+    }
+}
+
+pub struct DeleteSession {
+    pub user_id: UserId,
+    pub session_id: SessionId,
+}
+
+// パスワードの誤りなどでログインに失敗した
+pub struct RecordLoginFailure {
+    pub email: UserEmail,
+    pub client: SessionClient,
+}
+
+// 登録中のシークレットで生成したコードを確認して二要素認証を有効にする
+pub struct EnableTotp {
+    pub user_id: UserId,
+    pub code: TotpCode,
+}
+
+pub struct DisableTotp {
+    pub user_id: UserId,
+    pub second_factor: SecondFactor,
+}
+
+// パスワードを確認できたログインを、二要素目の入力待ちとして保存する
+pub struct CreateLoginChallenge {
+    pub challenge_token: LoginChallengeToken,
+    pub user_id: UserId,
+    pub email: UserEmail,
+    pub setup_required: bool,
+}
+
+impl CreateLoginChallenge {
+    pub fn new(user_id: UserId, email: UserEmail, setup_required: bool) -> Self {
+        Self {
+            challenge_token: LoginChallengeToken::new(generate_token()),
+            user_id,
+            email,
+            setup_required,
+        }
+    }
+}
+
+// IdP からのコールバックで受け取った認可コードでログインを完了する
+pub struct CompleteOidcLogin {
+    pub state: OidcState,
+    pub code: AuthorizationCode,
+}
+
+// パスワードの再設定を申し込む。メールで送るリンクに含めるトークンをここで発行する
+pub struct RequestPasswordReset {
+    pub email: UserEmail,
+    pub token: PasswordResetToken,
+}
+
+impl RequestPasswordReset {
+    pub fn new(email: UserEmail) -> Self {
+        Self {
+            email,
+            token: PasswordResetToken::new(generate_token()),
+        }
+    }
+}
+
+// メールで受け取ったトークンを確認して新しいパスワードを設定する
+pub struct ResetPassword {
+    pub token: PasswordResetToken,
+    pub new_password: Password,
+}
+
+fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
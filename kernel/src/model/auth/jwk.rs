@@ -0,0 +1,15 @@
+// アクセストークンの署名を検証するための公開鍵。JWKS として公開する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicJwk {
+    pub kid: String,
+    pub key: PublicKey,
+}
+
+// 座標や鍵の値は Base64URL（パディングなし）で表す
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKey {
+    // EdDSA で署名する
+    Ed25519 { x: String },
+    // ES256 で署名する
+    P256 { x: String, y: String },
+}
@@ -1,5 +1,33 @@
 pub mod event;
+pub mod jwk;
+pub mod oidc;
+pub mod password_reset;
+pub mod session;
+pub mod throttle;
+pub mod totp;
 
-use crate::tuple_value_object_with_simple_error;
+use crate::{
+    model::user::{UserId, UserRole},
+    tuple_value_object_with_simple_error,
+};
 
 tuple_value_object_with_simple_error!(AccessToken, String, AccessTokenError);
+tuple_value_object_with_simple_error!(RefreshToken, String, RefreshTokenError);
+
+// ログイン・リフレッシュ時に発行するトークンの組
+#[derive(Debug)]
+pub struct AuthTokens {
+    pub user_id: UserId,
+    pub access_token: AccessToken,
+    pub refresh_token: RefreshToken,
+    // アクセストークンの有効期間（秒）
+    pub expires_in: u64,
+}
+
+// アクセストークンから分かる利用者の情報
+#[derive(Debug, Clone)]
+pub struct TokenSubject {
+    pub user_id: UserId,
+    // 署名付きトークンのように、トークン自体がロールを持つ場合のみ Some
+    pub role: Option<UserRole>,
+}
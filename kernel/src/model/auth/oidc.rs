@@ -0,0 +1,31 @@
+use crate::{
+    model::user::{UserEmail, UserId},
+    tuple_value_object_with_simple_error,
+};
+
+// IdP へのリダイレクト時に発行し、コールバックで受け取る値。ログインの開始から終了までを結びつける
+tuple_value_object_with_simple_error!(OidcState, String, OidcStateError);
+// IdP が発行する認可コード。トークンエンドポイントで ID トークンと交換する
+tuple_value_object_with_simple_error!(
+    AuthorizationCode,
+    String,
+    AuthorizationCodeError,
+    [NonEmpty, MaxChars(2048)]
+);
+
+// IdP で認証を終えてコールバックに戻るまでの秒数
+pub const OIDC_LOGIN_TTL: u64 = 600;
+
+// IdP の認可エンドポイントへ利用者を送るための URL
+#[derive(Debug)]
+pub struct OidcAuthorization {
+    pub authorization_url: String,
+}
+
+#[derive(Debug)]
+pub struct OidcLogin {
+    pub user_id: UserId,
+    pub email: UserEmail,
+    // true の場合、IdP のクレームに合わせてロールを変更した
+    pub role_changed: bool,
+}
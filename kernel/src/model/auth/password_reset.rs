@@ -0,0 +1,4 @@
+use crate::tuple_value_object_with_simple_error;
+
+// パスワードの再設定用のリンクに含めるトークン。一度だけ使える
+tuple_value_object_with_simple_error!(PasswordResetToken, String, PasswordResetTokenError);
@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+
+use crate::tuple_value_object_with_simple_error;
+
+// セッションは 1 回のログインと、そこからリフレッシュで発行されたトークンの系列を表す
+tuple_value_object_with_simple_error!(SessionId, uuid::Uuid, SessionIdError);
+
+// ログイン・リフレッシュを行ったクライアントの情報
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionClient {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Session {
+    pub session_id: SessionId,
+    pub client: SessionClient,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    // 一覧を取得したリクエストのアクセストークンが属するセッションか
+    pub current: bool,
+}
@@ -0,0 +1,63 @@
+// ログインの失敗が続くメールアドレス・IP アドレスをロックする規則
+#[derive(Debug, Clone)]
+pub struct LoginThrottle {
+    // この回数連続で失敗するとロックする
+    pub max_failures_per_email: u64,
+    pub max_failures_per_ip: u64,
+    // 最初のロックの秒数。以降は失敗するたびに倍になる
+    pub lockout_base: u64,
+    // ロックの秒数の上限。失敗回数もこの秒数だけ失敗がなければ忘れる
+    pub lockout_max: u64,
+}
+
+// ログインの失敗が続いたため、一定時間ログインを受け付けない状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginLockout {
+    // ログインを再び試せるまでの秒数
+    pub retry_after: u64,
+}
+
+impl LoginThrottle {
+    // failures 回連続で失敗した後のロックの秒数を返す
+    pub fn lockout_for(&self, failures: u64, max_failures: u64) -> Option<u64> {
+        if failures < max_failures {
+            return None;
+        }
+        let exponent = u32::try_from(failures - max_failures).unwrap_or(u32::MAX);
+        let seconds = 2u64
+            .checked_pow(exponent)
+            .and_then(|x| x.checked_mul(self.lockout_base))
+            .unwrap_or(u64::MAX);
+        Some(seconds.min(self.lockout_max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle {
+            max_failures_per_email: 5,
+            max_failures_per_ip: 20,
+            lockout_base: 30,
+            lockout_max: 3600,
+        }
+    }
+
+    #[test]
+    fn does_not_lock_below_threshold() {
+        assert_eq!(throttle().lockout_for(0, 5), None);
+        assert_eq!(throttle().lockout_for(4, 5), None);
+    }
+
+    #[test]
+    fn doubles_lockout_until_max() {
+        let throttle = throttle();
+        assert_eq!(throttle.lockout_for(5, 5), Some(30));
+        assert_eq!(throttle.lockout_for(6, 5), Some(60));
+        assert_eq!(throttle.lockout_for(7, 5), Some(120));
+        assert_eq!(throttle.lockout_for(12, 5), Some(3600));
+        assert_eq!(throttle.lockout_for(u64::MAX, 5), Some(3600));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{
+    model::user::{UserEmail, UserId},
+    tuple_value_object_with_simple_error,
+};
+
+// 認証アプリに表示される 6 桁のコード
+tuple_value_object_with_simple_error!(
+    TotpCode,
+    String,
+    TotpCodeError,
+    [Trimmed, NonEmpty, MaxChars(16)]
+);
+// 認証アプリを使えないときに一度だけ使えるコード
+tuple_value_object_with_simple_error!(
+    RecoveryCode,
+    String,
+    RecoveryCodeError,
+    [Trimmed, NonEmpty, MaxChars(32)]
+);
+// パスワードの確認後、二要素目の入力を待っているログインを表すトークン
+tuple_value_object_with_simple_error!(LoginChallengeToken, String, LoginChallengeTokenError);
+
+// 二要素目の入力を待つ秒数
+pub const LOGIN_CHALLENGE_TTL: u64 = 300;
+// 二要素目をこの回数間違えると、パスワードからやり直させる
+pub const LOGIN_CHALLENGE_MAX_FAILURES: u64 = 5;
+
+// ログイン時に二要素目を求めるかどうか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TotpRequirement {
+    Disabled,
+    Enabled,
+    // 二要素認証が必須のロールだが、まだ登録していない
+    SetupRequired,
+}
+
+// 登録中のシークレットと、認証アプリに読み込ませる otpauth URI
+#[derive(Debug)]
+pub struct TotpSetup {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum SecondFactor {
+    Totp(TotpCode),
+    RecoveryCode(RecoveryCode),
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginChallenge {
+    pub user_id: UserId,
+    // 二要素目の誤りもログインの失敗として数えるために保持する
+    pub email: UserEmail,
+    // true の場合、二要素目として登録中のシークレットのコードを確認して登録を完了する
+    pub setup_required: bool,
+}
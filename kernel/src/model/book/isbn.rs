@@ -0,0 +1,170 @@
+use crate::model::value_object::{ValueObject, ValueObjectError};
+
+// ISBN を表す値オブジェクト
+// ISBN-10・ISBN-13 のどちらでも受け付け、内部では正規化した ISBN-13（数字 13 桁）を保持する
+#[derive(Debug, Eq, Hash, PartialEq, Clone)]
+pub struct Isbn(String);
+
+impl Isbn {
+    // 数字 13 桁の ISBN-13
+    pub fn isbn13(&self) -> &str {
+        &self.0
+    }
+
+    // 978 で始まる ISBN-13 のみ ISBN-10 に変換できる
+    pub fn isbn10(&self) -> Option<String> {
+        let body = self.0.strip_prefix("978")?.get(..9)?;
+        let check = isbn10_check_digit(&digits(body)?);
+        Some(format!("{body}{check}"))
+    }
+}
+
+impl ValueObject for Isbn {
+    type Value = String;
+    type Error = IsbnError;
+
+    fn inner_ref(&self) -> &Self::Value {
+        &self.0
+    }
+
+    fn into_inner(self) -> Self::Value {
+        self.0
+    }
+}
+
+impl TryFrom<String> for Isbn {
+    type Error = IsbnError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        // 区切りのハイフンと空白は取り除く
+        let normalized = value
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        // 以降はバイト数で桁を数えて分割するため、マルチバイト文字を含む値は先に弾く
+        if !normalized.is_ascii() {
+            return Err(IsbnError::InvalidValue(value));
+        }
+
+        let isbn13 = match normalized.len() {
+            10 => {
+                let (body, check) = normalized.split_at(9);
+                let body_digits =
+                    digits(body).ok_or_else(|| IsbnError::InvalidValue(value.clone()))?;
+                if isbn10_check_digit(&body_digits).to_string() != check {
+                    return Err(IsbnError::InvalidValue(value));
+                }
+                let body13 = format!("978{body}");
+                let check13 = isbn13_check_digit(
+                    &digits(&body13).ok_or_else(|| IsbnError::InvalidValue(value.clone()))?,
+                );
+                format!("{body13}{check13}")
+            }
+            13 => {
+                let all_digits =
+                    digits(&normalized).ok_or_else(|| IsbnError::InvalidValue(value.clone()))?;
+                let (body, check) = all_digits.split_at(12);
+                if !(normalized.starts_with("978") || normalized.starts_with("979"))
+                    || isbn13_check_digit(body) != check[0]
+                {
+                    return Err(IsbnError::InvalidValue(value));
+                }
+                normalized
+            }
+            _ => return Err(IsbnError::InvalidValue(value)),
+        };
+
+        Ok(Self(isbn13))
+    }
+}
+
+impl std::fmt::Display for Isbn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+fn digits(s: &str) -> Option<Vec<u32>> {
+    s.chars().map(|c| c.to_digit(10)).collect()
+}
+
+// ISBN-10 のチェックディジット（重み 10〜2、モジュラス 11。10 は X で表す）
+fn isbn10_check_digit(body: &[u32]) -> char {
+    let sum: u32 = body.iter().zip((2..=10).rev()).map(|(d, w)| d * w).sum();
+    match (11 - sum % 11) % 11 {
+        10 => 'X',
+        d => char::from_digit(d, 10).unwrap_or('0'),
+    }
+}
+
+// ISBN-13 のチェックディジット（重み 1, 3 の交互、モジュラス 10）
+fn isbn13_check_digit(body: &[u32]) -> u32 {
+    let sum: u32 = body
+        .iter()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IsbnError {
+    #[error("invalid {0}")]
+    InvalidValue(String),
+}
+
+impl ValueObjectError for IsbnError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_isbn13_with_hyphens() {
+        let isbn = Isbn::try_from("978-4-06-536957-9".to_string()).unwrap();
+        assert_eq!(isbn.isbn13(), "9784065369579");
+        assert_eq!(isbn.isbn10().as_deref(), Some("4065369576"));
+    }
+
+    #[test]
+    fn converts_isbn10_to_isbn13() {
+        let isbn = Isbn::try_from("4 06 536957 6".to_string()).unwrap();
+        assert_eq!(isbn.inner_ref(), "9784065369579");
+
+        // チェックディジットが X の ISBN-10
+        let isbn = Isbn::try_from("0-8044-2957-x".to_string()).unwrap();
+        assert_eq!(isbn.isbn13(), "9780804429573");
+        assert_eq!(isbn.isbn10().as_deref(), Some("080442957X"));
+    }
+
+    #[test]
+    fn isbn10_is_not_available_for_979_prefix() {
+        let isbn = Isbn::try_from("979-10-90636-07-1".to_string()).unwrap();
+        assert_eq!(isbn.isbn10(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        for value in [
+            "test isbn",
+            "",
+            "978-4-06-536957-0",
+            "4065369570",
+            "9774065369570",
+            "97840653695791",
+            "40653695X6",
+            // 10 バイトだが 10 文字ではない
+            "aあいう",
+            "４０６５３６９５７６",
+        ] {
+            assert!(
+                matches!(
+                    Isbn::try_from(value.to_string()),
+                    Err(IsbnError::InvalidValue(_))
+                ),
+                "{value}"
+            );
+        }
+    }
+}
@@ -5,51 +5,105 @@ use thiserror::Error;
 use uuid::Uuid;
 
 pub mod event;
+pub mod isbn;
 
 use crate::impl_entity;
 use crate::tuple_value_object_with_simple_error;
 
+use super::book_copy::{BookCopyCount, BookCopyId};
 use super::checkout::CheckoutId;
+use super::list::{Cursor, SortOrder};
 use super::user::BookOwner;
 use super::user::CheckoutUser;
+use super::user::UserId;
+
+pub use isbn::{Isbn, IsbnError};
 
 tuple_value_object_with_simple_error!(BookId, Uuid, BookIdError);
-tuple_value_object_with_simple_error!(Title, String, TitleError);
-tuple_value_object_with_simple_error!(Author, String, AuthorError);
-tuple_value_object_with_simple_error!(Isbn, String, IsbnError);
-tuple_value_object_with_simple_error!(Description, String, DescriptionError);
+// 上限は books テーブルのカラム長に合わせる
+tuple_value_object_with_simple_error!(
+    Title,
+    String,
+    TitleError,
+    [Trimmed, NonEmpty, MaxChars(255), NoControlChars]
+);
+tuple_value_object_with_simple_error!(
+    Author,
+    String,
+    AuthorError,
+    [Trimmed, NonEmpty, MaxChars(255), NoControlChars]
+);
+tuple_value_object_with_simple_error!(
+    Description,
+    String,
+    DescriptionError,
+    [Trimmed, MaxChars(1024), NoControlCharsExceptLineBreaks]
+);
 
 #[cfg(not(feature = "test-utils"))]
-#[derive(Debug, derive_new::new, Dissolve)]
+#[derive(Debug, Dissolve)]
 pub struct Book {
     book_id: BookId,
     title: Title,
     author: Author,
-    isbn: Isbn,
+    // 旧データで正しい ISBN に直せなかったものは None になる
+    isbn: Option<Isbn>,
     description: Description,
     owner: BookOwner,
-    checkout: Option<Checkout>,
+    copy_count: BookCopyCount,
+    // 貸出中の冊ごとの貸出情報
+    checkouts: Vec<Checkout>,
 }
 
 #[cfg(feature = "test-utils")]
-#[derive(Debug, derive_new::new, Dissolve)]
+#[derive(Debug, Dissolve)]
 pub struct Book {
     pub book_id: BookId,
     pub title: Title,
     pub author: Author,
-    pub isbn: Isbn,
+    // 旧データで正しい ISBN に直せなかったものは None になる
+    pub isbn: Option<Isbn>,
     pub description: Description,
     pub owner: BookOwner,
-    pub checkout: Option<Checkout>,
+    pub copy_count: BookCopyCount,
+    // 貸出中の冊ごとの貸出情報
+    pub checkouts: Vec<Checkout>,
 }
 
 impl_entity!(Book, book_id, BookId);
 
+impl Book {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        book_id: BookId,
+        title: Title,
+        author: Author,
+        isbn: Option<Isbn>,
+        description: Description,
+        owner: BookOwner,
+        copy_count: BookCopyCount,
+        checkouts: Vec<Checkout>,
+    ) -> Self {
+        Self {
+            book_id,
+            title,
+            author,
+            isbn,
+            description,
+            owner,
+            copy_count,
+            checkouts,
+        }
+    }
+}
+
 #[derive(Debug, Dissolve)]
 pub struct Checkout {
     pub checkout_id: CheckoutId,
+    pub book_copy_id: BookCopyId,
     pub checked_out_by: CheckoutUser,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Error)]
@@ -73,4 +127,85 @@ pub enum BookError {
 pub struct BookListOptions {
     pub limit: i64,
     pub offset: i64,
+    pub filter: BookListFilter,
+    pub sort: BookSort,
+}
+
+// カーソル方式で蔵書一覧を取得する際の条件
+pub struct BookListCursorOptions {
+    pub limit: i64,
+    pub after: Option<Cursor>,
+    pub filter: BookListFilter,
+    pub sort: BookSort,
+}
+
+// 蔵書一覧の絞り込み条件
+// 指定されていない条件（None）は絞り込みに使用しない
+#[derive(Debug, Default, Clone)]
+pub struct BookListFilter {
+    // タイトル・著者・ISBN・説明のいずれかに含まれる文字列
+    pub keyword: Option<String>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub isbn: Option<String>,
+    pub description: Option<String>,
+    pub owner_id: Option<UserId>,
+    pub availability: Option<BookAvailability>,
+    pub checked_out_by: Option<UserId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookAvailability {
+    Available,
+    CheckedOut,
+}
+
+// 蔵書一覧の並び順
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookSort {
+    pub key: BookSortKey,
+    pub order: SortOrder,
+}
+
+impl BookSort {
+    pub fn new(key: BookSortKey, order: Option<SortOrder>) -> Self {
+        Self {
+            key,
+            order: order.unwrap_or(key.default_order()),
+        }
+    }
+}
+
+impl Default for BookSort {
+    fn default() -> Self {
+        Self::new(BookSortKey::default(), None)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BookSortKey {
+    Title,
+    Author,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    LastCheckedOutAt,
+    CheckoutCount,
+    // キーワード検索との関連度順（キーワードが無い場合は CreatedAt と同じ）
+    Relevance,
+}
+
+impl BookSortKey {
+    // 並び順が指定されなかったときの既定値
+    // 文字列は昇順、日時・件数・関連度は降順とする
+    pub fn default_order(&self) -> SortOrder {
+        match self {
+            BookSortKey::Title | BookSortKey::Author => SortOrder::Asc,
+            BookSortKey::CreatedAt
+            | BookSortKey::UpdatedAt
+            | BookSortKey::LastCheckedOutAt
+            | BookSortKey::CheckoutCount
+            | BookSortKey::Relevance => SortOrder::Desc,
+        }
+    }
 }
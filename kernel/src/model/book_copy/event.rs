@@ -0,0 +1,26 @@
+use crate::model::{book::BookId, user::UserId};
+
+use super::{Barcode, BookCopyId, CopyCondition, CopyLocation};
+
+pub struct CreateBookCopy {
+    pub book_id: BookId,
+    // 指定がなければ採番する
+    pub barcode: Option<Barcode>,
+    pub condition: CopyCondition,
+    pub location: CopyLocation,
+    pub requested_by: UserId,
+}
+
+pub struct UpdateBookCopy {
+    pub book_copy_id: BookCopyId,
+    pub book_id: BookId,
+    pub condition: CopyCondition,
+    pub location: CopyLocation,
+    pub requested_by: UserId,
+}
+
+pub struct DeleteBookCopy {
+    pub book_copy_id: BookCopyId,
+    pub book_id: BookId,
+    pub requested_by: UserId,
+}
@@ -0,0 +1,62 @@
+pub mod event;
+
+use derive_getters::{Dissolve, Getters};
+
+use crate::enum_value_object_with_simple_error;
+use crate::impl_entity;
+use crate::tuple_value_object_with_simple_error;
+
+use super::book::{BookId, Checkout};
+
+tuple_value_object_with_simple_error!(BookCopyId, uuid::Uuid, BookCopyIdError);
+// 上限は book_copies テーブルのカラム長に合わせる
+tuple_value_object_with_simple_error!(
+    Barcode,
+    String,
+    BarcodeError,
+    [Trimmed, NonEmpty, MaxChars(64), NoControlChars]
+);
+tuple_value_object_with_simple_error!(
+    CopyLocation,
+    String,
+    CopyLocationError,
+    [Trimmed, MaxChars(255), NoControlChars]
+);
+
+enum_value_object_with_simple_error!(
+    #[derive(Default, Copy)]
+    CopyCondition {
+        New,
+        #[default]
+        Good,
+        Worn,
+        Damaged,
+    },
+    CopyConditionError
+);
+
+// 貸し出しの単位となる物理的な 1 冊
+#[derive(Debug, derive_new::new, Getters, Dissolve)]
+pub struct BookCopy {
+    book_copy_id: BookCopyId,
+    book_id: BookId,
+    barcode: Barcode,
+    condition: CopyCondition,
+    location: CopyLocation,
+    checkout: Option<Checkout>,
+}
+
+impl_entity!(BookCopy, book_copy_id, BookCopyId);
+
+impl BookCopy {
+    pub fn is_available(&self) -> bool {
+        self.checkout.is_none()
+    }
+}
+
+// 蔵書ごとの所蔵数と貸出可能数
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BookCopyCount {
+    pub total: i64,
+    pub available: i64,
+}
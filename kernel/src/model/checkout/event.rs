@@ -1,11 +1,13 @@
 use chrono::{DateTime, Utc};
 
-use crate::model::{book::BookId, user::UserId};
+use crate::model::{book::BookId, book_copy::BookCopyId, user::UserId};
 
 use super::CheckoutId;
 
 pub struct CreateCheckout {
     pub book_id: BookId,
+    // None の場合は貸出可能な冊のいずれかを貸し出す
+    pub book_copy_id: Option<BookCopyId>,
     pub checked_out_by: UserId,
     pub checked_out_at: DateTime<Utc>,
 }
@@ -16,3 +18,11 @@ pub struct UpdateReturned {
     pub returned_by: UserId,
     pub returned_at: DateTime<Utc>,
 }
+
+// 返却期限の延長
+pub struct RenewCheckout {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub requested_by: UserId,
+    pub renewed_at: DateTime<Utc>,
+}
@@ -1,6 +1,10 @@
 pub mod event;
+pub mod policy;
+
+use std::collections::HashMap;
 
 use chrono::DateTime;
+use chrono::Duration;
 use chrono::Utc;
 use derive_getters::Dissolve;
 use uuid::Uuid;
@@ -12,7 +16,9 @@ use super::book::Author;
 use super::book::BookId;
 use super::book::Isbn;
 use super::book::Title;
-use super::user::UserId;
+use super::book_copy::{Barcode, BookCopyId};
+use super::list::Cursor;
+use super::user::{UserId, UserRole};
 
 tuple_value_object_with_simple_error!(CheckoutId, Uuid, CheckoutIdError);
 
@@ -21,16 +27,60 @@ pub struct Checkout {
     checkout_id: CheckoutId,
     checked_out_by: UserId,
     checked_out_at: DateTime<Utc>,
+    due_at: DateTime<Utc>,
+    // 返却期限を延長した回数
+    renewal_count: i32,
     returned_at: Option<DateTime<Utc>>,
     book: CheckoutBook,
 }
 
 impl_entity!(Checkout, checkout_id, CheckoutId);
 
+impl Checkout {
+    // 返却されないまま返却期限を過ぎているか
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.returned_at.is_none() && self.due_at < now
+    }
+}
+
 #[derive(Debug, derive_new::new, Dissolve)]
 pub struct CheckoutBook {
     book_id: BookId,
     title: Title,
     author: Author,
-    isbn: Isbn,
+    isbn: Option<Isbn>,
+    book_copy_id: BookCopyId,
+    barcode: Barcode,
+}
+
+// 貸出期間と延長の条件
+#[derive(Debug, Clone)]
+pub struct LoanTerms {
+    pub default_period: Duration,
+    // ロールごとの貸出期間。含まれないロールは default_period を使う
+    pub role_periods: HashMap<UserRole, Duration>,
+    // 1 件の貸出を延長できる回数
+    pub max_renewals: i32,
+}
+
+impl LoanTerms {
+    pub fn period_for(&self, role: &UserRole) -> Duration {
+        self.role_periods
+            .get(role)
+            .copied()
+            .unwrap_or(self.default_period)
+    }
+}
+
+// カーソル方式で貸出中の一覧を取得する際の条件
+pub struct CheckoutListOptions {
+    pub limit: i64,
+    pub after: Option<Cursor>,
+    pub filter: CheckoutListFilter,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CheckoutListFilter {
+    // Some(true) なら延滞中のもののみ、Some(false) なら延滞していないもののみ
+    pub overdue: Option<bool>,
 }
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::model::user::UserRole;
+
+// 新しい貸出を認めるかどうかの規則
+#[derive(Debug, Clone)]
+pub struct CheckoutPolicy {
+    // 同時に借りられる冊数の上限
+    pub default_max_loans: i64,
+    // ロールごとの上限。含まれないロールは default_max_loans を使う
+    pub role_max_loans: HashMap<UserRole, i64>,
+}
+
+// 貸出を求めた利用者の状況
+#[derive(Debug, Clone)]
+pub struct BorrowerStanding {
+    pub role: UserRole,
+    // 貸出中の冊数
+    pub active_loans: i64,
+    // 貸出中のうち返却期限を過ぎている冊数
+    pub overdue_loans: i64,
+    // 借りようとしている蔵書の所有者であるか
+    pub owns_book: bool,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CheckoutPolicyViolation {
+    #[error("borrowing one's own book is not allowed")]
+    OwnBook,
+
+    #[error("{0} overdue loan(s) must be returned first")]
+    HasOverdueLoans(i64),
+
+    #[error("loan limit ({0}) reached")]
+    LoanLimitReached(i64),
+}
+
+impl CheckoutPolicy {
+    pub fn max_loans_for(&self, role: &UserRole) -> i64 {
+        self.role_max_loans
+            .get(role)
+            .copied()
+            .unwrap_or(self.default_max_loans)
+    }
+
+    // 規則に反する場合は最初に見つかったものを返す
+    pub fn check(&self, standing: &BorrowerStanding) -> Result<(), CheckoutPolicyViolation> {
+        if standing.owns_book {
+            return Err(CheckoutPolicyViolation::OwnBook);
+        }
+
+        if standing.overdue_loans > 0 {
+            return Err(CheckoutPolicyViolation::HasOverdueLoans(
+                standing.overdue_loans,
+            ));
+        }
+
+        let limit = self.max_loans_for(&standing.role);
+        if standing.active_loans >= limit {
+            return Err(CheckoutPolicyViolation::LoanLimitReached(limit));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> CheckoutPolicy {
+        CheckoutPolicy {
+            default_max_loans: 2,
+            role_max_loans: [(UserRole::Admin, 5)].into(),
+        }
+    }
+
+    fn standing(role: UserRole, active_loans: i64) -> BorrowerStanding {
+        BorrowerStanding {
+            role,
+            active_loans,
+            overdue_loans: 0,
+            owns_book: false,
+        }
+    }
+
+    #[test]
+    fn test_loan_limit_per_role() {
+        let policy = policy();
+        assert_eq!(policy.check(&standing(UserRole::User, 1)), Ok(()));
+        assert_eq!(
+            policy.check(&standing(UserRole::User, 2)),
+            Err(CheckoutPolicyViolation::LoanLimitReached(2))
+        );
+        assert_eq!(policy.check(&standing(UserRole::Admin, 4)), Ok(()));
+        assert_eq!(
+            policy.check(&standing(UserRole::Admin, 5)),
+            Err(CheckoutPolicyViolation::LoanLimitReached(5))
+        );
+    }
+
+    #[test]
+    fn test_own_book_and_overdue_take_precedence() {
+        let policy = policy();
+        let overdue = BorrowerStanding {
+            overdue_loans: 1,
+            ..standing(UserRole::User, 2)
+        };
+        assert_eq!(
+            policy.check(&overdue),
+            Err(CheckoutPolicyViolation::HasOverdueLoans(1))
+        );
+
+        let own = BorrowerStanding {
+            owns_book: true,
+            ..overdue
+        };
+        assert_eq!(policy.check(&own), Err(CheckoutPolicyViolation::OwnBook));
+    }
+}
@@ -0,0 +1,33 @@
+use crate::model::user::{Password, UserEmail, UserId, UserName, UserRole};
+
+use super::{InvitationId, InvitationToken};
+
+// 管理者がメールアドレスとロールを指定して招待する。メールで送るリンクに含めるトークンをここで発行する
+pub struct CreateInvitation {
+    pub email: UserEmail,
+    pub role: UserRole,
+    pub invited_by: UserId,
+    pub token: InvitationToken,
+}
+
+impl CreateInvitation {
+    pub fn new(email: UserEmail, role: UserRole, invited_by: UserId) -> Self {
+        Self {
+            email,
+            role,
+            invited_by,
+            token: InvitationToken::new(uuid::Uuid::new_v4().simple().to_string()),
+        }
+    }
+}
+
+// 招待された人が自分で名前とパスワードを決めてユーザーになる
+pub struct AcceptInvitation {
+    pub token: InvitationToken,
+    pub name: UserName,
+    pub password: Password,
+}
+
+pub struct DeleteInvitation {
+    pub invitation_id: InvitationId,
+}
@@ -0,0 +1,26 @@
+pub mod event;
+
+use chrono::{DateTime, Utc};
+use derive_getters::{Dissolve, Getters};
+
+use crate::{impl_entity, tuple_value_object_with_simple_error};
+
+use super::user::{UserEmail, UserId, UserRole};
+
+tuple_value_object_with_simple_error!(InvitationId, uuid::Uuid, InvitationIdError);
+// 招待メールのリンクに含めるトークン。受け入れると使えなくなる
+tuple_value_object_with_simple_error!(InvitationToken, String, InvitationTokenError);
+
+#[derive(Debug, derive_new::new, Getters, Dissolve)]
+pub struct Invitation {
+    invitation_id: InvitationId,
+    email: UserEmail,
+    // 受け入れたときに作成するユーザーのロール
+    role: UserRole,
+    // 招待した管理者。削除されている場合は None
+    invited_by: Option<UserId>,
+    expires_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+}
+
+impl_entity!(Invitation, invitation_id, InvitationId);
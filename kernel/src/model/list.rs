@@ -1,3 +1,5 @@
+use uuid::Uuid;
+
 pub struct PaginatedList<T> {
     pub total: i64,
     pub limit: i64,
@@ -10,3 +12,38 @@ impl<T> PaginatedList<T> {
         self.items
     }
 }
+
+// キーセット（カーソル）方式でページングした一覧
+// next_cursor が None のときは、これ以上続きが無いことを表す
+pub struct CursorPage<T> {
+    pub limit: i64,
+    pub items: Vec<T>,
+    pub next_cursor: Option<Cursor>,
+}
+
+impl<T> CursorPage<T> {
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+}
+
+// 一覧のどこまでを読んだかを表す位置
+// 最後に返した要素の並び替えキーの値と ID を保持する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub sort_key: Option<String>,
+    pub id: Uuid,
+}
+
+#[derive(Debug, Clone)]
+pub struct CursorOptions {
+    pub limit: i64,
+    pub after: Option<Cursor>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
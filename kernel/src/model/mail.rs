@@ -0,0 +1,9 @@
+use crate::model::user::UserEmail;
+
+// 利用者に送るメール。本文はプレーンテキストとする
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: UserEmail,
+    pub subject: String,
+    pub body: String,
+}
@@ -1,6 +1,11 @@
+pub mod api_key;
 pub mod auth;
 pub mod book;
+pub mod book_copy;
 pub mod checkout;
+pub mod invitation;
+pub mod mail;
+pub mod reservation;
 pub mod user;
 
 pub mod list;
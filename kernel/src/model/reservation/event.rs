@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+
+use crate::model::{book::BookId, user::UserId};
+
+use super::ReservationId;
+
+pub struct CreateReservation {
+    pub book_id: BookId,
+    pub reserved_by: UserId,
+    pub reserved_at: DateTime<Utc>,
+}
+
+pub struct DeleteReservation {
+    pub reservation_id: ReservationId,
+    pub book_id: BookId,
+    pub requested_by: UserId,
+}
@@ -0,0 +1,37 @@
+pub mod event;
+
+use chrono::DateTime;
+use chrono::Utc;
+use derive_getters::{Dissolve, Getters};
+
+use crate::impl_entity;
+use crate::tuple_value_object_with_simple_error;
+
+use super::book::BookId;
+use super::user::UserId;
+
+tuple_value_object_with_simple_error!(ReservationId, uuid::Uuid, ReservationIdError);
+
+#[derive(Debug, derive_new::new, Getters, Dissolve)]
+pub struct Reservation {
+    reservation_id: ReservationId,
+    book_id: BookId,
+    reserved_by: UserId,
+    reserved_at: DateTime<Utc>,
+    // 待ち行列での順番（1 始まり）
+    position: i64,
+    status: ReservationStatus,
+}
+
+impl_entity!(Reservation, reservation_id, ReservationId);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservationStatus {
+    // 貸出可能な冊が空くのを待っている
+    Waiting,
+    // 冊が取り置かれていて、期限までに貸し出しを受けられる
+    ReadyForPickup {
+        ready_at: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    },
+}
@@ -0,0 +1,4 @@
+use crate::tuple_value_object_with_simple_error;
+
+// メールアドレスの変更を確認するリンクに含めるトークン。一度だけ使える
+tuple_value_object_with_simple_error!(EmailChangeToken, String, EmailChangeTokenError);
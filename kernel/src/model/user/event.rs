@@ -1,4 +1,4 @@
-use super::{Password, UserEmail, UserId, UserName, UserRole};
+use super::{email_change::EmailChangeToken, Password, UserEmail, UserId, UserName, UserRole};
 
 #[derive(Debug)]
 pub struct CreateUser {
@@ -13,6 +13,12 @@ pub struct UpdateUserRole {
     pub role: UserRole,
 }
 
+#[derive(Debug)]
+pub struct UpdateUserName {
+    pub user_id: UserId,
+    pub name: UserName,
+}
+
 #[derive(Debug)]
 pub struct UpdateUserPassword {
     pub user_id: UserId,
@@ -24,3 +30,27 @@ pub struct UpdateUserPassword {
 pub struct DeleteUser {
     pub user_id: UserId,
 }
+
+// メールアドレスの変更を申し込む。新しいアドレスに送るリンクに含めるトークンをここで発行する
+#[derive(Debug)]
+pub struct RequestEmailChange {
+    pub user_id: UserId,
+    pub new_email: UserEmail,
+    pub token: EmailChangeToken,
+}
+
+impl RequestEmailChange {
+    pub fn new(user_id: UserId, new_email: UserEmail) -> Self {
+        Self {
+            user_id,
+            new_email,
+            token: EmailChangeToken::new(uuid::Uuid::new_v4().simple().to_string()),
+        }
+    }
+}
+
+// 新しいアドレスで受け取ったトークンを確認してメールアドレスを変更する
+#[derive(Debug)]
+pub struct ConfirmEmailChange {
+    pub token: EmailChangeToken,
+}
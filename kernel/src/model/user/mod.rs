@@ -1,4 +1,6 @@
+pub mod email_change;
 pub mod event;
+pub mod password;
 
 use std::fmt::Display;
 
@@ -12,7 +14,12 @@ use crate::tuple_value_object_requiring_error_definition;
 use crate::tuple_value_object_with_simple_error;
 
 tuple_value_object_with_simple_error!(UserId, uuid::Uuid, UserIdError);
-tuple_value_object_with_simple_error!(UserName, String, UserNameError);
+tuple_value_object_with_simple_error!(
+    UserName,
+    String,
+    UserNameError,
+    [Trimmed, NonEmpty, MaxChars(255), NoControlChars]
+);
 tuple_value_object_requiring_error_definition!(
     UserEmail,
     email_address::EmailAddress,
@@ -0,0 +1,145 @@
+use std::{collections::HashSet, sync::Arc};
+
+use thiserror::Error;
+
+use super::Password;
+use crate::model::value_object::ValueObject;
+
+// 長すぎる入力でハッシュ化に時間がかからないよう、ポリシーによらず上限を設ける
+pub const PASSWORD_MAX_CHARS: usize = 128;
+
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+// 新しく設定するパスワードに求める条件
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    // 英小文字・英大文字・数字・記号のうち、含める必要のある種類の数
+    pub min_character_classes: usize,
+    // 使わせないパスワード。小文字にして保持する
+    denylist: Arc<HashSet<String>>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PasswordPolicyError {
+    #[error("password must be at least {0} characters")]
+    TooShort(usize),
+
+    #[error("password must be at most {0} characters")]
+    TooLong(usize),
+
+    #[error("password must contain at least {0} kinds of characters")]
+    TooFewCharacterClasses(usize),
+
+    #[error("password is too common")]
+    Common,
+}
+
+impl PasswordPolicy {
+    // 組み込みの一覧に、運用者が用意した一覧（漏洩したパスワードなど）を加えて使う
+    pub fn new(
+        min_length: usize,
+        min_character_classes: usize,
+        denylist: impl IntoIterator<Item = String>,
+    ) -> Self {
+        let denylist = parse_list(COMMON_PASSWORDS)
+            .chain(denylist.into_iter().map(|x| x.trim().to_lowercase()))
+            .filter(|x| !x.is_empty())
+            .collect();
+        Self {
+            min_length,
+            min_character_classes,
+            denylist: Arc::new(denylist),
+        }
+    }
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self::new(8, 1, [])
+    }
+}
+
+impl Password {
+    // 規則に反する場合は最初に見つかったものを返す
+    pub fn check(&self, policy: &PasswordPolicy) -> Result<(), PasswordPolicyError> {
+        let password = self.inner_ref();
+        let length = password.chars().count();
+        if length < policy.min_length {
+            return Err(PasswordPolicyError::TooShort(policy.min_length));
+        }
+        if length > PASSWORD_MAX_CHARS {
+            return Err(PasswordPolicyError::TooLong(PASSWORD_MAX_CHARS));
+        }
+
+        let classes = [
+            password.chars().any(char::is_lowercase),
+            password.chars().any(char::is_uppercase),
+            password.chars().any(char::is_numeric),
+            password
+                .chars()
+                .any(|c| !c.is_lowercase() && !c.is_uppercase() && !c.is_numeric()),
+        ];
+        if classes.into_iter().filter(|x| *x).count() < policy.min_character_classes {
+            return Err(PasswordPolicyError::TooFewCharacterClasses(
+                policy.min_character_classes,
+            ));
+        }
+
+        if policy.denylist.contains(&password.to_lowercase()) {
+            return Err(PasswordPolicyError::Common);
+        }
+
+        Ok(())
+    }
+}
+
+// 空行と # で始まる行を除いて読み取る
+fn parse_list(list: &str) -> impl Iterator<Item = String> + '_ {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(policy: &PasswordPolicy, password: &str) -> Result<(), PasswordPolicyError> {
+        Password::new(password.to_string()).check(policy)
+    }
+
+    #[test]
+    fn test_length_and_character_classes() {
+        let policy = PasswordPolicy::new(10, 3, []);
+        assert_eq!(
+            check(&policy, "Ab1-"),
+            Err(PasswordPolicyError::TooShort(10))
+        );
+        assert_eq!(
+            check(&policy, "correcthorsebattery"),
+            Err(PasswordPolicyError::TooFewCharacterClasses(3))
+        );
+        assert_eq!(check(&policy, "correct-horse-9"), Ok(()));
+        assert_eq!(check(&policy, "Correct Horse 9"), Ok(()));
+        assert_eq!(
+            check(&policy, &"Aa1".repeat(50)),
+            Err(PasswordPolicyError::TooLong(PASSWORD_MAX_CHARS))
+        );
+    }
+
+    #[test]
+    fn test_denylist_ignores_case() {
+        let policy = PasswordPolicy::new(8, 1, ["  Tr0ub4dor&3 ".to_string()]);
+        assert_eq!(
+            check(&policy, "PassWord123"),
+            Err(PasswordPolicyError::Common)
+        );
+        assert_eq!(
+            check(&policy, "tr0ub4dor&3"),
+            Err(PasswordPolicyError::Common)
+        );
+        assert_eq!(check(&policy, "a-less-common-phrase"), Ok(()));
+    }
+}
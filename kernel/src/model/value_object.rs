@@ -13,8 +13,121 @@ where
 // マーカートレイト
 pub trait ValueObjectError: std::error::Error {}
 
+// 文字列の値オブジェクトが満たすべき条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringInvariant {
+    // 前後の空白を取り除いた値を保持する
+    Trimmed,
+    NonEmpty,
+    MaxChars(usize),
+    NoControlChars,
+    // 改行（\n, \r）以外の制御文字を許可しない
+    NoControlCharsExceptLineBreaks,
+}
+
+impl StringInvariant {
+    pub fn is_satisfied_by(&self, value: &str) -> bool {
+        match self {
+            StringInvariant::Trimmed => value.trim() == value,
+            StringInvariant::NonEmpty => !value.is_empty(),
+            StringInvariant::MaxChars(max) => value.chars().count() <= *max,
+            StringInvariant::NoControlChars => !value.chars().any(char::is_control),
+            StringInvariant::NoControlCharsExceptLineBreaks => !value
+                .chars()
+                .any(|c| c.is_control() && c != '\n' && c != '\r'),
+        }
+    }
+}
+
+impl std::fmt::Display for StringInvariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StringInvariant::Trimmed => write!(f, "must not have leading or trailing whitespace"),
+            StringInvariant::NonEmpty => write!(f, "must not be empty"),
+            StringInvariant::MaxChars(max) => write!(f, "must be at most {max} characters"),
+            StringInvariant::NoControlChars => write!(f, "must not contain control characters"),
+            StringInvariant::NoControlCharsExceptLineBreaks => {
+                write!(
+                    f,
+                    "must not contain control characters other than line breaks"
+                )
+            }
+        }
+    }
+}
+
+// Trimmed が宣言されていれば前後の空白を取り除いてから、各条件を検証する
+pub fn apply_string_invariants(
+    value: String,
+    invariants: &[StringInvariant],
+) -> Result<String, StringInvariant> {
+    let value = if invariants.contains(&StringInvariant::Trimmed) && value.trim() != value {
+        value.trim().to_string()
+    } else {
+        value
+    };
+
+    match invariants.iter().find(|i| !i.is_satisfied_by(&value)) {
+        Some(violated) => Err(*violated),
+        None => Ok(value),
+    }
+}
+
 #[macro_export]
 macro_rules! tuple_value_object_with_simple_error {
+    // 文字列の値オブジェクトに不変条件を宣言する
+    // 例: tuple_value_object_with_simple_error!(Title, String, TitleError, [Trimmed, NonEmpty, MaxChars(255)]);
+    // 不変条件を迂回できないよう new は生成せず、TryFrom からのみ作成する
+    ($name:ident, String, $error:ident, [$($invariant:ident $(($arg:expr))?),+ $(,)?]) => {
+        #[derive(Debug, Eq, Hash, PartialEq, Clone)]
+        pub struct $name(String);
+
+        impl $name {
+            pub const INVARIANTS: &'static [$crate::model::value_object::StringInvariant] = &[
+                $($crate::model::value_object::StringInvariant::$invariant $(($arg))?),+
+            ];
+        }
+
+        impl $crate::model::value_object::ValueObject for $name {
+            type Value = String;
+            type Error = $error;
+
+            fn inner_ref(&self) -> &Self::Value {
+                &self.0
+            }
+
+            fn into_inner(self) -> Self::Value {
+                self.0
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = $error;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                $crate::model::value_object::apply_string_invariants(value, Self::INVARIANTS)
+                    .map(Self)
+                    .map_err($error::InvalidValue)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        #[derive(Debug, thiserror::Error)]
+        pub enum $error {
+            #[error("{0}")]
+            InvalidValue($crate::model::value_object::StringInvariant),
+
+            #[error("parse error: {0}")]
+            ParseError(#[from] Box<dyn std::error::Error + Send + Sync>),
+        }
+
+        impl $crate::model::value_object::ValueObjectError for $error {}
+    };
     ($name:ident, $value:ty, $error:ident) => {
         #[derive(Debug, Eq, Hash, PartialEq, Clone, derive_new::new)]
         pub struct $name($value);
@@ -166,3 +279,46 @@ macro_rules! define_id_with_uuid {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVARIANTS: &[StringInvariant] = &[
+        StringInvariant::Trimmed,
+        StringInvariant::NonEmpty,
+        StringInvariant::MaxChars(5),
+        StringInvariant::NoControlChars,
+    ];
+
+    #[test]
+    fn trims_before_checking() {
+        assert_eq!(
+            apply_string_invariants("  abc ".to_string(), INVARIANTS).unwrap(),
+            "abc"
+        );
+        assert_eq!(
+            apply_string_invariants("   ".to_string(), INVARIANTS),
+            Err(StringInvariant::NonEmpty)
+        );
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        assert!(apply_string_invariants("あいうえお".to_string(), INVARIANTS).is_ok());
+        assert_eq!(
+            apply_string_invariants("あいうえおか".to_string(), INVARIANTS),
+            Err(StringInvariant::MaxChars(5))
+        );
+    }
+
+    #[test]
+    fn rejects_control_characters() {
+        assert_eq!(
+            apply_string_invariants("a\u{7}b".to_string(), INVARIANTS),
+            Err(StringInvariant::NoControlChars)
+        );
+        assert!(StringInvariant::NoControlCharsExceptLineBreaks.is_satisfied_by("a\r\nb"));
+        assert!(!StringInvariant::NoControlCharsExceptLineBreaks.is_satisfied_by("a\tb"));
+    }
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::model::{
+    api_key::{
+        event::{CreateApiKey, DeleteApiKey},
+        ApiKey, ApiKeyGrant, CreatedApiKey,
+    },
+    user::UserId,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn create(&self, event: CreateApiKey) -> ApiKeyRepositoryResult<CreatedApiKey>;
+
+    async fn find_all(&self, user_id: &UserId) -> ApiKeyRepositoryResult<Vec<ApiKey>>;
+
+    async fn delete(&self, event: DeleteApiKey) -> ApiKeyRepositoryResult<()>;
+
+    // パスワードの変更やセッションの強制的な無効化に合わせて、ユーザーのキーをすべて削除する
+    async fn delete_all(&self, user_id: &UserId) -> ApiKeyRepositoryResult<()>;
+
+    // 有効期限内の API キーであれば、持ち主と操作できる範囲を返して最終利用日時を更新する
+    async fn fetch_grant(&self, key: &str) -> ApiKeyRepositoryResult<Option<ApiKeyGrant>>;
+}
+
+#[derive(Debug, Error)]
+pub enum ApiKeyRepositoryError {
+    #[error("api key not found")]
+    NotFound,
+
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type ApiKeyRepositoryResult<T> = Result<T, ApiKeyRepositoryError>;
@@ -2,17 +2,23 @@ use async_trait::async_trait;
 use thiserror::Error;
 
 use crate::model::{
-    auth::{event::CreateToken, AccessToken, AccessTokenError},
+    auth::{
+        event::{CreateToken, DeleteSession, RotateToken},
+        jwk::PublicJwk,
+        session::Session,
+        AccessToken, AccessTokenError, AuthTokens, TokenSubject,
+    },
     user::{Password, UserEmail, UserId, UserIdError},
 };
 
 #[mockall::automock]
 #[async_trait]
 pub trait AuthRepository: Send + Sync {
-    async fn fetch_user_id_from_token(
+    // 有効なアクセストークンであれば、その利用者の情報を返す
+    async fn fetch_token_subject(
         &self,
         access_token: &AccessToken,
-    ) -> AuthRepositoryResult<Option<UserId>>;
+    ) -> AuthRepositoryResult<Option<TokenSubject>>;
 
     async fn verify_user(
         &self,
@@ -20,9 +26,36 @@ pub trait AuthRepository: Send + Sync {
         password: &Password,
     ) -> AuthRepositoryResult<UserId>;
 
-    async fn create_token(&self, event: CreateToken) -> AuthRepositoryResult<AccessToken>;
+    async fn create_token(&self, event: CreateToken) -> AuthRepositoryResult<AuthTokens>;
+
+    // リフレッシュトークンを使い捨てにして新しいトークンの組を発行する。
+    // 使用済みのリフレッシュトークンが再び使われた場合は、同じログインから
+    // 発行されたトークンをすべて無効にする
+    async fn rotate_token(&self, event: RotateToken) -> AuthRepositoryResult<AuthTokens>;
 
     async fn delete_token(&self, access_token: &AccessToken) -> AuthRepositoryResult<()>;
+
+    // ユーザーのログイン中のセッションを最終利用日時の新しい順に返す
+    async fn find_sessions(
+        &self,
+        user_id: &UserId,
+        current: &AccessToken,
+    ) -> AuthRepositoryResult<Vec<Session>>;
+
+    async fn delete_session(&self, event: DeleteSession) -> AuthRepositoryResult<()>;
+
+    // ユーザーのすべてのセッションを無効にする
+    async fn delete_all_sessions(&self, user_id: &UserId) -> AuthRepositoryResult<()>;
+
+    // current が属するセッション以外のユーザーのセッションを無効にする
+    async fn delete_other_sessions(
+        &self,
+        user_id: &UserId,
+        current: &AccessToken,
+    ) -> AuthRepositoryResult<()>;
+
+    // アクセストークンの署名の検証に使う公開鍵。署名しない形式のトークンでは空になる
+    fn public_keys(&self) -> Vec<PublicJwk>;
 }
 
 #[derive(Debug, Error)]
@@ -36,6 +69,15 @@ pub enum AuthRepositoryError {
     #[error("invalid password")]
     InvalidPassword,
 
+    #[error("invalid refresh token")]
+    InvalidRefreshToken,
+
+    #[error("refresh token has already been used")]
+    RefreshTokenReused,
+
+    #[error("session not found")]
+    SessionNotFound,
+
     #[error("unexpected error occurred: {0}")]
     Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
 }
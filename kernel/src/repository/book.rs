@@ -4,9 +4,9 @@ use thiserror::Error;
 use crate::model::{
     book::{
         event::{CreateBook, DeleteBook, UpdateBook},
-        Book, BookId, BookListOptions,
+        Book, BookId, BookListCursorOptions, BookListOptions,
     },
-    list::PaginatedList,
+    list::{CursorPage, PaginatedList},
     user::UserId,
 };
 
@@ -16,6 +16,10 @@ pub trait BookRepository: Send + Sync {
     async fn create(&self, event: CreateBook, owner_id: UserId) -> BookRepositoryResult<()>;
     async fn find_all(&self, options: BookListOptions)
         -> BookRepositoryResult<PaginatedList<Book>>;
+    async fn find_all_by_cursor(
+        &self,
+        options: BookListCursorOptions,
+    ) -> BookRepositoryResult<CursorPage<Book>>;
     async fn find_by_id(&self, id: &BookId) -> BookRepositoryResult<Option<Book>>;
     async fn update(&self, event: UpdateBook) -> BookRepositoryResult<()>;
     async fn delete(&self, event: DeleteBook) -> BookRepositoryResult<()>;
@@ -32,8 +36,8 @@ pub enum BookRepositoryError {
     #[error("book not found: {0}")]
     NotFound(BookId),
 
-    #[error("no resource was affected: {0}")]
-    NoResourceAffected(String),
+    #[error("user ({1}) is not the owner of book ({0})")]
+    NotBookOwner(BookId, UserId),
 }
 
 pub type BookRepositoryResult<T> = Result<T, BookRepositoryError>;
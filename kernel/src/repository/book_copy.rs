@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::model::{
+    book::BookId,
+    book_copy::{
+        event::{CreateBookCopy, DeleteBookCopy, UpdateBookCopy},
+        BookCopy, BookCopyId,
+    },
+    user::UserId,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait BookCopyRepository: Send + Sync {
+    async fn create(&self, event: CreateBookCopy) -> BookCopyRepositoryResult<()>;
+    async fn find_by_book_id(&self, book_id: &BookId) -> BookCopyRepositoryResult<Vec<BookCopy>>;
+    async fn update(&self, event: UpdateBookCopy) -> BookCopyRepositoryResult<()>;
+    async fn delete(&self, event: DeleteBookCopy) -> BookCopyRepositoryResult<()>;
+}
+
+#[derive(Debug, Error)]
+pub enum BookCopyRepositoryError {
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("saved entity is invalid: {0}")]
+    InvalidSavedEntity(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("book not found: {0}")]
+    BookNotFound(BookId),
+
+    #[error("book copy not found: {0}")]
+    NotFound(BookCopyId),
+
+    #[error("user ({1}) is not the owner of book ({0})")]
+    NotBookOwner(BookId, UserId),
+
+    #[error("barcode already exists: {0}")]
+    DuplicateBarcode(String),
+
+    #[error("book copy is checked out: {0}")]
+    CheckedOut(BookCopyId),
+
+    #[error("book copy has checkout history: {0}")]
+    HasCheckoutHistory(BookCopyId),
+}
+
+pub type BookCopyRepositoryResult<T> = Result<T, BookCopyRepositoryError>;
@@ -3,10 +3,12 @@ use thiserror::Error;
 
 use crate::model::{
     book::BookId,
+    book_copy::BookCopyId,
     checkout::{
-        event::{CreateCheckout, UpdateReturned},
-        Checkout, CheckoutId,
+        event::{CreateCheckout, RenewCheckout, UpdateReturned},
+        Checkout, CheckoutId, CheckoutListOptions,
     },
+    list::CursorPage,
     user::UserId,
 };
 
@@ -14,7 +16,10 @@ use crate::model::{
 #[async_trait]
 pub trait CheckoutRepository: Send + Sync {
     async fn create(&self, event: CreateCheckout) -> CheckoutRepositoryResult<()>;
-    async fn find_unreturned_all(&self) -> CheckoutRepositoryResult<Vec<Checkout>>;
+    async fn find_unreturned_all(
+        &self,
+        options: CheckoutListOptions,
+    ) -> CheckoutRepositoryResult<CursorPage<Checkout>>;
     async fn find_unreturned_by_user_id(
         &self,
         user_id: &UserId,
@@ -24,6 +29,8 @@ pub trait CheckoutRepository: Send + Sync {
         book_id: &BookId,
     ) -> CheckoutRepositoryResult<Vec<Checkout>>;
     async fn update_returned(&self, event: UpdateReturned) -> CheckoutRepositoryResult<()>;
+    // 予約が入っている蔵書の貸出は延長できない
+    async fn renew(&self, event: RenewCheckout) -> CheckoutRepositoryResult<()>;
 }
 
 #[derive(Debug, Error)]
@@ -40,12 +47,39 @@ pub enum CheckoutRepositoryError {
     #[error("book not found: {0}")]
     BookNotFound(BookId),
 
-    #[error("book already checked out: {0}")]
-    BookAlreadyCheckedOut(BookId),
+    #[error("no available copy of book: {0}")]
+    NoAvailableCopy(BookId),
+
+    #[error("book copy not found: {0}")]
+    BookCopyNotFound(BookCopyId),
+
+    #[error("book copy already checked out: {0}")]
+    BookCopyAlreadyCheckedOut(BookCopyId),
+
+    #[error("available copies of book ({0}) are held for other users' reservations")]
+    ReservedForOthers(BookId),
+
+    #[error("user ({0}) has reached the loan limit ({1})")]
+    LoanLimitReached(UserId, i64),
+
+    #[error("user ({0}) has overdue loans")]
+    HasOverdueLoans(UserId),
+
+    #[error("user ({1}) cannot borrow own book ({0})")]
+    OwnBook(BookId, UserId),
 
     #[error("no resource was affected: {0}")]
     NoResourceAffected(String),
 
+    #[error("cannot renew: checkout (ID: {2}) of book (ID: {0}) is not held by user (ID: {1})")]
+    CannotRenew(BookId, UserId, CheckoutId),
+
+    #[error("checkout ({0}) has reached the maximum number of renewals")]
+    RenewalLimitReached(CheckoutId),
+
+    #[error("book ({0}) is reserved by other users and cannot be renewed")]
+    RenewalBlockedByReservation(BookId),
+
     #[error("cannot return: unable to process a return for a checkout (ID: {2}) of abook (ID: {0}) to a user (ID: {1}).")]
     CannotReturn(BookId, UserId, CheckoutId),
 }
@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    model::user::{
+        event::{ConfirmEmailChange, RequestEmailChange},
+        User,
+    },
+    repository::mailer::MailerError,
+};
+
+// ユーザーが自身のメールアドレスを変更する。
+// 新しいアドレスに送ったリンクで受け取れることを確認してから変更する
+#[mockall::automock]
+#[async_trait]
+pub trait EmailChangeRepository: Send + Sync {
+    // トークンを保存して確認用のリンクを新しいアドレスに送る。
+    // 以前に申し込んだ変更のリンクは使えなくなる
+    async fn request(&self, event: RequestEmailChange) -> EmailChangeRepositoryResult<()>;
+
+    // トークンを使用済みにしてメールアドレスを変更し、変更後のユーザーを返す
+    async fn confirm(&self, event: ConfirmEmailChange) -> EmailChangeRepositoryResult<User>;
+}
+
+#[derive(Debug, Error)]
+pub enum EmailChangeRepositoryError {
+    #[error("email change token is invalid or expired")]
+    InvalidToken,
+
+    #[error("email address is already registered")]
+    EmailAlreadyRegistered,
+
+    #[error("mailer error: {0}")]
+    Mailer(#[from] MailerError),
+
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type EmailChangeRepositoryResult<T> = Result<T, EmailChangeRepositoryError>;
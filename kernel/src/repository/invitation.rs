@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    model::{
+        invitation::{
+            event::{AcceptInvitation, CreateInvitation, DeleteInvitation},
+            Invitation,
+        },
+        user::{password::PasswordPolicyError, User},
+    },
+    repository::mailer::MailerError,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait InvitationRepository: Send + Sync {
+    // 招待を保存し、受け入れるためのリンクをメールで送る。
+    // 同じメールアドレスへの招待が残っている場合は置き換える
+    async fn create(&self, event: CreateInvitation) -> InvitationRepositoryResult<Invitation>;
+
+    // 有効期限内の招待を新しい順に返す
+    async fn find_pending(&self) -> InvitationRepositoryResult<Vec<Invitation>>;
+
+    async fn delete(&self, event: DeleteInvitation) -> InvitationRepositoryResult<()>;
+
+    // 招待を使用済みにして、招待時のロールでユーザーを作成する
+    async fn accept(&self, event: AcceptInvitation) -> InvitationRepositoryResult<User>;
+}
+
+#[derive(Debug, Error)]
+pub enum InvitationRepositoryError {
+    #[error("invitation not found")]
+    NotFound,
+
+    #[error("invitation token is invalid or expired")]
+    InvalidToken,
+
+    #[error("email is already registered")]
+    EmailAlreadyRegistered,
+
+    #[error("weak password: {0}")]
+    WeakPassword(#[from] PasswordPolicyError),
+
+    #[error("mailer error: {0}")]
+    Mailer(#[from] MailerError),
+
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type InvitationRepositoryResult<T> = Result<T, InvitationRepositoryError>;
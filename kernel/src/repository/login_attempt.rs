@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::model::{
+    auth::{event::RecordLoginFailure, session::SessionClient, throttle::LoginLockout},
+    user::UserEmail,
+};
+
+// ログインの試行回数を数え、失敗が続くメールアドレス・IP アドレスからのログインを制限する
+#[mockall::automock]
+#[async_trait]
+pub trait LoginAttemptRepository: Send + Sync {
+    // メールアドレスと IP アドレスのどちらかがロックされていれば、長い方の残り時間を返す
+    async fn find_lockout(
+        &self,
+        email: &UserEmail,
+        client: &SessionClient,
+    ) -> LoginAttemptRepositoryResult<Option<LoginLockout>>;
+
+    // 失敗回数を数えて必要ならロックし、監査用に失敗を記録する
+    async fn record_failure(&self, event: RecordLoginFailure) -> LoginAttemptRepositoryResult<()>;
+
+    // ログインに成功したメールアドレスの失敗回数を消す
+    async fn clear_failures(&self, email: &UserEmail) -> LoginAttemptRepositoryResult<()>;
+}
+
+#[derive(Debug, Error)]
+pub enum LoginAttemptRepositoryError {
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type LoginAttemptRepositoryResult<T> = Result<T, LoginAttemptRepositoryError>;
@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::model::mail::Mail;
+
+// メールの送信。SMTP のほか、開発やテスト向けにファイルやログへ書き出す実装を切り替えて使う
+#[mockall::automock]
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> MailerResult<()>;
+}
+
+#[derive(Debug, Error)]
+pub enum MailerError {
+    #[error("invalid mail: {0}")]
+    InvalidMail(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("failed to send mail: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type MailerResult<T> = Result<T, MailerError>;
@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::model::auth::{
+    event::CompleteOidcLogin,
+    oidc::{OidcAuthorization, OidcLogin},
+};
+
+// OpenID Connect の認可コードフロー（PKCE）によるシングルサインオンを扱う
+#[mockall::automock]
+#[async_trait]
+pub trait OidcRepository: Send + Sync {
+    // state・nonce・PKCE の検証値を保存し、IdP の認可エンドポイントの URL を返す
+    async fn begin_login(&self) -> OidcRepositoryResult<OidcAuthorization>;
+
+    // 認可コードを ID トークンと交換して検証し、対応するユーザーを返す。
+    // 初めてログインする利用者は、同じメールアドレスのユーザーに紐づけるか新しく作成する
+    async fn complete_login(&self, event: CompleteOidcLogin) -> OidcRepositoryResult<OidcLogin>;
+}
+
+#[derive(Debug, Error)]
+pub enum OidcRepositoryError {
+    #[error("single sign-on is not configured")]
+    NotConfigured,
+
+    #[error("login state is invalid or expired")]
+    InvalidState,
+
+    #[error("identity provider error: {0}")]
+    IdentityProvider(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("invalid id token: {0}")]
+    InvalidIdToken(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("email address is not verified by the identity provider")]
+    EmailNotVerified,
+
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type OidcRepositoryResult<T> = Result<T, OidcRepositoryError>;
@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    model::{
+        auth::event::{RequestPasswordReset, ResetPassword},
+        user::{password::PasswordPolicyError, UserId},
+    },
+    repository::mailer::MailerError,
+};
+
+// パスワードを忘れた利用者が、メールで受け取ったリンクから自分でパスワードを再設定する
+#[mockall::automock]
+#[async_trait]
+pub trait PasswordResetRepository: Send + Sync {
+    // メールアドレスに該当するユーザーがいれば、トークンを保存して再設定用のリンクをメールで送る。
+    // アカウントの有無を推測されないよう、該当するユーザーがいなくてもエラーにしない
+    async fn request(&self, event: RequestPasswordReset) -> PasswordResetRepositoryResult<()>;
+
+    // トークンを使用済みにしてパスワードを変更し、対象のユーザーを返す
+    async fn confirm(&self, event: ResetPassword) -> PasswordResetRepositoryResult<UserId>;
+}
+
+#[derive(Debug, Error)]
+pub enum PasswordResetRepositoryError {
+    #[error("password reset token is invalid or expired")]
+    InvalidToken,
+
+    #[error("weak password: {0}")]
+    WeakPassword(#[from] PasswordPolicyError),
+
+    #[error("mailer error: {0}")]
+    Mailer(#[from] MailerError),
+
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type PasswordResetRepositoryResult<T> = Result<T, PasswordResetRepositoryError>;
@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::model::{
+    book::BookId,
+    reservation::{
+        event::{CreateReservation, DeleteReservation},
+        Reservation, ReservationId,
+    },
+    user::UserId,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait ReservationRepository: Send + Sync {
+    async fn create(&self, event: CreateReservation) -> ReservationRepositoryResult<()>;
+    // 受け取り期限を過ぎた予約を取り除いた上で、待ち行列の順に返す
+    async fn find_by_book_id(
+        &self,
+        book_id: &BookId,
+    ) -> ReservationRepositoryResult<Vec<Reservation>>;
+    async fn delete(&self, event: DeleteReservation) -> ReservationRepositoryResult<()>;
+}
+
+#[derive(Debug, Error)]
+pub enum ReservationRepositoryError {
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("saved entity is invalid: {0}")]
+    InvalidSavedEntity(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("book not found: {0}")]
+    BookNotFound(BookId),
+
+    #[error("reservation not found: {0}")]
+    NotFound(ReservationId),
+
+    #[error("book ({0}) has already been reserved by user ({1})")]
+    AlreadyReserved(BookId, UserId),
+
+    #[error("book ({0}) has already been checked out by user ({1})")]
+    AlreadyCheckedOut(BookId, UserId),
+
+    #[error("book can be checked out without reservation: {0}")]
+    BookAvailable(BookId),
+
+    #[error("user ({1}) cannot cancel reservation ({0})")]
+    CannotCancel(ReservationId, UserId),
+}
+
+pub type ReservationRepositoryResult<T> = Result<T, ReservationRepositoryError>;
@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::model::{
+    auth::{
+        event::{CreateLoginChallenge, DisableTotp, EnableTotp},
+        totp::{LoginChallenge, LoginChallengeToken, SecondFactor, TotpRequirement, TotpSetup},
+    },
+    user::UserId,
+};
+
+// TOTP による二要素認証の設定と、二要素目の入力を待っているログインを扱う
+#[mockall::automock]
+#[async_trait]
+pub trait TotpRepository: Send + Sync {
+    async fn find_requirement(&self, user_id: &UserId) -> TotpRepositoryResult<TotpRequirement>;
+
+    // 新しいシークレットを生成して登録中にする。登録中のシークレットがあれば置き換える
+    async fn create_secret(&self, user_id: &UserId) -> TotpRepositoryResult<TotpSetup>;
+
+    // 登録中のシークレットを有効にし、新しいリカバリーコードを返す
+    async fn enable(&self, event: EnableTotp) -> TotpRepositoryResult<Vec<String>>;
+
+    async fn disable(&self, event: DisableTotp) -> TotpRepositoryResult<()>;
+
+    // 有効なシークレットのコード、または未使用のリカバリーコードであることを確認する。
+    // 確認したコードは再び使えなくなる
+    async fn verify(
+        &self,
+        user_id: &UserId,
+        second_factor: &SecondFactor,
+    ) -> TotpRepositoryResult<()>;
+
+    async fn create_challenge(&self, event: CreateLoginChallenge) -> TotpRepositoryResult<()>;
+
+    async fn find_challenge(
+        &self,
+        challenge_token: &LoginChallengeToken,
+    ) -> TotpRepositoryResult<Option<LoginChallenge>>;
+
+    // 二要素目の誤りを数え、上限に達したらログインの試行を破棄する
+    async fn fail_challenge(
+        &self,
+        challenge_token: &LoginChallengeToken,
+    ) -> TotpRepositoryResult<()>;
+
+    async fn delete_challenge(
+        &self,
+        challenge_token: &LoginChallengeToken,
+    ) -> TotpRepositoryResult<()>;
+}
+
+#[derive(Debug, Error)]
+pub enum TotpRepositoryError {
+    #[error("invalid totp code or recovery code")]
+    InvalidCode,
+
+    #[error("two-factor authentication is already enabled")]
+    AlreadyEnabled,
+
+    #[error("two-factor authentication is not enabled")]
+    NotEnabled,
+
+    #[error("two-factor authentication is required for this user")]
+    Required,
+
+    #[error("unexpected error occurred: {0}")]
+    Unexpected(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type TotpRepositoryResult<T> = Result<T, TotpRepositoryError>;
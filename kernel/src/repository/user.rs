@@ -1,17 +1,22 @@
 use async_trait::async_trait;
 use thiserror::Error;
 
-use crate::model::user::{
-    event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
-    User, UserId,
+use crate::model::{
+    list::{CursorOptions, CursorPage},
+    user::{
+        event::{CreateUser, DeleteUser, UpdateUserName, UpdateUserPassword, UpdateUserRole},
+        password::PasswordPolicyError,
+        User, UserId,
+    },
 };
 
 #[mockall::automock]
 #[async_trait]
 pub trait UserRepository: Send + Sync {
     async fn find_current_user(&self, user_id: &UserId) -> UserRepositoryResult<Option<User>>;
-    async fn find_all(&self) -> UserRepositoryResult<Vec<User>>;
+    async fn find_all(&self, options: CursorOptions) -> UserRepositoryResult<CursorPage<User>>;
     async fn create(&self, event: CreateUser) -> UserRepositoryResult<User>;
+    async fn update_name(&self, event: UpdateUserName) -> UserRepositoryResult<User>;
     async fn update_password(&self, event: UpdateUserPassword) -> UserRepositoryResult<()>;
     async fn update_role(&self, event: UpdateUserRole) -> UserRepositoryResult<()>;
     async fn delete(&self, event: DeleteUser) -> UserRepositoryResult<()>;
@@ -28,15 +33,18 @@ pub enum UserRepositoryError {
     #[error("not found: {0}")]
     NotFound(UserId),
 
-    #[error("no resource was affected: {0}")]
-    NoResourceAffected(String),
-
     #[error("transaction error: {0}")]
     Transaction(#[source] Box<dyn std::error::Error + Send + Sync>),
 
+    #[error("email address is already registered")]
+    EmailAlreadyRegistered,
+
     #[error("invalid password")]
     InvalidPassword,
 
+    #[error("weak password: {0}")]
+    WeakPassword(#[from] PasswordPolicyError),
+
     #[error("password hash error: {0}")]
     PasswordHash(#[source] Box<dyn std::error::Error + Send + Sync>),
 }
@@ -1,25 +1,50 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use adapter::{
     database::ConnectionPool,
+    jwt::JwtKeySet,
+    oidc::OidcClient,
     redis::RedisClient,
     repository::{
-        auth::AuthRepositoryImpl, book::BookRepositoryImpl, checkout::CheckoutRepositoryImpl,
-        health::HealthCheckRepositoryImpl, user::UserRepositoryImpl,
+        api_key::ApiKeyRepositoryImpl, auth::AuthRepositoryImpl, book::BookRepositoryImpl,
+        book_copy::BookCopyRepositoryImpl, checkout::CheckoutRepositoryImpl,
+        email_change::EmailChangeRepositoryImpl, health::HealthCheckRepositoryImpl,
+        invitation::InvitationRepositoryImpl, jwt_auth::JwtAuthRepositoryImpl,
+        login_attempt::LoginAttemptRepositoryImpl, oidc::OidcRepositoryImpl,
+        password_reset::PasswordResetRepositoryImpl, reservation::ReservationRepositoryImpl,
+        totp::TotpRepositoryImpl, user::UserRepositoryImpl,
     },
 };
+use kernel::model::{
+    auth::throttle::LoginThrottle,
+    checkout::{policy::CheckoutPolicy, LoanTerms},
+    user::{password::PasswordPolicy, UserRole},
+};
 use kernel::repository::{
-    auth::AuthRepository, book::BookRepository, checkout::CheckoutRepository,
-    health::HealthCheckRepository, user::UserRepository,
+    api_key::ApiKeyRepository, auth::AuthRepository, book::BookRepository,
+    book_copy::BookCopyRepository, checkout::CheckoutRepository,
+    email_change::EmailChangeRepository, health::HealthCheckRepository,
+    invitation::InvitationRepository, login_attempt::LoginAttemptRepository, mailer::Mailer,
+    oidc::OidcRepository, password_reset::PasswordResetRepository,
+    reservation::ReservationRepository, totp::TotpRepository, user::UserRepository,
 };
-use shared::config::AppConfig;
+use shared::config::{AppConfig, AuthConfig, LoanConfig, PasswordConfig};
 
 #[derive(Clone)]
 pub struct AppRegistryImpl {
+    api_key_repository: Arc<dyn ApiKeyRepository>,
     auth_repository: Arc<dyn AuthRepository>,
     book_repository: Arc<dyn BookRepository>,
+    book_copy_repository: Arc<dyn BookCopyRepository>,
     checkout_repository: Arc<dyn CheckoutRepository>,
+    email_change_repository: Arc<dyn EmailChangeRepository>,
     health_check_repository: Arc<dyn HealthCheckRepository>,
+    invitation_repository: Arc<dyn InvitationRepository>,
+    login_attempt_repository: Arc<dyn LoginAttemptRepository>,
+    oidc_repository: Arc<dyn OidcRepository>,
+    password_reset_repository: Arc<dyn PasswordResetRepository>,
+    reservation_repository: Arc<dyn ReservationRepository>,
+    totp_repository: Arc<dyn TotpRepository>,
     user_repository: Arc<dyn UserRepository>,
 }
 
@@ -27,40 +52,177 @@ impl AppRegistryImpl {
     pub fn new(
         pool: ConnectionPool,
         redis_client: Arc<RedisClient>,
+        oidc_client: Option<Arc<OidcClient>>,
+        jwt_keys: Option<Arc<JwtKeySet>>,
+        mailer: Arc<dyn Mailer>,
         app_config: AppConfig,
     ) -> Self {
+        let password_policy = build_password_policy(&app_config.password);
         // 依存解決
-        let auth_repository = Arc::new(AuthRepositoryImpl::new(
+        let api_key_repository = Arc::new(ApiKeyRepositoryImpl::new(pool.clone()));
+        // 署名鍵を設定している場合はアクセストークンに JWT を使う
+        let auth_repository: Arc<dyn AuthRepository> = match jwt_keys {
+            Some(keys) => Arc::new(JwtAuthRepositoryImpl::new(
+                pool.clone(),
+                redis_client.clone(),
+                keys,
+                app_config.auth.ttl,
+                app_config.auth.refresh_ttl,
+            )),
+            None => Arc::new(AuthRepositoryImpl::new(
+                pool.clone(),
+                redis_client.clone(),
+                app_config.auth.ttl,
+                app_config.auth.refresh_ttl,
+                app_config.auth.sliding_expiration,
+            )),
+        };
+        let book_repository = Arc::new(BookRepositoryImpl::new(pool.clone()));
+        let book_copy_repository = Arc::new(BookCopyRepositoryImpl::new(pool.clone()));
+        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(
             pool.clone(),
-            redis_client,
-            app_config.auth.ttl,
+            app_config.reservation.pickup_window,
+            build_loan_terms(&app_config.loan),
+            build_checkout_policy(&app_config.loan),
+        ));
+        let email_change_repository = Arc::new(EmailChangeRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            mailer.clone(),
+            app_config.auth.email_change_ttl,
+            app_config.mail.link_base_url.clone(),
         ));
-        let book_repository = Arc::new(BookRepositoryImpl::new(pool.clone()));
-        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(pool.clone()));
         let health_check_repository = Arc::new(HealthCheckRepositoryImpl::new(pool.clone()));
-        let user_repository = Arc::new(UserRepositoryImpl::new(pool.clone()));
+        let invitation_repository = Arc::new(InvitationRepositoryImpl::new(
+            pool.clone(),
+            mailer.clone(),
+            password_policy.clone(),
+            app_config.auth.invitation_ttl,
+            app_config.mail.link_base_url.clone(),
+        ));
+        let login_attempt_repository = Arc::new(LoginAttemptRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            build_login_throttle(&app_config.auth),
+        ));
+        let oidc_repository = Arc::new(OidcRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            oidc_client,
+        ));
+        let password_reset_repository = Arc::new(PasswordResetRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            mailer,
+            password_policy.clone(),
+            app_config.auth.password_reset_ttl,
+            app_config.mail.link_base_url,
+        ));
+        let reservation_repository = Arc::new(ReservationRepositoryImpl::new(
+            pool.clone(),
+            app_config.reservation.pickup_window,
+        ));
+        let totp_repository = Arc::new(TotpRepositoryImpl::new(
+            pool.clone(),
+            redis_client,
+            app_config.auth.totp_issuer,
+            app_config.auth.totp_required_for_admin,
+        ));
+        let user_repository = Arc::new(UserRepositoryImpl::new(pool.clone(), password_policy));
 
         Self {
+            api_key_repository,
             auth_repository,
             book_repository,
+            book_copy_repository,
             checkout_repository,
+            email_change_repository,
             health_check_repository,
+            invitation_repository,
+            login_attempt_repository,
+            oidc_repository,
+            password_reset_repository,
+            reservation_repository,
+            totp_repository,
             user_repository,
         }
     }
 }
 
+// 設定値の秒数から貸出期間と延長の条件を組み立てる
+fn build_loan_terms(config: &LoanConfig) -> LoanTerms {
+    let seconds = |s: u64| chrono::Duration::seconds(i64::try_from(s).unwrap_or(i64::MAX));
+    let role_periods = [
+        (UserRole::Admin, config.admin_period),
+        (UserRole::User, config.user_period),
+    ]
+    .into_iter()
+    .filter_map(|(role, period)| period.map(|p| (role, seconds(p))))
+    .collect::<HashMap<_, _>>();
+
+    LoanTerms {
+        default_period: seconds(config.period),
+        role_periods,
+        max_renewals: i32::try_from(config.max_renewals).unwrap_or(i32::MAX),
+    }
+}
+
+fn build_checkout_policy(config: &LoanConfig) -> CheckoutPolicy {
+    let role_max_loans = [
+        (UserRole::Admin, config.admin_max_loans),
+        (UserRole::User, config.user_max_loans),
+    ]
+    .into_iter()
+    .filter_map(|(role, max_loans)| max_loans.map(|m| (role, i64::from(m))))
+    .collect::<HashMap<_, _>>();
+
+    CheckoutPolicy {
+        default_max_loans: i64::from(config.max_loans),
+        role_max_loans,
+    }
+}
+
+fn build_login_throttle(config: &AuthConfig) -> LoginThrottle {
+    LoginThrottle {
+        max_failures_per_email: config.login_max_failures,
+        max_failures_per_ip: config.login_max_failures_per_ip,
+        lockout_base: config.login_lockout_base,
+        lockout_max: config.login_lockout_max,
+    }
+}
+
+fn build_password_policy(config: &PasswordConfig) -> PasswordPolicy {
+    PasswordPolicy::new(
+        config.min_length,
+        config.min_character_classes,
+        config.denylist.iter().cloned(),
+    )
+}
+
 #[mockall::automock]
 pub trait AppRegistryExt {
+    fn api_key_repository(&self) -> Arc<dyn ApiKeyRepository>;
     fn auth_repository(&self) -> Arc<dyn AuthRepository>;
     fn book_repository(&self) -> Arc<dyn BookRepository>;
+    fn book_copy_repository(&self) -> Arc<dyn BookCopyRepository>;
     fn checkout_repository(&self) -> Arc<dyn CheckoutRepository>;
+    fn email_change_repository(&self) -> Arc<dyn EmailChangeRepository>;
     fn health_check_repository(&self) -> Arc<dyn HealthCheckRepository>;
+    fn invitation_repository(&self) -> Arc<dyn InvitationRepository>;
+    fn login_attempt_repository(&self) -> Arc<dyn LoginAttemptRepository>;
+    fn oidc_repository(&self) -> Arc<dyn OidcRepository>;
+    fn password_reset_repository(&self) -> Arc<dyn PasswordResetRepository>;
+    fn reservation_repository(&self) -> Arc<dyn ReservationRepository>;
+    fn totp_repository(&self) -> Arc<dyn TotpRepository>;
     fn user_repository(&self) -> Arc<dyn UserRepository>;
 }
 
 impl AppRegistryExt for AppRegistryImpl {
     // 依存解決したインスタンスを返すメソッド
+    fn api_key_repository(&self) -> Arc<dyn ApiKeyRepository> {
+        self.api_key_repository.clone()
+    }
+
     fn auth_repository(&self) -> Arc<dyn AuthRepository> {
         self.auth_repository.clone()
     }
@@ -69,14 +231,46 @@ impl AppRegistryExt for AppRegistryImpl {
         self.book_repository.clone()
     }
 
+    fn book_copy_repository(&self) -> Arc<dyn BookCopyRepository> {
+        self.book_copy_repository.clone()
+    }
+
     fn checkout_repository(&self) -> Arc<dyn CheckoutRepository> {
         self.checkout_repository.clone()
     }
 
+    fn email_change_repository(&self) -> Arc<dyn EmailChangeRepository> {
+        self.email_change_repository.clone()
+    }
+
     fn health_check_repository(&self) -> Arc<dyn HealthCheckRepository> {
         self.health_check_repository.clone()
     }
 
+    fn invitation_repository(&self) -> Arc<dyn InvitationRepository> {
+        self.invitation_repository.clone()
+    }
+
+    fn login_attempt_repository(&self) -> Arc<dyn LoginAttemptRepository> {
+        self.login_attempt_repository.clone()
+    }
+
+    fn oidc_repository(&self) -> Arc<dyn OidcRepository> {
+        self.oidc_repository.clone()
+    }
+
+    fn password_reset_repository(&self) -> Arc<dyn PasswordResetRepository> {
+        self.password_reset_repository.clone()
+    }
+
+    fn reservation_repository(&self) -> Arc<dyn ReservationRepository> {
+        self.reservation_repository.clone()
+    }
+
+    fn totp_repository(&self) -> Arc<dyn TotpRepository> {
+        self.totp_repository.clone()
+    }
+
     fn user_repository(&self) -> Arc<dyn UserRepository> {
         self.user_repository.clone()
     }
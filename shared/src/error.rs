@@ -1,6 +1,8 @@
 use axum::{http::StatusCode, response::IntoResponse};
 use thiserror::Error;
 
+use crate::problem::ProblemDetails;
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("{0}")]
@@ -35,28 +37,23 @@ impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
         use AppError::*;
 
-        let status_code = match self {
-            UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
-            EntityNotFound(_) => StatusCode::NOT_FOUND,
-            ValidationError(_) | ConvertToUuidError(_) => StatusCode::BAD_REQUEST,
-            UnauthenticatedError | ForbiddenOperation => StatusCode::FORBIDDEN,
-            UnauthorizedError => StatusCode::UNAUTHORIZED,
-            e @ (TransactionError(_)
+        let (status_code, code) = match &self {
+            ValidationError(report) => return ProblemDetails::from(report).into_response(),
+            UnprocessableEntity(_) => (StatusCode::UNPROCESSABLE_ENTITY, "unprocessable_entity"),
+            EntityNotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            ConvertToUuidError(_) => (StatusCode::BAD_REQUEST, "invalid_id"),
+            UnauthenticatedError => (StatusCode::FORBIDDEN, "login_failed"),
+            ForbiddenOperation => (StatusCode::FORBIDDEN, "forbidden"),
+            UnauthorizedError => (StatusCode::UNAUTHORIZED, "unauthorized"),
+            TransactionError(_)
             | SpecificOperationError(_)
             | NoRowsAffectedError(_)
             | KeyValueStoreError(_)
             | BcryptError(_)
-            | ConversionEntityError(_)) => {
-                tracing::error!(
-                    error.cause_chain = ?e,
-                    error.message = %e,
-                    "Unexpected error happened"
-                );
-                StatusCode::INTERNAL_SERVER_ERROR
-            }
+            | ConversionEntityError(_) => return ProblemDetails::internal(&self).into_response(),
         };
 
-        status_code.into_response()
+        ProblemDetails::new(status_code, code, &self).into_response()
     }
 }
 
@@ -0,0 +1,93 @@
+use std::future::Future;
+
+// エラーメッセージの言語
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    Ja,
+    En,
+}
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+impl Locale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::Ja => "ja",
+            Locale::En => "en",
+        }
+    }
+
+    // Accept-Language の値から、対応している言語のうち最も優先度の高いものを選ぶ
+    // 対応している言語が含まれない場合は None を返す
+    pub fn from_accept_language(value: &str) -> Option<Self> {
+        let mut candidates = value
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                Some((Self::from_language_tag(tag)?, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect::<Vec<_>>();
+        // 同じ優先度であれば先に書かれたものを優先する
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates.first().map(|(locale, _)| *locale)
+    }
+
+    fn from_language_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split('-').next()?;
+        if primary.eq_ignore_ascii_case("ja") {
+            Some(Locale::Ja)
+        } else if primary.eq_ignore_ascii_case("en") {
+            Some(Locale::En)
+        } else {
+            None
+        }
+    }
+}
+
+// 与えられた言語をリクエストの処理中に参照できるようにする
+pub async fn scope<F: Future>(locale: Locale, f: F) -> F::Output {
+    LOCALE.scope(locale, f).await
+}
+
+// リクエストの処理中でなければ既定の言語を返す
+pub fn current() -> Locale {
+    LOCALE.try_with(|locale| *locale).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_supported_locale_by_quality() {
+        assert_eq!(Locale::from_accept_language("en-US"), Some(Locale::En));
+        assert_eq!(
+            Locale::from_accept_language("ja-JP,ja;q=0.9"),
+            Some(Locale::Ja)
+        );
+        assert_eq!(
+            Locale::from_accept_language("ja;q=0.5, en;q=0.8"),
+            Some(Locale::En)
+        );
+        assert_eq!(
+            Locale::from_accept_language("fr-FR, en;q=0.7, ja;q=0.7"),
+            Some(Locale::En)
+        );
+    }
+
+    #[test]
+    fn ignores_unsupported_or_rejected_locales() {
+        assert_eq!(Locale::from_accept_language("fr, de;q=0.5"), None);
+        assert_eq!(Locale::from_accept_language("*"), None);
+        assert_eq!(Locale::from_accept_language("en;q=0"), None);
+        assert_eq!(Locale::from_accept_language(""), None);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod config;
 pub mod env;
 pub mod error;
+pub mod i18n;
+pub mod message;
+pub mod problem;
+pub mod request_id;
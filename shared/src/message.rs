@@ -0,0 +1,417 @@
+use crate::i18n::Locale;
+
+// エラーコードごとの文言
+// コードに対応する文言が無い場合は None を返し、呼び出し側で既定の文言を使う
+pub fn error_message(code: &str, locale: Locale) -> Option<&'static str> {
+    let (ja, en) = match code {
+        // 共通
+        "validation_failed" => (
+            "入力内容に誤りがあります。",
+            "The request has invalid fields.",
+        ),
+        "internal_error" => (
+            "予期しないエラーが発生しました。",
+            "An unexpected error occurred.",
+        ),
+        "invalid_id" => ("ID の形式が正しくありません。", "The ID is malformed."),
+        "invalid_cursor" => (
+            "カーソルの形式が正しくありません。",
+            "The cursor is malformed.",
+        ),
+        "invalid_request_body" => (
+            "リクエストの本文の形式が正しくありません。",
+            "The request body is malformed.",
+        ),
+        "unsupported_media_type" => (
+            "リクエストの本文は JSON で送ってください。",
+            "The request body must be JSON.",
+        ),
+        "invalid_query" => (
+            "クエリパラメータの形式が正しくありません。",
+            "The query parameters are malformed.",
+        ),
+        "not_found" => (
+            "指定されたリソースが見つかりません。",
+            "The requested resource was not found.",
+        ),
+        "unprocessable_entity" => (
+            "リクエストを処理できませんでした。",
+            "The request could not be processed.",
+        ),
+        // 認証・認可
+        "unauthenticated" => ("ログインが必要です。", "Authentication is required."),
+        "unauthorized" => ("認可情報が間違っています。", "The credentials are invalid."),
+        "invalid_access_token" => (
+            "アクセストークンが無効です。",
+            "The access token is invalid.",
+        ),
+        "too_many_login_attempts" => (
+            "ログインの失敗が続いたため、しばらくログインできません。時間をおいて再度お試しください。",
+            "Too many failed login attempts. Please try again later.",
+        ),
+        "invalid_refresh_token" => (
+            "リフレッシュトークンが無効です。再度ログインしてください。",
+            "The refresh token is invalid. Please log in again.",
+        ),
+        "refresh_token_reused" => (
+            "使用済みのリフレッシュトークンが使われたため、このログインを無効にしました。再度ログインしてください。",
+            "A refresh token was reused, so this login has been revoked. Please log in again.",
+        ),
+        "invalid_credentials" | "login_failed" => (
+            "ログインに失敗しました。",
+            "The email or password is incorrect.",
+        ),
+        "invalid_mfa_token" => (
+            "ログインの有効期限が切れました。再度ログインしてください。",
+            "The login has expired. Please log in again.",
+        ),
+        "invalid_totp_code" => (
+            "認証コードが正しくありません。",
+            "The authentication code is incorrect.",
+        ),
+        "totp_already_enabled" => (
+            "二要素認証は既に有効です。",
+            "Two-factor authentication is already enabled.",
+        ),
+        "totp_not_enabled" => (
+            "二要素認証が有効になっていません。",
+            "Two-factor authentication is not enabled.",
+        ),
+        "totp_required" => (
+            "このアカウントでは二要素認証を無効にできません。",
+            "Two-factor authentication is required for this account.",
+        ),
+        "invalid_password_reset_token" => (
+            "パスワードの再設定用のリンクが無効か、有効期限が切れています。再度お申し込みください。",
+            "The password reset link is invalid or has expired. Please request a new one.",
+        ),
+        "invalid_email_change_token" => (
+            "メールアドレスの変更を確認するリンクが無効か、有効期限が切れています。再度お申し込みください。",
+            "The email change link is invalid or has expired. Please request the change again.",
+        ),
+        "invalid_invitation_token" => (
+            "招待のリンクが無効か、有効期限が切れています。管理者に招待し直してもらってください。",
+            "The invitation link is invalid or has expired. Please ask an administrator to invite you again.",
+        ),
+        "oidc_not_configured" => (
+            "シングルサインオンは設定されていません。",
+            "Single sign-on is not configured.",
+        ),
+        "oidc_login_denied" | "invalid_oidc_state" | "invalid_id_token" => (
+            "シングルサインオンに失敗しました。再度ログインしてください。",
+            "Single sign-on failed. Please log in again.",
+        ),
+        "oidc_email_not_verified" => (
+            "IdP でメールアドレスが確認されていないため、ログインできません。",
+            "The email address is not verified by the identity provider, so you cannot sign in.",
+        ),
+        "oidc_provider_error" => (
+            "認証サーバーとの通信に失敗しました。時間をおいて再度お試しください。",
+            "Failed to communicate with the identity provider. Please try again later.",
+        ),
+        "api_key_not_allowed" => (
+            "この操作は API キーでは行えません。ログインして操作してください。",
+            "This operation cannot be performed with an API key. Please log in.",
+        ),
+        "insufficient_scope" => (
+            "API キーにこの操作の権限がありません。",
+            "The API key does not have the scope required for this operation.",
+        ),
+        "forbidden" => (
+            "許可されていない操作です。",
+            "You are not allowed to perform this operation.",
+        ),
+        // 利用者
+        "user_not_found" => ("利用者が見つかりません。", "The user was not found."),
+        "session_not_found" => ("セッションが見つかりません。", "The session was not found."),
+        "api_key_not_found" => ("API キーが見つかりません。", "The API key was not found."),
+        "invitation_not_found" => ("招待が見つかりません。", "The invitation was not found."),
+        "email_already_registered" => (
+            "このメールアドレスは既に登録されています。",
+            "The email address is already registered.",
+        ),
+        // 蔵書・冊
+        "book_not_found" => ("蔵書が見つかりません。", "The book was not found."),
+        "book_copy_not_found" => ("冊が見つかりません。", "The book copy was not found."),
+        "not_book_owner" => (
+            "蔵書の所有者のみが操作できます。",
+            "Only the owner of the book can perform this operation.",
+        ),
+        "duplicate_barcode" => (
+            "同じバーコードの冊が既に登録されています。",
+            "A book copy with the same barcode already exists.",
+        ),
+        "book_copy_checked_out" => (
+            "貸出中の冊は削除できません。",
+            "A book copy that is checked out cannot be deleted.",
+        ),
+        "book_copy_has_history" => (
+            "貸出履歴のある冊は削除できません。",
+            "A book copy with checkout history cannot be deleted.",
+        ),
+        // 貸出
+        "checkout_not_found" => ("貸出が見つかりません。", "The checkout was not found."),
+        "no_available_copy" => (
+            "貸出可能な冊がありません。",
+            "No copy of the book is available.",
+        ),
+        "book_copy_already_checked_out" => (
+            "指定された冊は貸出中です。",
+            "The book copy is already checked out.",
+        ),
+        "reserved_for_others" => (
+            "他の利用者の予約のために取り置かれています。",
+            "The book is held for other users' reservations.",
+        ),
+        "renewal_limit_reached" => (
+            "延長できる回数の上限に達しています。",
+            "The renewal limit has been reached.",
+        ),
+        "renewal_blocked_by_reservation" => (
+            "他の利用者が予約しているため延長できません。",
+            "The checkout cannot be renewed because the book is reserved.",
+        ),
+        "not_borrower" => (
+            "借りた本人のみが操作できます。",
+            "Only the borrower can perform this operation.",
+        ),
+        "loan_limit_reached" => (
+            "貸出数の上限に達しています。",
+            "You have reached your loan limit.",
+        ),
+        "overdue_loans" => (
+            "返却期限を過ぎた貸出があります。先に返却してください。",
+            "You have overdue loans. Please return them first.",
+        ),
+        "own_book" => (
+            "自分が所有する蔵書は借りられません。",
+            "You cannot borrow your own book.",
+        ),
+        // 予約
+        "reservation_not_found" => ("予約が見つかりません。", "The reservation was not found."),
+        "already_reserved" => (
+            "この蔵書は既に予約しています。",
+            "You have already reserved this book.",
+        ),
+        "already_checked_out" => (
+            "この蔵書は既に借りています。",
+            "You have already checked out this book.",
+        ),
+        "book_available" => (
+            "貸出可能な冊があるため予約できません。",
+            "The book cannot be reserved because a copy is available.",
+        ),
+        "not_reserver" => (
+            "予約した本人のみが取り消せます。",
+            "Only the user who made the reservation can cancel it.",
+        ),
+        "expires_at_in_past" => (
+            "有効期限には未来の日時を指定してください。",
+            "The expiry must be in the future.",
+        ),
+        "expires_at_too_far" => (
+            "有効期限は 365 日以内で指定してください。",
+            "The expiry must be within 365 days.",
+        ),
+        _ => return None,
+    };
+
+    Some(match locale {
+        Locale::Ja => ja,
+        Locale::En => en,
+    })
+}
+
+// 入力値の誤りの種類
+// garde の検証結果と、kernel の値オブジェクトのエラーをこの形にそろえてから文言にする
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    Required,
+    TooShort(usize),
+    TooLong(usize),
+    TooSmall(String),
+    TooLarge(String),
+    InvalidEmail,
+    InvalidFormat,
+    SurroundingWhitespace,
+    ControlCharacters,
+    IncorrectPassword,
+    // 英小文字・英大文字・数字・記号のうち、含める必要のある種類の数
+    TooFewCharacterClasses(usize),
+    CommonPassword,
+    // 個別の検証規則。コードがカタログに無ければ与えられた文字列をそのまま文言に使う
+    Rule(String),
+}
+
+impl Violation {
+    pub fn code(&self) -> &str {
+        match self {
+            Violation::Required => "required",
+            Violation::TooShort(_) => "too_short",
+            Violation::TooLong(_) => "too_long",
+            Violation::TooSmall(_) => "too_small",
+            Violation::TooLarge(_) => "too_large",
+            Violation::InvalidEmail => "invalid_email",
+            Violation::InvalidFormat => "invalid_format",
+            Violation::SurroundingWhitespace => "surrounding_whitespace",
+            Violation::ControlCharacters => "control_characters",
+            Violation::IncorrectPassword => "incorrect_password",
+            Violation::TooFewCharacterClasses(_) => "too_few_character_classes",
+            Violation::CommonPassword => "common_password",
+            Violation::Rule(code) if rule_message(code, Locale::default()).is_some() => code,
+            Violation::Rule(_) => "invalid",
+        }
+    }
+
+    pub fn message(&self, locale: Locale) -> String {
+        match (self, locale) {
+            (Violation::Required, Locale::Ja) => "入力してください。".into(),
+            (Violation::Required, Locale::En) => "This field is required.".into(),
+            (Violation::TooShort(min), Locale::Ja) => format!("{min} 文字以上で入力してください。"),
+            (Violation::TooShort(min), Locale::En) => format!("Must be at least {min} characters."),
+            (Violation::TooLong(max), Locale::Ja) => format!("{max} 文字以内で入力してください。"),
+            (Violation::TooLong(max), Locale::En) => format!("Must be at most {max} characters."),
+            (Violation::TooSmall(min), Locale::Ja) => format!("{min} 以上の値を指定してください。"),
+            (Violation::TooSmall(min), Locale::En) => format!("Must be at least {min}."),
+            (Violation::TooLarge(max), Locale::Ja) => format!("{max} 以下の値を指定してください。"),
+            (Violation::TooLarge(max), Locale::En) => format!("Must be at most {max}."),
+            (Violation::InvalidEmail, Locale::Ja) => {
+                "メールアドレスの形式が正しくありません。".into()
+            }
+            (Violation::InvalidEmail, Locale::En) => "Must be a valid email address.".into(),
+            (Violation::InvalidFormat, Locale::Ja) => "形式が正しくありません。".into(),
+            (Violation::InvalidFormat, Locale::En) => "The format is invalid.".into(),
+            (Violation::SurroundingWhitespace, Locale::Ja) => {
+                "前後に空白を含めないでください。".into()
+            }
+            (Violation::SurroundingWhitespace, Locale::En) => {
+                "Must not have leading or trailing whitespace.".into()
+            }
+            (Violation::ControlCharacters, Locale::Ja) => "制御文字を含めないでください。".into(),
+            (Violation::ControlCharacters, Locale::En) => {
+                "Must not contain control characters.".into()
+            }
+            (Violation::IncorrectPassword, Locale::Ja) => "パスワードが正しくありません。".into(),
+            (Violation::IncorrectPassword, Locale::En) => "The password is incorrect.".into(),
+            (Violation::TooFewCharacterClasses(min), Locale::Ja) => format!(
+                "英小文字・英大文字・数字・記号のうち {min} 種類以上を含めてください。"
+            ),
+            (Violation::TooFewCharacterClasses(min), Locale::En) => format!(
+                "Must contain at least {min} of lowercase letters, uppercase letters, digits and symbols."
+            ),
+            (Violation::CommonPassword, Locale::Ja) => {
+                "よく使われているパスワードは使えません。".into()
+            }
+            (Violation::CommonPassword, Locale::En) => {
+                "This password is too common. Please choose another.".into()
+            }
+            (Violation::Rule(code), locale) => rule_message(code, locale)
+                .map(ToString::to_string)
+                .unwrap_or_else(|| code.clone()),
+        }
+    }
+}
+
+// 個別の検証規則の文言
+fn rule_message(code: &str, locale: Locale) -> Option<&'static str> {
+    let (ja, en) = match code {
+        "offset_with_after" => (
+            "offset と after は同時に指定できません。",
+            "offset cannot be combined with after.",
+        ),
+        "relevance_without_keyword" => (
+            "関連度順で並べるには検索キーワード (q) を指定してください。",
+            "Sorting by relevance requires a search keyword (q).",
+        ),
+        "expires_at_in_past" => (
+            "有効期限には未来の日時を指定してください。",
+            "The expiry must be in the future.",
+        ),
+        "expires_at_too_far" => (
+            "有効期限は 365 日以内で指定してください。",
+            "The expiry must be within 365 days.",
+        ),
+        _ => return None,
+    };
+
+    Some(match locale {
+        Locale::Ja => ja,
+        Locale::En => en,
+    })
+}
+
+// garde の組み込みの規則は英語の文言しか返さないため、文言から種類を判別する
+impl From<&garde::Error> for Violation {
+    fn from(error: &garde::Error) -> Self {
+        let message = error.message();
+        if let Some(min) = message.strip_prefix("length is lower than ") {
+            return match min.parse::<usize>() {
+                Ok(1) => Violation::Required,
+                Ok(min) => Violation::TooShort(min),
+                Err(_) => Violation::Rule(message.to_string()),
+            };
+        }
+        if let Some(max) = message.strip_prefix("length is greater than ") {
+            return match max.parse::<usize>() {
+                Ok(max) => Violation::TooLong(max),
+                Err(_) => Violation::Rule(message.to_string()),
+            };
+        }
+        if let Some(min) = message.strip_prefix("lower than ") {
+            return Violation::TooSmall(min.to_string());
+        }
+        if let Some(max) = message.strip_prefix("greater than ") {
+            return Violation::TooLarge(max.to_string());
+        }
+        if message.starts_with("not a valid email") {
+            return Violation::InvalidEmail;
+        }
+        Violation::Rule(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_garde_errors_to_violations() {
+        let cases = [
+            ("length is lower than 1", Violation::Required),
+            ("length is lower than 8", Violation::TooShort(8)),
+            ("length is greater than 255", Violation::TooLong(255)),
+            ("lower than 1", Violation::TooSmall("1".into())),
+            ("not a valid email: missing '@'", Violation::InvalidEmail),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(Violation::from(&garde::Error::new(message)), expected);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_rule_message() {
+        let known = Violation::from(&garde::Error::new("offset_with_after"));
+        assert_eq!(known.code(), "offset_with_after");
+        assert_eq!(
+            known.message(Locale::En),
+            "offset cannot be combined with after."
+        );
+
+        let unknown = Violation::from(&garde::Error::new("something went wrong"));
+        assert_eq!(unknown.code(), "invalid");
+        assert_eq!(unknown.message(Locale::Ja), "something went wrong");
+    }
+
+    #[test]
+    fn localizes_error_codes() {
+        assert_eq!(
+            error_message("book_not_found", Locale::Ja),
+            Some("蔵書が見つかりません。")
+        );
+        assert_eq!(
+            error_message("book_not_found", Locale::En),
+            Some("The book was not found.")
+        );
+        assert_eq!(error_message("no_such_code", Locale::En), None);
+    }
+}
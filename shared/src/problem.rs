@@ -0,0 +1,155 @@
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{ToResponse, ToSchema};
+
+use crate::{
+    i18n,
+    message::{error_message, Violation},
+    request_id,
+};
+
+// エラーレスポンスの本文（RFC 7807 の application/problem+json）
+// 個別の問題を表す URI は用意していないため、type は常に about:blank とし、
+// クライアントは code でエラーの種類を判別する
+// title は HTTP のステータスの説明、detail は Accept-Language に応じた文言とする
+#[derive(Debug, Serialize, Deserialize, ToSchema, ToResponse)]
+#[serde(rename_all = "camelCase")]
+#[response(
+    description = "Problem details (RFC 7807)",
+    content_type = "application/problem+json"
+)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    // エラーの種類を表す識別子。文言とは異なり変更しない
+    pub code: String,
+    pub detail: String,
+    // 入力値の誤りをフィールド単位で返す
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, violation: Violation) -> Self {
+        Self {
+            field: field.into(),
+            code: violation.code().to_string(),
+            message: violation.message(i18n::current()),
+        }
+    }
+}
+
+impl ProblemDetails {
+    // detail はカタログにコードの文言があればそれを使い、無ければ与えられた文言を使う
+    pub fn new(status: StatusCode, code: impl Into<String>, detail: impl ToString) -> Self {
+        let code = code.into();
+        let detail = error_message(&code, i18n::current())
+            .map(ToString::to_string)
+            .unwrap_or_else(|| detail.to_string());
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            code,
+            detail,
+            errors: vec![],
+            request_id: request_id::current(),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    // 入力値の誤り
+    pub fn invalid_fields(errors: Vec<FieldError>) -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "validation_failed",
+            "request has invalid fields",
+        )
+        .with_errors(errors)
+    }
+
+    // 想定外のエラーは内容をログにだけ残し、クライアントには詳細を返さない
+    pub fn internal(error: &(dyn std::error::Error + 'static)) -> Self {
+        tracing::error!(
+            error.cause_chain = ?error,
+            error.message = %error,
+            "unexpected error happened"
+        );
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "unexpected error happened",
+        )
+    }
+}
+
+impl From<FieldError> for ProblemDetails {
+    fn from(error: FieldError) -> Self {
+        Self::invalid_fields(vec![error])
+    }
+}
+
+impl From<&garde::Report> for ProblemDetails {
+    fn from(report: &garde::Report) -> Self {
+        Self::invalid_fields(
+            report
+                .iter()
+                .map(|(path, error)| {
+                    FieldError::new(to_camel_case(&path.to_string()), Violation::from(error))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (status, Json(self)).into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        response.headers_mut().insert(
+            header::CONTENT_LANGUAGE,
+            HeaderValue::from_static(i18n::current().as_str()),
+        );
+        response
+    }
+}
+
+// garde のパスは Rust のフィールド名なので、リクエストの JSON に合わせて camelCase にする
+fn to_camel_case(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut upper = false;
+    for c in path.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            result.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
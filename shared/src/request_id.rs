@@ -0,0 +1,18 @@
+use std::future::Future;
+
+// リクエストごとに振る ID。エラーレスポンスやログからリクエストを辿れるようにする
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+pub const HEADER_NAME: &str = "x-request-id";
+
+// 与えられた ID をリクエストの処理中に参照できるようにする
+pub async fn scope<F: Future>(request_id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(request_id, f).await
+}
+
+// リクエストの処理中でなければ None を返す
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
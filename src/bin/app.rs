@@ -1,5 +1,12 @@
-use adapter::{database::connect_database_with, redis::RedisClient};
-use axum::{http::Method, Router};
+use adapter::{
+    database::connect_database_with, jwt::JwtKeySet, mailer::MailerImpl, oidc::OidcClient,
+    redis::RedisClient,
+};
+use api::extractor::TrustedProxies;
+use axum::{
+    http::{HeaderName, Method},
+    middleware, Extension, Router,
+};
 use opentelemetry::global;
 use registry::AppRegistryImpl;
 use shared::{config::AppConfig, env::Environment};
@@ -26,7 +33,7 @@ async fn main() -> Result<()> {
 
 async fn bootstrap() -> Result<()> {
     // 環境変数からアプリケーション全体の設定を読み込む
-    let app_config = AppConfig::new()?;
+    let mut app_config = AppConfig::new()?;
 
     // データベースの接続
     let pool = connect_database_with(&app_config.database);
@@ -34,13 +41,49 @@ async fn bootstrap() -> Result<()> {
     // Redis への接続を担うクライアントのインスタンス化
     let kvs = Arc::new(RedisClient::new(&app_config.redis)?);
 
+    // シングルサインオンを設定している場合は、IdP とやり取りするクライアントを用意する
+    let oidc_client = app_config
+        .oidc
+        .take()
+        .map(OidcClient::new)
+        .transpose()?
+        .map(Arc::new);
+
+    // アクセストークンに JWT を使う場合は署名鍵を読み込む
+    let jwt_keys = app_config
+        .auth
+        .jwt
+        .take()
+        .map(|config| JwtKeySet::load(&config))
+        .transpose()?
+        .map(Arc::new);
+
+    // パスワードの再設定などのメールを送る方法を設定から選ぶ
+    let mailer = Arc::new(MailerImpl::new(&app_config.mail)?);
+
+    // X-Forwarded-For を信頼するリバースプロキシ
+    let trusted_proxies = TrustedProxies::new(app_config.proxy.trusted_proxies.iter().copied());
+
     // 依存解決
-    let registry = Arc::new(AppRegistryImpl::new(pool, kvs, app_config));
+    let registry = Arc::new(AppRegistryImpl::new(
+        pool,
+        kvs,
+        oidc_client,
+        jwt_keys,
+        mailer,
+        app_config,
+    ));
 
     // ルーティングの設定
     let app = Router::new()
         .merge(api::route::v1::routes())
         .merge(api::route::auth::build_auth_routers())
+        .merge(api::route::well_known::build_well_known_routers())
+        // エラーメッセージの言語を Accept-Language から決める
+        .layer(middleware::from_fn(api::middleware::locale))
+        // エラーレスポンスやログにリクエスト ID を含める
+        .layer(middleware::from_fn(api::middleware::request_id))
+        .layer(Extension(trusted_proxies))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
@@ -62,17 +105,21 @@ async fn bootstrap() -> Result<()> {
     println!("Listening on {}", addr);
 
     // サーバーの起動
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .context("Unexpected server error")
-        .inspect_err(|e| {
-            tracing::error!(
-                error.cause_chain = ?e,
-                error.message = %e,
-                "Unexpected error"
-            )
-        })
+    // セッション一覧に表示する接続元 IP アドレスを取得できるようにする
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .context("Unexpected server error")
+    .inspect_err(|e| {
+        tracing::error!(
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Unexpected error"
+        )
+    })
 }
 
 fn init_logger() -> Result<()> {
@@ -121,6 +168,7 @@ fn cors() -> CorsLayer {
         .allow_headers(cors::Any)
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
         .allow_origin(cors::Any)
+        .expose_headers([HeaderName::from_static(shared::request_id::HEADER_NAME)])
 }
 
 async fn shutdown_signal() {